@@ -0,0 +1,72 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use indicatif::{ProgressBar, ProgressStyle};
+use losslessvault_core::{archive::PackProgress, Vault};
+
+pub fn pack(vault: &Vault, archive_path: PathBuf) -> Result<()> {
+    let pb = ProgressBar::new(0);
+    pb.set_style(
+        ProgressStyle::with_template("{spinner:.green} [{bar:40.cyan/blue}] {pos}/{len} {msg}")
+            .unwrap()
+            .progress_chars("=>-"),
+    );
+
+    vault.pack_archive(
+        &archive_path,
+        Some(&mut |progress| match progress {
+            PackProgress::Start { total } => {
+                pb.set_length(total as u64);
+                pb.set_position(0);
+                pb.set_message("Packing vault archive...");
+            }
+            PackProgress::Packed { path } => {
+                pb.inc(1);
+                pb.set_message(format!("packed {}", path.display()));
+            }
+            PackProgress::Skipped { path } => {
+                pb.inc(1);
+                pb.set_message(format!("unchanged {}", path.display()));
+            }
+            PackProgress::Unpacked { .. } => {}
+            PackProgress::Complete { packed, skipped } => {
+                pb.finish_with_message(format!("{packed} packed, {skipped} unchanged"));
+            }
+        }),
+    )?;
+
+    println!("\nArchive written to {}", archive_path.display());
+    Ok(())
+}
+
+pub fn unpack(vault: &Vault, archive_path: PathBuf, dest_path: PathBuf) -> Result<()> {
+    let pb = ProgressBar::new(0);
+    pb.set_style(
+        ProgressStyle::with_template("{spinner:.green} [{bar:40.cyan/blue}] {pos}/{len} {msg}")
+            .unwrap()
+            .progress_chars("=>-"),
+    );
+
+    vault.unpack_archive(
+        &archive_path,
+        &dest_path,
+        Some(&mut |progress| match progress {
+            PackProgress::Start { total } => {
+                pb.set_length(total as u64);
+                pb.set_position(0);
+                pb.set_message("Unpacking vault archive...");
+            }
+            PackProgress::Unpacked { path } => {
+                pb.inc(1);
+                pb.set_message(format!("restored {}", path.display()));
+            }
+            PackProgress::Packed { .. } | PackProgress::Skipped { .. } => {}
+            PackProgress::Complete { packed, .. } => {
+                pb.finish_with_message(format!("{packed} file(s) restored"));
+            }
+        }),
+    )?;
+
+    println!("\nArchive extracted to {}", dest_path.display());
+    Ok(())
+}
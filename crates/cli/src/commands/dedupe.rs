@@ -0,0 +1,30 @@
+use anyhow::Result;
+use losslessvault_core::hasher::perceptual::PHASH_NEAR_DUPLICATE_THRESHOLD;
+use losslessvault_core::Vault;
+
+/// Report clusters of near-duplicate photos already saved to the vault
+/// (re-encoded or resized copies that exact SHA-256 matching can't catch).
+pub fn run(vault: &Vault, threshold: Option<u32>) -> Result<()> {
+    let threshold = threshold.unwrap_or(PHASH_NEAR_DUPLICATE_THRESHOLD);
+    let clusters = vault.dedupe_report(threshold)?;
+
+    if clusters.is_empty() {
+        println!("No near-duplicates found (threshold: {threshold} bits).");
+        return Ok(());
+    }
+
+    println!(
+        "Found {} cluster(s) of near-duplicate photos (threshold: {threshold} bits):",
+        clusters.len()
+    );
+    println!("{}", "-".repeat(60));
+
+    for (i, cluster) in clusters.iter().enumerate() {
+        println!("Cluster #{}", i + 1);
+        for filename in cluster {
+            println!("  {filename}");
+        }
+    }
+
+    Ok(())
+}
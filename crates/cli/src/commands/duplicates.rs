@@ -1,21 +1,31 @@
 use anyhow::Result;
+use losslessvault_core::domain::Confidence;
 use losslessvault_core::Vault;
 
-pub fn run(vault: &Vault, id: Option<i64>) -> Result<()> {
+pub fn run(vault: &Vault, id: Option<i64>, similar_only: bool) -> Result<()> {
     match id {
         Some(id) => show_group(vault, id),
-        None => list_groups(vault),
+        None => list_groups(vault, similar_only),
     }
 }
 
-fn list_groups(vault: &Vault) -> Result<()> {
+fn list_groups(vault: &Vault, similar_only: bool) -> Result<()> {
     let groups = vault.groups()?;
+    let groups: Vec<_> = groups
+        .into_iter()
+        .filter(|g| !similar_only || g.confidence != Confidence::Certain)
+        .collect();
 
     if groups.is_empty() {
-        println!("No duplicates found. Run `lsvault sources scan` first.");
+        let filtered = if similar_only { " similar" } else { "" };
+        println!("No{filtered} duplicates found. Run `lsvault sources scan` first.");
         return Ok(());
     }
 
+    if let Some(level) = vault.similarity_level()? {
+        println!("Similarity tolerance: {}\n", level.as_str());
+    }
+
     println!(
         "{:<6} {:<12} {:<8} {}",
         "ID", "Confidence", "Members", "Source of Truth"
@@ -44,13 +54,22 @@ fn list_groups(vault: &Vault) -> Result<()> {
 
 fn show_group(vault: &Vault, id: i64) -> Result<()> {
     let group = vault.group(id)?;
+    let reference_source_ids = vault.reference_source_ids()?;
 
-    println!("Group #{} ({})", group.id, group.confidence);
+    let tolerance = match vault.similarity_level()? {
+        Some(level) => format!(", tolerance: {}", level.as_str()),
+        None => String::new(),
+    };
+    println!("Group #{} ({}{})", group.id, group.confidence, tolerance);
     println!("{}", "-".repeat(60));
 
     for member in &group.members {
         let marker = if member.id == group.source_of_truth_id {
             " [SOURCE]"
+        } else if !reference_source_ids.is_empty()
+            && !reference_source_ids.contains(&member.source_id)
+        {
+            " [OUTSIDE REFERENCE]"
         } else {
             ""
         };
@@ -1,10 +1,32 @@
-use std::path::Path;
+use std::path::PathBuf;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use indicatif::{ProgressBar, ProgressStyle};
-use photopack_core::{export::ExportProgress, Vault};
+use losslessvault_core::{
+    export::{ExportFormat, ExportProgress},
+    Vault,
+};
+
+pub fn set(vault: &Vault, path: PathBuf) -> Result<()> {
+    vault.set_export_path(&path)?;
+    let resolved = vault.get_export_path()?.unwrap();
+    println!("Export path set to: {}", resolved.display());
+    Ok(())
+}
+
+pub fn show(vault: &Vault) -> Result<()> {
+    match vault.get_export_path()? {
+        Some(path) => println!("Export path: {}", path.display()),
+        None => println!("No export path configured. Use `lsvault export set <path>` to set one."),
+    }
+    Ok(())
+}
+
+pub fn run(vault: &Vault, format: &str, quality: Option<u8>) -> Result<()> {
+    let format = ExportFormat::parse(format)
+        .ok_or_else(|| anyhow!("unsupported export format: {format} (expected heic, avif, webp, or jpegxl)"))?;
+    let quality = quality.unwrap_or_else(|| format.default_quality());
 
-pub fn run(vault: &mut Vault, path: &Path, quality: u8) -> Result<()> {
     let pb = ProgressBar::new(0);
     pb.set_style(
         ProgressStyle::with_template("{spinner:.green} [{bar:40.cyan/blue}] {pos}/{len} {msg}")
@@ -13,13 +35,13 @@ pub fn run(vault: &mut Vault, path: &Path, quality: u8) -> Result<()> {
     );
 
     vault.export(
-        path,
+        format,
         quality,
         Some(&mut |progress| match progress {
             ExportProgress::Start { total } => {
                 pb.set_length(total as u64);
                 pb.set_position(0);
-                pb.set_message("Converting photos to HEIC...");
+                pb.set_message(format!("Converting photos to {format}..."));
             }
             ExportProgress::Converted { target, .. } => {
                 pb.inc(1);
@@ -28,10 +50,7 @@ pub fn run(vault: &mut Vault, path: &Path, quality: u8) -> Result<()> {
             ExportProgress::Skipped { .. } => {
                 pb.inc(1);
             }
-            ExportProgress::Complete {
-                converted,
-                skipped,
-            } => {
+            ExportProgress::Complete { converted, skipped } => {
                 pb.finish_with_message(format!("{converted} converted, {skipped} skipped"));
             }
         }),
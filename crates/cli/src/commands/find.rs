@@ -0,0 +1,28 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use photopack_core::Vault;
+
+pub fn run(vault: &Vault, path: PathBuf) -> Result<()> {
+    let matches = vault.find_similar(&path)?;
+
+    if matches.is_empty() {
+        println!("No matches found for {}.", path.display());
+        return Ok(());
+    }
+
+    println!("{} match(es) for {}:\n", matches.len(), path.display());
+    println!("{:<12} {:<8} {}", "Confidence", "Distance", "Path");
+    println!("{}", "-".repeat(80));
+
+    for m in &matches {
+        println!(
+            "{:<12} {:<8} {}",
+            m.confidence,
+            m.distance,
+            m.photo.path.display(),
+        );
+    }
+
+    Ok(())
+}
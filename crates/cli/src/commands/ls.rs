@@ -24,7 +24,7 @@ fn list_files(vault: &Vault) -> Result<()> {
     let photos = vault.photos()?;
     let groups = vault.groups()?;
 
-    let data = StatusData::build(&groups);
+    let data = StatusData::build(&sources, &groups);
     let agg = compute_aggregates(&photos, &groups, &data);
 
     let source_name_map: HashMap<i64, String> = sources
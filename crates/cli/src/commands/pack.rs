@@ -40,15 +40,30 @@ fn run_lossless(vault: &mut Vault, path: Option<PathBuf>) -> Result<()> {
         VaultSaveProgress::Skipped { .. } => {
             pb.inc(1);
         }
+        VaultSaveProgress::Deduplicated { path, .. } => {
+            pb.inc(1);
+            pb.set_message(format!("-> {} (deduplicated)", path.display()));
+        }
         VaultSaveProgress::Removed { path } => {
             pb.set_message(format!("removed superseded: {}", path.display()));
         }
+        VaultSaveProgress::Rejected { path, reason } => {
+            pb.set_message(format!("skipped {}: {reason}", path.display()));
+        }
         VaultSaveProgress::Complete {
             copied,
             skipped,
+            deduplicated,
+            bytes_saved,
             removed,
         } => {
             let mut msg = format!("{copied} copied, {skipped} skipped");
+            if deduplicated > 0 {
+                msg.push_str(&format!(
+                    ", {deduplicated} deduplicated ({:.1} MB saved)",
+                    bytes_saved as f64 / 1_048_576.0
+                ));
+            }
             if removed > 0 {
                 msg.push_str(&format!(", {removed} superseded removed"));
             }
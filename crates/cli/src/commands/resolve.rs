@@ -0,0 +1,40 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use losslessvault_core::resolve::Resolution;
+use losslessvault_core::Vault;
+
+pub fn run(
+    vault: &Vault,
+    id: Option<i64>,
+    resolution: Resolution,
+    dry_run: bool,
+) -> Result<()> {
+    let report = match id {
+        Some(id) => vault.resolve_group(id, resolution, dry_run)?,
+        None => vault.resolve_all(resolution, dry_run)?,
+    };
+
+    let verb = if dry_run { "Would affect" } else { "Affected" };
+    println!(
+        "{verb} {} file(s), reclaiming {:.1} MB",
+        report.files_affected,
+        report.bytes_reclaimed as f64 / (1024.0 * 1024.0),
+    );
+
+    Ok(())
+}
+
+pub fn parse_resolution(action: &str, move_to: Option<PathBuf>) -> Result<Resolution> {
+    match action {
+        "delete" => Ok(Resolution::Delete),
+        "move" => move_to
+            .map(Resolution::MoveTo)
+            .ok_or_else(|| anyhow::anyhow!("`--move-to <path>` is required for `--action move`")),
+        "hardlink" => Ok(Resolution::HardLink),
+        "symlink" => Ok(Resolution::SymLink),
+        other => Err(anyhow::anyhow!(
+            "unknown resolution action: {other} (expected delete, move, hardlink, or symlink)"
+        )),
+    }
+}
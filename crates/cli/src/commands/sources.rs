@@ -2,11 +2,59 @@ use std::path::PathBuf;
 
 use anyhow::Result;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
-use photopack_core::{ScanProgress, Vault};
+use photopack_core::hasher::perceptual::{HashAlg, ResizeFilter};
+use photopack_core::matching::confidence::SimilarityLevel;
+use photopack_core::{ScanConfig, ScanProgress, SourceRole, Vault};
 
-pub fn add(vault: &Vault, path: PathBuf) -> Result<()> {
-    let source = vault.add_source(&path)?;
-    println!("Added source: {}", source.path.display());
+fn parse_hash_alg(name: &str) -> Result<HashAlg> {
+    match name {
+        "mean" => Ok(HashAlg::Mean),
+        "gradient" => Ok(HashAlg::Gradient),
+        "double-gradient" => Ok(HashAlg::DoubleGradient),
+        "blockhash" => Ok(HashAlg::Blockhash),
+        "dct" => Ok(HashAlg::Dct),
+        other => Err(anyhow::anyhow!(
+            "unknown hash algorithm: {other} (expected mean, gradient, double-gradient, blockhash, or dct)"
+        )),
+    }
+}
+
+fn parse_resize_filter(name: &str) -> Result<ResizeFilter> {
+    match name {
+        "nearest" => Ok(ResizeFilter::Nearest),
+        "triangle" => Ok(ResizeFilter::Triangle),
+        "lanczos3" => Ok(ResizeFilter::Lanczos3),
+        other => Err(anyhow::anyhow!(
+            "unknown resize filter: {other} (expected nearest, triangle, or lanczos3)"
+        )),
+    }
+}
+
+fn parse_similarity_level(name: &str) -> Result<SimilarityLevel> {
+    match name {
+        "minimal" => Ok(SimilarityLevel::Minimal),
+        "low" => Ok(SimilarityLevel::Low),
+        "medium" => Ok(SimilarityLevel::Medium),
+        "high" => Ok(SimilarityLevel::High),
+        "very-high" => Ok(SimilarityLevel::VeryHigh),
+        "maximum" => Ok(SimilarityLevel::Maximum),
+        other => Err(anyhow::anyhow!(
+            "unknown similarity level: {other} (expected minimal, low, medium, high, very-high, or maximum)"
+        )),
+    }
+}
+
+pub fn add(vault: &Vault, path: PathBuf, reference: bool) -> Result<()> {
+    let source = if reference {
+        vault.add_source_with_role(&path, SourceRole::Reference)?
+    } else {
+        vault.add_source(&path)?
+    };
+    println!(
+        "Added source: {}{}",
+        source.path.display(),
+        if reference { " (reference)" } else { "" }
+    );
     Ok(())
 }
 
@@ -39,7 +87,65 @@ fn source_display_name(source: &str) -> &str {
         .unwrap_or(source)
 }
 
-pub fn scan(vault: &mut Vault) -> Result<()> {
+pub fn scan(
+    vault: &mut Vault,
+    threshold: Option<u32>,
+    similarity: Option<String>,
+    hash_alg: Option<String>,
+    resize_filter: Option<String>,
+    near_certain: Option<u32>,
+    high: Option<u32>,
+    required_votes: Option<u32>,
+    rebuild_cache: bool,
+    min_resolution: Option<u32>,
+    min_file_size: Option<u64>,
+    exclude_patterns: Vec<String>,
+    allowed_extensions: Vec<String>,
+    blocked_extensions: Vec<String>,
+    jobs: Option<usize>,
+) -> Result<()> {
+    if let Some(threshold) = threshold {
+        vault.set_similarity_threshold(threshold)?;
+    } else if let Some(similarity) = similarity {
+        vault.set_similarity(parse_similarity_level(&similarity)?)?;
+    }
+    if let Some(hash_alg) = hash_alg {
+        vault.set_hash_alg(parse_hash_alg(&hash_alg)?)?;
+    }
+    if let Some(resize_filter) = resize_filter {
+        vault.set_resize_filter(parse_resize_filter(&resize_filter)?)?;
+    }
+    if let Some(near_certain) = near_certain {
+        vault.set_near_certain_threshold(near_certain)?;
+    }
+    if let Some(high) = high {
+        vault.set_high_threshold(high)?;
+    }
+    if let Some(required_votes) = required_votes {
+        vault.set_required_votes(required_votes)?;
+    }
+    if rebuild_cache {
+        vault.rebuild_hash_cache()?;
+    }
+    if jobs.is_some() {
+        vault.set_scan_thread_limit(jobs)?;
+    }
+    if min_resolution.is_some()
+        || min_file_size.is_some()
+        || !exclude_patterns.is_empty()
+        || !allowed_extensions.is_empty()
+        || !blocked_extensions.is_empty()
+    {
+        vault.set_scan_config(&ScanConfig {
+            min_width: min_resolution,
+            min_height: min_resolution,
+            min_file_size,
+            exclude_patterns,
+            allowed_extensions,
+            blocked_extensions,
+        })?;
+    }
+
     let mp = MultiProgress::new();
     let mut active_pb: Option<ProgressBar> = None;
     let mut current_len: u64 = 0;
@@ -81,9 +187,19 @@ pub fn scan(vault: &mut Vault) -> Result<()> {
                 pb.inc(1);
             }
         }
+        ScanProgress::Excluded { source, count } => {
+            mp.println(format!(
+                "  Skipped {count} excluded file(s) in {}",
+                source_display_name(&source)
+            ))
+            .ok();
+        }
         ScanProgress::FilesRemoved { count } => {
             mp.println(format!("  Cleaned {count} stale entries")).ok();
         }
+        ScanProgress::MovesDetected { count } => {
+            mp.println(format!("  Detected {count} moved/renamed file(s), re-homed without re-packing")).ok();
+        }
         ScanProgress::AnalysisStart { count } => {
             // Finish hashing bar — stays visible with done style
             if let Some(pb) = active_pb.take() {
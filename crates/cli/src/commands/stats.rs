@@ -0,0 +1,144 @@
+use anyhow::Result;
+use losslessvault_core::hasher::perceptual::PHASH_NEAR_DUPLICATE_THRESHOLD;
+use losslessvault_core::stats::VaultStats;
+use losslessvault_core::Vault;
+
+pub fn run(vault: &Vault, threshold: Option<u32>, json: bool) -> Result<()> {
+    let threshold = threshold.unwrap_or(PHASH_NEAR_DUPLICATE_THRESHOLD);
+    let stats = vault.stats(threshold)?;
+
+    if json {
+        println!("{}", to_json(&stats));
+    } else {
+        print_human(&stats, threshold);
+    }
+
+    Ok(())
+}
+
+fn print_human(stats: &VaultStats, threshold: u32) {
+    println!("Vault stats");
+    println!("{}", "-".repeat(60));
+    println!("Photos:           {}", stats.total_photos);
+    println!(
+        "Logical size:     {:.1} MB",
+        stats.total_bytes as f64 / 1_048_576.0
+    );
+    println!(
+        "Unique size:      {:.1} MB",
+        stats.unique_bytes as f64 / 1_048_576.0
+    );
+    println!(
+        "Reclaimed:        {:.1} MB ({} exact-duplicate group(s))",
+        stats.bytes_reclaimed as f64 / 1_048_576.0,
+        stats.exact_duplicate_groups.len(),
+    );
+    println!(
+        "Near-duplicates:  {} cluster(s) (threshold: {threshold} bits)",
+        stats.near_duplicate_clusters.len(),
+    );
+
+    if !stats.by_extension.is_empty() {
+        println!("\nBy extension:");
+        for (ext, ext_stats) in &stats.by_extension {
+            let label = if ext.is_empty() { "(none)" } else { ext.as_str() };
+            println!(
+                "  {:<10} {:>6} files  {:>8.1} MB",
+                label,
+                ext_stats.count,
+                ext_stats.bytes as f64 / 1_048_576.0
+            );
+        }
+    }
+
+    if !stats.exact_duplicate_groups.is_empty() {
+        println!("\nExact duplicate groups:");
+        for group in &stats.exact_duplicate_groups {
+            println!(
+                "  {} ({} copies, {:.1} MB reclaimable)",
+                group.sha256,
+                group.paths.len(),
+                group.bytes_reclaimable as f64 / 1_048_576.0
+            );
+        }
+    }
+
+    if !stats.near_duplicate_clusters.is_empty() {
+        println!("\nNear-duplicate clusters:");
+        for (i, cluster) in stats.near_duplicate_clusters.iter().enumerate() {
+            println!("  Cluster #{}: {}", i + 1, cluster.join(", "));
+        }
+    }
+}
+
+fn to_json(stats: &VaultStats) -> String {
+    let extensions: Vec<String> = stats
+        .by_extension
+        .iter()
+        .map(|(ext, s)| {
+            format!(
+                "{{\"extension\":{},\"count\":{},\"bytes\":{}}}",
+                json_string(ext),
+                s.count,
+                s.bytes
+            )
+        })
+        .collect();
+
+    let exact_groups: Vec<String> = stats
+        .exact_duplicate_groups
+        .iter()
+        .map(|g| {
+            let paths: Vec<String> = g
+                .paths
+                .iter()
+                .map(|p| json_string(&p.display().to_string()))
+                .collect();
+            format!(
+                "{{\"sha256\":{},\"paths\":[{}],\"bytes_reclaimable\":{}}}",
+                json_string(&g.sha256),
+                paths.join(","),
+                g.bytes_reclaimable
+            )
+        })
+        .collect();
+
+    let near_clusters: Vec<String> = stats
+        .near_duplicate_clusters
+        .iter()
+        .map(|cluster| {
+            let names: Vec<String> = cluster.iter().map(|f| json_string(f)).collect();
+            format!("[{}]", names.join(","))
+        })
+        .collect();
+
+    format!(
+        "{{\"total_photos\":{},\"total_bytes\":{},\"unique_bytes\":{},\"bytes_reclaimed\":{},\"exact_duplicate_groups\":[{}],\"near_duplicate_clusters\":[{}],\"by_extension\":[{}]}}",
+        stats.total_photos,
+        stats.total_bytes,
+        stats.unique_bytes,
+        stats.bytes_reclaimed,
+        exact_groups.join(","),
+        near_clusters.join(","),
+        extensions.join(","),
+    )
+}
+
+/// Minimal JSON string encoding: escape backslash, quote, and control characters.
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
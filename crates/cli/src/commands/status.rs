@@ -3,16 +3,171 @@ use std::collections::{HashMap, HashSet};
 use anyhow::Result;
 use comfy_table::{presets::UTF8_FULL, Cell, Color, ContentArrangement, Table};
 use photopack_core::domain::{DuplicateGroup, PhotoFile, Source};
-use photopack_core::Vault;
+use photopack_core::hasher::perceptual::hamming_distance;
+use photopack_core::{SourceRole, Vault};
+
+/// How closely a duplicate matches its group's source-of-truth, derived from
+/// perceptual-hash Hamming distance (czkawka's SIMILAR_VALUES buckets).
+/// `Exact` is reserved for a byte-identical (SHA-256) match; the rest grade
+/// how much the pixels actually differ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum SimilarityTier {
+    Exact,
+    VerySimilar,
+    Similar,
+    Loose,
+}
+
+impl SimilarityTier {
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            SimilarityTier::Exact => "Exact",
+            SimilarityTier::VerySimilar => "Very similar",
+            SimilarityTier::Similar => "Similar",
+            SimilarityTier::Loose => "Loose",
+        }
+    }
+
+    pub(crate) fn color(self) -> Color {
+        match self {
+            SimilarityTier::Exact => Color::Green,
+            SimilarityTier::VerySimilar => Color::Yellow,
+            SimilarityTier::Similar => Color::DarkYellow,
+            SimilarityTier::Loose => Color::Red,
+        }
+    }
+}
+
+/// czkawka's SIMILAR_VALUES: rows are ascending hash bit-lengths (8/16/32/64),
+/// columns are ascending Hamming-distance thresholds. A distance is mapped to
+/// the lowest (strictest) column it doesn't exceed — the finest tier it still
+/// qualifies for.
+const SIMILAR_VALUES: [[u32; 6]; 4] = [
+    [1, 2, 5, 7, 14, 20],
+    [2, 5, 15, 30, 40, 40],
+    [4, 10, 20, 40, 40, 40],
+    [6, 20, 40, 40, 40, 40],
+];
+
+fn similar_values_row(hash_bits: u32) -> usize {
+    match hash_bits {
+        8 => 0,
+        16 => 1,
+        32 => 2,
+        _ => 3, // 64-bit is the only size this codebase's hashers produce
+    }
+}
+
+/// Maps a Hamming distance to a `SimilarityTier` using `SIMILAR_VALUES`.
+/// Columns 0-1 are "very similar", 2-3 "similar", 4-5 "loose"; a distance
+/// past every column in the row still counts as `Loose` rather than panicking
+/// — it can only be reached by a matcher configured more permissively than
+/// these defaults.
+pub(crate) fn tier_from_distance(distance: u32, hash_bits: u32) -> SimilarityTier {
+    if distance == 0 {
+        return SimilarityTier::Exact;
+    }
+    let row = SIMILAR_VALUES[similar_values_row(hash_bits)];
+    let column = row.iter().position(|&threshold| distance <= threshold);
+    match column {
+        Some(0) | Some(1) => SimilarityTier::VerySimilar,
+        Some(2) | Some(3) => SimilarityTier::Similar,
+        _ => SimilarityTier::Loose,
+    }
+}
+
+/// Grades `member` against its group's `sot` photo. Falls back to SHA-256
+/// equality (`Exact` or nothing) when the two don't share a perceptual hash
+/// of the same kind — e.g. one was hashed before a `--hash-alg` switch, or a
+/// hash failed to compute. Checks `phash` first, then `dhash`, then `ahash`,
+/// matching the priority order `matching::mod` uses when confirming a group.
+pub(crate) fn similarity_tier_for_member(member: &PhotoFile, sot: &PhotoFile) -> Option<SimilarityTier> {
+    if member.sha256 == sot.sha256 {
+        return Some(SimilarityTier::Exact);
+    }
+    [(member.phash, sot.phash), (member.dhash, sot.dhash), (member.ahash, sot.ahash)]
+        .into_iter()
+        .find_map(|(m, s)| match (m, s) {
+            (Some(m), Some(s)) => Some(tier_from_distance(hamming_distance(m, s), 64)),
+            _ => None,
+        })
+}
+
+/// A photo's row classification in the files table — shared by the table
+/// renderer (which also needs a color) and the JSON/CSV exporters (which
+/// only need the label).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PhotoRole {
+    /// Lives in a `SourceRole::Reference` source; never pruned.
+    Protected,
+    /// The group's elected (or reference-preferred) source-of-truth.
+    BestCopy,
+    /// A removable duplicate, graded by how close a match it is.
+    Duplicate(SimilarityTier),
+    Unique,
+}
+
+impl PhotoRole {
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            PhotoRole::Protected => "Protected",
+            PhotoRole::BestCopy => "Best Copy",
+            PhotoRole::Duplicate(tier) => tier.label(),
+            PhotoRole::Unique => "Unique",
+        }
+    }
+
+    pub(crate) fn color(self) -> Option<Color> {
+        match self {
+            PhotoRole::Protected => Some(Color::Blue),
+            PhotoRole::BestCopy => Some(Color::Green),
+            PhotoRole::Duplicate(tier) => Some(tier.color()),
+            PhotoRole::Unique => None,
+        }
+    }
+}
+
+pub(crate) fn photo_role(photo: &PhotoFile, data: &StatusData) -> PhotoRole {
+    let is_grouped = data.grouped_ids.contains(&photo.id);
+    if !is_grouped {
+        return PhotoRole::Unique;
+    }
+    if data.is_reference(photo.id) {
+        return PhotoRole::Protected;
+    }
+    if data.photo_is_sot.get(&photo.id).copied().unwrap_or(false) {
+        return PhotoRole::BestCopy;
+    }
+    let tier = data
+        .photo_similarity_tier
+        .get(&photo.id)
+        .copied()
+        .unwrap_or(SimilarityTier::Exact);
+    PhotoRole::Duplicate(tier)
+}
 
 /// Precomputed lookup data for rendering the status dashboard.
 pub(crate) struct StatusData {
     /// photo_id → group_id
     pub(crate) photo_group: HashMap<i64, i64>,
-    /// photo_id → true if source-of-truth
+    /// photo_id → true if source-of-truth (a reference-source member always
+    /// wins this, even over the group's stored `source_of_truth_id`)
     pub(crate) photo_is_sot: HashMap<i64, bool>,
+    /// photo_id → true if the photo comes from a `SourceRole::Reference`
+    /// source — a curated archive that's never pruned or counted as a
+    /// duplicate, no matter its place in a group
+    pub(crate) photo_is_reference: HashMap<i64, bool>,
+    /// photo_id → similarity tier against its group's effective
+    /// source-of-truth. Only populated for non-SOT members; missing/mixed
+    /// hashes fall back to `None` (treated as sha256-exact-or-unknown by callers).
+    pub(crate) photo_similarity_tier: HashMap<i64, SimilarityTier>,
     /// Set of all photo IDs that belong to a group
     pub(crate) grouped_ids: HashSet<i64>,
+    /// photo_id → `(dev, ino)` of its current on-disk path, for members of a
+    /// group only. `None` on platforms/paths without inode info. Used to
+    /// tell a hardlinked duplicate (frees no space) from an independently
+    /// stored one.
+    pub(crate) photo_dev_ino: HashMap<i64, Option<(u64, u64)>>,
 }
 
 /// Aggregated statistics derived from photos and groups.
@@ -23,7 +178,29 @@ pub(crate) struct Aggregates {
     pub(crate) total_duplicates: usize,
     pub(crate) total_unique: usize,
     pub(crate) total_disk: u64,
+    /// Logical duplicate bytes: the summed size of every non-SOT duplicate,
+    /// as if each one occupied distinct disk blocks. Excludes any duplicate
+    /// living in a `SourceRole::Reference` source — that copy is protected
+    /// and will never actually be pruned.
     pub(crate) savings: u64,
+    /// Bytes actually reclaimable by removing non-SOT duplicates: like
+    /// `savings`, but members sharing a `(dev, ino)` with the retained copy
+    /// or with an earlier duplicate in the same group (i.e. already
+    /// hardlinked to it) count once, since deleting the rest frees nothing.
+    /// Falls back to `savings`'-style full-size counting on platforms or
+    /// paths without inode info (e.g. Windows).
+    pub(crate) reclaimable_bytes: u64,
+    /// Photos whose filename extension disagrees with their sniffed
+    /// container format (see `format_sniff::is_mismatched`) — the common
+    /// case of a file renamed or mis-exported by a phone or editor.
+    pub(crate) total_mismatched: usize,
+    /// Duplicate breakdown by `SimilarityTier`, so the overview can show how
+    /// many matches are byte-identical vs. merely visually similar before a
+    /// user prunes them.
+    pub(crate) duplicates_exact: usize,
+    pub(crate) duplicates_very_similar: usize,
+    pub(crate) duplicates_similar: usize,
+    pub(crate) duplicates_loose: usize,
 }
 
 /// Per-source statistics.
@@ -34,38 +211,105 @@ pub(crate) struct SourceStats {
 }
 
 impl StatusData {
-    pub(crate) fn build(groups: &[DuplicateGroup]) -> Self {
+    pub(crate) fn build(sources: &[Source], groups: &[DuplicateGroup]) -> Self {
+        let reference_source_ids: HashSet<i64> = sources
+            .iter()
+            .filter(|s| s.role == SourceRole::Reference)
+            .map(|s| s.id)
+            .collect();
+
         let mut photo_group: HashMap<i64, i64> = HashMap::new();
         let mut photo_is_sot: HashMap<i64, bool> = HashMap::new();
+        let mut photo_is_reference: HashMap<i64, bool> = HashMap::new();
+        let mut photo_similarity_tier: HashMap<i64, SimilarityTier> = HashMap::new();
         let mut grouped_ids: HashSet<i64> = HashSet::new();
+        let mut photo_dev_ino: HashMap<i64, Option<(u64, u64)>> = HashMap::new();
 
         for group in groups {
+            // A reference-source member always wins source-of-truth — an
+            // authoritative archive copy outranks the format/quality ladder
+            // `elect_source_of_truth_ranked` already ran at scan time.
+            let reference_member = group
+                .members
+                .iter()
+                .find(|m| reference_source_ids.contains(&m.source_id));
+            let sot_member = reference_member
+                .or_else(|| group.members.iter().find(|m| m.id == group.source_of_truth_id))
+                .unwrap_or(&group.members[0]);
+
             for member in &group.members {
                 photo_group.insert(member.id, group.id);
-                photo_is_sot.insert(member.id, member.id == group.source_of_truth_id);
+                photo_is_sot.insert(member.id, member.id == sot_member.id);
+                photo_is_reference.insert(
+                    member.id,
+                    reference_source_ids.contains(&member.source_id),
+                );
+                if member.id != sot_member.id {
+                    if let Some(tier) = similarity_tier_for_member(member, sot_member) {
+                        photo_similarity_tier.insert(member.id, tier);
+                    }
+                }
                 grouped_ids.insert(member.id);
+                photo_dev_ino.insert(member.id, dev_ino_of(&member.path));
             }
         }
 
         Self {
             photo_group,
             photo_is_sot,
+            photo_is_reference,
+            photo_similarity_tier,
             grouped_ids,
+            photo_dev_ino,
         }
     }
 
     pub(crate) fn is_duplicate(&self, photo_id: i64) -> bool {
         self.grouped_ids.contains(&photo_id)
             && !self.photo_is_sot.get(&photo_id).copied().unwrap_or(false)
+            && !self.photo_is_reference.get(&photo_id).copied().unwrap_or(false)
     }
 
     pub(crate) fn vault_eligible(&self, photo_id: i64) -> bool {
         if self.grouped_ids.contains(&photo_id) {
             self.photo_is_sot.get(&photo_id).copied().unwrap_or(false)
+                || self.photo_is_reference.get(&photo_id).copied().unwrap_or(false)
         } else {
             true
         }
     }
+
+    pub(crate) fn is_reference(&self, photo_id: i64) -> bool {
+        self.photo_is_reference.get(&photo_id).copied().unwrap_or(false)
+    }
+
+    /// Whether `photo_id` is a duplicate that shares a `(dev, ino)` with its
+    /// group's source of truth — i.e. it's already a hard link pointing at
+    /// the same physical file, so removing it would free no disk space.
+    pub(crate) fn is_hardlinked_duplicate(&self, photo_id: i64) -> bool {
+        if !self.is_duplicate(photo_id) {
+            return false;
+        }
+        let Some(gid) = self.photo_group.get(&photo_id) else {
+            return false;
+        };
+        let sot_id = self
+            .photo_group
+            .iter()
+            .filter(|(_, g)| *g == gid)
+            .map(|(id, _)| *id)
+            .find(|id| self.photo_is_sot.get(id).copied().unwrap_or(false));
+        let Some(sot_id) = sot_id else {
+            return false;
+        };
+        match (
+            self.photo_dev_ino.get(&photo_id).copied().flatten(),
+            self.photo_dev_ino.get(&sot_id).copied().flatten(),
+        ) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
+        }
+    }
 }
 
 pub(crate) fn compute_aggregates(photos: &[PhotoFile], groups: &[DuplicateGroup], data: &StatusData) -> Aggregates {
@@ -79,6 +323,47 @@ pub(crate) fn compute_aggregates(photos: &[PhotoFile], groups: &[DuplicateGroup]
         .filter(|p| data.is_duplicate(p.id))
         .map(|p| p.size)
         .sum();
+    let total_mismatched = photos.iter().filter(|p| is_format_mismatched(p)).count();
+
+    let mut reclaimable_bytes: u64 = 0;
+    for group in groups {
+        let sot_member = group
+            .members
+            .iter()
+            .find(|m| data.photo_is_sot.get(&m.id).copied().unwrap_or(false));
+        let mut seen_ino: HashSet<(u64, u64)> = HashSet::new();
+        if let Some(ino) = sot_member.and_then(|m| data.photo_dev_ino.get(&m.id).copied().flatten()) {
+            seen_ino.insert(ino);
+        }
+        for member in &group.members {
+            if !data.is_duplicate(member.id) {
+                continue;
+            }
+            match data.photo_dev_ino.get(&member.id).copied().flatten() {
+                Some(ino) => {
+                    if seen_ino.insert(ino) {
+                        reclaimable_bytes += member.size;
+                    }
+                    // else: already hardlinked to the retained copy or to
+                    // another duplicate in this group — frees nothing.
+                }
+                None => reclaimable_bytes += member.size,
+            }
+        }
+    }
+
+    let mut duplicates_exact = 0;
+    let mut duplicates_very_similar = 0;
+    let mut duplicates_similar = 0;
+    let mut duplicates_loose = 0;
+    for photo in photos.iter().filter(|p| data.is_duplicate(p.id)) {
+        match data.photo_similarity_tier.get(&photo.id) {
+            Some(SimilarityTier::Exact) | None => duplicates_exact += 1,
+            Some(SimilarityTier::VerySimilar) => duplicates_very_similar += 1,
+            Some(SimilarityTier::Similar) => duplicates_similar += 1,
+            Some(SimilarityTier::Loose) => duplicates_loose += 1,
+        }
+    }
 
     Aggregates {
         total_photos,
@@ -87,9 +372,39 @@ pub(crate) fn compute_aggregates(photos: &[PhotoFile], groups: &[DuplicateGroup]
         total_unique,
         total_disk,
         savings,
+        reclaimable_bytes,
+        total_mismatched,
+        duplicates_exact,
+        duplicates_very_similar,
+        duplicates_similar,
+        duplicates_loose,
     }
 }
 
+/// Device + inode identity for a photo's current on-disk path, used to tell
+/// hardlinked duplicates (one physical file, several catalog entries) from
+/// byte-identical copies stored independently. Looked up live at reporting
+/// time rather than cached on `PhotoFile`/in the catalog, since a hardlink
+/// can be made or broken between scans without re-hashing the file. Returns
+/// `None` on platforms without inode semantics (e.g. Windows) or if the path
+/// can no longer be stat'd; callers then fall back to counting the full size.
+#[cfg(unix)]
+fn dev_ino_of(path: &std::path::Path) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(path).ok().map(|m| (m.dev(), m.ino()))
+}
+
+#[cfg(not(unix))]
+fn dev_ino_of(_path: &std::path::Path) -> Option<(u64, u64)> {
+    None
+}
+
+/// Whether `photo`'s declared (extension-derived) format disagrees with the
+/// format sniffed from its actual file header.
+pub(crate) fn is_format_mismatched(photo: &PhotoFile) -> bool {
+    photopack_core::format_sniff::is_mismatched(&photo.path, photo.format)
+}
+
 pub(crate) fn compute_source_stats(photos: &[PhotoFile]) -> HashMap<i64, SourceStats> {
     let mut stats: HashMap<i64, SourceStats> = HashMap::new();
     for photo in photos {
@@ -103,13 +418,21 @@ pub(crate) fn compute_source_stats(photos: &[PhotoFile]) -> HashMap<i64, SourceS
     stats
 }
 
-pub fn run(vault: &Vault) -> Result<()> {
+pub fn run(vault: &Vault, list: bool, mismatched_only: bool, format: ReportFormat) -> Result<()> {
+    if format != ReportFormat::Table {
+        return export_report(vault, mismatched_only, format);
+    }
+
+    if list {
+        return list_files(vault, mismatched_only);
+    }
+
     let sources = vault.sources()?;
     let photos = vault.photos()?;
     let groups = vault.groups()?;
     let vault_path = vault.get_vault_path()?;
 
-    let data = StatusData::build(&groups);
+    let data = StatusData::build(&sources, &groups);
     let agg = compute_aggregates(&photos, &groups, &data);
     let source_stats = compute_source_stats(&photos);
 
@@ -131,9 +454,10 @@ pub fn run(vault: &Vault) -> Result<()> {
         format_size(agg.total_disk)
     );
     println!(
-        "   Unique:     {:>8}        Savings:     {}",
+        "   Unique:     {:>8}        Savings:     {} ({} reclaimable)",
         agg.total_unique,
-        format_size(agg.savings)
+        format_size(agg.savings),
+        format_size(agg.reclaimable_bytes)
     );
     println!(
         "   Groups:     {:>8}        Sources:     {:>8}",
@@ -144,6 +468,18 @@ pub fn run(vault: &Vault) -> Result<()> {
         "   Duplicates: {:>8}        Vault:       {}",
         agg.total_duplicates, vault_display
     );
+    if agg.total_mismatched > 0 {
+        println!(
+            "   Mismatched: {:>8}        (extension doesn't match sniffed format)",
+            agg.total_mismatched
+        );
+    }
+    if agg.total_duplicates > 0 {
+        println!(
+            "   Tiers:      {} exact, {} very similar, {} similar, {} loose",
+            agg.duplicates_exact, agg.duplicates_very_similar, agg.duplicates_similar, agg.duplicates_loose
+        );
+    }
 
     // Sources table
     let mut sources_table = Table::new();
@@ -155,6 +491,7 @@ pub fn run(vault: &Vault) -> Result<()> {
         Cell::new("Photos"),
         Cell::new("Size"),
         Cell::new("Last Scanned"),
+        Cell::new("Reference"),
     ]);
 
     for source in &sources {
@@ -168,12 +505,18 @@ pub fn run(vault: &Vault) -> Result<()> {
                 .unwrap_or_else(|| "unknown".to_string()),
             None => "never".to_string(),
         };
+        let reference = if source.role == photopack_core::SourceRole::Reference {
+            Cell::new("\u{2714}").fg(Color::Green)
+        } else {
+            Cell::new("")
+        };
         sources_table.add_row(vec![
             Cell::new(source.id),
             Cell::new(&name),
             Cell::new(count),
             Cell::new(format_size(size)),
             Cell::new(scanned),
+            reference,
         ]);
     }
 
@@ -183,7 +526,98 @@ pub fn run(vault: &Vault) -> Result<()> {
     println!("{sources_table}");
 
     println!();
-    println!("  Run 'photopack ls' to show the full files table.");
+    println!("  Run 'lsvault catalog list' to show the full files table.");
+    println!();
+
+    Ok(())
+}
+
+/// Full files table: every photo with its group, role, vault eligibility,
+/// and format-mismatch status. With `mismatched_only`, rows are filtered
+/// down to files whose extension disagrees with their sniffed format.
+fn list_files(vault: &Vault, mismatched_only: bool) -> Result<()> {
+    let sources = vault.sources()?;
+    let photos = vault.photos()?;
+    let groups = vault.groups()?;
+
+    let data = StatusData::build(&sources, &groups);
+    let agg = compute_aggregates(&photos, &groups, &data);
+
+    let source_name_map: HashMap<i64, String> = sources
+        .iter()
+        .map(|s| (s.id, source_display_name(s)))
+        .collect();
+
+    let mut files_table = Table::new();
+    files_table.load_preset(UTF8_FULL);
+    files_table.set_content_arrangement(ContentArrangement::Dynamic);
+    files_table.set_header(vec![
+        Cell::new("File"),
+        Cell::new("Source"),
+        Cell::new("Fmt"),
+        Cell::new("Size"),
+        Cell::new("Group"),
+        Cell::new("Role"),
+        Cell::new("Vault"),
+    ]);
+
+    let header_len = 7; // File, Source, Fmt, Size, Group, Role, Vault
+
+    // Partition and sort
+    let (grouped_photos, ungrouped_photos) = sort_photos_for_display(&photos, &data);
+    let keep = |photo: &PhotoFile| !mismatched_only || is_format_mismatched(photo);
+
+    // Add grouped photo rows
+    let mut last_group_id: Option<i64> = None;
+    let mut any_grouped_shown = false;
+
+    for photo in &grouped_photos {
+        if !keep(photo) {
+            continue;
+        }
+        any_grouped_shown = true;
+        let gid = *data.photo_group.get(&photo.id).unwrap();
+
+        if last_group_id.is_some() && last_group_id != Some(gid) {
+            let empty_row: Vec<Cell> = (0..header_len).map(|_| Cell::new("")).collect();
+            files_table.add_row(empty_row);
+        }
+        last_group_id = Some(gid);
+
+        add_photo_row(&mut files_table, photo, &source_name_map, &data);
+    }
+
+    let ungrouped_photos: Vec<&PhotoFile> = ungrouped_photos
+        .into_iter()
+        .filter(|p| keep(*p))
+        .collect();
+
+    // Separator between grouped and ungrouped
+    if any_grouped_shown && !ungrouped_photos.is_empty() {
+        let empty_row: Vec<Cell> = (0..header_len).map(|_| Cell::new("")).collect();
+        files_table.add_row(empty_row);
+    }
+
+    for photo in &ungrouped_photos {
+        add_photo_row(&mut files_table, photo, &source_name_map, &data);
+    }
+
+    println!();
+    println!("  Files");
+    println!("  -----");
+    println!("{files_table}");
+    println!();
+    if mismatched_only {
+        println!(
+            "  {} mismatched files ({} total, {} groups, {} duplicates)",
+            agg.total_mismatched, agg.total_photos, agg.total_groups, agg.total_duplicates
+        );
+    } else {
+        println!(
+            "  {} files ({} groups, {} duplicates, {} mismatched)",
+            agg.total_photos, agg.total_groups, agg.total_duplicates, agg.total_mismatched
+        );
+    }
     println!();
 
     Ok(())
@@ -239,10 +673,16 @@ pub(crate) fn add_photo_row(
         .cloned()
         .unwrap_or_else(|| "?".to_string());
 
+    let fmt_cell = if is_format_mismatched(photo) {
+        Cell::new(format!("{} \u{26A0}", photo.format.as_str())).fg(Color::Red)
+    } else {
+        Cell::new(photo.format.as_str())
+    };
+
     let mut row: Vec<Cell> = vec![
         Cell::new(&filename),
         Cell::new(&source_name),
-        Cell::new(photo.format.as_str()),
+        fmt_cell,
         Cell::new(format_size(photo.size)),
     ];
 
@@ -254,16 +694,16 @@ pub(crate) fn add_photo_row(
     }
 
     // Role column
-    let is_sot = data.photo_is_sot.get(&photo.id).copied().unwrap_or(false);
-    let is_grouped = data.grouped_ids.contains(&photo.id);
-
-    if is_grouped && is_sot {
-        row.push(Cell::new("Best Copy").fg(Color::Green));
-    } else if is_grouped {
-        row.push(Cell::new("Duplicate").fg(Color::Yellow));
+    let role = photo_role(photo, data);
+    let label = if data.is_hardlinked_duplicate(photo.id) {
+        format!("{} (linked)", role.label())
     } else {
-        row.push(Cell::new("Unique"));
-    }
+        role.label().to_string()
+    };
+    row.push(match role.color() {
+        Some(color) => Cell::new(label).fg(color),
+        None => Cell::new(label),
+    });
 
     // Vault column
     if data.vault_eligible(photo.id) {
@@ -295,6 +735,258 @@ pub(crate) fn format_size(bytes: u64) -> String {
     }
 }
 
+/// Output mode for `catalog` / `catalog list`: the interactive dashboard, or
+/// a machine-readable dump of the same data for scripting and diffing vault
+/// state across scans.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ReportFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+pub(crate) fn parse_report_format(name: &str) -> Result<ReportFormat> {
+    match name {
+        "table" => Ok(ReportFormat::Table),
+        "json" => Ok(ReportFormat::Json),
+        "csv" => Ok(ReportFormat::Csv),
+        other => Err(anyhow::anyhow!(
+            "unknown output format: {other} (expected table, json, or csv)"
+        )),
+    }
+}
+
+/// One row of the machine-readable files listing.
+#[derive(Debug, PartialEq)]
+pub(crate) struct PhotoReportRow {
+    pub(crate) path: String,
+    pub(crate) source: String,
+    pub(crate) format: String,
+    pub(crate) size: u64,
+    pub(crate) sha256: String,
+    pub(crate) group_id: Option<i64>,
+    pub(crate) role: String,
+    pub(crate) vault_eligible: bool,
+}
+
+/// One row of the machine-readable per-source breakdown.
+#[derive(Debug, PartialEq)]
+pub(crate) struct SourceReportRow {
+    pub(crate) id: i64,
+    pub(crate) name: String,
+    pub(crate) photo_count: usize,
+    pub(crate) total_size: u64,
+    pub(crate) is_reference: bool,
+}
+
+/// Everything the dashboard and the files table show, bundled into one
+/// serializable struct so the JSON/CSV exporters and the terminal renderer
+/// read from a single source of truth instead of duplicating the
+/// aggregation logic.
+#[derive(Debug, PartialEq)]
+pub(crate) struct StatusReport {
+    pub(crate) aggregates: Aggregates,
+    pub(crate) sources: Vec<SourceReportRow>,
+    pub(crate) photos: Vec<PhotoReportRow>,
+}
+
+pub(crate) fn build_report(
+    sources: &[Source],
+    photos: &[PhotoFile],
+    groups: &[DuplicateGroup],
+    mismatched_only: bool,
+) -> StatusReport {
+    let data = StatusData::build(sources, groups);
+    let aggregates = compute_aggregates(photos, groups, &data);
+    let source_stats = compute_source_stats(photos);
+
+    let source_rows = sources
+        .iter()
+        .map(|source| {
+            let stats = source_stats.get(&source.id);
+            SourceReportRow {
+                id: source.id,
+                name: source_display_name(source),
+                photo_count: stats.map(|s| s.photo_count).unwrap_or(0),
+                total_size: stats.map(|s| s.total_size).unwrap_or(0),
+                is_reference: source.role == SourceRole::Reference,
+            }
+        })
+        .collect();
+
+    let source_name_map: HashMap<i64, String> = sources
+        .iter()
+        .map(|s| (s.id, source_display_name(s)))
+        .collect();
+
+    let (grouped_photos, ungrouped_photos) = sort_photos_for_display(photos, &data);
+    let keep = |photo: &PhotoFile| !mismatched_only || is_format_mismatched(photo);
+
+    let photo_rows = grouped_photos
+        .into_iter()
+        .chain(ungrouped_photos)
+        .filter(|p| keep(p))
+        .map(|photo| PhotoReportRow {
+            path: photo.path.display().to_string(),
+            source: source_name_map
+                .get(&photo.source_id)
+                .cloned()
+                .unwrap_or_else(|| "?".to_string()),
+            format: photo.format.as_str().to_string(),
+            size: photo.size,
+            sha256: photo.sha256.clone(),
+            group_id: data.photo_group.get(&photo.id).copied(),
+            role: {
+                let label = photo_role(photo, &data).label();
+                if data.is_hardlinked_duplicate(photo.id) {
+                    format!("{label} (linked)")
+                } else {
+                    label.to_string()
+                }
+            },
+            vault_eligible: data.vault_eligible(photo.id),
+        })
+        .collect();
+
+    StatusReport {
+        aggregates,
+        sources: source_rows,
+        photos: photo_rows,
+    }
+}
+
+fn report_to_json(report: &StatusReport) -> String {
+    let agg = &report.aggregates;
+    let aggregates_json = format!(
+        "{{\"total_photos\":{},\"total_groups\":{},\"total_duplicates\":{},\"total_unique\":{},\
+         \"total_disk\":{},\"savings\":{},\"reclaimable_bytes\":{},\"total_mismatched\":{},\
+         \"duplicates_exact\":{},\"duplicates_very_similar\":{},\"duplicates_similar\":{},\
+         \"duplicates_loose\":{}}}",
+        agg.total_photos,
+        agg.total_groups,
+        agg.total_duplicates,
+        agg.total_unique,
+        agg.total_disk,
+        agg.savings,
+        agg.reclaimable_bytes,
+        agg.total_mismatched,
+        agg.duplicates_exact,
+        agg.duplicates_very_similar,
+        agg.duplicates_similar,
+        agg.duplicates_loose,
+    );
+
+    let sources_json: Vec<String> = report
+        .sources
+        .iter()
+        .map(|s| {
+            format!(
+                "{{\"id\":{},\"name\":{},\"photo_count\":{},\"total_size\":{},\"is_reference\":{}}}",
+                s.id,
+                json_string(&s.name),
+                s.photo_count,
+                s.total_size,
+                s.is_reference,
+            )
+        })
+        .collect();
+
+    let photos_json: Vec<String> = report
+        .photos
+        .iter()
+        .map(|p| {
+            let group_id = p
+                .group_id
+                .map(|g| g.to_string())
+                .unwrap_or_else(|| "null".to_string());
+            format!(
+                "{{\"path\":{},\"source\":{},\"format\":{},\"size\":{},\"sha256\":{},\
+                 \"group_id\":{},\"role\":{},\"vault_eligible\":{}}}",
+                json_string(&p.path),
+                json_string(&p.source),
+                json_string(&p.format),
+                p.size,
+                json_string(&p.sha256),
+                group_id,
+                json_string(&p.role),
+                p.vault_eligible,
+            )
+        })
+        .collect();
+
+    format!(
+        "{{\"aggregates\":{},\"sources\":[{}],\"photos\":[{}]}}",
+        aggregates_json,
+        sources_json.join(","),
+        photos_json.join(","),
+    )
+}
+
+fn report_to_csv(report: &StatusReport) -> String {
+    let mut out = String::new();
+    out.push_str("path,source,format,size,sha256,group_id,role,vault_eligible\n");
+    for p in &report.photos {
+        let group_id = p.group_id.map(|g| g.to_string()).unwrap_or_default();
+        out.push_str(&format!(
+            "{},{},{},{},{},{},{},{}\n",
+            csv_field(&p.path),
+            csv_field(&p.source),
+            csv_field(&p.format),
+            p.size,
+            csv_field(&p.sha256),
+            group_id,
+            csv_field(&p.role),
+            p.vault_eligible,
+        ));
+    }
+    out
+}
+
+/// Minimal JSON string encoding: escape backslash, quote, and control characters.
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Minimal CSV field quoting (RFC 4180): quote and escape only when the field
+/// contains a comma, quote, or newline.
+fn csv_field(s: &str) -> String {
+    if s.contains(',') || s.contains('"') || s.contains('\n') {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn export_report(vault: &Vault, mismatched_only: bool, format: ReportFormat) -> Result<()> {
+    let sources = vault.sources()?;
+    let photos = vault.photos()?;
+    let groups = vault.groups()?;
+
+    let report = build_report(&sources, &photos, &groups, mismatched_only);
+
+    match format {
+        ReportFormat::Json => println!("{}", report_to_json(&report)),
+        ReportFormat::Csv => print!("{}", report_to_csv(&report)),
+        ReportFormat::Table => unreachable!("export_report is only called for json/csv"),
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::PathBuf;
@@ -343,6 +1035,7 @@ mod tests {
             id: 1,
             path: PathBuf::from("/home/user/photos"),
             last_scanned: None,
+            role: photopack_core::SourceRole::Standard,
         };
         assert_eq!(source_display_name(&source), "photos");
     }
@@ -353,6 +1046,7 @@ mod tests {
             id: 1,
             path: PathBuf::from("/mnt/external/camera/2024"),
             last_scanned: None,
+            role: photopack_core::SourceRole::Standard,
         };
         assert_eq!(source_display_name(&source), "2024");
     }
@@ -363,6 +1057,7 @@ mod tests {
             id: 1,
             path: PathBuf::from("/"),
             last_scanned: None,
+            role: photopack_core::SourceRole::Standard,
         };
         // Root has no file_name(), falls back to display()
         assert_eq!(source_display_name(&source), "/");
@@ -380,6 +1075,7 @@ mod tests {
             sha256: format!("sha_{id}"),
             phash: None,
             dhash: None,
+            ahash: None,
             exif: None,
             mtime: 1000 + id,
         }
@@ -397,20 +1093,117 @@ mod tests {
         }
     }
 
+    /// Like `make_group`, but each entry in `member_sources` pairs a photo ID
+    /// with the source it came from, so reference-source membership can vary
+    /// within a single group.
+    fn make_group_with_sources(id: i64, sot_id: i64, member_sources: &[(i64, i64)]) -> DuplicateGroup {
+        DuplicateGroup {
+            id,
+            source_of_truth_id: sot_id,
+            confidence: Confidence::Certain,
+            members: member_sources
+                .iter()
+                .map(|&(mid, source_id)| {
+                    make_photo(mid, source_id, &format!("/photos/{mid}.jpg"), 1000)
+                })
+                .collect(),
+        }
+    }
+
+    fn make_source(id: i64, role: photopack_core::SourceRole) -> Source {
+        Source {
+            id,
+            path: PathBuf::from(format!("/sources/{id}")),
+            last_scanned: None,
+            role,
+        }
+    }
+
+    fn make_photo_with_phash(id: i64, phash: u64) -> PhotoFile {
+        PhotoFile {
+            phash: Some(phash),
+            ..make_photo(id, 1, &format!("/photos/{id}.jpg"), 1000)
+        }
+    }
+
+    fn make_group_from_photos(id: i64, sot_id: i64, members: Vec<PhotoFile>) -> DuplicateGroup {
+        DuplicateGroup {
+            id,
+            source_of_truth_id: sot_id,
+            confidence: Confidence::Certain,
+            members,
+        }
+    }
+
+    // ── tier_from_distance / similarity_tier_for_member ──────────────
+
+    #[test]
+    fn test_tier_from_distance_zero_is_exact() {
+        assert_eq!(tier_from_distance(0, 64), SimilarityTier::Exact);
+    }
+
+    #[test]
+    fn test_tier_from_distance_64bit_buckets() {
+        // Row for 64-bit hashes: [6, 20, 40, 40, 40, 40] — columns 2-5 share
+        // the same threshold, so "similar" covers 21-40 and only a distance
+        // past 40 falls through to "loose".
+        assert_eq!(tier_from_distance(6, 64), SimilarityTier::VerySimilar);
+        assert_eq!(tier_from_distance(20, 64), SimilarityTier::VerySimilar);
+        assert_eq!(tier_from_distance(25, 64), SimilarityTier::Similar);
+        assert_eq!(tier_from_distance(100, 64), SimilarityTier::Loose);
+    }
+
+    #[test]
+    fn test_tier_from_distance_8bit_buckets() {
+        // Row for 8-bit hashes: [1, 2, 5, 7, 14, 20]
+        assert_eq!(tier_from_distance(1, 8), SimilarityTier::VerySimilar);
+        assert_eq!(tier_from_distance(5, 8), SimilarityTier::Similar);
+        assert_eq!(tier_from_distance(14, 8), SimilarityTier::Loose);
+    }
+
+    #[test]
+    fn test_similarity_tier_for_member_same_sha256_is_exact() {
+        let sot = make_photo(10, 1, "/a.jpg", 1000);
+        let member = PhotoFile {
+            sha256: sot.sha256.clone(),
+            ..make_photo(11, 1, "/b.jpg", 1000)
+        };
+        assert_eq!(similarity_tier_for_member(&member, &sot), Some(SimilarityTier::Exact));
+    }
+
+    #[test]
+    fn test_similarity_tier_for_member_uses_phash_distance() {
+        let sot = make_photo_with_phash(10, 0b0000_0000);
+        let member = make_photo_with_phash(11, 0b0010_0000); // distance 1
+        assert_eq!(
+            similarity_tier_for_member(&member, &sot),
+            Some(SimilarityTier::VerySimilar)
+        );
+    }
+
+    #[test]
+    fn test_similarity_tier_for_member_no_shared_hash_is_none() {
+        let sot = make_photo(10, 1, "/a.jpg", 1000); // no phash/dhash/ahash, distinct sha256
+        let member = make_photo(11, 1, "/b.jpg", 1000);
+        assert_eq!(similarity_tier_for_member(&member, &sot), None);
+    }
+
     // ── StatusData ──────────────────────────────────────────────────
 
     #[test]
     fn test_status_data_empty_groups() {
-        let data = StatusData::build(&[]);
+        let data = StatusData::build(&[], &[]);
         assert!(data.photo_group.is_empty());
         assert!(data.photo_is_sot.is_empty());
+        assert!(data.photo_is_reference.is_empty());
+        assert!(data.photo_similarity_tier.is_empty());
         assert!(data.grouped_ids.is_empty());
     }
 
     #[test]
     fn test_status_data_single_group() {
         let groups = vec![make_group(1, 10, &[10, 11, 12])];
-        let data = StatusData::build(&groups);
+        let data = StatusData::build(&[], &groups);
 
         assert_eq!(data.photo_group.get(&10), Some(&1));
         assert_eq!(data.photo_group.get(&11), Some(&1));
@@ -427,7 +1220,7 @@ mod tests {
             make_group(1, 10, &[10, 11]),
             make_group(2, 20, &[20, 21]),
         ];
-        let data = StatusData::build(&groups);
+        let data = StatusData::build(&[], &groups);
 
         assert_eq!(data.photo_group.get(&10), Some(&1));
         assert_eq!(data.photo_group.get(&20), Some(&2));
@@ -439,7 +1232,7 @@ mod tests {
     #[test]
     fn test_is_duplicate_sot_is_not_duplicate() {
         let groups = vec![make_group(1, 10, &[10, 11])];
-        let data = StatusData::build(&groups);
+        let data = StatusData::build(&[], &groups);
 
         assert!(!data.is_duplicate(10)); // SOT
         assert!(data.is_duplicate(11));  // duplicate
@@ -447,7 +1240,7 @@ mod tests {
 
     #[test]
     fn test_is_duplicate_ungrouped_is_not_duplicate() {
-        let data = StatusData::build(&[]);
+        let data = StatusData::build(&[], &[]);
         assert!(!data.is_duplicate(99));
     }
 
@@ -456,7 +1249,7 @@ mod tests {
     #[test]
     fn test_vault_eligible_sot_is_eligible() {
         let groups = vec![make_group(1, 10, &[10, 11])];
-        let data = StatusData::build(&groups);
+        let data = StatusData::build(&[], &groups);
 
         assert!(data.vault_eligible(10));  // SOT → eligible
     }
@@ -464,22 +1257,110 @@ mod tests {
     #[test]
     fn test_vault_eligible_duplicate_not_eligible() {
         let groups = vec![make_group(1, 10, &[10, 11])];
-        let data = StatusData::build(&groups);
+        let data = StatusData::build(&[], &groups);
 
         assert!(!data.vault_eligible(11)); // duplicate → not eligible
     }
 
     #[test]
     fn test_vault_eligible_ungrouped_is_eligible() {
-        let data = StatusData::build(&[]);
+        let data = StatusData::build(&[], &[]);
         assert!(data.vault_eligible(99)); // ungrouped → eligible
     }
 
+    // ── reference sources ────────────────────────────────────────────
+
+    #[test]
+    fn test_reference_source_member_is_never_duplicate() {
+        let sources = vec![
+            make_source(1, photopack_core::SourceRole::Standard),
+            make_source(2, photopack_core::SourceRole::Reference),
+        ];
+        // Group elected 10 (source 1) as SOT, but 11 lives in the reference
+        // source — it must stay protected regardless of that election.
+        let groups = vec![make_group_with_sources(1, 10, &[(10, 1), (11, 2)])];
+        let data = StatusData::build(&sources, &groups);
+
+        assert!(!data.is_duplicate(11));
+        assert!(data.vault_eligible(11));
+        assert!(data.is_reference(11));
+    }
+
+    #[test]
+    fn test_reference_source_member_becomes_effective_sot() {
+        let sources = vec![
+            make_source(1, photopack_core::SourceRole::Standard),
+            make_source(2, photopack_core::SourceRole::Reference),
+        ];
+        // Stored source_of_truth_id still points at 10, but the reference
+        // member (11) must win display-level SOT status.
+        let groups = vec![make_group_with_sources(1, 10, &[(10, 1), (11, 2)])];
+        let data = StatusData::build(&sources, &groups);
+
+        assert_eq!(data.photo_is_sot.get(&10), Some(&false));
+        assert_eq!(data.photo_is_sot.get(&11), Some(&true));
+        assert!(data.is_duplicate(10)); // the non-reference member is now the duplicate
+    }
+
+    #[test]
+    fn test_non_reference_group_keeps_stored_sot() {
+        let sources = vec![make_source(1, photopack_core::SourceRole::Standard)];
+        let groups = vec![make_group(1, 10, &[10, 11])];
+        let data = StatusData::build(&sources, &groups);
+
+        assert_eq!(data.photo_is_sot.get(&10), Some(&true));
+        assert!(!data.is_reference(10));
+        assert!(!data.is_reference(11));
+    }
+
+    // ── similarity tier breakdown ────────────────────────────────────
+
+    #[test]
+    fn test_status_data_populates_similarity_tier_for_non_sot_members() {
+        let groups = vec![make_group_from_photos(
+            1,
+            10,
+            vec![
+                make_photo_with_phash(10, 0b0000_0000), // SOT
+                make_photo_with_phash(11, 0b0010_0000),  // distance 1 → very similar
+            ],
+        )];
+        let data = StatusData::build(&[], &groups);
+
+        assert_eq!(data.photo_similarity_tier.get(&10), None); // SOT isn't tiered against itself
+        assert_eq!(
+            data.photo_similarity_tier.get(&11),
+            Some(&SimilarityTier::VerySimilar)
+        );
+    }
+
+    #[test]
+    fn test_aggregates_tier_breakdown() {
+        let groups = vec![make_group_from_photos(
+            1,
+            10,
+            vec![
+                make_photo_with_phash(10, 0),                 // SOT
+                make_photo_with_phash(11, 0b0010_0000),         // distance 1 → very similar
+                make_photo_with_phash(12, (1u64 << 25) - 1),    // distance 25 → similar
+            ],
+        )];
+        let photos = groups[0].members.clone();
+        let data = StatusData::build(&[], &groups);
+        let agg = compute_aggregates(&photos, &groups, &data);
+
+        assert_eq!(agg.total_duplicates, 2);
+        assert_eq!(agg.duplicates_very_similar, 1);
+        assert_eq!(agg.duplicates_similar, 1);
+        assert_eq!(agg.duplicates_exact, 0);
+        assert_eq!(agg.duplicates_loose, 0);
+    }
+
     // ── compute_aggregates ──────────────────────────────────────────
 
     #[test]
     fn test_aggregates_empty() {
-        let data = StatusData::build(&[]);
+        let data = StatusData::build(&[], &[]);
         let agg = compute_aggregates(&[], &[], &data);
 
         assert_eq!(agg, Aggregates {
@@ -489,6 +1370,12 @@ mod tests {
             total_unique: 0,
             total_disk: 0,
             savings: 0,
+            reclaimable_bytes: 0,
+            total_mismatched: 0,
+            duplicates_exact: 0,
+            duplicates_very_similar: 0,
+            duplicates_similar: 0,
+            duplicates_loose: 0,
         });
     }
 
@@ -499,7 +1386,7 @@ mod tests {
             make_photo(2, 1, "/b.jpg", 2000),
             make_photo(3, 1, "/c.jpg", 3000),
         ];
-        let data = StatusData::build(&[]);
+        let data = StatusData::build(&[], &[]);
         let agg = compute_aggregates(&photos, &[], &data);
 
         assert_eq!(agg, Aggregates {
@@ -509,6 +1396,12 @@ mod tests {
             total_unique: 3,
             total_disk: 6000,
             savings: 0,
+            reclaimable_bytes: 0,
+            total_mismatched: 0,
+            duplicates_exact: 0,
+            duplicates_very_similar: 0,
+            duplicates_similar: 0,
+            duplicates_loose: 0,
         });
     }
 
@@ -521,7 +1414,7 @@ mod tests {
             make_photo(20, 1, "/d.jpg", 2000),  // unique
         ];
         let groups = vec![make_group(1, 10, &[10, 11, 12])];
-        let data = StatusData::build(&groups);
+        let data = StatusData::build(&[], &groups);
         let agg = compute_aggregates(&photos, &groups, &data);
 
         assert_eq!(agg, Aggregates {
@@ -531,6 +1424,14 @@ mod tests {
             total_unique: 2, // SOT(10) + unique(20)
             total_disk: 14000,
             savings: 7000, // 3000 + 4000 (duplicate sizes)
+            // Counted from the group's own members, which `make_group` builds
+            // at 1000 bytes each; fake paths stat as non-hardlinked.
+            reclaimable_bytes: 2000,
+            total_mismatched: 0,
+            duplicates_exact: 2, // make_photo gives every member a distinct sha256 and no hash
+            duplicates_very_similar: 0,
+            duplicates_similar: 0,
+            duplicates_loose: 0,
         });
     }
 
@@ -546,7 +1447,7 @@ mod tests {
             make_group(1, 10, &[10, 11]),
             make_group(2, 20, &[20, 21]),
         ];
-        let data = StatusData::build(&groups);
+        let data = StatusData::build(&[], &groups);
         let agg = compute_aggregates(&photos, &groups, &data);
 
         assert_eq!(agg, Aggregates {
@@ -556,6 +1457,12 @@ mod tests {
             total_unique: 2,
             total_disk: 6000,
             savings: 3000, // 1000 + 2000
+            reclaimable_bytes: 2000, // from `make_group`'s 1000-byte members
+            total_mismatched: 0,
+            duplicates_exact: 2,
+            duplicates_very_similar: 0,
+            duplicates_similar: 0,
+            duplicates_loose: 0,
         });
     }
 
@@ -567,7 +1474,7 @@ mod tests {
             make_photo(11, 1, "/b.jpg", 5000),
         ];
         let groups = vec![make_group(1, 10, &[10, 11])];
-        let data = StatusData::build(&groups);
+        let data = StatusData::build(&[], &groups);
         let agg = compute_aggregates(&photos, &groups, &data);
 
         assert_eq!(agg.total_duplicates, 1);
@@ -575,6 +1482,69 @@ mod tests {
         assert_eq!(agg.savings, 5000);
     }
 
+    #[test]
+    fn test_reclaimable_bytes_falls_back_to_savings_without_inode_info() {
+        // Fake paths never stat successfully, so every duplicate is treated
+        // as not hardlinked and counted at full size, same as `savings`.
+        let photos = vec![
+            make_photo(10, 1, "/a.jpg", 5000),
+            make_photo(11, 1, "/b.jpg", 3000),
+        ];
+        let groups = vec![make_group_from_photos(1, 10, photos.clone())];
+        let data = StatusData::build(&[], &groups);
+        let agg = compute_aggregates(&photos, &groups, &data);
+
+        assert_eq!(agg.savings, 3000);
+        assert_eq!(agg.reclaimable_bytes, 3000);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_reclaimable_bytes_collapses_hardlinked_duplicate() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let sot_path = dir.join("sot.jpg");
+        let linked_path = dir.join("linked.jpg");
+        let independent_path = dir.join("independent.jpg");
+        std::fs::write(&sot_path, b"same bytes").unwrap();
+        std::fs::hard_link(&sot_path, &linked_path).unwrap();
+        std::fs::write(&independent_path, b"same bytes").unwrap();
+
+        let photos = vec![
+            make_photo(10, 1, sot_path.to_str().unwrap(), 10),
+            make_photo(11, 1, linked_path.to_str().unwrap(), 10),
+            make_photo(12, 1, independent_path.to_str().unwrap(), 10),
+        ];
+        let groups = vec![make_group_from_photos(1, 10, photos.clone())];
+        let data = StatusData::build(&[], &groups);
+        let agg = compute_aggregates(&photos, &groups, &data);
+
+        assert_eq!(agg.savings, 20); // logical: both duplicates counted
+        assert_eq!(agg.reclaimable_bytes, 10); // the hardlink frees nothing
+        assert!(data.is_hardlinked_duplicate(11));
+        assert!(!data.is_hardlinked_duplicate(12));
+    }
+
+    #[test]
+    fn test_aggregates_savings_excludes_reference_source_duplicate() {
+        let sources = vec![
+            make_source(1, photopack_core::SourceRole::Standard),
+            make_source(2, photopack_core::SourceRole::Reference),
+        ];
+        let photos = vec![
+            make_photo(10, 1, "/a.jpg", 5000), // elected SOT by the scanner
+            make_photo(11, 2, "/b.jpg", 3000), // protected: lives in the reference source
+        ];
+        let groups = vec![make_group_with_sources(1, 10, &[(10, 1), (11, 2)])];
+        let data = StatusData::build(&sources, &groups);
+        let agg = compute_aggregates(&photos, &groups, &data);
+
+        // The reference member (11) becomes the effective SOT and is never
+        // duplicate/removable; photo 10 is the one left contributing savings.
+        assert_eq!(agg.total_duplicates, 1);
+        assert_eq!(agg.savings, 5000);
+    }
+
     // ── compute_source_stats ────────────────────────────────────────
 
     #[test]
@@ -621,7 +1591,7 @@ mod tests {
             make_photo(2, 1, "/a.jpg", 200),
             make_photo(3, 1, "/m.jpg", 300),
         ];
-        let data = StatusData::build(&[]);
+        let data = StatusData::build(&[], &[]);
         let (grouped, ungrouped) = sort_photos_for_display(&photos, &data);
 
         assert!(grouped.is_empty());
@@ -639,7 +1609,7 @@ mod tests {
             make_photo(10, 1, "/sot.jpg", 200),
         ];
         let groups = vec![make_group(1, 10, &[10, 11])];
-        let data = StatusData::build(&groups);
+        let data = StatusData::build(&[], &groups);
         let (grouped, ungrouped) = sort_photos_for_display(&photos, &data);
 
         assert!(ungrouped.is_empty());
@@ -661,7 +1631,7 @@ mod tests {
             make_group(1, 10, &[10, 11]),
             make_group(2, 20, &[20, 21]),
         ];
-        let data = StatusData::build(&groups);
+        let data = StatusData::build(&[], &groups);
         let (grouped, _) = sort_photos_for_display(&photos, &data);
 
         // Group 1 first, then group 2; SOT first in each
@@ -679,7 +1649,7 @@ mod tests {
             make_photo(10, 1, "/sot.jpg", 100),
         ];
         let groups = vec![make_group(1, 10, &[10, 11])];
-        let data = StatusData::build(&groups);
+        let data = StatusData::build(&[], &groups);
         let (grouped, ungrouped) = sort_photos_for_display(&photos, &data);
 
         assert_eq!(grouped.len(), 2);
@@ -688,4 +1658,97 @@ mod tests {
         assert_eq!(grouped[1].id, 11);
         assert_eq!(ungrouped[0].id, 30);
     }
+
+    #[test]
+    fn test_parse_report_format_valid() {
+        assert_eq!(parse_report_format("table").unwrap(), ReportFormat::Table);
+        assert_eq!(parse_report_format("json").unwrap(), ReportFormat::Json);
+        assert_eq!(parse_report_format("csv").unwrap(), ReportFormat::Csv);
+    }
+
+    #[test]
+    fn test_parse_report_format_invalid() {
+        assert!(parse_report_format("xml").is_err());
+    }
+
+    #[test]
+    fn test_json_string_escapes_special_characters() {
+        assert_eq!(json_string("plain"), "\"plain\"");
+        assert_eq!(json_string("a\"b"), "\"a\\\"b\"");
+        assert_eq!(json_string("a\\b"), "\"a\\\\b\"");
+        assert_eq!(json_string("a\nb"), "\"a\\nb\"");
+    }
+
+    #[test]
+    fn test_csv_field_quotes_only_when_needed() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("a\"b"), "\"a\"\"b\"");
+    }
+
+    #[test]
+    fn test_build_report_counts_match_aggregates() {
+        let sources = vec![make_source(1, photopack_core::SourceRole::Standard)];
+        let photos = vec![
+            make_photo(10, 1, "/sot.jpg", 1000),
+            make_photo(11, 1, "/dup.jpg", 1000),
+            make_photo(30, 1, "/unique.jpg", 500),
+        ];
+        let groups = vec![make_group(1, 10, &[10, 11])];
+
+        let report = build_report(&sources, &photos, &groups, false);
+
+        assert_eq!(report.photos.len(), 3);
+        assert_eq!(report.sources.len(), 1);
+        assert_eq!(report.aggregates.total_photos, 3);
+        assert_eq!(report.aggregates.total_duplicates, 1);
+
+        let dup_row = report.photos.iter().find(|p| p.path == "/dup.jpg").unwrap();
+        assert_eq!(dup_row.group_id, Some(1));
+        assert!(!dup_row.vault_eligible);
+
+        let unique_row = report.photos.iter().find(|p| p.path == "/unique.jpg").unwrap();
+        assert_eq!(unique_row.group_id, None);
+        assert!(unique_row.vault_eligible);
+    }
+
+    #[test]
+    fn test_build_report_mismatched_only_filters_photos() {
+        let sources = vec![make_source(1, photopack_core::SourceRole::Standard)];
+        let photos = vec![make_photo(1, 1, "/a.jpg", 1000)];
+        let report = build_report(&sources, &photos, &[], true);
+
+        // A plain .jpg with Jpeg-format contents is never mismatched, so the
+        // filtered report has no photo rows.
+        assert!(report.photos.is_empty());
+    }
+
+    #[test]
+    fn test_report_to_json_contains_expected_fields() {
+        let sources = vec![make_source(1, photopack_core::SourceRole::Standard)];
+        let photos = vec![make_photo(1, 1, "/a.jpg", 1000)];
+        let report = build_report(&sources, &photos, &[], false);
+
+        let json = report_to_json(&report);
+        assert!(json.contains("\"total_photos\":1"));
+        assert!(json.contains("\"path\":\"/a.jpg\""));
+        assert!(json.contains("\"role\":\"Unique\""));
+    }
+
+    #[test]
+    fn test_report_to_csv_has_header_and_row() {
+        let sources = vec![make_source(1, photopack_core::SourceRole::Standard)];
+        let photos = vec![make_photo(1, 1, "/a.jpg", 1000)];
+        let report = build_report(&sources, &photos, &[], false);
+
+        let csv = report_to_csv(&report);
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next().unwrap(),
+            "path,source,format,size,sha256,group_id,role,vault_eligible"
+        );
+        let row = lines.next().unwrap();
+        assert!(row.starts_with("/a.jpg,"));
+        assert!(row.contains("Unique"));
+    }
 }
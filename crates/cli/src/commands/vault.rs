@@ -2,7 +2,9 @@ use std::path::PathBuf;
 
 use anyhow::Result;
 use indicatif::{ProgressBar, ProgressStyle};
-use losslessvault_core::{export::ExportProgress, vault_save::VaultSaveProgress, Vault};
+use losslessvault_core::{
+    export::ExportProgress, prune::PrunePolicy, restore::RestoreProgress, vault_save::VaultSaveProgress, Vault,
+};
 
 pub fn set(vault: &Vault, path: PathBuf) -> Result<()> {
     vault.set_vault_path(&path)?;
@@ -19,7 +21,7 @@ pub fn show(vault: &Vault) -> Result<()> {
     Ok(())
 }
 
-pub fn save(vault: &mut Vault) -> Result<()> {
+pub fn sync(vault: &mut Vault, link: bool) -> Result<()> {
     let pb = ProgressBar::new(0);
     pb.set_style(
         ProgressStyle::with_template("{spinner:.green} [{bar:40.cyan/blue}] {pos}/{len} {msg}")
@@ -27,7 +29,7 @@ pub fn save(vault: &mut Vault) -> Result<()> {
             .progress_chars("=>-"),
     );
 
-    vault.vault_save(Some(&mut |progress| match progress {
+    let mut on_progress = |progress: VaultSaveProgress| match progress {
         VaultSaveProgress::Start { total } => {
             pb.set_length(total as u64);
             pb.set_position(0);
@@ -40,15 +42,150 @@ pub fn save(vault: &mut Vault) -> Result<()> {
         VaultSaveProgress::Skipped { .. } => {
             pb.inc(1);
         }
-        VaultSaveProgress::Complete { copied, skipped } => {
-            pb.finish_with_message(format!("{copied} copied, {skipped} skipped"));
+        VaultSaveProgress::Deduplicated { path, .. } => {
+            pb.inc(1);
+            pb.set_message(format!("-> {} (deduplicated)", path.display()));
+        }
+        VaultSaveProgress::Moved { from, to } => {
+            pb.set_message(format!("moved: {} -> {}", from.display(), to.display()));
+        }
+        VaultSaveProgress::Removed { path } => {
+            pb.set_message(format!("removed superseded: {}", path.display()));
+        }
+        VaultSaveProgress::Linked { target, canonical } => {
+            pb.set_message(format!(
+                "linked superseded: {} -> {}",
+                target.display(),
+                canonical.display()
+            ));
+        }
+        VaultSaveProgress::Rejected { path, reason } => {
+            pb.set_message(format!("skipped {}: {reason}", path.display()));
+        }
+        VaultSaveProgress::Complete {
+            copied,
+            skipped,
+            deduplicated,
+            bytes_saved,
+            removed,
+        } => {
+            let mut msg = format!("{copied} copied, {skipped} skipped");
+            if deduplicated > 0 {
+                msg.push_str(&format!(
+                    ", {deduplicated} deduplicated ({:.1} MB saved)",
+                    bytes_saved as f64 / 1_048_576.0
+                ));
+            }
+            if removed > 0 {
+                let verb = if link { "linked" } else { "removed" };
+                msg.push_str(&format!(", {removed} superseded {verb}"));
+            }
+            pb.finish_with_message(msg);
         }
-    }))?;
+    };
+
+    if link {
+        vault.vault_save_linked(Some(&mut on_progress))?;
+    } else {
+        vault.vault_save(Some(&mut on_progress))?;
+    }
 
     println!("Vault save complete.");
     Ok(())
 }
 
+pub fn restore(vault: &Vault, dest: PathBuf) -> Result<()> {
+    let pb = ProgressBar::new(0);
+    pb.set_style(
+        ProgressStyle::with_template("{spinner:.green} [{bar:40.cyan/blue}] {pos}/{len} {msg}")
+            .unwrap()
+            .progress_chars("=>-"),
+    );
+
+    let report = vault.vault_restore(
+        &dest,
+        Some(&mut |progress| match progress {
+            RestoreProgress::Start { total } => {
+                pb.set_length(total as u64);
+                pb.set_position(0);
+                pb.set_message("Restoring photos from vault...");
+            }
+            RestoreProgress::Restored { target, .. } => {
+                pb.inc(1);
+                pb.set_message(format!("-> {}", target.display()));
+            }
+            RestoreProgress::Corrupt { hash } => {
+                pb.inc(1);
+                pb.set_message(format!("CORRUPT: {hash}"));
+            }
+            RestoreProgress::Complete { restored, corrupt } => {
+                pb.finish_with_message(format!("{restored} restored, {corrupt} corrupt"));
+            }
+        }),
+    )?;
+
+    if !report.corrupt.is_empty() {
+        println!("\nCorrupt objects (not restored — recorded digest no longer matches content):");
+        for hash in &report.corrupt {
+            println!("  {hash}");
+        }
+    }
+
+    println!(
+        "\nRestore complete: {} restored, {} already present.",
+        report.restored.len(),
+        report.skipped.len()
+    );
+    Ok(())
+}
+
+pub fn gc(vault: &Vault) -> Result<()> {
+    let removed = vault.vault_gc()?;
+    if removed.is_empty() {
+        println!("No orphaned objects found.");
+    } else {
+        for path in &removed {
+            println!("removed: {}", path.display());
+        }
+        println!("\n{} orphaned object(s) removed.", removed.len());
+    }
+    Ok(())
+}
+
+pub fn prune(vault: &Vault, policy: &PrunePolicy, dry_run: bool) -> Result<()> {
+    let plan = vault.plan_prune(policy)?;
+    let to_remove: Vec<_> = plan.iter().filter(|e| !e.keep).collect();
+
+    if dry_run {
+        if to_remove.is_empty() {
+            println!("Nothing to prune.");
+        } else {
+            for entry in &to_remove {
+                println!("would remove: {}", entry.path.display());
+            }
+            println!("\n{} file(s) would be removed (dry run).", to_remove.len());
+        }
+        return Ok(());
+    }
+
+    if to_remove.is_empty() {
+        println!("Nothing to prune.");
+        return Ok(());
+    }
+
+    let removed = vault.apply_prune(
+        &plan,
+        Some(&mut |progress| {
+            if let VaultSaveProgress::Removed { path } = progress {
+                println!("removed: {}", path.display());
+            }
+        }),
+    )?;
+
+    println!("\n{removed} file(s) removed.");
+    Ok(())
+}
+
 pub fn export_set(vault: &Vault, path: PathBuf) -> Result<()> {
     vault.set_export_path(&path)?;
     let resolved = vault.get_export_path()?.unwrap();
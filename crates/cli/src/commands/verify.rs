@@ -0,0 +1,61 @@
+use anyhow::Result;
+use indicatif::{ProgressBar, ProgressStyle};
+use losslessvault_core::{verify::VerifyProgress, Vault};
+
+pub fn run(vault: &Vault) -> Result<()> {
+    let pb = ProgressBar::new(0);
+    pb.set_style(
+        ProgressStyle::with_template("{spinner:.green} [{bar:40.cyan/blue}] {pos}/{len} {msg}")
+            .unwrap()
+            .progress_chars("=>-"),
+    );
+
+    let report = vault.verify(Some(&mut |progress| match progress {
+        VerifyProgress::Start { total } => {
+            pb.set_length(total as u64);
+            pb.set_position(0);
+            pb.set_message("Verifying vault integrity...");
+        }
+        VerifyProgress::Checked { .. } => {
+            pb.inc(1);
+        }
+        VerifyProgress::Corrupt { path, .. } => {
+            pb.inc(1);
+            pb.set_message(format!("CORRUPT: {}", path.display()));
+        }
+        VerifyProgress::Complete {
+            ok,
+            corrupt,
+            missing,
+        } => {
+            pb.finish_with_message(format!("{ok} ok, {corrupt} corrupt, {missing} missing"));
+        }
+    }))?;
+
+    if !report.corrupt.is_empty() {
+        println!("\nCorrupt objects (recorded digest no longer matches content):");
+        for (path, expected, actual) in &report.corrupt {
+            println!("  {} (expected {expected}, found {actual})", path.display());
+        }
+    }
+
+    if !report.missing.is_empty() {
+        println!("\nMissing objects (in manifest but not on disk):");
+        for path in &report.missing {
+            println!("  {}", path.display());
+        }
+    }
+
+    if !report.orphaned.is_empty() {
+        println!("\nOrphaned objects (on disk but not in manifest):");
+        for path in &report.orphaned {
+            println!("  {}", path.display());
+        }
+    }
+
+    if report.corrupt.is_empty() && report.missing.is_empty() && report.orphaned.is_empty() {
+        println!("\nVault verified: {} object(s) intact.", report.ok);
+    }
+
+    Ok(())
+}
@@ -29,32 +29,178 @@ enum Commands {
     Catalog {
         #[command(subcommand)]
         action: Option<CatalogAction>,
+
+        /// Output format: table, json, or csv
+        #[arg(long, default_value = "table")]
+        format: String,
     },
     /// Manage the vault: a permanent lossless archive of your best photos
     Vault {
         #[command(subcommand)]
         action: VaultAction,
     },
-    /// Export optimized HEIC photos from your catalog (macOS)
+    /// Export optimized photos from your catalog (macOS)
     Export {
         #[command(subcommand)]
         action: Option<ExportAction>,
 
-        /// HEIC quality (0-100, default: 85)
-        #[arg(long, default_value_t = 85)]
-        quality: u8,
+        /// Target codec: heic, avif, webp, or jpegxl (default: heic)
+        #[arg(long, default_value = "heic")]
+        format: String,
+
+        /// Encoder quality (0-100, defaults to the format's recommended value)
+        #[arg(long)]
+        quality: Option<u8>,
+    },
+    /// Report clusters of near-duplicate photos already saved to the vault
+    Dedupe {
+        /// Hamming distance threshold for the pHash comparison (default: 10)
+        #[arg(long)]
+        threshold: Option<u32>,
+    },
+    /// Show vault storage and duplication stats: totals, dedup savings, and a
+    /// breakdown by extension
+    Stats {
+        /// Hamming distance threshold for near-duplicate clustering (default: 10)
+        #[arg(long)]
+        threshold: Option<u32>,
+
+        /// Print the report as machine-readable JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Re-check every vault object against its recorded digest (bitrot scan)
+    Verify,
+    /// Pack the vault into a single compressed archive file, or restore one
+    Archive {
+        #[command(subcommand)]
+        action: ArchiveAction,
+    },
+    /// Resolve duplicate groups by deleting, moving, or linking away the
+    /// non-canonical members (the source of truth is always left untouched)
+    Resolve {
+        /// Group ID (omit to resolve every group)
+        id: Option<i64>,
+
+        /// What to do with non-canonical members: delete, move, hardlink, or symlink
+        #[arg(long, default_value = "delete")]
+        action: String,
+
+        /// Destination root for `--action move` (layout preserved under it)
+        #[arg(long)]
+        move_to: Option<PathBuf>,
+
+        /// Preview the effect without touching disk or the catalog
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ArchiveAction {
+    /// Bundle the vault into a single compressed archive file
+    Pack {
+        /// Path to write the archive to
+        path: PathBuf,
+    },
+    /// Restore a vault archive into a destination directory
+    Unpack {
+        /// Path to the archive file
+        archive: PathBuf,
+        /// Directory to extract into
+        dest: PathBuf,
     },
 }
 
 #[derive(Subcommand)]
 enum SourcesAction {
-    /// Register a directory as a photo source
+    /// Register a directory as a photo source, or a .zip/.tar/.tar.gz
+    /// archive to extract and register in one step
     Add {
-        /// Path to the photo directory
+        /// Path to the photo directory, or a .zip/.tar/.tar.gz archive
         path: PathBuf,
+
+        /// Mark this as a reference (curated archive) source: its photos
+        /// always win source-of-truth over standard sources
+        #[arg(long)]
+        reference: bool,
     },
     /// Scan all sources for photos and find duplicates
-    Scan,
+    Scan {
+        /// Perceptual-hash similarity tolerance (Hamming distance) for the
+        /// pure-phash matching phase; persists for future scans (default: 3)
+        #[arg(long)]
+        threshold: Option<u32>,
+
+        /// Perceptual-hash similarity tolerance as a preset instead of a raw
+        /// Hamming distance: minimal, low, medium, high, very-high, or
+        /// maximum; persists for future scans. Ignored if `--threshold` is
+        /// also given.
+        #[arg(long)]
+        similarity: Option<String>,
+
+        /// Primary hash algorithm: mean, gradient, double-gradient, blockhash,
+        /// or dct; persists and forces a one-time recompute (default: mean)
+        #[arg(long)]
+        hash_alg: Option<String>,
+
+        /// Downscale filter used before hashing: nearest, triangle, or
+        /// lanczos3; persists and forces a one-time recompute (default: lanczos3)
+        #[arg(long)]
+        resize_filter: Option<String>,
+
+        /// Hamming-distance threshold at/under which a perceptual hash
+        /// comparison (phash, dhash, and ahash alike) earns NearCertain
+        /// confidence; persists, overriding the hash algorithm's default
+        #[arg(long)]
+        near_certain: Option<u32>,
+
+        /// Hamming-distance threshold at/under which a perceptual hash
+        /// comparison earns High confidence; persists, overriding the hash
+        /// algorithm's default
+        #[arg(long)]
+        high: Option<u32>,
+
+        /// Require exactly this many of the available perceptual hashes
+        /// (phash, dhash, ahash) to agree before grouping a pair, overriding
+        /// the default N-of-M consensus rule; persists for future scans
+        #[arg(long)]
+        required_votes: Option<u32>,
+
+        /// Ignore every cached fingerprint and perceptual hash, recomputing
+        /// everything from scratch for this scan
+        #[arg(long)]
+        rebuild_cache: bool,
+
+        /// Skip images narrower or shorter than this many pixels; persists
+        /// for future scans
+        #[arg(long)]
+        min_resolution: Option<u32>,
+
+        /// Skip files smaller than this many bytes; persists for future scans
+        #[arg(long)]
+        min_file_size: Option<u64>,
+
+        /// Skip paths matching this `*`-glob (repeatable); persists for
+        /// future scans
+        #[arg(long = "exclude")]
+        exclude_patterns: Vec<String>,
+
+        /// Only scan files with this extension, no leading dot (repeatable);
+        /// persists for future scans
+        #[arg(long = "only-extension")]
+        allowed_extensions: Vec<String>,
+
+        /// Skip files with this extension, no leading dot (repeatable);
+        /// persists for future scans
+        #[arg(long = "exclude-extension")]
+        blocked_extensions: Vec<String>,
+
+        /// Cap the number of hashing threads (default: all cores); persists
+        /// for future scans. Useful for predictable CI behavior.
+        #[arg(long)]
+        jobs: Option<usize>,
+    },
     /// Unregister a source and remove its photos from the catalog
     Rm {
         /// Path to the source directory
@@ -65,11 +211,25 @@ enum SourcesAction {
 #[derive(Subcommand)]
 enum CatalogAction {
     /// Show the full files table with roles and vault eligibility
-    List,
+    List {
+        /// Only show files whose declared extension doesn't match their
+        /// sniffed format
+        #[arg(long)]
+        mismatched: bool,
+    },
     /// List all duplicate groups, or show details of a specific group
     Duplicates {
         /// Group ID (omit to list all)
         id: Option<i64>,
+
+        /// Only show perceptual-similarity groups (skip exact SHA-256 matches)
+        #[arg(long)]
+        similar: bool,
+    },
+    /// Look up catalog photos similar to an image outside the catalog
+    Find {
+        /// Path to the image to look up (does not need to be in a registered source)
+        path: PathBuf,
     },
 }
 
@@ -81,7 +241,50 @@ enum VaultAction {
         path: PathBuf,
     },
     /// Sync deduplicated best-quality photos to the vault (byte-for-byte copies)
-    Sync,
+    Sync {
+        /// Collapse superseded duplicates to hard links pointing at the
+        /// source-of-truth instead of deleting them, so every original path
+        /// keeps existing while its bytes are stored exactly once
+        #[arg(long)]
+        link: bool,
+    },
+    /// Rebuild files from the vault's object store into a destination
+    /// directory, verifying each object's digest before writing it out
+    Restore {
+        /// Directory to restore files into
+        dest: PathBuf,
+    },
+    /// Remove vault objects no browse path links to anymore (orphaned by a
+    /// deleted or re-linked file)
+    Gc,
+    /// Prune the vault's dated tree down to a retention policy (keep-last /
+    /// keep-daily / keep-weekly / keep-monthly / keep-yearly)
+    Prune {
+        /// Always keep the N most recently dated files, regardless of the
+        /// bucket quotas below
+        #[arg(long, default_value_t = 0)]
+        keep_last: usize,
+
+        /// Keep one file per day for this many of the most recent days
+        #[arg(long, default_value_t = 0)]
+        keep_daily: usize,
+
+        /// Keep one file per week for this many of the most recent weeks
+        #[arg(long, default_value_t = 0)]
+        keep_weekly: usize,
+
+        /// Keep one file per month for this many of the most recent months
+        #[arg(long, default_value_t = 0)]
+        keep_monthly: usize,
+
+        /// Keep one file per year for this many of the most recent years
+        #[arg(long, default_value_t = 0)]
+        keep_yearly: usize,
+
+        /// Print what would be removed without touching disk
+        #[arg(long)]
+        dry_run: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -114,24 +317,106 @@ fn main() -> Result<()> {
     match cli.command {
         Commands::Sources { action } => match action {
             None => commands::sources::list(&vault)?,
-            Some(SourcesAction::Add { path }) => commands::sources::add(&vault, path)?,
-            Some(SourcesAction::Scan) => commands::sources::scan(&mut vault)?,
+            Some(SourcesAction::Add { path, reference }) => {
+                commands::sources::add(&vault, path, reference)?
+            }
+            Some(SourcesAction::Scan {
+                threshold,
+                similarity,
+                hash_alg,
+                resize_filter,
+                near_certain,
+                high,
+                required_votes,
+                rebuild_cache,
+                min_resolution,
+                min_file_size,
+                exclude_patterns,
+                allowed_extensions,
+                blocked_extensions,
+                jobs,
+            }) => commands::sources::scan(
+                &mut vault,
+                threshold,
+                similarity,
+                hash_alg,
+                resize_filter,
+                near_certain,
+                high,
+                required_votes,
+                rebuild_cache,
+                min_resolution,
+                min_file_size,
+                exclude_patterns,
+                allowed_extensions,
+                blocked_extensions,
+                jobs,
+            )?,
             Some(SourcesAction::Rm { path }) => commands::sources::rm(&vault, path)?,
         },
-        Commands::Catalog { action } => match action {
-            None => commands::status::run(&vault, false)?,
-            Some(CatalogAction::List) => commands::status::run(&vault, true)?,
-            Some(CatalogAction::Duplicates { id }) => commands::duplicates::run(&vault, id)?,
-        },
+        Commands::Catalog { action, format } => {
+            let format = commands::status::parse_report_format(&format)?;
+            match action {
+                None => commands::status::run(&vault, false, false, format)?,
+                Some(CatalogAction::List { mismatched }) => {
+                    commands::status::run(&vault, true, mismatched, format)?
+                }
+                Some(CatalogAction::Duplicates { id, similar }) => {
+                    commands::duplicates::run(&vault, id, similar)?
+                }
+                Some(CatalogAction::Find { path }) => commands::find::run(&vault, path)?,
+            }
+        }
         Commands::Vault { action } => match action {
             VaultAction::Set { path } => commands::vault::set(&vault, path)?,
-            VaultAction::Sync => commands::vault::sync(&mut vault)?,
+            VaultAction::Sync { link } => commands::vault::sync(&mut vault, link)?,
+            VaultAction::Restore { dest } => commands::vault::restore(&vault, dest)?,
+            VaultAction::Gc => commands::vault::gc(&vault)?,
+            VaultAction::Prune {
+                keep_last,
+                keep_daily,
+                keep_weekly,
+                keep_monthly,
+                keep_yearly,
+                dry_run,
+            } => {
+                let policy = losslessvault_core::prune::PrunePolicy {
+                    keep_last,
+                    keep_daily,
+                    keep_weekly,
+                    keep_monthly,
+                    keep_yearly,
+                };
+                commands::vault::prune(&vault, &policy, dry_run)?
+            }
         },
-        Commands::Export { action, quality } => match action {
+        Commands::Export {
+            action,
+            format,
+            quality,
+        } => match action {
             Some(ExportAction::Set { path }) => commands::export::set(&vault, path)?,
             Some(ExportAction::Show) => commands::export::show(&vault)?,
-            None => commands::export::run(&vault, quality)?,
+            None => commands::export::run(&vault, &format, quality)?,
+        },
+        Commands::Dedupe { threshold } => commands::dedupe::run(&vault, threshold)?,
+        Commands::Stats { threshold, json } => commands::stats::run(&vault, threshold, json)?,
+        Commands::Verify => commands::verify::run(&vault)?,
+        Commands::Archive { action } => match action {
+            ArchiveAction::Pack { path } => commands::archive::pack(&vault, path)?,
+            ArchiveAction::Unpack { archive, dest } => {
+                commands::archive::unpack(&vault, archive, dest)?
+            }
         },
+        Commands::Resolve {
+            id,
+            action,
+            move_to,
+            dry_run,
+        } => {
+            let resolution = commands::resolve::parse_resolution(&action, move_to)?;
+            commands::resolve::run(&vault, id, resolution, dry_run)?;
+        }
     }
 
     Ok(())
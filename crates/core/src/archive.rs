@@ -0,0 +1,597 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use rayon::prelude::*;
+use walkdir::WalkDir;
+
+use crate::error::{Error, Result};
+use crate::hasher::compute_sha256;
+
+/// Progress callback events for `pack_vault` / `unpack_archive`.
+pub enum PackProgress {
+    /// Starting with the number of files to process.
+    Start { total: usize },
+    /// A file was compressed into the archive.
+    Packed { path: PathBuf },
+    /// An unchanged file was skipped on an incremental pack run.
+    Skipped { path: PathBuf },
+    /// A file was decompressed and verified during unpack.
+    Unpacked { path: PathBuf },
+    /// Operation completed.
+    Complete { packed: usize, skipped: usize },
+}
+
+/// One archive member, as recorded in the index at the archive's tail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    pub relative_path: String,
+    pub offset: u64,
+    pub compressed_size: u64,
+    pub uncompressed_size: u64,
+    pub sha256: String,
+}
+
+/// Marks the archive format at the very end of the file, right after the
+/// index, so `unpack_archive` can find the tail without reading from the front.
+const MAGIC: &[u8; 8] = b"LSVAULT1";
+
+/// Caps `unpack_archive` enforces against a hostile or corrupt index before
+/// writing anything to disk — a crafted archive could otherwise claim a
+/// handful of tiny compressed members that decompress into terabytes
+/// (zip-bomb style), or simply list more members than the caller ever
+/// intends to restore.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArchiveLimits {
+    /// Reject the archive if the sum of every member's `uncompressed_size`
+    /// exceeds this.
+    pub max_total_uncompressed_size: u64,
+    /// Reject the archive if it lists more members than this.
+    pub max_entries: usize,
+    /// Reject the archive if any single member's `uncompressed_size`
+    /// exceeds this.
+    pub max_entry_uncompressed_size: u64,
+}
+
+impl Default for ArchiveLimits {
+    fn default() -> Self {
+        Self {
+            max_total_uncompressed_size: 500 * 1024 * 1024 * 1024,
+            max_entries: 1_000_000,
+            max_entry_uncompressed_size: 10 * 1024 * 1024 * 1024,
+        }
+    }
+}
+
+/// Bundle every file under `vault_path` into a single container at
+/// `archive_path`: each member is compressed independently as its own zstd
+/// frame (so extraction can seek straight to one member), one after another,
+/// followed by a tab-separated index of (path, offset, sizes, SHA-256) and an
+/// 8-byte magic footer.
+///
+/// Hashing runs in parallel across files with rayon; compression is applied
+/// sequentially while appending, since members share one growing file and
+/// need stable offsets. On an incremental run against an existing
+/// `archive_path`, members whose SHA-256 is unchanged have their compressed
+/// frames copied over from the previous archive without re-compressing them.
+pub fn pack_vault(
+    vault_path: &Path,
+    archive_path: &Path,
+    mut progress_cb: Option<&mut dyn FnMut(PackProgress)>,
+) -> Result<Vec<ArchiveEntry>> {
+    let previous_by_path: HashMap<String, ArchiveEntry> = read_index(archive_path)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|e| (e.relative_path.clone(), e))
+        .collect();
+
+    let files: Vec<PathBuf> = WalkDir::new(vault_path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.path().to_path_buf())
+        .collect();
+
+    if let Some(ref mut cb) = progress_cb {
+        cb(PackProgress::Start { total: files.len() });
+    }
+
+    let hashed: Vec<(PathBuf, String)> = files
+        .par_iter()
+        .filter_map(|path| compute_sha256(path).ok().map(|h| (path.clone(), h)))
+        .collect();
+
+    // The previous archive is overwritten below, so keep its bytes to copy
+    // unchanged members' frames from.
+    let previous_bytes = if previous_by_path.is_empty() {
+        None
+    } else {
+        fs::read(archive_path).ok()
+    };
+
+    let mut writer = BufWriter::new(File::create(archive_path)?);
+    let mut entries = Vec::new();
+    let mut offset = 0u64;
+    let mut packed = 0usize;
+    let mut skipped = 0usize;
+
+    for (path, sha256) in &hashed {
+        let relative_path = path
+            .strip_prefix(vault_path)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/");
+
+        if let Some(prev) = previous_by_path.get(&relative_path) {
+            let frame = previous_bytes.as_deref().and_then(|bytes| {
+                let start = usize::try_from(prev.offset).ok()?;
+                let end = start.checked_add(usize::try_from(prev.compressed_size).ok()?)?;
+                bytes.get(start..end)
+            });
+            if let (true, Some(frame)) = (&prev.sha256 == sha256, frame) {
+                writer.write_all(frame)?;
+                entries.push(ArchiveEntry {
+                    offset,
+                    ..prev.clone()
+                });
+                offset += prev.compressed_size;
+                skipped += 1;
+                if let Some(ref mut cb) = progress_cb {
+                    cb(PackProgress::Skipped { path: path.clone() });
+                }
+                continue;
+            }
+        }
+
+        let uncompressed_size = fs::metadata(path)?.len();
+        let mut input = File::open(path)?;
+        let start = offset;
+        let mut counting = CountingWriter::new(&mut writer);
+        zstd::stream::copy_encode(&mut input, &mut counting, 0)?;
+        let compressed_size = counting.count;
+        offset += compressed_size;
+
+        entries.push(ArchiveEntry {
+            relative_path: relative_path.clone(),
+            offset: start,
+            compressed_size,
+            uncompressed_size,
+            sha256: sha256.clone(),
+        });
+        packed += 1;
+        if let Some(ref mut cb) = progress_cb {
+            cb(PackProgress::Packed { path: path.clone() });
+        }
+    }
+
+    write_index(&mut writer, &entries)?;
+    writer.flush()?;
+
+    if let Some(ref mut cb) = progress_cb {
+        cb(PackProgress::Complete { packed, skipped });
+    }
+
+    Ok(entries)
+}
+
+/// Extract every member of `archive_path` into `dest_path` under the default
+/// `ArchiveLimits`. See `unpack_archive_with_limits`.
+pub fn unpack_archive(
+    archive_path: &Path,
+    dest_path: &Path,
+    progress_cb: Option<&mut dyn FnMut(PackProgress)>,
+) -> Result<Vec<ArchiveEntry>> {
+    unpack_archive_with_limits(archive_path, dest_path, ArchiveLimits::default(), progress_cb)
+}
+
+/// Extract every member of `archive_path` into `dest_path`, re-streaming each
+/// file through SHA-256 after decompression and failing with
+/// `Error::ArchiveCorrupt` if it no longer matches the index.
+///
+/// The index is untrusted input — it may come from a shared or downloaded
+/// archive — so every entry is validated against `limits` and path-checked
+/// *before* any file is written: entries whose `relative_path` contains a
+/// `..`, an absolute/root component, or anything else besides a plain
+/// `Normal` component are rejected, as is any archive whose entry count or
+/// uncompressed sizes (individually or summed) exceed `limits`.
+pub fn unpack_archive_with_limits(
+    archive_path: &Path,
+    dest_path: &Path,
+    limits: ArchiveLimits,
+    mut progress_cb: Option<&mut dyn FnMut(PackProgress)>,
+) -> Result<Vec<ArchiveEntry>> {
+    let entries = read_index(archive_path)?;
+
+    if entries.len() > limits.max_entries {
+        return Err(Error::ArchiveLimitExceeded(format!(
+            "{} entries exceeds the limit of {}",
+            entries.len(),
+            limits.max_entries
+        )));
+    }
+
+    let mut targets = Vec::with_capacity(entries.len());
+    let mut total_uncompressed = 0u64;
+    for entry in &entries {
+        if entry.uncompressed_size > limits.max_entry_uncompressed_size {
+            return Err(Error::ArchiveLimitExceeded(format!(
+                "member {} is {} bytes uncompressed, exceeding the limit of {}",
+                entry.relative_path, entry.uncompressed_size, limits.max_entry_uncompressed_size
+            )));
+        }
+        total_uncompressed = total_uncompressed.saturating_add(entry.uncompressed_size);
+        if total_uncompressed > limits.max_total_uncompressed_size {
+            return Err(Error::ArchiveLimitExceeded(format!(
+                "total uncompressed size exceeds the limit of {} bytes",
+                limits.max_total_uncompressed_size
+            )));
+        }
+        targets.push(safe_extraction_target(dest_path, &entry.relative_path)?);
+    }
+
+    let mut file = File::open(archive_path)?;
+
+    if let Some(ref mut cb) = progress_cb {
+        cb(PackProgress::Start {
+            total: entries.len(),
+        });
+    }
+
+    for (entry, target) in entries.iter().zip(&targets) {
+        file.seek(SeekFrom::Start(entry.offset))?;
+        let mut frame = vec![0u8; entry.compressed_size as usize];
+        file.read_exact(&mut frame)?;
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut out = File::create(target)?;
+        zstd::stream::copy_decode(frame.as_slice(), &mut out)?;
+        drop(out);
+
+        let actual = compute_sha256(target)?;
+        if actual != entry.sha256 {
+            return Err(Error::ArchiveCorrupt {
+                path: target.clone(),
+                expected: entry.sha256.clone(),
+                actual,
+            });
+        }
+
+        if let Some(ref mut cb) = progress_cb {
+            cb(PackProgress::Unpacked {
+                path: target.clone(),
+            });
+        }
+    }
+
+    if let Some(ref mut cb) = progress_cb {
+        cb(PackProgress::Complete {
+            packed: entries.len(),
+            skipped: 0,
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Read the tail index out of an archive file, or an empty list if the file
+/// isn't a recognized archive. A file that can't be opened is an error;
+/// `pack_vault` treats that as an empty index (first pack run).
+fn read_index(archive_path: &Path) -> Result<Vec<ArchiveEntry>> {
+    let mut file = File::open(archive_path)?;
+    let file_len = file.metadata()?.len();
+    if file_len < 16 {
+        return Ok(Vec::new());
+    }
+
+    file.seek(SeekFrom::End(-16))?;
+    let mut footer = [0u8; 16];
+    file.read_exact(&mut footer)?;
+    let index_len = u64::from_le_bytes(footer[0..8].try_into().unwrap());
+    if &footer[8..16] != MAGIC {
+        return Ok(Vec::new());
+    }
+    if file_len < 16 + index_len {
+        return Ok(Vec::new());
+    }
+
+    file.seek(SeekFrom::Start(file_len - 16 - index_len))?;
+    let mut index_bytes = vec![0u8; index_len as usize];
+    file.read_exact(&mut index_bytes)?;
+
+    Ok(String::from_utf8_lossy(&index_bytes)
+        .lines()
+        .filter_map(parse_index_line)
+        .collect())
+}
+
+/// Resolve an index entry's `relative_path` to a concrete path under
+/// `dest_path`, rejecting anything that isn't a plain relative path confined
+/// to the destination — a `..` component, an absolute path, or (on Windows)
+/// a drive prefix would otherwise let a crafted archive write outside
+/// `dest_path` during extraction.
+fn safe_extraction_target(dest_path: &Path, relative_path: &str) -> Result<PathBuf> {
+    let candidate = Path::new(relative_path);
+    if candidate
+        .components()
+        .any(|c| !matches!(c, std::path::Component::Normal(_)))
+    {
+        return Err(Error::ArchiveUnsafePath(relative_path.to_string()));
+    }
+
+    let target = dest_path.join(candidate);
+    if !target.starts_with(dest_path) {
+        return Err(Error::ArchiveUnsafePath(relative_path.to_string()));
+    }
+
+    Ok(target)
+}
+
+fn parse_index_line(line: &str) -> Option<ArchiveEntry> {
+    let parts: Vec<&str> = line.split('\t').collect();
+    if parts.len() != 5 {
+        return None;
+    }
+    Some(ArchiveEntry {
+        relative_path: parts[0].to_string(),
+        offset: parts[1].parse().ok()?,
+        compressed_size: parts[2].parse().ok()?,
+        uncompressed_size: parts[3].parse().ok()?,
+        sha256: parts[4].to_string(),
+    })
+}
+
+fn write_index<W: Write>(writer: &mut W, entries: &[ArchiveEntry]) -> Result<()> {
+    let mut index_bytes = Vec::new();
+    for entry in entries {
+        writeln!(
+            index_bytes,
+            "{}\t{}\t{}\t{}\t{}",
+            entry.relative_path,
+            entry.offset,
+            entry.compressed_size,
+            entry.uncompressed_size,
+            entry.sha256,
+        )
+        .expect("writing to an in-memory buffer cannot fail");
+    }
+    writer.write_all(&index_bytes)?;
+    writer.write_all(&(index_bytes.len() as u64).to_le_bytes())?;
+    writer.write_all(MAGIC)?;
+    Ok(())
+}
+
+/// Wraps a `Write` to count bytes passed through it, so we can record each
+/// zstd frame's compressed size without a separate pass over the archive.
+struct CountingWriter<W> {
+    inner: W,
+    count: u64,
+}
+
+impl<W: Write> CountingWriter<W> {
+    fn new(inner: W) -> Self {
+        Self { inner, count: 0 }
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_tree(root: &Path, files: &[(&str, &[u8])]) {
+        for (relative, content) in files {
+            let path = root.join(relative);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).unwrap();
+            }
+            fs::write(path, content).unwrap();
+        }
+    }
+
+    /// Craft an archive file whose index is exactly `entries`, with no
+    /// compressed payload — enough to exercise `unpack_archive`'s upfront
+    /// index validation (path safety, entry-count/size limits), which all
+    /// run before a single compressed frame is read.
+    fn write_index_only_archive(archive_path: &Path, entries: &[ArchiveEntry]) {
+        let mut writer = BufWriter::new(File::create(archive_path).unwrap());
+        write_index(&mut writer, entries).unwrap();
+        writer.flush().unwrap();
+    }
+
+    fn entry(relative_path: &str, uncompressed_size: u64) -> ArchiveEntry {
+        ArchiveEntry {
+            relative_path: relative_path.to_string(),
+            offset: 0,
+            compressed_size: 0,
+            uncompressed_size,
+            sha256: "deadbeef".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_pack_then_unpack_roundtrip() {
+        let vault = tempfile::tempdir().unwrap();
+        write_tree(
+            vault.path(),
+            &[
+                ("2024/01/01/a.jpg", b"photo a bytes"),
+                ("2024/01/02/b.jpg", b"photo b bytes, a bit longer"),
+            ],
+        );
+
+        let archive = tempfile::tempdir().unwrap();
+        let archive_path = archive.path().join("vault.lsvault");
+        let entries = pack_vault(vault.path(), &archive_path, None).unwrap();
+        assert_eq!(entries.len(), 2);
+
+        let dest = tempfile::tempdir().unwrap();
+        let unpacked = unpack_archive(&archive_path, dest.path(), None).unwrap();
+        assert_eq!(unpacked.len(), 2);
+
+        assert_eq!(
+            fs::read(dest.path().join("2024/01/01/a.jpg")).unwrap(),
+            b"photo a bytes"
+        );
+        assert_eq!(
+            fs::read(dest.path().join("2024/01/02/b.jpg")).unwrap(),
+            b"photo b bytes, a bit longer"
+        );
+    }
+
+    #[test]
+    fn test_pack_empty_vault() {
+        let vault = tempfile::tempdir().unwrap();
+        let archive = tempfile::tempdir().unwrap();
+        let archive_path = archive.path().join("vault.lsvault");
+
+        let entries = pack_vault(vault.path(), &archive_path, None).unwrap();
+        assert!(entries.is_empty());
+
+        let dest = tempfile::tempdir().unwrap();
+        let unpacked = unpack_archive(&archive_path, dest.path(), None).unwrap();
+        assert!(unpacked.is_empty());
+    }
+
+    #[test]
+    fn test_incremental_pack_skips_unchanged_members() {
+        let vault = tempfile::tempdir().unwrap();
+        write_tree(vault.path(), &[("a.jpg", b"unchanged"), ("b.jpg", b"will change")]);
+
+        let archive = tempfile::tempdir().unwrap();
+        let archive_path = archive.path().join("vault.lsvault");
+        pack_vault(vault.path(), &archive_path, None).unwrap();
+
+        fs::write(vault.path().join("b.jpg"), b"changed now").unwrap();
+
+        let mut packed_paths = Vec::new();
+        let mut skipped_paths = Vec::new();
+        pack_vault(
+            vault.path(),
+            &archive_path,
+            Some(&mut |progress| match progress {
+                PackProgress::Packed { path } => packed_paths.push(path),
+                PackProgress::Skipped { path } => skipped_paths.push(path),
+                _ => {}
+            }),
+        )
+        .unwrap();
+
+        assert_eq!(packed_paths, vec![vault.path().join("b.jpg")]);
+        assert_eq!(skipped_paths, vec![vault.path().join("a.jpg")]);
+    }
+
+    #[test]
+    fn test_unpack_detects_tampered_archive() {
+        let vault = tempfile::tempdir().unwrap();
+        write_tree(vault.path(), &[("a.jpg", b"original content")]);
+
+        let archive = tempfile::tempdir().unwrap();
+        let archive_path = archive.path().join("vault.lsvault");
+        pack_vault(vault.path(), &archive_path, None).unwrap();
+
+        // Flip a byte in the compressed payload region (well before the tail index).
+        let mut bytes = fs::read(&archive_path).unwrap();
+        bytes[0] ^= 0xFF;
+        fs::write(&archive_path, &bytes).unwrap();
+
+        let dest = tempfile::tempdir().unwrap();
+        let result = unpack_archive(&archive_path, dest.path(), None);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_pack_nonexistent_vault_produces_empty_archive() {
+        let archive = tempfile::tempdir().unwrap();
+        let archive_path = archive.path().join("vault.lsvault");
+        let entries = pack_vault(Path::new("/nonexistent/vault"), &archive_path, None).unwrap();
+        assert!(entries.is_empty());
+    }
+
+    #[test]
+    fn test_unpack_rejects_parent_dir_traversal() {
+        let archive = tempfile::tempdir().unwrap();
+        let archive_path = archive.path().join("evil.lsvault");
+        write_index_only_archive(&archive_path, &[entry("../../escape.jpg", 10)]);
+
+        let dest = tempfile::tempdir().unwrap();
+        let result = unpack_archive(&archive_path, dest.path(), None);
+        assert!(matches!(result, Err(Error::ArchiveUnsafePath(_))));
+        assert!(!dest.path().parent().unwrap().join("escape.jpg").exists());
+    }
+
+    #[test]
+    fn test_unpack_rejects_absolute_path_entry() {
+        let archive = tempfile::tempdir().unwrap();
+        let archive_path = archive.path().join("evil.lsvault");
+        write_index_only_archive(&archive_path, &[entry("/etc/passwd", 10)]);
+
+        let dest = tempfile::tempdir().unwrap();
+        let result = unpack_archive(&archive_path, dest.path(), None);
+        assert!(matches!(result, Err(Error::ArchiveUnsafePath(_))));
+    }
+
+    #[test]
+    fn test_unpack_rejects_entry_over_per_entry_limit() {
+        let archive = tempfile::tempdir().unwrap();
+        let archive_path = archive.path().join("huge.lsvault");
+        write_index_only_archive(&archive_path, &[entry("a.jpg", 1_000)]);
+
+        let dest = tempfile::tempdir().unwrap();
+        let limits = ArchiveLimits {
+            max_entry_uncompressed_size: 999,
+            ..ArchiveLimits::default()
+        };
+        let result = unpack_archive_with_limits(&archive_path, dest.path(), limits, None);
+        assert!(matches!(result, Err(Error::ArchiveLimitExceeded(_))));
+    }
+
+    #[test]
+    fn test_unpack_rejects_total_size_over_limit() {
+        let archive = tempfile::tempdir().unwrap();
+        let archive_path = archive.path().join("huge_total.lsvault");
+        write_index_only_archive(
+            &archive_path,
+            &[entry("a.jpg", 600), entry("b.jpg", 600)],
+        );
+
+        let dest = tempfile::tempdir().unwrap();
+        let limits = ArchiveLimits {
+            max_total_uncompressed_size: 1_000,
+            ..ArchiveLimits::default()
+        };
+        let result = unpack_archive_with_limits(&archive_path, dest.path(), limits, None);
+        assert!(matches!(result, Err(Error::ArchiveLimitExceeded(_))));
+    }
+
+    #[test]
+    fn test_unpack_rejects_too_many_entries() {
+        let archive = tempfile::tempdir().unwrap();
+        let archive_path = archive.path().join("many.lsvault");
+        let entries: Vec<ArchiveEntry> = (0..5)
+            .map(|i| entry(&format!("{i}.jpg"), 10))
+            .collect();
+        write_index_only_archive(&archive_path, &entries);
+
+        let dest = tempfile::tempdir().unwrap();
+        let limits = ArchiveLimits {
+            max_entries: 3,
+            ..ArchiveLimits::default()
+        };
+        let result = unpack_archive_with_limits(&archive_path, dest.path(), limits, None);
+        assert!(matches!(result, Err(Error::ArchiveLimitExceeded(_))));
+    }
+}
@@ -0,0 +1,142 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use crate::hasher::perceptual::hamming_distance;
+
+/// In-memory BK-tree (Burkhard-Keller tree) over 64-bit perceptual hashes.
+/// Every node stores its children in a map keyed by the integer Hamming
+/// distance from that node to the child; a lookup descends the tree the
+/// same way, pruning any subtree whose edge label can't possibly contain a
+/// match (triangle inequality), so `find_similar` runs in roughly O(log n)
+/// comparisons instead of the O(n^2) pairwise scan `cluster_by_hamming` does.
+///
+/// Held in memory only — see `Catalog::rebuild_hash_indexes`, which rebuilds
+/// it from `list_all_photos` on open rather than persisting it.
+#[derive(Debug, Default)]
+pub struct BkTree {
+    root: Option<Box<Node>>,
+}
+
+#[derive(Debug)]
+struct Node {
+    id: i64,
+    hash: u64,
+    children: HashMap<u32, Box<Node>>,
+}
+
+impl BkTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert `(id, hash)`. A hash that exactly matches one already in the
+    /// tree has its id replaced rather than becoming a sibling, since two
+    /// nodes at distance 0 would never be distinguishable during a lookup.
+    pub fn insert(&mut self, id: i64, hash: u64) {
+        let Some(root) = &mut self.root else {
+            self.root = Some(Box::new(Node {
+                id,
+                hash,
+                children: HashMap::new(),
+            }));
+            return;
+        };
+
+        let mut node = root.as_mut();
+        loop {
+            let distance = hamming_distance(node.hash, hash);
+            if distance == 0 {
+                node.id = id;
+                return;
+            }
+            match node.children.entry(distance) {
+                Entry::Occupied(entry) => node = entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    entry.insert(Box::new(Node {
+                        id,
+                        hash,
+                        children: HashMap::new(),
+                    }));
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Find every inserted id within `max_distance` of `hash`, paired with
+    /// its actual distance from `hash`.
+    pub fn find_similar(&self, hash: u64, max_distance: u32) -> Vec<(i64, u32)> {
+        let mut results = Vec::new();
+        if let Some(root) = &self.root {
+            Self::search(root, hash, max_distance, &mut results);
+        }
+        results
+    }
+
+    fn search(node: &Node, hash: u64, max_distance: u32, results: &mut Vec<(i64, u32)>) {
+        let distance = hamming_distance(node.hash, hash);
+        if distance <= max_distance {
+            results.push((node.id, distance));
+        }
+
+        let low = distance.saturating_sub(max_distance);
+        let high = distance + max_distance;
+        for (&edge, child) in &node.children {
+            if edge >= low && edge <= high {
+                Self::search(child, hash, max_distance, results);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_similar_on_empty_tree() {
+        let tree = BkTree::new();
+        assert!(tree.find_similar(0, 10).is_empty());
+    }
+
+    #[test]
+    fn test_find_similar_finds_exact_match() {
+        let mut tree = BkTree::new();
+        tree.insert(1, 0b1010);
+        assert_eq!(tree.find_similar(0b1010, 0), vec![(1, 0)]);
+    }
+
+    #[test]
+    fn test_find_similar_respects_max_distance() {
+        let mut tree = BkTree::new();
+        tree.insert(1, 0b0000);
+        tree.insert(2, 0b0001); // distance 1
+        tree.insert(3, u64::MAX); // distance 64
+
+        let mut close = tree.find_similar(0b0000, 1);
+        close.sort();
+        assert_eq!(close, vec![(1, 0), (2, 1)]);
+
+        assert!(tree.find_similar(0b0000, 1).iter().all(|&(id, _)| id != 3));
+    }
+
+    #[test]
+    fn test_insert_replaces_id_on_exact_hash_collision() {
+        let mut tree = BkTree::new();
+        tree.insert(1, 0b1010);
+        tree.insert(2, 0b1010);
+        assert_eq!(tree.find_similar(0b1010, 0), vec![(2, 0)]);
+    }
+
+    #[test]
+    fn test_find_similar_many_entries() {
+        let mut tree = BkTree::new();
+        // Hashes far enough apart (multiples of 1000) that none collide
+        // within a small Hamming radius, exercising a deep, branchy tree.
+        for i in 0..200u64 {
+            tree.insert(i as i64, i * 1000);
+        }
+        assert_eq!(tree.find_similar(57_000, 0), vec![(57, 0)]);
+        assert!(tree.find_similar(57_000 + (1 << 40), 0).is_empty());
+    }
+}
@@ -0,0 +1,157 @@
+//! Change-feed over catalog mutations, for an incremental UI or background
+//! indexer that wants to react to what changed instead of re-reading
+//! `list_all_photos` after every write.
+//!
+//! Every event is persisted to the `events` table in the same transaction as
+//! the mutation it describes — see `Catalog::emit_event` — so a rolled-back
+//! write (an orphan-photo foreign-key failure, say) never produces an event
+//! for something that didn't actually happen. `id` (the table's rowid) is
+//! the replay cursor: a consumer that was offline persists the last `id` it
+//! saw and passes it to `Catalog::watch_since` to catch up deterministically
+//! before streaming live.
+
+use rusqlite::{params, Connection, Row};
+
+use crate::error::Result;
+
+/// One catalog mutation, as delivered by `Catalog::watch`/`watch_since`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CatalogEvent {
+    PhotoUpserted { id: i64 },
+    GroupInserted { sot: i64, members: Vec<i64> },
+    SourceAdded { id: i64 },
+    PhotoRemoved { id: i64 },
+}
+
+impl CatalogEvent {
+    fn kind(&self) -> &'static str {
+        match self {
+            CatalogEvent::PhotoUpserted { .. } => "photo_upserted",
+            CatalogEvent::GroupInserted { .. } => "group_inserted",
+            CatalogEvent::SourceAdded { .. } => "source_added",
+            CatalogEvent::PhotoRemoved { .. } => "photo_removed",
+        }
+    }
+
+    fn entity_id(&self) -> Option<i64> {
+        match self {
+            CatalogEvent::PhotoUpserted { id } => Some(*id),
+            CatalogEvent::SourceAdded { id } => Some(*id),
+            CatalogEvent::PhotoRemoved { id } => Some(*id),
+            CatalogEvent::GroupInserted { .. } => None,
+        }
+    }
+
+    fn sot_id(&self) -> Option<i64> {
+        match self {
+            CatalogEvent::GroupInserted { sot, .. } => Some(*sot),
+            _ => None,
+        }
+    }
+
+    fn members_csv(&self) -> Option<String> {
+        match self {
+            CatalogEvent::GroupInserted { members, .. } => {
+                Some(members.iter().map(i64::to_string).collect::<Vec<_>>().join(","))
+            }
+            _ => None,
+        }
+    }
+
+    fn from_row(row: &Row) -> rusqlite::Result<(i64, CatalogEvent)> {
+        let id: i64 = row.get(0)?;
+        let kind: String = row.get(1)?;
+        let entity_id: Option<i64> = row.get(2)?;
+        let sot_id: Option<i64> = row.get(3)?;
+        let members: Option<String> = row.get(4)?;
+
+        let event = match kind.as_str() {
+            "photo_upserted" => CatalogEvent::PhotoUpserted { id: entity_id.unwrap_or_default() },
+            "source_added" => CatalogEvent::SourceAdded { id: entity_id.unwrap_or_default() },
+            "photo_removed" => CatalogEvent::PhotoRemoved { id: entity_id.unwrap_or_default() },
+            "group_inserted" => CatalogEvent::GroupInserted {
+                sot: sot_id.unwrap_or_default(),
+                members: members
+                    .unwrap_or_default()
+                    .split(',')
+                    .filter(|s| !s.is_empty())
+                    .filter_map(|s| s.parse().ok())
+                    .collect(),
+            },
+            _ => CatalogEvent::PhotoUpserted { id: entity_id.unwrap_or_default() },
+        };
+        Ok((id, event))
+    }
+}
+
+/// Persist `event` and return the `events.id` it was stored under, for the
+/// caller to emit to live subscribers once its enclosing transaction commits.
+pub fn record_event(conn: &Connection, event: &CatalogEvent, created_at: i64) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO events (kind, entity_id, sot_id, members, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![event.kind(), event.entity_id(), event.sot_id(), event.members_csv(), created_at],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Every event committed with `id` strictly greater than `since_id`, in
+/// commit order — the backlog `Catalog::watch_since` replays before handing
+/// the caller a live subscription.
+pub fn events_since(conn: &Connection, since_id: i64) -> Result<Vec<CatalogEvent>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, kind, entity_id, sot_id, members FROM events WHERE id > ?1 ORDER BY id",
+    )?;
+    let events = stmt
+        .query_map(params![since_id], |row| CatalogEvent::from_row(row))?
+        .collect::<std::result::Result<Vec<(i64, CatalogEvent)>, _>>()?
+        .into_iter()
+        .map(|(_, event)| event)
+        .collect();
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conn_with_schema() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::catalog::schema::initialize(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_record_and_replay_photo_upserted() {
+        let conn = conn_with_schema();
+        let id = record_event(&conn, &CatalogEvent::PhotoUpserted { id: 42 }, 100).unwrap();
+        assert_eq!(id, 1);
+
+        let events = events_since(&conn, 0).unwrap();
+        assert_eq!(events, vec![CatalogEvent::PhotoUpserted { id: 42 }]);
+    }
+
+    #[test]
+    fn test_events_since_excludes_events_at_or_before_cursor() {
+        let conn = conn_with_schema();
+        record_event(&conn, &CatalogEvent::SourceAdded { id: 1 }, 100).unwrap();
+        let second_id = record_event(&conn, &CatalogEvent::PhotoUpserted { id: 2 }, 100).unwrap();
+        record_event(&conn, &CatalogEvent::PhotoUpserted { id: 3 }, 100).unwrap();
+
+        let events = events_since(&conn, second_id).unwrap();
+        assert_eq!(events, vec![CatalogEvent::PhotoUpserted { id: 3 }]);
+    }
+
+    #[test]
+    fn test_group_inserted_round_trips_sot_and_members() {
+        let conn = conn_with_schema();
+        record_event(
+            &conn,
+            &CatalogEvent::GroupInserted { sot: 1, members: vec![1, 2, 3] },
+            100,
+        )
+        .unwrap();
+
+        let events = events_since(&conn, 0).unwrap();
+        assert_eq!(events, vec![CatalogEvent::GroupInserted { sot: 1, members: vec![1, 2, 3] }]);
+    }
+}
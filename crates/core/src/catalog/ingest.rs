@@ -0,0 +1,340 @@
+//! One-shot merge of an independently-scanned catalog into this one.
+//!
+//! Unlike `replication`'s incremental `export_delta`/`merge_delta` — built
+//! for two copies of the *same* library kept in sync over time, keyed on
+//! `photos.path` — `ingest` is for combining two catalogs that scanned
+//! different (or only partially overlapping) source trees, e.g. after
+//! cataloging photos on two machines that were never meant to share a path
+//! layout. Rows are matched by content (`sha256`) rather than location, and
+//! every foreign key is remapped through a translation table keyed by the
+//! other catalog's local ids, since those ids mean nothing once copied here.
+
+use std::collections::HashMap;
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::Result;
+
+/// Counts of what an `ingest` call actually did, for reporting to the user.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IngestReport {
+    pub sources_added: usize,
+    pub photos_added: usize,
+    pub photos_skipped: usize,
+    pub photos_conflicting: usize,
+    pub groups_added: usize,
+    pub group_members_added: usize,
+}
+
+/// Merge every source, photo, duplicate group, and group membership from
+/// `other` into `local`. `other` is only ever read; the translation tables
+/// built up here exist purely to rewrite `other`'s local ids into `local`'s
+/// as rows are copied across.
+///
+/// A photo already present in `local` by `sha256` (the same content,
+/// wherever it lives) is counted as skipped rather than duplicated. A photo
+/// whose `path` already exists in `local` with other content is left alone
+/// and counted as conflicting, whatever its `mtime`: `photos.path` is
+/// globally unique, so inserting would fail outright, and silently
+/// overwriting a local row during a merge is worse than asking the user to
+/// resolve it by hand.
+pub fn ingest_catalog(local: &Connection, other: &Connection) -> Result<IngestReport> {
+    let mut report = IngestReport::default();
+    let mut source_id_map: HashMap<i64, i64> = HashMap::new();
+    let mut photo_id_map: HashMap<i64, i64> = HashMap::new();
+
+    let mut stmt = other.prepare("SELECT id, path, role FROM sources")?;
+    let sources: Vec<(i64, String, String)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<std::result::Result<_, _>>()?;
+    drop(stmt);
+    for (other_id, path, role) in sources {
+        let local_id = resolve_source_id(local, &path, &role)?;
+        if local_id.1 {
+            report.sources_added += 1;
+        }
+        source_id_map.insert(other_id, local_id.0);
+    }
+
+    let mut stmt = other.prepare(
+        "SELECT id, source_id, path, size, format, sha256, phash, dhash, ahash, mtime,
+                exif_date, exif_camera_make, exif_camera_model, exif_gps_lat, exif_gps_lon,
+                exif_width, exif_height
+         FROM photos",
+    )?;
+    let photos: Vec<OtherPhoto> = stmt
+        .query_map([], |row| {
+            Ok(OtherPhoto {
+                id: row.get(0)?,
+                source_id: row.get(1)?,
+                path: row.get(2)?,
+                size: row.get(3)?,
+                format: row.get(4)?,
+                sha256: row.get(5)?,
+                phash: row.get(6)?,
+                dhash: row.get(7)?,
+                ahash: row.get(8)?,
+                mtime: row.get(9)?,
+                exif_date: row.get(10)?,
+                exif_camera_make: row.get(11)?,
+                exif_camera_model: row.get(12)?,
+                exif_gps_lat: row.get(13)?,
+                exif_gps_lon: row.get(14)?,
+                exif_width: row.get(15)?,
+                exif_height: row.get(16)?,
+            })
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    drop(stmt);
+
+    for photo in photos {
+        if let Some(local_id) = photo_id_by_sha256(local, &photo.sha256)? {
+            photo_id_map.insert(photo.id, local_id);
+            report.photos_skipped += 1;
+            continue;
+        }
+
+        let existing_by_path: Option<(i64, i64)> = local
+            .query_row("SELECT id, mtime FROM photos WHERE path = ?1", params![photo.path], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .optional()?;
+        if let Some((local_id, _)) = existing_by_path {
+            photo_id_map.insert(photo.id, local_id);
+            report.photos_conflicting += 1;
+            continue;
+        }
+
+        let Some(&local_source_id) = source_id_map.get(&photo.source_id) else {
+            continue;
+        };
+        local.execute(
+            "INSERT INTO photos (source_id, path, size, format, sha256, phash, dhash, ahash, mtime,
+             exif_date, exif_camera_make, exif_camera_model, exif_gps_lat, exif_gps_lon,
+             exif_width, exif_height, updated_at)
+             VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11,?12,?13,?14,?15,?16,?17)",
+            params![
+                local_source_id,
+                photo.path,
+                photo.size,
+                photo.format,
+                photo.sha256,
+                photo.phash,
+                photo.dhash,
+                photo.ahash,
+                photo.mtime,
+                photo.exif_date,
+                photo.exif_camera_make,
+                photo.exif_camera_model,
+                photo.exif_gps_lat,
+                photo.exif_gps_lon,
+                photo.exif_width,
+                photo.exif_height,
+                crate::catalog::current_timestamp(),
+            ],
+        )?;
+        photo_id_map.insert(photo.id, local.last_insert_rowid());
+        report.photos_added += 1;
+    }
+
+    let mut stmt = other.prepare("SELECT id, source_of_truth_id, confidence FROM duplicate_groups")?;
+    let groups: Vec<(i64, i64, String)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<std::result::Result<_, _>>()?;
+    drop(stmt);
+
+    let mut group_id_map: HashMap<i64, i64> = HashMap::new();
+    for (other_group_id, other_sot_id, confidence) in groups {
+        let Some(&local_sot_id) = photo_id_map.get(&other_sot_id) else {
+            continue;
+        };
+        local.execute(
+            "INSERT INTO duplicate_groups (source_of_truth_id, confidence) VALUES (?1, ?2)",
+            params![local_sot_id, confidence],
+        )?;
+        group_id_map.insert(other_group_id, local.last_insert_rowid());
+        report.groups_added += 1;
+    }
+
+    let mut stmt = other.prepare("SELECT group_id, photo_id, added_at FROM group_members")?;
+    let members: Vec<(i64, i64, i64)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<std::result::Result<_, _>>()?;
+    drop(stmt);
+    for (other_group_id, other_photo_id, added_at) in members {
+        let (Some(&local_group_id), Some(&local_photo_id)) =
+            (group_id_map.get(&other_group_id), photo_id_map.get(&other_photo_id))
+        else {
+            continue;
+        };
+        let added = local.execute(
+            "INSERT OR IGNORE INTO group_members (group_id, photo_id, added_at) VALUES (?1, ?2, ?3)",
+            params![local_group_id, local_photo_id, added_at],
+        )?;
+        report.group_members_added += added;
+    }
+
+    Ok(report)
+}
+
+struct OtherPhoto {
+    id: i64,
+    source_id: i64,
+    path: String,
+    size: i64,
+    format: String,
+    sha256: String,
+    phash: Option<i64>,
+    dhash: Option<i64>,
+    ahash: Option<i64>,
+    mtime: i64,
+    exif_date: Option<String>,
+    exif_camera_make: Option<String>,
+    exif_camera_model: Option<String>,
+    exif_gps_lat: Option<f64>,
+    exif_gps_lon: Option<f64>,
+    exif_width: Option<i64>,
+    exif_height: Option<i64>,
+}
+
+/// Find (or create) `local`'s `sources` row for `path`, returning its id and
+/// whether it was just created.
+fn resolve_source_id(local: &Connection, path: &str, role: &str) -> Result<(i64, bool)> {
+    if let Some(id) = local
+        .query_row("SELECT id FROM sources WHERE path = ?1", params![path], |row| row.get(0))
+        .optional()?
+    {
+        return Ok((id, false));
+    }
+    local.execute("INSERT INTO sources (path, role) VALUES (?1, ?2)", params![path, role])?;
+    Ok((local.last_insert_rowid(), true))
+}
+
+fn photo_id_by_sha256(conn: &Connection, sha256: &str) -> Result<Option<i64>> {
+    Ok(conn
+        .query_row("SELECT id FROM photos WHERE sha256 = ?1 LIMIT 1", params![sha256], |row| row.get(0))
+        .optional()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conn_with_schema() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::catalog::schema::initialize(&conn).unwrap();
+        conn
+    }
+
+    fn insert_source(conn: &Connection, path: &str) -> i64 {
+        conn.execute("INSERT INTO sources (path, role) VALUES (?1, 'standard')", params![path]).unwrap();
+        conn.last_insert_rowid()
+    }
+
+    fn insert_photo(conn: &Connection, source_id: i64, path: &str, sha256: &str, mtime: i64) -> i64 {
+        conn.execute(
+            "INSERT INTO photos (source_id, path, size, format, sha256, mtime) VALUES (?1, ?2, 10, 'JPEG', ?3, ?4)",
+            params![source_id, path, sha256, mtime],
+        )
+        .unwrap();
+        conn.last_insert_rowid()
+    }
+
+    #[test]
+    fn test_ingest_adds_a_new_source_and_photo() {
+        let local = conn_with_schema();
+        let other = conn_with_schema();
+        let source = insert_source(&other, "/nas/photos");
+        insert_photo(&other, source, "/nas/photos/a.jpg", "aaa", 100);
+
+        let report = ingest_catalog(&local, &other).unwrap();
+        assert_eq!(report.sources_added, 1);
+        assert_eq!(report.photos_added, 1);
+
+        let count: i64 = local.query_row("SELECT COUNT(*) FROM photos", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_ingest_skips_a_photo_already_present_by_sha256_under_a_different_path() {
+        let local = conn_with_schema();
+        let local_source = insert_source(&local, "/laptop/photos");
+        insert_photo(&local, local_source, "/laptop/photos/a.jpg", "aaa", 100);
+
+        let other = conn_with_schema();
+        let other_source = insert_source(&other, "/nas/photos");
+        insert_photo(&other, other_source, "/nas/backup/a.jpg", "aaa", 999);
+
+        let report = ingest_catalog(&local, &other).unwrap();
+        assert_eq!(report.photos_skipped, 1);
+        assert_eq!(report.photos_added, 0);
+
+        let count: i64 = local.query_row("SELECT COUNT(*) FROM photos", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_ingest_flags_a_same_path_different_content_photo_as_conflicting_without_overwriting() {
+        let local = conn_with_schema();
+        let local_source = insert_source(&local, "/shared/photos");
+        insert_photo(&local, local_source, "/shared/photos/a.jpg", "local_hash", 100);
+
+        let other = conn_with_schema();
+        let other_source = insert_source(&other, "/shared/photos");
+        insert_photo(&other, other_source, "/shared/photos/a.jpg", "other_hash", 200);
+
+        let report = ingest_catalog(&local, &other).unwrap();
+        assert_eq!(report.photos_conflicting, 1);
+        assert_eq!(report.photos_added, 0);
+
+        let sha: String = local
+            .query_row("SELECT sha256 FROM photos WHERE path = '/shared/photos/a.jpg'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(sha, "local_hash", "a conflicting row must not be silently overwritten");
+    }
+
+    #[test]
+    fn test_ingest_remaps_duplicate_group_and_members_onto_local_photo_ids() {
+        let local = conn_with_schema();
+        let other = conn_with_schema();
+        let other_source = insert_source(&other, "/nas/photos");
+        let sot = insert_photo(&other, other_source, "/nas/photos/a.jpg", "aaa", 100);
+        let dup = insert_photo(&other, other_source, "/nas/photos/a_copy.jpg", "bbb", 100);
+        other
+            .execute(
+                "INSERT INTO duplicate_groups (source_of_truth_id, confidence) VALUES (?1, 'Certain')",
+                params![sot],
+            )
+            .unwrap();
+        let group_id = other.last_insert_rowid();
+        other
+            .execute(
+                "INSERT INTO group_members (group_id, photo_id) VALUES (?1, ?2), (?1, ?3)",
+                params![group_id, sot, dup],
+            )
+            .unwrap();
+
+        let report = ingest_catalog(&local, &other).unwrap();
+        assert_eq!(report.groups_added, 1);
+        assert_eq!(report.group_members_added, 2);
+
+        let members: i64 = local.query_row("SELECT COUNT(*) FROM group_members", [], |row| row.get(0)).unwrap();
+        assert_eq!(members, 2);
+    }
+
+    #[test]
+    fn test_ingest_is_idempotent_when_run_twice() {
+        let local = conn_with_schema();
+        let other = conn_with_schema();
+        let source = insert_source(&other, "/nas/photos");
+        insert_photo(&other, source, "/nas/photos/a.jpg", "aaa", 100);
+
+        ingest_catalog(&local, &other).unwrap();
+        let second = ingest_catalog(&local, &other).unwrap();
+        assert_eq!(second.photos_added, 0);
+        assert_eq!(second.photos_skipped, 1);
+
+        let count: i64 = local.query_row("SELECT COUNT(*) FROM photos", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 1);
+    }
+}
@@ -0,0 +1,438 @@
+//! Crash-safe journal for vault-save operations.
+//!
+//! `Vault::vault_save_inner` can touch thousands of files; a process killed
+//! partway through leaves the vault in an unknown state with no record of
+//! what had already happened. Before copying a single byte, the planned
+//! operations are written to the `vault_operations` table as a pending run;
+//! each operation is marked done as it completes. On the next `Catalog::open`,
+//! `resume_or_rollback` finds any unfinished run and either finishes it
+//! (the default — every operation here is idempotent, so re-doing one that
+//! already completed is harmless) or unwinds it back to the prior state.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection};
+
+use crate::error::Result;
+use crate::vault_save;
+
+/// One planned vault-save operation, as recorded in `vault_operations`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JournalOp {
+    /// Copy (or dedup-link) `source`'s content into the vault at `target`.
+    Copy {
+        source: PathBuf,
+        target: PathBuf,
+        sha256: String,
+        size: u64,
+    },
+    /// Remove a superseded vault file, whose bytes remain available at `canonical`.
+    Remove { path: PathBuf, canonical: PathBuf },
+    /// Collapse a superseded vault file to a hard link at `canonical`.
+    Link { target: PathBuf, canonical: PathBuf },
+}
+
+/// A journaled operation together with its row state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalEntry {
+    pub seq: i64,
+    pub op: JournalOp,
+    pub done: bool,
+}
+
+/// What `resume_or_rollback` did with a stale run, if it found one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JournalOutcome {
+    /// No unfinished run was found — nothing to do.
+    Clean,
+    /// Finished the remaining (not-yet-done) operations of a prior run.
+    Resumed { run_id: String, completed: usize },
+    /// Unwound a prior run's completed operations back to the prior state.
+    RolledBack { run_id: String, undone: usize },
+}
+
+fn op_type_str(op: &JournalOp) -> &'static str {
+    match op {
+        JournalOp::Copy { .. } => "copy",
+        JournalOp::Remove { .. } => "remove",
+        JournalOp::Link { .. } => "link",
+    }
+}
+
+/// Write `ops` as a new pending run, so a crash after this point has a
+/// durable record of exactly what was planned. Call before any op in `ops`
+/// is carried out.
+pub fn begin_run(conn: &Connection, run_id: &str, ops: &[JournalOp]) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    {
+        let mut stmt = tx.prepare(
+            "INSERT INTO vault_operations (run_id, seq, op_type, path_a, path_b, sha256, size, done)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 0)",
+        )?;
+        for (seq, op) in ops.iter().enumerate() {
+            let seq = seq as i64;
+            match op {
+                JournalOp::Copy {
+                    source,
+                    target,
+                    sha256,
+                    size,
+                } => {
+                    stmt.execute(params![
+                        run_id,
+                        seq,
+                        op_type_str(op),
+                        source.to_string_lossy(),
+                        target.to_string_lossy(),
+                        sha256,
+                        *size as i64,
+                    ])?;
+                }
+                JournalOp::Remove { path, canonical } => {
+                    stmt.execute(params![
+                        run_id,
+                        seq,
+                        op_type_str(op),
+                        path.to_string_lossy(),
+                        canonical.to_string_lossy(),
+                        None::<String>,
+                        None::<i64>,
+                    ])?;
+                }
+                JournalOp::Link { target, canonical } => {
+                    stmt.execute(params![
+                        run_id,
+                        seq,
+                        op_type_str(op),
+                        target.to_string_lossy(),
+                        canonical.to_string_lossy(),
+                        None::<String>,
+                        None::<i64>,
+                    ])?;
+                }
+            }
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Mark one operation of `run_id` complete.
+pub fn mark_done(conn: &Connection, run_id: &str, seq: i64) -> Result<()> {
+    conn.execute(
+        "UPDATE vault_operations SET done = 1 WHERE run_id = ?1 AND seq = ?2",
+        params![run_id, seq],
+    )?;
+    Ok(())
+}
+
+/// Delete every row belonging to `run_id` — call once it's fully resolved
+/// (completed, resumed to completion, or rolled back).
+pub fn clear_run(conn: &Connection, run_id: &str) -> Result<()> {
+    conn.execute("DELETE FROM vault_operations WHERE run_id = ?1", params![run_id])?;
+    Ok(())
+}
+
+/// The `run_id` of a run left with at least one unfinished operation, if any.
+fn find_unfinished_run(conn: &Connection) -> Result<Option<String>> {
+    let run_id: Option<String> = conn
+        .query_row(
+            "SELECT run_id FROM vault_operations WHERE done = 0 ORDER BY id LIMIT 1",
+            [],
+            |row| row.get(0),
+        )
+        .ok();
+    Ok(run_id)
+}
+
+fn load_run(conn: &Connection, run_id: &str) -> Result<Vec<JournalEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT seq, op_type, path_a, path_b, sha256, size, done
+         FROM vault_operations WHERE run_id = ?1 ORDER BY seq",
+    )?;
+    let rows = stmt
+        .query_map(params![run_id], |row| {
+            let seq: i64 = row.get(0)?;
+            let op_type: String = row.get(1)?;
+            let path_a: String = row.get(2)?;
+            let path_b: Option<String> = row.get(3)?;
+            let sha256: Option<String> = row.get(4)?;
+            let size: Option<i64> = row.get(5)?;
+            let done: i64 = row.get(6)?;
+
+            let op = match op_type.as_str() {
+                "copy" => JournalOp::Copy {
+                    source: PathBuf::from(path_a),
+                    target: PathBuf::from(path_b.unwrap_or_default()),
+                    sha256: sha256.unwrap_or_default(),
+                    size: size.unwrap_or(0) as u64,
+                },
+                "remove" => JournalOp::Remove {
+                    path: PathBuf::from(path_a),
+                    canonical: PathBuf::from(path_b.unwrap_or_default()),
+                },
+                _ => JournalOp::Link {
+                    target: PathBuf::from(path_a),
+                    canonical: PathBuf::from(path_b.unwrap_or_default()),
+                },
+            };
+
+            Ok(JournalEntry {
+                seq,
+                op,
+                done: done != 0,
+            })
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Finish (resume) the not-yet-done operations of a crashed run. Every
+/// journaled op is idempotent — re-copying an already-present object or
+/// re-removing an already-gone file is a no-op — so resuming is always
+/// safe, which is why it's the default `Vault::open` takes.
+fn resume_run(conn: &Connection, vault_path: &Path, run_id: &str, entries: &[JournalEntry]) -> Result<usize> {
+    let mut completed = 0;
+    for entry in entries {
+        if entry.done {
+            continue;
+        }
+        let ok = match &entry.op {
+            JournalOp::Copy {
+                source,
+                target,
+                sha256,
+                size,
+            } => vault_save::copy_photo_to_vault(
+                vault_path,
+                source,
+                sha256,
+                target,
+                *size,
+                vault_save::VerifyMode::SizeOnly,
+            )
+            .is_ok(),
+            JournalOp::Remove { path, .. } => !path.exists() || fs::remove_file(path).is_ok(),
+            JournalOp::Link { target, canonical } => vault_save::make_hard_link(target, canonical).is_ok(),
+        };
+        if ok {
+            mark_done(conn, run_id, entry.seq)?;
+            completed += 1;
+        }
+    }
+    clear_run(conn, run_id)?;
+    Ok(completed)
+}
+
+/// Unwind a crashed run's already-completed operations back to the state
+/// before it started.
+///
+/// - A completed `Copy` is undone by removing its `target` display link —
+///   the object bytes are left in place (content-addressed, so they may be
+///   shared with another photo; deleting them isn't safe to assume here).
+/// - A completed `Remove` is undone by re-linking `path` to the still-live
+///   `canonical`, restoring the path (its bytes, byte-identical to the
+///   source-of-truth, are recoverable this way even though the original
+///   inode is gone).
+/// - A completed `Link` left `target` pointing at `canonical`, which is
+///   already the vault's desired end state for that content — there's
+///   nothing incorrect to unwind, so it's left as-is.
+fn rollback_run(conn: &Connection, run_id: &str, entries: &[JournalEntry]) -> Result<usize> {
+    let mut undone = 0;
+    for entry in entries.iter().rev() {
+        if !entry.done {
+            continue;
+        }
+        match &entry.op {
+            JournalOp::Copy { target, .. } => {
+                if target.exists() {
+                    let _ = fs::remove_file(target);
+                }
+                undone += 1;
+            }
+            JournalOp::Remove { path, canonical } => {
+                if !path.exists() {
+                    let _ = vault_save::make_hard_link(path, canonical);
+                }
+                undone += 1;
+            }
+            JournalOp::Link { .. } => {}
+        }
+    }
+    clear_run(conn, run_id)?;
+    Ok(undone)
+}
+
+/// Detect an unfinished vault-save run left by a prior process and resolve
+/// it — by default, finishing the operations it hadn't gotten to yet
+/// (`rollback = false`); pass `rollback = true` to unwind the run's
+/// completed operations back to the prior state instead (e.g. a future
+/// `--rollback` flag for a user who'd rather undo a partial run than let it
+/// finish). Called once from `Catalog::open`, before the catalog is handed
+/// back to its caller.
+pub fn resume_or_rollback(conn: &Connection, vault_path: &Path, rollback: bool) -> Result<JournalOutcome> {
+    let Some(run_id) = find_unfinished_run(conn)? else {
+        return Ok(JournalOutcome::Clean);
+    };
+
+    let entries = load_run(conn, &run_id)?;
+    if rollback {
+        let undone = rollback_run(conn, &run_id, &entries)?;
+        Ok(JournalOutcome::RolledBack { run_id, undone })
+    } else {
+        let completed = resume_run(conn, vault_path, &run_id, &entries)?;
+        Ok(JournalOutcome::Resumed { run_id, completed })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conn_with_schema() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::catalog::schema::initialize(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_find_unfinished_run_none_when_empty() {
+        let conn = conn_with_schema();
+        assert_eq!(find_unfinished_run(&conn).unwrap(), None);
+    }
+
+    #[test]
+    fn test_begin_run_then_find_unfinished_run() {
+        let conn = conn_with_schema();
+        let ops = vec![JournalOp::Remove {
+            path: PathBuf::from("/vault/2024/01/01/a.jpg"),
+            canonical: PathBuf::from("/vault/2024/01/01/b.jpg"),
+        }];
+        begin_run(&conn, "run-1", &ops).unwrap();
+        assert_eq!(find_unfinished_run(&conn).unwrap(), Some("run-1".to_string()));
+    }
+
+    #[test]
+    fn test_mark_done_and_clear_run() {
+        let conn = conn_with_schema();
+        let ops = vec![JournalOp::Remove {
+            path: PathBuf::from("/vault/2024/01/01/a.jpg"),
+            canonical: PathBuf::from("/vault/2024/01/01/b.jpg"),
+        }];
+        begin_run(&conn, "run-1", &ops).unwrap();
+        mark_done(&conn, "run-1", 0).unwrap();
+        assert_eq!(find_unfinished_run(&conn).unwrap(), None);
+
+        clear_run(&conn, "run-1").unwrap();
+        let entries = load_run(&conn, "run-1").unwrap();
+        assert!(entries.is_empty());
+    }
+
+    #[test]
+    fn test_resume_or_rollback_clean_when_no_runs() {
+        let conn = conn_with_schema();
+        let outcome = resume_or_rollback(&conn, Path::new("/vault"), false).unwrap();
+        assert_eq!(outcome, JournalOutcome::Clean);
+    }
+
+    #[test]
+    fn test_resume_or_rollback_resumes_pending_remove() {
+        let tmp = tempfile::tempdir().unwrap();
+        let vault_path = tmp.path();
+        let superseded = vault_path.join("superseded.jpg");
+        let canonical = vault_path.join("canonical.jpg");
+        fs::write(&superseded, b"dup").unwrap();
+        fs::write(&canonical, b"dup").unwrap();
+
+        let conn = conn_with_schema();
+        let ops = vec![JournalOp::Remove {
+            path: superseded.clone(),
+            canonical: canonical.clone(),
+        }];
+        begin_run(&conn, "run-1", &ops).unwrap();
+
+        let outcome = resume_or_rollback(&conn, vault_path, false).unwrap();
+        assert_eq!(
+            outcome,
+            JournalOutcome::Resumed {
+                run_id: "run-1".to_string(),
+                completed: 1
+            }
+        );
+        assert!(!superseded.exists());
+        assert_eq!(find_unfinished_run(&conn).unwrap(), None);
+    }
+
+    #[test]
+    fn test_resume_or_rollback_rollback_restores_removed_path() {
+        let tmp = tempfile::tempdir().unwrap();
+        let vault_path = tmp.path();
+        let canonical = vault_path.join("canonical.jpg");
+        let superseded = vault_path.join("superseded.jpg");
+        fs::write(&canonical, b"dup").unwrap();
+
+        let conn = conn_with_schema();
+        // The second op is still pending, so the run counts as unfinished.
+        let ops = vec![
+            JournalOp::Remove {
+                path: superseded.clone(),
+                canonical: canonical.clone(),
+            },
+            JournalOp::Remove {
+                path: vault_path.join("pending.jpg"),
+                canonical: canonical.clone(),
+            },
+        ];
+        begin_run(&conn, "run-1", &ops).unwrap();
+        mark_done(&conn, "run-1", 0).unwrap();
+
+        let outcome = resume_or_rollback(&conn, vault_path, true).unwrap();
+        assert_eq!(
+            outcome,
+            JournalOutcome::RolledBack {
+                run_id: "run-1".to_string(),
+                undone: 1
+            }
+        );
+        assert!(superseded.exists(), "rollback re-links the removed path from canonical");
+    }
+
+    #[test]
+    fn test_resume_or_rollback_rollback_undoes_completed_copy() {
+        let tmp = tempfile::tempdir().unwrap();
+        let vault_path = tmp.path();
+        let target = vault_path.join("2024/01/01/photo.jpg");
+        fs::create_dir_all(target.parent().unwrap()).unwrap();
+        fs::write(&target, b"data").unwrap();
+
+        let conn = conn_with_schema();
+        // The second op is still pending, so the run counts as unfinished.
+        let ops = vec![
+            JournalOp::Copy {
+                source: PathBuf::from("/src/photo.jpg"),
+                target: target.clone(),
+                sha256: "abc123".to_string(),
+                size: 4,
+            },
+            JournalOp::Copy {
+                source: PathBuf::from("/src/pending.jpg"),
+                target: vault_path.join("2024/01/01/pending.jpg"),
+                sha256: "def456".to_string(),
+                size: 4,
+            },
+        ];
+        begin_run(&conn, "run-1", &ops).unwrap();
+        mark_done(&conn, "run-1", 0).unwrap();
+
+        let outcome = resume_or_rollback(&conn, vault_path, true).unwrap();
+        assert_eq!(
+            outcome,
+            JournalOutcome::RolledBack {
+                run_id: "run-1".to_string(),
+                undone: 1
+            }
+        );
+        assert!(!target.exists());
+    }
+}
@@ -1,20 +1,145 @@
+pub mod bktree;
+pub mod events;
+pub mod ingest;
+pub mod journal;
+pub mod phash_cache;
+pub mod query_lang;
+pub mod replication;
 pub mod schema;
 
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex};
 
+use regex::RegexSet;
 use rusqlite::{params, Connection};
 
 use crate::domain::*;
 use crate::error::{Error, Result};
+use bktree::BkTree;
+use phash_cache::PhashCache;
 
 /// SQLite-backed catalog for photo metadata and duplicate groups.
 pub struct Catalog {
     conn: Connection,
+    /// In-memory BK-tree indexes over `phash`/`dhash`/`ahash`, rebuilt from the
+    /// database on `open` and kept in sync by `upsert_photo` et al. — see
+    /// `find_similar`. Not persisted: rebuilding is a single pass over
+    /// `list_all_photos` and far cheaper than keeping a serialized tree
+    /// consistent across every code path that writes `photos`.
+    phash_index: RefCell<BkTree>,
+    dhash_index: RefCell<BkTree>,
+    ahash_index: RefCell<BkTree>,
+    /// Write-back LRU cache over `sha256 -> (phash, dhash, ahash)`, fronting
+    /// `get_phashes_by_sha256s` — see `phash_cache`.
+    phash_cache: RefCell<PhashCache>,
+    /// Held for the duration of `gc`, so two overlapping GC passes against
+    /// the same catalog never race each other over the same rows.
+    gc_lock: Mutex<()>,
+    /// Live `watch`/`watch_since` subscribers — see `emit_event`. A sender
+    /// whose receiver has hung up is dropped the next time an event fires
+    /// rather than eagerly, since there's no cheaper way to notice than
+    /// trying to send.
+    event_subscribers: Mutex<Vec<mpsc::Sender<events::CatalogEvent>>>,
+}
+
+/// Result of a `Catalog::gc` pass — see `gc`.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct GcReport {
+    /// `group_members` rows whose `photo_id` no longer existed.
+    pub orphaned_members: usize,
+    /// `duplicate_groups` dropped for having fewer than two surviving
+    /// members, or a dangling `source_of_truth_id`.
+    pub orphaned_groups: usize,
+    /// Photos left over from a `sources` row that no longer exists. Counted
+    /// but not deleted — a removed source should take its photos with it via
+    /// `remove_source`, so a surviving orphan points at a bug or an
+    /// interrupted removal rather than something `gc` should guess about.
+    pub orphaned_photos: usize,
+}
+
+/// Result of `Catalog::diff_source` — the changeset needed to bring a
+/// source's catalog entries back in sync with what's actually on disk.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SourceDiff {
+    /// On disk, not yet cataloged.
+    pub added: Vec<PathBuf>,
+    /// Cataloged, but the observed mtime is newer than the stored one.
+    pub modified: Vec<PathBuf>,
+    /// Cataloged for this source, but absent from the observed filesystem.
+    pub deleted: Vec<PathBuf>,
+}
+
+/// Filter criteria for `Catalog::search`. Every field is optional; an unset
+/// field places no constraint on the result. `text` is pushed down to the
+/// `photos_fts` FTS5 index rather than a `LIKE` scan over camera make/model,
+/// so it stays fast as the catalog grows; every other field is an ordinary
+/// indexed `WHERE` clause.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PhotoQuery {
+    /// Free-text match against `exif_camera_make`/`exif_camera_model`.
+    pub text: Option<String>,
+    /// Exact match against `exif_camera_make`, unlike `text`'s fuzzy FTS
+    /// match — use this when the caller already knows the precise value.
+    pub camera_make: Option<String>,
+    /// Exact match against `exif_camera_model`.
+    pub camera_model: Option<String>,
+    pub format: Option<PhotoFormat>,
+    /// Inclusive lower bound on `exif_date` (string comparison — EXIF dates
+    /// are already stored in a sortable format).
+    pub exif_date_from: Option<String>,
+    /// Inclusive upper bound on `exif_date`.
+    pub exif_date_to: Option<String>,
+    pub size_min: Option<u64>,
+    pub size_max: Option<u64>,
+    /// Inclusive GPS bounding box: `(min_lat, min_lon, max_lat, max_lon)`.
+    pub bbox: Option<(f64, f64, f64, f64)>,
+    /// Sort order for the result set. Unset leaves rows in SQLite's default
+    /// (unspecified) order, matching `search`'s prior behavior.
+    pub order_by: Option<SearchOrderBy>,
+}
+
+/// Sort order for `Catalog::search` results — see `PhotoQuery::order_by`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchOrderBy {
+    ExifDateAsc,
+    ExifDateDesc,
+    SizeAsc,
+    SizeDesc,
+    PathAsc,
+    PathDesc,
+}
+
+impl SearchOrderBy {
+    fn as_sql(&self) -> &'static str {
+        match self {
+            SearchOrderBy::ExifDateAsc => "photos.exif_date ASC",
+            SearchOrderBy::ExifDateDesc => "photos.exif_date DESC",
+            SearchOrderBy::SizeAsc => "photos.size ASC",
+            SearchOrderBy::SizeDesc => "photos.size DESC",
+            SearchOrderBy::PathAsc => "photos.path ASC",
+            SearchOrderBy::PathDesc => "photos.path DESC",
+        }
+    }
+}
+
+/// Current Unix timestamp (seconds), used to stamp `updated_at`/`added_at`
+/// columns so `Catalog::export_delta` can select only what changed recently.
+pub(crate) fn current_timestamp() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
 }
 
 impl Catalog {
     /// Open or create a catalog at the given path with WAL mode.
+    ///
+    /// If a prior process crashed mid-`vault_save` and left an unfinished
+    /// journal entry (see `journal::resume_or_rollback`), this finishes it
+    /// before returning — every journaled operation is idempotent, so
+    /// completing one that already ran is harmless.
     pub fn open(path: &Path) -> Result<Self> {
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)?;
@@ -24,7 +149,30 @@ impl Catalog {
         conn.pragma_update(None, "foreign_keys", "ON")?;
         schema::initialize(&conn)?;
         schema::migrate(&conn)?;
-        Ok(Self { conn })
+        Self::resume_pending_vault_save(&conn)?;
+        let catalog = Self {
+            conn,
+            phash_index: RefCell::new(BkTree::new()),
+            dhash_index: RefCell::new(BkTree::new()),
+            ahash_index: RefCell::new(BkTree::new()),
+            phash_cache: RefCell::new(PhashCache::new(phash_cache::DEFAULT_CAPACITY)),
+            gc_lock: Mutex::new(()),
+            event_subscribers: Mutex::new(Vec::new()),
+        };
+        catalog.rebuild_hash_indexes()?;
+        Ok(catalog)
+    }
+
+    /// Run `journal::resume_or_rollback` if a vault path is configured —
+    /// there's nothing to resume against otherwise.
+    fn resume_pending_vault_save(conn: &Connection) -> Result<()> {
+        let vault_path: Option<String> = conn
+            .query_row("SELECT value FROM config WHERE key = 'vault_path'", [], |row| row.get(0))
+            .ok();
+        if let Some(vault_path) = vault_path {
+            journal::resume_or_rollback(conn, Path::new(&vault_path), false)?;
+        }
+        Ok(())
     }
 
     /// Open an in-memory catalog (for testing).
@@ -33,12 +181,278 @@ impl Catalog {
         conn.pragma_update(None, "foreign_keys", "ON")?;
         schema::initialize(&conn)?;
         schema::migrate(&conn)?;
-        Ok(Self { conn })
+        Ok(Self {
+            conn,
+            phash_index: RefCell::new(BkTree::new()),
+            dhash_index: RefCell::new(BkTree::new()),
+            ahash_index: RefCell::new(BkTree::new()),
+            phash_cache: RefCell::new(PhashCache::new(phash_cache::DEFAULT_CAPACITY)),
+            gc_lock: Mutex::new(()),
+            event_subscribers: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Populate the in-memory pHash/dHash/aHash BK-tree indexes from every
+    /// cataloged photo. Called on `open` and again after `ingest` merges in
+    /// another catalog's photos; a freshly-created catalog has nothing to
+    /// index yet, so this is a no-op there.
+    fn rebuild_hash_indexes(&self) -> Result<()> {
+        let mut phash_index = BkTree::new();
+        let mut dhash_index = BkTree::new();
+        let mut ahash_index = BkTree::new();
+        for photo in self.list_all_photos()? {
+            if let Some(phash) = photo.phash {
+                phash_index.insert(photo.id, phash);
+            }
+            if let Some(dhash) = photo.dhash {
+                dhash_index.insert(photo.id, dhash);
+            }
+            if let Some(ahash) = photo.ahash {
+                ahash_index.insert(photo.id, ahash);
+            }
+        }
+        *self.phash_index.borrow_mut() = phash_index;
+        *self.dhash_index.borrow_mut() = dhash_index;
+        *self.ahash_index.borrow_mut() = ahash_index;
+        Ok(())
+    }
+
+    /// Find photos whose pHash is within `max_distance` of `hash`, via the
+    /// in-memory BK-tree index — near-logarithmic per lookup instead of the
+    /// O(n^2) pairwise scan `cluster_by_hamming` needs to group a whole
+    /// catalog. Returns `(photo_id, distance)` pairs in tree-traversal
+    /// order; they are not sorted by distance.
+    pub fn find_similar(&self, hash: u64, max_distance: u32) -> Result<Vec<(i64, u32)>> {
+        Ok(self.phash_index.borrow().find_similar(hash, max_distance))
+    }
+
+    /// Same as `find_similar`, but against the dHash index.
+    pub fn find_similar_dhash(&self, hash: u64, max_distance: u32) -> Result<Vec<(i64, u32)>> {
+        Ok(self.dhash_index.borrow().find_similar(hash, max_distance))
+    }
+
+    /// Same as `find_similar`, but against the aHash index.
+    pub fn find_similar_ahash(&self, hash: u64, max_distance: u32) -> Result<Vec<(i64, u32)>> {
+        Ok(self.ahash_index.borrow().find_similar(hash, max_distance))
+    }
+
+    /// Find every pair of photos whose pHash is within `max_distance` of each
+    /// other across the whole catalog — the whole-catalog counterpart to
+    /// `find_similar`'s single-hash lookup. Builds a fresh BK-tree over every
+    /// non-null `phash` and walks it once per photo, which is near-linear
+    /// rather than the O(n^2) pairwise scan `perceptual::cluster_by_hamming`
+    /// needs for the same job, so it stays usable as the catalog grows into
+    /// the hundreds of thousands of photos.
+    ///
+    /// Each unordered pair is reported once, lower id first. Turning these
+    /// candidates into actual duplicate groups (and deciding what
+    /// `Confidence` to assign) is left to the caller via `insert_group`.
+    pub fn find_similar_candidates(&self, max_distance: u32) -> Result<Vec<(i64, i64, u32)>> {
+        let photos = self.list_all_photos()?;
+
+        let mut tree = BkTree::new();
+        for photo in &photos {
+            if let Some(phash) = photo.phash {
+                tree.insert(photo.id, phash);
+            }
+        }
+
+        let mut pairs = Vec::new();
+        for photo in &photos {
+            let Some(phash) = photo.phash else { continue };
+            for (other_id, distance) in tree.find_similar(phash, max_distance) {
+                if other_id > photo.id {
+                    pairs.push((photo.id, other_id, distance));
+                }
+            }
+        }
+        Ok(pairs)
+    }
+
+    // ── Replication ──────────────────────────────────────────────────
+
+    /// Collect every photo, group membership, and tombstone recorded at or
+    /// after `since` (a Unix timestamp), ready to send to a peer catalog via
+    /// `merge_delta`. See `replication` for the merge semantics.
+    pub fn export_delta(&self, since: i64) -> Result<replication::CatalogDelta> {
+        replication::export_delta(&self.conn, since)
+    }
+
+    /// Merge a peer's delta into this catalog. Every row it writes is
+    /// stamped with the current time, so re-exporting this catalog later
+    /// forwards the merge to a third peer instead of silently dropping it.
+    pub fn merge_delta(&self, delta: replication::CatalogDelta) -> Result<replication::MergeStats> {
+        replication::merge_delta(&self.conn, &delta, current_timestamp())
+    }
+
+    // ── Ingest ───────────────────────────────────────────────────────
+
+    /// Merge another catalog database file into this one — the one-shot
+    /// counterpart to `export_delta`/`merge_delta`'s incremental replication,
+    /// for combining two catalogs that were scanned independently (e.g. on
+    /// two different machines) rather than kept in sync over time. See
+    /// `ingest` for the matching rules.
+    ///
+    /// `other_path` is never written to: it's copied to a temporary file
+    /// first, which is opened and migrated to the current schema version (if
+    /// it's on an older one) so `ingest_catalog` never has to deal with a
+    /// stale schema. The copy is removed again once the merge completes.
+    pub fn ingest(&self, other_path: &Path) -> Result<ingest::IngestReport> {
+        let tmp_path = other_path.with_extension(format!("ingest-{}.tmp", std::process::id()));
+        std::fs::copy(other_path, &tmp_path)?;
+        let result = (|| {
+            let other = Self::open(&tmp_path)?;
+            let report = ingest::ingest_catalog(&self.conn, &other.conn)?;
+            self.rebuild_hash_indexes()?;
+            Ok(report)
+        })();
+        let _ = std::fs::remove_file(&tmp_path);
+        let _ = std::fs::remove_file(PathBuf::from(format!("{}-wal", tmp_path.display())));
+        let _ = std::fs::remove_file(PathBuf::from(format!("{}-shm", tmp_path.display())));
+        result
+    }
+
+    // ── Change feed ────────────────────────────────────────────────────
+
+    /// Subscribe to every `CatalogEvent` committed from here on — see
+    /// `events`. Nothing already in the catalog is replayed; use
+    /// `watch_since` to catch up on history first.
+    pub fn watch(&self) -> mpsc::Receiver<events::CatalogEvent> {
+        let (tx, rx) = mpsc::channel();
+        self.event_subscribers.lock().unwrap().push(tx);
+        rx
+    }
+
+    /// Replay every event committed after `since_id` (a cursor the caller
+    /// persisted from a prior event's `events.id`, 0 to replay everything),
+    /// then subscribe to new ones as they commit. The replay and the
+    /// subscription happen under the same lock an emitting write also takes
+    /// (see `emit_event`), so no event committed concurrently can slip
+    /// through the gap between the two and be missed or delivered twice.
+    pub fn watch_since(&self, since_id: i64) -> Result<mpsc::Receiver<events::CatalogEvent>> {
+        let (tx, rx) = mpsc::channel();
+        let mut subscribers = self.event_subscribers.lock().unwrap();
+        for event in events::events_since(&self.conn, since_id)? {
+            let _ = tx.send(event);
+        }
+        subscribers.push(tx);
+        Ok(rx)
+    }
+
+    /// Notify every live `watch`/`watch_since` subscriber of an event whose
+    /// row has already committed. Called only after the transaction that
+    /// wrote it commits, so a subscriber never observes an event for a write
+    /// that was rolled back.
+    fn emit_event(&self, event: events::CatalogEvent) {
+        let mut subscribers = self.event_subscribers.lock().unwrap();
+        subscribers.retain(|tx| tx.send(event.clone()).is_ok());
+    }
+
+    // ── Garbage collection ────────────────────────────────────────────
+
+    /// Repair dangling references left behind by a crash or a bug, and
+    /// reclaim the freed disk space. `remove_source`/`remove_photos_by_paths`
+    /// hand-roll their own cascading cleanup inline, which is easy to get
+    /// subtly wrong; `gc` is the backstop that catches whatever they (or a
+    /// future write path) miss.
+    ///
+    /// Guarded by `gc_lock` so only one pass runs against this catalog at a
+    /// time — a concurrent `gc()` call blocks on the mutex rather than
+    /// racing the first over the same rows. Deletes run in a single
+    /// transaction; `VACUUM` and the WAL checkpoint that actually reclaim
+    /// the space run afterward, since SQLite refuses to `VACUUM` inside a
+    /// transaction.
+    pub fn gc(&self) -> Result<GcReport> {
+        let _guard = self.gc_lock.lock().unwrap();
+
+        let tx = self.conn.unchecked_transaction()?;
+
+        let orphaned_members = tx.execute(
+            "DELETE FROM group_members WHERE photo_id NOT IN (SELECT id FROM photos)",
+            [],
+        )?;
+
+        // A group is doomed once it has a dangling source_of_truth_id or
+        // fewer than two surviving members — re-evaluated fresh for each
+        // DELETE below, so the second statement sees the first's effects.
+        const DOOMED_GROUPS: &str = "SELECT id FROM duplicate_groups
+             WHERE source_of_truth_id NOT IN (SELECT id FROM photos)
+                OR id NOT IN (SELECT group_id FROM group_members GROUP BY group_id HAVING COUNT(*) >= 2)";
+
+        tx.execute(
+            &format!("DELETE FROM group_members WHERE group_id IN ({DOOMED_GROUPS})"),
+            [],
+        )?;
+        let orphaned_groups = tx.execute(
+            &format!("DELETE FROM duplicate_groups WHERE id IN ({DOOMED_GROUPS})"),
+            [],
+        )?;
+
+        let orphaned_photos: i64 = tx.query_row(
+            "SELECT COUNT(*) FROM photos WHERE source_id NOT IN (SELECT id FROM sources)",
+            [],
+            |row| row.get(0),
+        )?;
+
+        tx.commit()?;
+
+        self.conn.execute_batch("VACUUM;")?;
+        self.conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")?;
+
+        Ok(GcReport {
+            orphaned_members,
+            orphaned_groups,
+            orphaned_photos: orphaned_photos as usize,
+        })
+    }
+
+    // ── Perceptual hash cache ────────────────────────────────────────
+
+    /// Resize the `phash_cache` LRU, evicting (and flushing, if dirty) down
+    /// to the new capacity if it's smaller than the current entry count.
+    /// `phash_cache::DEFAULT_CAPACITY` is used until this is called.
+    pub fn set_phash_cache_capacity(&self, capacity: usize) -> Result<()> {
+        self.phash_cache.borrow_mut().set_capacity(&self.conn, capacity)
+    }
+
+    /// Force every dirty `phash_cache` entry to back-fill the `photos` rows
+    /// that share its content hash but are still missing their own phash.
+    /// Normally unnecessary — eviction already flushes a dirty entry before
+    /// dropping it — but useful to call after a bulk `upsert_photos_batch`
+    /// to make the backfill visible to a query run outside this `Catalog`.
+    /// Returns the number of entries flushed.
+    pub fn flush_phash_cache(&self) -> Result<usize> {
+        self.phash_cache.borrow_mut().flush(&self.conn)
+    }
+
+    // ── Vault operations journal ─────────────────────────────────────
+
+    /// Record `ops` as a new pending vault-save run, so a crash partway
+    /// through executing them leaves a durable trail. See `journal::begin_run`.
+    pub fn journal_begin_run(&self, run_id: &str, ops: &[journal::JournalOp]) -> Result<()> {
+        journal::begin_run(&self.conn, run_id, ops)
+    }
+
+    /// Mark one operation of `run_id` complete.
+    pub fn journal_mark_done(&self, run_id: &str, seq: i64) -> Result<()> {
+        journal::mark_done(&self.conn, run_id, seq)
+    }
+
+    /// Delete every row of a fully-resolved run.
+    pub fn journal_clear_run(&self, run_id: &str) -> Result<()> {
+        journal::clear_run(&self.conn, run_id)
     }
 
     // ── Sources ──────────────────────────────────────────────────────
 
     pub fn add_source(&self, path: &Path) -> Result<Source> {
+        self.add_source_with_role(path, SourceRole::Standard)
+    }
+
+    /// Register a source with an explicit role. A `Reference` source is a
+    /// curated archive: during grouping its photos always win source-of-truth
+    /// over `Standard` sources (see `Vault::scan`'s ranking phase).
+    pub fn add_source_with_role(&self, path: &Path, role: SourceRole) -> Result<Source> {
         let canonical = path.canonicalize()?;
         let path_str = canonical.to_string_lossy();
 
@@ -56,28 +470,60 @@ impl Catalog {
             return Err(Error::SourceAlreadyExists(canonical));
         }
 
-        self.conn.execute(
-            "INSERT INTO sources (path) VALUES (?1)",
-            params![path_str.as_ref()],
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT INTO sources (path, role) VALUES (?1, ?2)",
+            params![path_str.as_ref(), role.as_str()],
         )?;
-        let id = self.conn.last_insert_rowid();
+        let id = tx.last_insert_rowid();
+        events::record_event(&tx, &events::CatalogEvent::SourceAdded { id }, current_timestamp())?;
+        tx.commit()?;
+
+        self.emit_event(events::CatalogEvent::SourceAdded { id });
         Ok(Source {
             id,
             path: canonical,
             last_scanned: None,
+            role,
         })
     }
 
+    /// Set (or clear) a registered source's reference role.
+    pub fn set_source_role(&self, path: &Path, role: SourceRole) -> Result<()> {
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let path_str = canonical.to_string_lossy();
+        let updated = self.conn.execute(
+            "UPDATE sources SET role = ?1 WHERE path = ?2",
+            params![role.as_str(), path_str.as_ref()],
+        )?;
+        if updated == 0 {
+            return Err(Error::SourceNotRegistered(canonical));
+        }
+        Ok(())
+    }
+
+    /// IDs of every source currently marked `Reference`.
+    pub fn reference_source_ids(&self) -> Result<std::collections::HashSet<i64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id FROM sources WHERE role = ?1")?;
+        let ids = stmt
+            .query_map(params![SourceRole::Reference.as_str()], |row| row.get(0))?
+            .collect::<std::result::Result<std::collections::HashSet<i64>, _>>()?;
+        Ok(ids)
+    }
+
     pub fn list_sources(&self) -> Result<Vec<Source>> {
         let mut stmt = self
             .conn
-            .prepare("SELECT id, path, last_scanned FROM sources")?;
+            .prepare("SELECT id, path, last_scanned, role FROM sources")?;
         let sources = stmt
             .query_map([], |row| {
                 Ok(Source {
                     id: row.get(0)?,
                     path: PathBuf::from(row.get::<_, String>(1)?),
                     last_scanned: row.get(2)?,
+                    role: SourceRole::from_str(&row.get::<_, String>(3)?),
                 })
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
@@ -103,13 +549,14 @@ impl Catalog {
         let source: Source = self
             .conn
             .query_row(
-                "SELECT id, path, last_scanned FROM sources WHERE path = ?1",
+                "SELECT id, path, last_scanned, role FROM sources WHERE path = ?1",
                 params![path_str.as_ref()],
                 |row| {
                     Ok(Source {
                         id: row.get(0)?,
                         path: PathBuf::from(row.get::<_, String>(1)?),
                         last_scanned: row.get(2)?,
+                        role: SourceRole::from_str(&row.get::<_, String>(3)?),
                     })
                 },
             )
@@ -169,6 +616,7 @@ impl Catalog {
 
         let path_strs: Vec<String> = paths.iter().map(|p| p.to_string_lossy().into_owned()).collect();
         let mut total_removed = 0usize;
+        let now = current_timestamp();
 
         // Process in chunks to respect SQLite variable limits
         for chunk in path_strs.chunks(500) {
@@ -182,6 +630,16 @@ impl Catalog {
                 .map(|s| s as &dyn rusqlite::types::ToSql)
                 .collect();
 
+            // Ids of the photos this chunk is about to remove, captured
+            // before any deletes run, so `PhotoRemoved` can be emitted for
+            // each once they're actually gone.
+            let removed_ids: Vec<i64> = {
+                let mut stmt =
+                    self.conn.prepare(&format!("SELECT id FROM photos WHERE path IN ({placeholders})"))?;
+                stmt.query_map(params.as_slice(), |row| row.get(0))?
+                    .collect::<std::result::Result<Vec<_>, _>>()?
+            };
+
             // Delete group_members for these photos
             self.conn.execute(
                 &format!(
@@ -210,22 +668,150 @@ impl Catalog {
                 params.as_slice(),
             )?;
 
+            // Tombstone the photos before deleting them, so `export_delta`
+            // propagates the removal instead of a peer resurrecting it from
+            // a stale copy on its next merge.
+            for path in chunk {
+                self.conn.execute(
+                    "INSERT INTO tombstones (kind, key, deleted_at) VALUES ('photo', ?1, ?2)
+                     ON CONFLICT(kind, key) DO UPDATE SET deleted_at = MAX(deleted_at, excluded.deleted_at)",
+                    params![path, now],
+                )?;
+            }
+
             // Delete the photos
             let removed = self.conn.execute(
                 &format!("DELETE FROM photos WHERE path IN ({placeholders})"),
                 params.as_slice(),
             )?;
             total_removed += removed;
+
+            for id in removed_ids {
+                events::record_event(&self.conn, &events::CatalogEvent::PhotoRemoved { id }, now)?;
+                self.emit_event(events::CatalogEvent::PhotoRemoved { id });
+            }
         }
 
         Ok(total_removed)
     }
 
+    /// Update a photo's recorded path after it's been moved on disk (e.g. by
+    /// `resolve::Resolution::MoveTo`). No-op if `old_path` isn't in the catalog.
+    pub fn update_photo_path(&self, old_path: &Path, new_path: &Path) -> Result<()> {
+        self.conn.execute(
+            "UPDATE photos SET path = ?1 WHERE path = ?2",
+            params![new_path.to_string_lossy().as_ref(), old_path.to_string_lossy().as_ref()],
+        )?;
+        Ok(())
+    }
+
+    /// Re-home a photo that `Vault::scan`'s move-detection pass matched by
+    /// content hash: update the row still at `old_path` in place with
+    /// `photo`'s new path/source/metadata, preserving its id. This is the
+    /// move counterpart to `upsert_photo` — where `upsert_photo` keys off the
+    /// (possibly unchanged) path, this keys off the path the row *used* to
+    /// have, so the id (and therefore its group membership and any packed
+    /// object keyed on it) survives the rename instead of being dropped and
+    /// re-inserted fresh. No-op if `old_path` isn't in the catalog.
+    pub fn rehome_photo(&self, old_path: &Path, photo: &PhotoFile) -> Result<()> {
+        let id: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT id FROM photos WHERE path = ?1",
+                params![old_path.to_string_lossy().as_ref()],
+                |row| row.get(0),
+            )
+            .ok();
+
+        self.conn.execute(
+            "UPDATE photos SET source_id=?1, path=?2, size=?3, format=?4, sha256=?5, phash=?6, dhash=?7, ahash=?8, mtime=?9,
+             exif_date=?10, exif_camera_make=?11, exif_camera_model=?12, exif_gps_lat=?13, exif_gps_lon=?14,
+             exif_width=?15, exif_height=?16, updated_at=?17
+             WHERE path=?18",
+            params![
+                photo.source_id,
+                photo.path.to_string_lossy().as_ref(),
+                photo.size as i64,
+                photo.format.as_str(),
+                photo.sha256,
+                photo.phash.map(|v| v as i64),
+                photo.dhash.map(|v| v as i64),
+                photo.ahash.map(|v| v as i64),
+                photo.mtime,
+                photo.exif.as_ref().and_then(|e| e.date.clone()),
+                photo.exif.as_ref().and_then(|e| e.camera_make.clone()),
+                photo.exif.as_ref().and_then(|e| e.camera_model.clone()),
+                photo.exif.as_ref().and_then(|e| e.gps_lat),
+                photo.exif.as_ref().and_then(|e| e.gps_lon),
+                photo.exif.as_ref().and_then(|e| e.width),
+                photo.exif.as_ref().and_then(|e| e.height),
+                current_timestamp(),
+                old_path.to_string_lossy().as_ref(),
+            ],
+        )?;
+
+        if let Some(id) = id {
+            self.index_photo_hashes(id, photo);
+            self.index_photo_fts(id, photo)?;
+            self.index_photo_phash_cache(photo)?;
+        }
+        Ok(())
+    }
+
+    /// Keep the BK-tree indexes in sync with a row just inserted or updated
+    /// under `id`. Called by every write path that touches
+    /// `phash`/`dhash`/`ahash` (`upsert_photo`, `upsert_photos_batch`,
+    /// `rehome_photo`).
+    fn index_photo_hashes(&self, id: i64, photo: &PhotoFile) {
+        if let Some(phash) = photo.phash {
+            self.phash_index.borrow_mut().insert(id, phash);
+        }
+        if let Some(dhash) = photo.dhash {
+            self.dhash_index.borrow_mut().insert(id, dhash);
+        }
+        if let Some(ahash) = photo.ahash {
+            self.ahash_index.borrow_mut().insert(id, ahash);
+        }
+    }
+
+    /// Keep `photos_fts` in sync with a row just inserted or updated under
+    /// `id`. `photos_fts` is an external-content FTS5 table, so SQLite
+    /// doesn't maintain it automatically — a plain delete-then-reinsert by
+    /// `rowid` is the standard way to refresh an external-content index
+    /// without wiring up triggers for every write path.
+    fn index_photo_fts(&self, id: i64, photo: &PhotoFile) -> Result<()> {
+        self.conn.execute("DELETE FROM photos_fts WHERE rowid = ?1", params![id])?;
+        self.conn.execute(
+            "INSERT INTO photos_fts(rowid, camera_make, camera_model) VALUES (?1, ?2, ?3)",
+            params![
+                id,
+                photo.exif.as_ref().and_then(|e| e.camera_make.clone()),
+                photo.exif.as_ref().and_then(|e| e.camera_model.clone()),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Record a freshly written hash in the write-back `phash_cache`, so a
+    /// later `get_phashes_by_sha256s` for this content doesn't need to hit
+    /// SQLite at all. Called by every write path that touches `phash`
+    /// (`upsert_photo`, `upsert_photos_batch`, `rehome_photo`) — same
+    /// convention as `index_photo_hashes`.
+    fn index_photo_phash_cache(&self, photo: &PhotoFile) -> Result<()> {
+        if let Some(phash) = photo.phash {
+            self.phash_cache
+                .borrow_mut()
+                .insert_dirty(&self.conn, &photo.sha256, phash, photo.dhash, photo.ahash)?;
+        }
+        Ok(())
+    }
+
     // ── Photos ───────────────────────────────────────────────────────
 
     pub fn upsert_photo(&self, photo: &PhotoFile) -> Result<i64> {
         let path_str = photo.path.to_string_lossy();
         let format_str = photo.format.as_str();
+        let now = current_timestamp();
 
         // Try to get existing photo by path
         let existing_id: Option<i64> = self
@@ -237,12 +823,17 @@ impl Catalog {
             )
             .ok();
 
-        if let Some(id) = existing_id {
-            self.conn.execute(
-                "UPDATE photos SET source_id=?1, size=?2, format=?3, sha256=?4, phash=?5, dhash=?6, mtime=?7,
-                 exif_date=?8, exif_camera_make=?9, exif_camera_model=?10, exif_gps_lat=?11, exif_gps_lon=?12,
-                 exif_width=?13, exif_height=?14
-                 WHERE id=?15",
+        // The row write and its `events` record share one transaction, so a
+        // rolled-back write (e.g. an orphan `source_id` foreign-key failure)
+        // never leaves behind an event for a photo that was never actually
+        // upserted — see `events`.
+        let tx = self.conn.unchecked_transaction()?;
+        let id = if let Some(id) = existing_id {
+            tx.execute(
+                "UPDATE photos SET source_id=?1, size=?2, format=?3, sha256=?4, phash=?5, dhash=?6, ahash=?7, mtime=?8,
+                 exif_date=?9, exif_camera_make=?10, exif_camera_model=?11, exif_gps_lat=?12, exif_gps_lon=?13,
+                 exif_width=?14, exif_height=?15, updated_at=?16
+                 WHERE id=?17",
                 params![
                     photo.source_id,
                     photo.size as i64,
@@ -250,6 +841,7 @@ impl Catalog {
                     photo.sha256,
                     photo.phash.map(|v| v as i64),
                     photo.dhash.map(|v| v as i64),
+                    photo.ahash.map(|v| v as i64),
                     photo.mtime,
                     photo.exif.as_ref().and_then(|e| e.date.clone()),
                     photo.exif.as_ref().and_then(|e| e.camera_make.clone()),
@@ -258,15 +850,16 @@ impl Catalog {
                     photo.exif.as_ref().and_then(|e| e.gps_lon),
                     photo.exif.as_ref().and_then(|e| e.width),
                     photo.exif.as_ref().and_then(|e| e.height),
+                    now,
                     id,
                 ],
             )?;
-            Ok(id)
+            id
         } else {
-            self.conn.execute(
-                "INSERT INTO photos (source_id, path, size, format, sha256, phash, dhash, mtime,
-                 exif_date, exif_camera_make, exif_camera_model, exif_gps_lat, exif_gps_lon, exif_width, exif_height)
-                 VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11,?12,?13,?14,?15)",
+            tx.execute(
+                "INSERT INTO photos (source_id, path, size, format, sha256, phash, dhash, ahash, mtime,
+                 exif_date, exif_camera_make, exif_camera_model, exif_gps_lat, exif_gps_lon, exif_width, exif_height, updated_at)
+                 VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11,?12,?13,?14,?15,?16,?17)",
                 params![
                     photo.source_id,
                     path_str.as_ref(),
@@ -275,6 +868,7 @@ impl Catalog {
                     photo.sha256,
                     photo.phash.map(|v| v as i64),
                     photo.dhash.map(|v| v as i64),
+                    photo.ahash.map(|v| v as i64),
                     photo.mtime,
                     photo.exif.as_ref().and_then(|e| e.date.clone()),
                     photo.exif.as_ref().and_then(|e| e.camera_make.clone()),
@@ -283,16 +877,26 @@ impl Catalog {
                     photo.exif.as_ref().and_then(|e| e.gps_lon),
                     photo.exif.as_ref().and_then(|e| e.width),
                     photo.exif.as_ref().and_then(|e| e.height),
+                    now,
                 ],
             )?;
-            Ok(self.conn.last_insert_rowid())
-        }
+            tx.last_insert_rowid()
+        };
+        events::record_event(&tx, &events::CatalogEvent::PhotoUpserted { id }, now)?;
+        tx.commit()?;
+
+        self.index_photo_hashes(id, photo);
+        self.index_photo_fts(id, photo)?;
+        self.index_photo_phash_cache(photo)?;
+        self.emit_event(events::CatalogEvent::PhotoUpserted { id });
+        Ok(id)
     }
 
     /// Upsert multiple photos in a single transaction for bulk performance.
     pub fn upsert_photos_batch(&mut self, photos: &[PhotoFile]) -> Result<Vec<i64>> {
         let tx = self.conn.transaction()?;
         let mut ids = Vec::with_capacity(photos.len());
+        let now = current_timestamp();
 
         for photo in photos {
             let path_str = photo.path.to_string_lossy();
@@ -308,10 +912,10 @@ impl Catalog {
 
             if let Some(id) = existing_id {
                 tx.execute(
-                    "UPDATE photos SET source_id=?1, size=?2, format=?3, sha256=?4, phash=?5, dhash=?6, mtime=?7,
-                     exif_date=?8, exif_camera_make=?9, exif_camera_model=?10, exif_gps_lat=?11, exif_gps_lon=?12,
-                     exif_width=?13, exif_height=?14
-                     WHERE id=?15",
+                    "UPDATE photos SET source_id=?1, size=?2, format=?3, sha256=?4, phash=?5, dhash=?6, ahash=?7, mtime=?8,
+                     exif_date=?9, exif_camera_make=?10, exif_camera_model=?11, exif_gps_lat=?12, exif_gps_lon=?13,
+                     exif_width=?14, exif_height=?15, updated_at=?16
+                     WHERE id=?17",
                     params![
                         photo.source_id,
                         photo.size as i64,
@@ -319,6 +923,7 @@ impl Catalog {
                         photo.sha256,
                         photo.phash.map(|v| v as i64),
                         photo.dhash.map(|v| v as i64),
+                        photo.ahash.map(|v| v as i64),
                         photo.mtime,
                         photo.exif.as_ref().and_then(|e| e.date.clone()),
                         photo.exif.as_ref().and_then(|e| e.camera_make.clone()),
@@ -327,15 +932,46 @@ impl Catalog {
                         photo.exif.as_ref().and_then(|e| e.gps_lon),
                         photo.exif.as_ref().and_then(|e| e.width),
                         photo.exif.as_ref().and_then(|e| e.height),
+                        now,
+                        id,
+                    ],
+                )?;
+                // Indexed directly against the fields rather than via
+                // `index_photo_hashes`, since `tx` already holds `self.conn`
+                // mutably borrowed and a method call would need all of `self`.
+                if let Some(phash) = photo.phash {
+                    self.phash_index.borrow_mut().insert(id, phash);
+                }
+                if let Some(dhash) = photo.dhash {
+                    self.dhash_index.borrow_mut().insert(id, dhash);
+                }
+                if let Some(ahash) = photo.ahash {
+                    self.ahash_index.borrow_mut().insert(id, ahash);
+                }
+                tx.execute("DELETE FROM photos_fts WHERE rowid = ?1", params![id])?;
+                tx.execute(
+                    "INSERT INTO photos_fts(rowid, camera_make, camera_model) VALUES (?1, ?2, ?3)",
+                    params![
                         id,
+                        photo.exif.as_ref().and_then(|e| e.camera_make.clone()),
+                        photo.exif.as_ref().and_then(|e| e.camera_model.clone()),
                     ],
                 )?;
+                if let Some(phash) = photo.phash {
+                    self.phash_cache.borrow_mut().insert_dirty(
+                        &tx,
+                        &photo.sha256,
+                        phash,
+                        photo.dhash,
+                        photo.ahash,
+                    )?;
+                }
                 ids.push(id);
             } else {
                 tx.execute(
-                    "INSERT INTO photos (source_id, path, size, format, sha256, phash, dhash, mtime,
-                     exif_date, exif_camera_make, exif_camera_model, exif_gps_lat, exif_gps_lon, exif_width, exif_height)
-                     VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11,?12,?13,?14,?15)",
+                    "INSERT INTO photos (source_id, path, size, format, sha256, phash, dhash, ahash, mtime,
+                     exif_date, exif_camera_make, exif_camera_model, exif_gps_lat, exif_gps_lon, exif_width, exif_height, updated_at)
+                     VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11,?12,?13,?14,?15,?16,?17)",
                     params![
                         photo.source_id,
                         path_str.as_ref(),
@@ -344,6 +980,7 @@ impl Catalog {
                         photo.sha256,
                         photo.phash.map(|v| v as i64),
                         photo.dhash.map(|v| v as i64),
+                        photo.ahash.map(|v| v as i64),
                         photo.mtime,
                         photo.exif.as_ref().and_then(|e| e.date.clone()),
                         photo.exif.as_ref().and_then(|e| e.camera_make.clone()),
@@ -352,9 +989,37 @@ impl Catalog {
                         photo.exif.as_ref().and_then(|e| e.gps_lon),
                         photo.exif.as_ref().and_then(|e| e.width),
                         photo.exif.as_ref().and_then(|e| e.height),
+                        now,
+                    ],
+                )?;
+                let id = tx.last_insert_rowid();
+                if let Some(phash) = photo.phash {
+                    self.phash_index.borrow_mut().insert(id, phash);
+                }
+                if let Some(dhash) = photo.dhash {
+                    self.dhash_index.borrow_mut().insert(id, dhash);
+                }
+                if let Some(ahash) = photo.ahash {
+                    self.ahash_index.borrow_mut().insert(id, ahash);
+                }
+                tx.execute(
+                    "INSERT INTO photos_fts(rowid, camera_make, camera_model) VALUES (?1, ?2, ?3)",
+                    params![
+                        id,
+                        photo.exif.as_ref().and_then(|e| e.camera_make.clone()),
+                        photo.exif.as_ref().and_then(|e| e.camera_model.clone()),
                     ],
                 )?;
-                ids.push(tx.last_insert_rowid());
+                if let Some(phash) = photo.phash {
+                    self.phash_cache.borrow_mut().insert_dirty(
+                        &tx,
+                        &photo.sha256,
+                        phash,
+                        photo.dhash,
+                        photo.ahash,
+                    )?;
+                }
+                ids.push(id);
             }
         }
 
@@ -375,31 +1040,111 @@ impl Catalog {
         Ok(mtime)
     }
 
-    /// Load all (path → mtime) pairs for a given source in a single query.
-    pub fn get_mtimes_for_source(&self, source_id: i64) -> Result<HashMap<PathBuf, i64>> {
+    /// Load all (path → (mtime, size)) pairs for a given source in a single
+    /// query. Used by `Vault::scan`'s fingerprint cache: a file whose mtime
+    /// *and* size both match the catalog is assumed unchanged, so its
+    /// SHA-256/perceptual hash is reused rather than recomputed. Keying on
+    /// mtime alone would miss an edit that happens to land on the same
+    /// second but changes content length.
+    pub fn get_mtimes_and_sizes_for_source(
+        &self,
+        source_id: i64,
+    ) -> Result<HashMap<PathBuf, (i64, u64)>> {
         let mut stmt = self
             .conn
-            .prepare("SELECT path, mtime FROM photos WHERE source_id = ?1")?;
+            .prepare("SELECT path, mtime, size FROM photos WHERE source_id = ?1")?;
         let rows = stmt
             .query_map(params![source_id], |row| {
-                Ok((PathBuf::from(row.get::<_, String>(0)?), row.get::<_, i64>(1)?))
+                Ok((
+                    PathBuf::from(row.get::<_, String>(0)?),
+                    (row.get::<_, i64>(1)?, row.get::<_, i64>(2)? as u64),
+                ))
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
         Ok(rows.into_iter().collect())
     }
 
+    /// Classify `observed` filesystem entries for `source_id` against what's
+    /// already cataloged, so a re-scan only has to hash what actually
+    /// changed. `observed` should already be filtered through `excludes`
+    /// upstream of this call for paths that never reach the catalog at all;
+    /// `excludes` is applied here too so a path that matches it is dropped
+    /// from consideration even if it's still sitting in `observed` (e.g. the
+    /// caller's own filtering missed it) rather than surfacing as Deleted.
+    ///
+    /// - `added`: on disk, not in the catalog.
+    /// - `modified`: in both, but the observed mtime is newer than stored.
+    /// - `deleted`: cataloged for this source, absent from `observed`.
+    pub fn diff_source(
+        &self,
+        source_id: i64,
+        observed: &[(PathBuf, i64)],
+        excludes: &RegexSet,
+    ) -> Result<SourceDiff> {
+        let cataloged = self.get_mtimes_and_sizes_for_source(source_id)?;
+        let mut diff = SourceDiff::default();
+
+        let mut seen: std::collections::HashSet<&PathBuf> = std::collections::HashSet::new();
+        for (path, mtime) in observed {
+            if excludes.is_match(&path.to_string_lossy()) {
+                // Excluded paths are treated as still present but out of
+                // scope — they must not fall through to `deleted` just
+                // because this pass skips classifying them.
+                seen.insert(path);
+                continue;
+            }
+            seen.insert(path);
+            match cataloged.get(path) {
+                None => diff.added.push(path.clone()),
+                Some((stored_mtime, _)) if mtime > stored_mtime => diff.modified.push(path.clone()),
+                Some(_) => {}
+            }
+        }
+
+        for path in cataloged.keys() {
+            if !seen.contains(path) {
+                diff.deleted.push(path.clone());
+            }
+        }
+
+        Ok(diff)
+    }
+
     /// Look up existing perceptual hashes by SHA-256 values.
-    /// Returns a map of sha256 → (phash, Option<dhash>) for entries that have phash.
-    pub fn get_phashes_by_sha256s(&self, sha256s: &[&str]) -> Result<HashMap<String, (u64, Option<u64>)>> {
+    /// Returns a map of sha256 → (phash, Option<dhash>, Option<ahash>) for
+    /// entries that have phash.
+    ///
+    /// Consults `phash_cache` first — a repeated dedup pass over the same
+    /// working set hits SQLite only for sha256s it hasn't seen before.
+    pub fn get_phashes_by_sha256s(
+        &self,
+        sha256s: &[&str],
+    ) -> Result<HashMap<String, (u64, Option<u64>, Option<u64>)>> {
         if sha256s.is_empty() {
             return Ok(HashMap::new());
         }
         let mut result = HashMap::new();
+        let mut misses: Vec<&str> = Vec::new();
+        {
+            let mut cache = self.phash_cache.borrow_mut();
+            for &sha in sha256s {
+                match cache.get(sha) {
+                    Some(hit) => {
+                        result.insert(sha.to_string(), hit);
+                    }
+                    None => misses.push(sha),
+                }
+            }
+        }
+        if misses.is_empty() {
+            return Ok(result);
+        }
+
         // Query in batches to avoid SQLite variable limits
-        for chunk in sha256s.chunks(500) {
+        for chunk in misses.chunks(500) {
             let placeholders: Vec<String> = (0..chunk.len()).map(|i| format!("?{}", i + 1)).collect();
             let sql = format!(
-                "SELECT sha256, phash, dhash FROM photos WHERE sha256 IN ({}) AND phash IS NOT NULL GROUP BY sha256",
+                "SELECT sha256, phash, dhash, ahash FROM photos WHERE sha256 IN ({}) AND phash IS NOT NULL GROUP BY sha256",
                 placeholders.join(", ")
             );
             let mut stmt = self.conn.prepare(&sql)?;
@@ -413,11 +1158,15 @@ impl Catalog {
                         row.get::<_, String>(0)?,
                         row.get::<_, i64>(1)? as u64,
                         row.get::<_, Option<i64>>(2)?.map(|v| v as u64),
+                        row.get::<_, Option<i64>>(3)?.map(|v| v as u64),
                     ))
                 })?
                 .collect::<std::result::Result<Vec<_>, _>>()?;
-            for (sha, phash, dhash) in rows {
-                result.insert(sha, (phash, dhash));
+            for (sha, phash, dhash, ahash) in rows {
+                self.phash_cache
+                    .borrow_mut()
+                    .insert_clean(&self.conn, &sha, phash, dhash, ahash)?;
+                result.insert(sha, (phash, dhash, ahash));
             }
         }
         Ok(result)
@@ -425,20 +1174,20 @@ impl Catalog {
 
     pub fn list_all_photos(&self) -> Result<Vec<PhotoFile>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, source_id, path, size, format, sha256, phash, dhash, mtime,
+            "SELECT id, source_id, path, size, format, sha256, phash, dhash, ahash, mtime,
              exif_date, exif_camera_make, exif_camera_model, exif_gps_lat, exif_gps_lon,
              exif_width, exif_height
              FROM photos",
         )?;
         let photos = stmt
             .query_map([], |row| {
-                let exif_date: Option<String> = row.get(9)?;
-                let exif_make: Option<String> = row.get(10)?;
-                let exif_model: Option<String> = row.get(11)?;
-                let exif_lat: Option<f64> = row.get(12)?;
-                let exif_lon: Option<f64> = row.get(13)?;
-                let exif_w: Option<u32> = row.get(14)?;
-                let exif_h: Option<u32> = row.get(15)?;
+                let exif_date: Option<String> = row.get(10)?;
+                let exif_make: Option<String> = row.get(11)?;
+                let exif_model: Option<String> = row.get(12)?;
+                let exif_lat: Option<f64> = row.get(13)?;
+                let exif_lon: Option<f64> = row.get(14)?;
+                let exif_w: Option<u32> = row.get(15)?;
+                let exif_h: Option<u32> = row.get(16)?;
 
                 let exif = if exif_date.is_some()
                     || exif_make.is_some()
@@ -467,69 +1216,271 @@ impl Catalog {
                     sha256: row.get(5)?,
                     phash: row.get::<_, Option<i64>>(6)?.map(|v| v as u64),
                     dhash: row.get::<_, Option<i64>>(7)?.map(|v| v as u64),
+                    ahash: row.get::<_, Option<i64>>(8)?.map(|v| v as u64),
                     exif,
-                    mtime: row.get(8)?,
+                    mtime: row.get(9)?,
                 })
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
         Ok(photos)
     }
 
-    pub fn count_photos(&self) -> Result<usize> {
-        let count: i64 = self
-            .conn
-            .query_row("SELECT COUNT(*) FROM photos", [], |row| row.get(0))?;
-        Ok(count as usize)
-    }
-
-    /// Get all catalog statistics in a single query for the status dashboard.
-    pub fn stats_summary(&self) -> Result<(usize, usize, usize)> {
-        let (photos, groups, duplicates) = self.conn.query_row(
-            "SELECT
-                (SELECT COUNT(*) FROM photos),
-                (SELECT COUNT(*) FROM duplicate_groups),
-                (SELECT COUNT(DISTINCT gm.photo_id) FROM group_members gm
-                 JOIN duplicate_groups dg ON gm.group_id = dg.id
-                 WHERE gm.photo_id != dg.source_of_truth_id)",
-            [],
-            |row| {
-                Ok((
-                    row.get::<_, i64>(0)? as usize,
-                    row.get::<_, i64>(1)? as usize,
-                    row.get::<_, i64>(2)? as usize,
-                ))
-            },
+    /// Look up every cataloged photo with the given content hash (hits
+    /// `idx_photos_sha256`), including the vault's own display copies since
+    /// the vault directory is auto-registered as a scan source. Used by
+    /// `Vault::vault_save_inner`'s move-detection pass to find a vault copy
+    /// of a photo that's since been renamed or moved at the source.
+    pub fn find_photos_by_sha256(&self, sha256: &str) -> Result<Vec<PhotoFile>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, source_id, path, size, format, sha256, phash, dhash, ahash, mtime,
+             exif_date, exif_camera_make, exif_camera_model, exif_gps_lat, exif_gps_lon,
+             exif_width, exif_height
+             FROM photos WHERE sha256 = ?1",
         )?;
-        Ok((photos, groups, duplicates))
-    }
+        let photos = stmt
+            .query_map(params![sha256], |row| {
+                let exif_date: Option<String> = row.get(10)?;
+                let exif_make: Option<String> = row.get(11)?;
+                let exif_model: Option<String> = row.get(12)?;
+                let exif_lat: Option<f64> = row.get(13)?;
+                let exif_lon: Option<f64> = row.get(14)?;
+                let exif_w: Option<u32> = row.get(15)?;
+                let exif_h: Option<u32> = row.get(16)?;
 
-    // ── Duplicate Groups ─────────────────────────────────────────────
+                let exif = if exif_date.is_some()
+                    || exif_make.is_some()
+                    || exif_model.is_some()
+                    || exif_lat.is_some()
+                {
+                    Some(ExifData {
+                        date: exif_date,
+                        camera_make: exif_make,
+                        camera_model: exif_model,
+                        gps_lat: exif_lat,
+                        gps_lon: exif_lon,
+                        width: exif_w,
+                        height: exif_h,
+                    })
+                } else {
+                    None
+                };
 
-    pub fn clear_groups(&self) -> Result<()> {
-        self.conn.execute("DELETE FROM group_members", [])?;
-        self.conn.execute("DELETE FROM duplicate_groups", [])?;
-        Ok(())
+                Ok(PhotoFile {
+                    id: row.get(0)?,
+                    source_id: row.get(1)?,
+                    path: PathBuf::from(row.get::<_, String>(2)?),
+                    size: row.get::<_, i64>(3)? as u64,
+                    format: parse_format(&row.get::<_, String>(4)?),
+                    sha256: row.get(5)?,
+                    phash: row.get::<_, Option<i64>>(6)?.map(|v| v as u64),
+                    dhash: row.get::<_, Option<i64>>(7)?.map(|v| v as u64),
+                    ahash: row.get::<_, Option<i64>>(8)?.map(|v| v as u64),
+                    exif,
+                    mtime: row.get(9)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(photos)
     }
 
-    pub fn insert_group(&self, source_of_truth_id: i64, confidence: Confidence, member_ids: &[i64]) -> Result<i64> {
-        self.conn.execute(
-            "INSERT INTO duplicate_groups (source_of_truth_id, confidence) VALUES (?1, ?2)",
-            params![source_of_truth_id, confidence.as_str()],
-        )?;
-        let group_id = self.conn.last_insert_rowid();
+    /// Query photos by EXIF metadata and free-text camera make/model,
+    /// pushing every predicate down to SQL — unlike `list_all_photos`, which
+    /// forces the caller to filter in Rust over the whole catalog. The
+    /// `text` predicate joins through the `photos_fts` FTS5 index; every
+    /// other predicate is an ordinary `WHERE` clause over indexed columns.
+    pub fn search(&self, query: &PhotoQuery) -> Result<Vec<PhotoFile>> {
+        let mut clauses: Vec<String> = Vec::new();
+        let mut params: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
+        let mut from = String::from("photos");
+
+        if let Some(text) = &query.text {
+            from.push_str(" JOIN photos_fts ON photos_fts.rowid = photos.id");
+            clauses.push(format!("photos_fts MATCH ?{}", params.len() + 1));
+            params.push(Box::new(text.clone()));
+        }
+        if let Some(make) = &query.camera_make {
+            clauses.push(format!("photos.exif_camera_make = ?{}", params.len() + 1));
+            params.push(Box::new(make.clone()));
+        }
+        if let Some(model) = &query.camera_model {
+            clauses.push(format!("photos.exif_camera_model = ?{}", params.len() + 1));
+            params.push(Box::new(model.clone()));
+        }
+        if let Some(format) = query.format {
+            clauses.push(format!("photos.format = ?{}", params.len() + 1));
+            params.push(Box::new(format.as_str().to_string()));
+        }
+        if let Some(from_date) = &query.exif_date_from {
+            clauses.push(format!("photos.exif_date >= ?{}", params.len() + 1));
+            params.push(Box::new(from_date.clone()));
+        }
+        if let Some(to_date) = &query.exif_date_to {
+            clauses.push(format!("photos.exif_date <= ?{}", params.len() + 1));
+            params.push(Box::new(to_date.clone()));
+        }
+        if let Some(min) = query.size_min {
+            clauses.push(format!("photos.size >= ?{}", params.len() + 1));
+            params.push(Box::new(min as i64));
+        }
+        if let Some(max) = query.size_max {
+            clauses.push(format!("photos.size <= ?{}", params.len() + 1));
+            params.push(Box::new(max as i64));
+        }
+        if let Some((min_lat, min_lon, max_lat, max_lon)) = query.bbox {
+            clauses.push(format!(
+                "photos.exif_gps_lat BETWEEN ?{} AND ?{} AND photos.exif_gps_lon BETWEEN ?{} AND ?{}",
+                params.len() + 1,
+                params.len() + 2,
+                params.len() + 3,
+                params.len() + 4,
+            ));
+            params.push(Box::new(min_lat));
+            params.push(Box::new(max_lat));
+            params.push(Box::new(min_lon));
+            params.push(Box::new(max_lon));
+        }
 
-        let mut stmt = self
-            .conn
-            .prepare("INSERT INTO group_members (group_id, photo_id) VALUES (?1, ?2)")?;
-        for &photo_id in member_ids {
-            stmt.execute(params![group_id, photo_id])?;
+        let mut sql = format!(
+            "SELECT photos.id, photos.source_id, photos.path, photos.size, photos.format, photos.sha256,
+             photos.phash, photos.dhash, photos.ahash, photos.mtime,
+             photos.exif_date, photos.exif_camera_make, photos.exif_camera_model,
+             photos.exif_gps_lat, photos.exif_gps_lon, photos.exif_width, photos.exif_height
+             FROM {from}"
+        );
+        if !clauses.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&clauses.join(" AND "));
         }
+        if let Some(order_by) = query.order_by {
+            sql.push_str(" ORDER BY ");
+            sql.push_str(order_by.as_sql());
+        }
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let param_refs: Vec<&dyn rusqlite::types::ToSql> =
+            params.iter().map(|p| p.as_ref()).collect();
+        let photos = stmt
+            .query_map(param_refs.as_slice(), |row| {
+                let exif_date: Option<String> = row.get(10)?;
+                let exif_make: Option<String> = row.get(11)?;
+                let exif_model: Option<String> = row.get(12)?;
+                let exif_lat: Option<f64> = row.get(13)?;
+                let exif_lon: Option<f64> = row.get(14)?;
+                let exif_w: Option<u32> = row.get(15)?;
+                let exif_h: Option<u32> = row.get(16)?;
+
+                let exif = if exif_date.is_some()
+                    || exif_make.is_some()
+                    || exif_model.is_some()
+                    || exif_lat.is_some()
+                {
+                    Some(ExifData {
+                        date: exif_date,
+                        camera_make: exif_make,
+                        camera_model: exif_model,
+                        gps_lat: exif_lat,
+                        gps_lon: exif_lon,
+                        width: exif_w,
+                        height: exif_h,
+                    })
+                } else {
+                    None
+                };
+
+                Ok(PhotoFile {
+                    id: row.get(0)?,
+                    source_id: row.get(1)?,
+                    path: PathBuf::from(row.get::<_, String>(2)?),
+                    size: row.get::<_, i64>(3)? as u64,
+                    format: parse_format(&row.get::<_, String>(4)?),
+                    sha256: row.get(5)?,
+                    phash: row.get::<_, Option<i64>>(6)?.map(|v| v as u64),
+                    dhash: row.get::<_, Option<i64>>(7)?.map(|v| v as u64),
+                    ahash: row.get::<_, Option<i64>>(8)?.map(|v| v as u64),
+                    exif,
+                    mtime: row.get(9)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(photos)
+    }
+
+    /// `search`, but taking the textual query form CLI users write ad-hoc
+    /// (`camera:"Canon" date>=2022-01-01 bbox:48.8,2.3,48.9,2.4`) instead of
+    /// a `PhotoQuery` — see `query_lang` for the grammar.
+    pub fn search_text(&self, query: &str) -> Result<Vec<PhotoFile>> {
+        self.search(&query_lang::parse(query)?)
+    }
+
+    pub fn count_photos(&self) -> Result<usize> {
+        let count: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM photos", [], |row| row.get(0))?;
+        Ok(count as usize)
+    }
+
+    /// Get all catalog statistics in a single query for the status dashboard.
+    pub fn stats_summary(&self) -> Result<(usize, usize, usize)> {
+        let (photos, groups, duplicates) = self.conn.query_row(
+            "SELECT
+                (SELECT COUNT(*) FROM photos),
+                (SELECT COUNT(*) FROM duplicate_groups),
+                (SELECT COUNT(DISTINCT gm.photo_id) FROM group_members gm
+                 JOIN duplicate_groups dg ON gm.group_id = dg.id
+                 WHERE gm.photo_id != dg.source_of_truth_id)",
+            [],
+            |row| {
+                Ok((
+                    row.get::<_, i64>(0)? as usize,
+                    row.get::<_, i64>(1)? as usize,
+                    row.get::<_, i64>(2)? as usize,
+                ))
+            },
+        )?;
+        Ok((photos, groups, duplicates))
+    }
+
+    // ── Duplicate Groups ─────────────────────────────────────────────
+
+    pub fn clear_groups(&self) -> Result<()> {
+        self.conn.execute("DELETE FROM group_members", [])?;
+        self.conn.execute("DELETE FROM duplicate_groups", [])?;
+        Ok(())
+    }
+
+    pub fn insert_group(&self, source_of_truth_id: i64, confidence: Confidence, member_ids: &[i64]) -> Result<i64> {
+        let now = current_timestamp();
+        let tx = self.conn.unchecked_transaction()?;
+
+        tx.execute(
+            "INSERT INTO duplicate_groups (source_of_truth_id, confidence) VALUES (?1, ?2)",
+            params![source_of_truth_id, confidence.as_str()],
+        )?;
+        let group_id = tx.last_insert_rowid();
+
+        {
+            let mut stmt =
+                tx.prepare("INSERT INTO group_members (group_id, photo_id, added_at) VALUES (?1, ?2, ?3)")?;
+            for &photo_id in member_ids {
+                stmt.execute(params![group_id, photo_id, now])?;
+            }
+        }
+
+        events::record_event(
+            &tx,
+            &events::CatalogEvent::GroupInserted { sot: source_of_truth_id, members: member_ids.to_vec() },
+            now,
+        )?;
+        tx.commit()?;
+
+        self.emit_event(events::CatalogEvent::GroupInserted { sot: source_of_truth_id, members: member_ids.to_vec() });
         Ok(group_id)
     }
 
     /// Clear existing groups and insert new ones in a single transaction.
     pub fn replace_groups_batch(&mut self, groups: &[(i64, Confidence, Vec<i64>)]) -> Result<Vec<i64>> {
         let tx = self.conn.transaction()?;
+        let now = current_timestamp();
 
         tx.execute("DELETE FROM group_members", [])?;
         tx.execute("DELETE FROM duplicate_groups", [])?;
@@ -545,8 +1496,8 @@ impl Catalog {
 
             for &photo_id in member_ids {
                 tx.execute(
-                    "INSERT INTO group_members (group_id, photo_id) VALUES (?1, ?2)",
-                    params![group_id, photo_id],
+                    "INSERT INTO group_members (group_id, photo_id, added_at) VALUES (?1, ?2, ?3)",
+                    params![group_id, photo_id, now],
                 )?;
             }
             group_ids.push(group_id);
@@ -560,7 +1511,7 @@ impl Catalog {
         // Single JOIN query to avoid N+1 problem
         let mut stmt = self.conn.prepare(
             "SELECT dg.id, dg.source_of_truth_id, dg.confidence,
-                    p.id, p.source_id, p.path, p.size, p.format, p.sha256, p.phash, p.dhash, p.mtime,
+                    p.id, p.source_id, p.path, p.size, p.format, p.sha256, p.phash, p.dhash, p.ahash, p.mtime,
                     p.exif_date, p.exif_camera_make, p.exif_camera_model, p.exif_gps_lat, p.exif_gps_lon,
                     p.exif_width, p.exif_height
              FROM duplicate_groups dg
@@ -571,13 +1522,13 @@ impl Catalog {
 
         let rows = stmt
             .query_map([], |row| {
-                let exif_date: Option<String> = row.get(12)?;
-                let exif_make: Option<String> = row.get(13)?;
-                let exif_model: Option<String> = row.get(14)?;
-                let exif_lat: Option<f64> = row.get(15)?;
-                let exif_lon: Option<f64> = row.get(16)?;
-                let exif_w: Option<u32> = row.get(17)?;
-                let exif_h: Option<u32> = row.get(18)?;
+                let exif_date: Option<String> = row.get(13)?;
+                let exif_make: Option<String> = row.get(14)?;
+                let exif_model: Option<String> = row.get(15)?;
+                let exif_lat: Option<f64> = row.get(16)?;
+                let exif_lon: Option<f64> = row.get(17)?;
+                let exif_w: Option<u32> = row.get(18)?;
+                let exif_h: Option<u32> = row.get(19)?;
 
                 let exif = if exif_date.is_some()
                     || exif_make.is_some()
@@ -610,8 +1561,9 @@ impl Catalog {
                         sha256: row.get(8)?,
                         phash: row.get::<_, Option<i64>>(9)?.map(|v| v as u64),
                         dhash: row.get::<_, Option<i64>>(10)?.map(|v| v as u64),
+                        ahash: row.get::<_, Option<i64>>(11)?.map(|v| v as u64),
                         exif,
-                        mtime: row.get(11)?,
+                        mtime: row.get(12)?,
                     },
                 ))
             })?
@@ -688,7 +1640,7 @@ impl Catalog {
 
     fn get_group_members(&self, group_id: i64) -> Result<Vec<PhotoFile>> {
         let mut stmt = self.conn.prepare(
-            "SELECT p.id, p.source_id, p.path, p.size, p.format, p.sha256, p.phash, p.dhash, p.mtime,
+            "SELECT p.id, p.source_id, p.path, p.size, p.format, p.sha256, p.phash, p.dhash, p.ahash, p.mtime,
              p.exif_date, p.exif_camera_make, p.exif_camera_model, p.exif_gps_lat, p.exif_gps_lon,
              p.exif_width, p.exif_height
              FROM photos p
@@ -697,13 +1649,13 @@ impl Catalog {
         )?;
         let photos = stmt
             .query_map(params![group_id], |row| {
-                let exif_date: Option<String> = row.get(9)?;
-                let exif_make: Option<String> = row.get(10)?;
-                let exif_model: Option<String> = row.get(11)?;
-                let exif_lat: Option<f64> = row.get(12)?;
-                let exif_lon: Option<f64> = row.get(13)?;
-                let exif_w: Option<u32> = row.get(14)?;
-                let exif_h: Option<u32> = row.get(15)?;
+                let exif_date: Option<String> = row.get(10)?;
+                let exif_make: Option<String> = row.get(11)?;
+                let exif_model: Option<String> = row.get(12)?;
+                let exif_lat: Option<f64> = row.get(13)?;
+                let exif_lon: Option<f64> = row.get(14)?;
+                let exif_w: Option<u32> = row.get(15)?;
+                let exif_h: Option<u32> = row.get(16)?;
 
                 let exif = if exif_date.is_some()
                     || exif_make.is_some()
@@ -732,14 +1684,60 @@ impl Catalog {
                     sha256: row.get(5)?,
                     phash: row.get::<_, Option<i64>>(6)?.map(|v| v as u64),
                     dhash: row.get::<_, Option<i64>>(7)?.map(|v| v as u64),
+                    ahash: row.get::<_, Option<i64>>(8)?.map(|v| v as u64),
                     exif,
-                    mtime: row.get(8)?,
+                    mtime: row.get(9)?,
                 })
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
         Ok(photos)
     }
 
+    // ── Export tracking ──────────────────────────────────────────────
+
+    /// Record that `sha256`'s content was exported to `target`, so a later
+    /// `Vault::export` recognizes it as already done even if the source
+    /// photo's path — and therefore its date-derived target name — has
+    /// since changed (see `rehome_photo`).
+    pub fn record_exported(&self, sha256: &str, target: &Path) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO exported_objects (sha256, target) VALUES (?1, ?2)
+             ON CONFLICT(sha256) DO UPDATE SET target = excluded.target",
+            params![sha256, target.to_string_lossy().as_ref()],
+        )?;
+        Ok(())
+    }
+
+    /// Look up previously exported targets for a batch of hashes in a single
+    /// query. Missing entries just aren't in the returned map.
+    pub fn exported_targets_by_sha256s(&self, sha256s: &[&str]) -> Result<HashMap<String, PathBuf>> {
+        if sha256s.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let mut result = HashMap::new();
+        for chunk in sha256s.chunks(500) {
+            let placeholders: Vec<String> = (0..chunk.len()).map(|i| format!("?{}", i + 1)).collect();
+            let sql = format!(
+                "SELECT sha256, target FROM exported_objects WHERE sha256 IN ({})",
+                placeholders.join(", ")
+            );
+            let mut stmt = self.conn.prepare(&sql)?;
+            let params: Vec<&dyn rusqlite::types::ToSql> = chunk
+                .iter()
+                .map(|s| s as &dyn rusqlite::types::ToSql)
+                .collect();
+            let rows = stmt
+                .query_map(params.as_slice(), |row| {
+                    Ok((row.get::<_, String>(0)?, PathBuf::from(row.get::<_, String>(1)?)))
+                })?
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            for (sha, target) in rows {
+                result.insert(sha, target);
+            }
+        }
+        Ok(result)
+    }
+
     // ── Config ───────────────────────────────────────────────────
 
     pub fn set_config(&self, key: &str, value: &str) -> Result<()> {
@@ -752,11 +1750,34 @@ impl Catalog {
     }
 
     /// Clear all cached perceptual hashes. Used when the hash algorithm changes.
+    ///
+    /// Also drops the in-memory `phash_cache` — left alone, it would go on
+    /// serving the very sha256 → hash pairs this just nulled out, silently
+    /// undoing the invalidation for any dedup pass that hits the cache
+    /// before the next `upsert_photo` repopulates it.
     pub fn clear_perceptual_hashes(&self) -> Result<usize> {
         let count = self.conn.execute(
-            "UPDATE photos SET phash = NULL, dhash = NULL WHERE phash IS NOT NULL",
+            "UPDATE photos SET phash = NULL, dhash = NULL, ahash = NULL WHERE phash IS NOT NULL",
             [],
         )?;
+        self.phash_cache.borrow_mut().clear();
+        Ok(count)
+    }
+
+    /// Clear only the one hash column `kind` selects, leaving the other two
+    /// intact. Used when just that artifact's version changed — e.g. the
+    /// primary hash algorithm changed but the fixed dHash/aHash computations
+    /// didn't, so there's no need to re-decode every image to redo them too.
+    /// See `clear_perceptual_hashes` for the all-at-once counterpart.
+    ///
+    /// The `phash_cache` bundles all three hashes per entry, so there's no
+    /// cheaper way to drop just `kind`'s slice of it — clear the whole thing
+    /// and let the next lookup repopulate from the (now partially-null) rows.
+    pub fn clear_perceptual_hash(&self, kind: HashKind) -> Result<usize> {
+        let column = kind.column();
+        let sql = format!("UPDATE photos SET {column} = NULL WHERE {column} IS NOT NULL");
+        let count = self.conn.execute(&sql, [])?;
+        self.phash_cache.borrow_mut().clear();
         Ok(count)
     }
 
@@ -766,6 +1787,120 @@ impl Catalog {
         Ok(count)
     }
 
+    // ── Broken Files ─────────────────────────────────────────────────
+
+    /// Quarantine a file `scan` couldn't process — a decode error or a panic
+    /// caught inside a third-party codec — keyed by path so a later rescan
+    /// at the same path overwrites the prior reason instead of accumulating
+    /// duplicates.
+    pub fn record_broken_file(&self, path: &Path, reason: &str, detected_at: i64) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO broken_files (path, reason, detected_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(path) DO UPDATE SET reason = excluded.reason, detected_at = excluded.detected_at",
+            params![path.to_string_lossy(), reason, detected_at],
+        )?;
+        Ok(())
+    }
+
+    /// Un-quarantine a path — used when a file `scan` previously flagged as
+    /// broken decodes successfully on a later rescan.
+    pub fn clear_broken_file(&self, path: &Path) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM broken_files WHERE path = ?1", params![path.to_string_lossy()])?;
+        Ok(())
+    }
+
+    /// List every quarantined path and the reason it was flagged, ordered by
+    /// path for stable display.
+    pub fn list_broken_files(&self) -> Result<Vec<(PathBuf, String)>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT path, reason FROM broken_files ORDER BY path")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    PathBuf::from(row.get::<_, String>(0)?),
+                    row.get::<_, String>(1)?,
+                ))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Count of quarantined files, for `Vault::status`.
+    pub fn broken_file_count(&self) -> Result<usize> {
+        let count: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM broken_files", [], |row| row.get(0))?;
+        Ok(count as usize)
+    }
+
+    // ── Hash Cache ───────────────────────────────────────────────────
+
+    /// Look up cached SHA-256 hashes for a batch of (path, size, mtime)
+    /// fingerprints in a single query. An entry only comes back if size and
+    /// mtime both still match what's stored — a changed file is a cache
+    /// miss, not a stale hit. See `Vault::scan`'s fingerprint phase.
+    pub fn get_cached_hashes(&self, paths: &[&Path]) -> Result<HashMap<PathBuf, (u64, i64, String)>> {
+        if paths.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let mut result = HashMap::new();
+        for chunk in paths.chunks(500) {
+            let placeholders: Vec<String> = (0..chunk.len()).map(|i| format!("?{}", i + 1)).collect();
+            let sql = format!(
+                "SELECT path, size, mtime, sha256 FROM hash_cache WHERE path IN ({})",
+                placeholders.join(", ")
+            );
+            let mut stmt = self.conn.prepare(&sql)?;
+            let params: Vec<&dyn rusqlite::types::ToSql> = chunk
+                .iter()
+                .map(|p| p.to_str().unwrap() as &dyn rusqlite::types::ToSql)
+                .collect();
+            let rows = stmt
+                .query_map(params.as_slice(), |row| {
+                    Ok((
+                        PathBuf::from(row.get::<_, String>(0)?),
+                        (
+                            row.get::<_, i64>(1)? as u64,
+                            row.get::<_, i64>(2)?,
+                            row.get::<_, String>(3)?,
+                        ),
+                    ))
+                })?
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            result.extend(rows);
+        }
+        Ok(result)
+    }
+
+    /// Upsert a batch of (path, size, mtime, sha256) fingerprints in a
+    /// single transaction, so a freshly hashed file is reused by the next
+    /// scan even if its catalog row later changes source or gets dropped.
+    pub fn upsert_hash_cache_batch(&mut self, entries: &[(PathBuf, u64, i64, String)]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO hash_cache (path, size, mtime, sha256) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT(path) DO UPDATE SET size = excluded.size, mtime = excluded.mtime, sha256 = excluded.sha256",
+            )?;
+            for (path, size, mtime, sha256) in entries {
+                stmt.execute(params![path.to_string_lossy(), *size as i64, mtime, sha256])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Drop every `hash_cache` entry, forcing the next scan to recompute
+    /// SHA-256 from scratch for every file instead of trusting a path+
+    /// size+mtime fingerprint it can no longer verify. Paired with
+    /// `reset_all_mtimes`/`clear_perceptual_hashes` by `Vault::rebuild_hash_cache`
+    /// for a full "ignore everything cached" rescan.
+    pub fn clear_hash_cache(&self) -> Result<usize> {
+        Ok(self.conn.execute("DELETE FROM hash_cache", [])?)
+    }
+
     pub fn get_config(&self, key: &str) -> Result<Option<String>> {
         let value = self
             .conn
@@ -779,6 +1914,51 @@ impl Catalog {
     }
 }
 
+/// Which of the `photos` table's three perceptual-hash columns a granular
+/// invalidation targets — see `Catalog::clear_perceptual_hash`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashKind {
+    Phash,
+    Dhash,
+    Ahash,
+}
+
+impl HashKind {
+    fn column(self) -> &'static str {
+        match self {
+            HashKind::Phash => "phash",
+            HashKind::Dhash => "dhash",
+            HashKind::Ahash => "ahash",
+        }
+    }
+}
+
+/// A source's role in grouping. A `Reference` source is a curated archive
+/// whose photos always win source-of-truth over `Standard` sources, and
+/// groups made up entirely of reference-folder photos can be suppressed from
+/// duplicate reporting (see `Vault::set_suppress_reference_only_groups`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceRole {
+    Standard,
+    Reference,
+}
+
+impl SourceRole {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SourceRole::Standard => "standard",
+            SourceRole::Reference => "reference",
+        }
+    }
+
+    fn from_str(s: &str) -> Self {
+        match s {
+            "reference" => SourceRole::Reference,
+            _ => SourceRole::Standard,
+        }
+    }
+}
+
 fn parse_format(s: &str) -> PhotoFormat {
     match s {
         "CR2" => PhotoFormat::Cr2,
@@ -831,6 +2011,7 @@ mod tests {
             sha256: sha.to_string(),
             phash: Some(12345),
             dhash: Some(67890),
+            ahash: Some(54321),
             exif: None,
             mtime: 1000,
         }
@@ -913,6 +2094,48 @@ mod tests {
         assert!(matches!(err, Error::SourceNotRegistered(_)));
     }
 
+    #[test]
+    fn test_add_source_defaults_to_standard_role() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(tmp.path().join("photos")).unwrap();
+        let catalog = Catalog::open_in_memory().unwrap();
+        let source = catalog.add_source(&tmp.path().join("photos")).unwrap();
+        assert!(catalog.reference_source_ids().unwrap().is_empty());
+        assert_eq!(source.path, tmp.path().join("photos").canonicalize().unwrap());
+    }
+
+    #[test]
+    fn test_add_source_with_reference_role() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(tmp.path().join("archive")).unwrap();
+        let catalog = Catalog::open_in_memory().unwrap();
+        let source = catalog
+            .add_source_with_role(&tmp.path().join("archive"), SourceRole::Reference)
+            .unwrap();
+        assert_eq!(catalog.reference_source_ids().unwrap(), [source.id].into());
+    }
+
+    #[test]
+    fn test_set_source_role_toggles_reference() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        assert!(catalog.reference_source_ids().unwrap().is_empty());
+
+        catalog.set_source_role(&source.path, SourceRole::Reference).unwrap();
+        assert_eq!(catalog.reference_source_ids().unwrap(), [source.id].into());
+
+        catalog.set_source_role(&source.path, SourceRole::Standard).unwrap();
+        assert!(catalog.reference_source_ids().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_set_source_role_not_registered() {
+        let catalog = Catalog::open_in_memory().unwrap();
+        let err = catalog
+            .set_source_role(Path::new("/nonexistent"), SourceRole::Reference)
+            .unwrap_err();
+        assert!(matches!(err, Error::SourceNotRegistered(_)));
+    }
+
     #[test]
     fn test_remove_source_cleans_empty_groups() {
         let (catalog, source, _tmp) = make_catalog_with_source();
@@ -986,6 +2209,24 @@ mod tests {
         assert_eq!(exif.width, Some(8192));
     }
 
+    #[test]
+    fn test_find_photos_by_sha256_matches_only_that_hash() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        catalog.upsert_photo(&make_photo(source.id, "/tmp/a.jpg", "shared")).unwrap();
+        catalog.upsert_photo(&make_photo(source.id, "/tmp/b.jpg", "shared")).unwrap();
+        catalog.upsert_photo(&make_photo(source.id, "/tmp/c.jpg", "other")).unwrap();
+
+        let matches = catalog.find_photos_by_sha256("shared").unwrap();
+        assert_eq!(matches.len(), 2);
+        assert!(matches.iter().all(|p| p.sha256 == "shared"));
+    }
+
+    #[test]
+    fn test_find_photos_by_sha256_no_match_returns_empty() {
+        let (catalog, _source, _tmp) = make_catalog_with_source();
+        assert!(catalog.find_photos_by_sha256("nonexistent").unwrap().is_empty());
+    }
+
     #[test]
     fn test_get_photo_mtime() {
         let (catalog, source, _tmp) = make_catalog_with_source();
@@ -998,40 +2239,722 @@ mod tests {
     }
 
     #[test]
-    fn test_count_photos() {
+    fn test_rehome_photo_preserves_id_and_updates_path() {
         let (catalog, source, _tmp) = make_catalog_with_source();
-        assert_eq!(catalog.count_photos().unwrap(), 0);
+        let id = catalog.upsert_photo(&make_photo(source.id, "/tmp/old.jpg", "stable_hash")).unwrap();
 
-        catalog.upsert_photo(&make_photo(source.id, "/tmp/a.jpg", "aaa")).unwrap();
-        catalog.upsert_photo(&make_photo(source.id, "/tmp/b.jpg", "bbb")).unwrap();
-        assert_eq!(catalog.count_photos().unwrap(), 2);
-    }
+        let mut moved = make_photo(source.id, "/tmp/new.jpg", "stable_hash");
+        moved.mtime = 2000;
+        catalog.rehome_photo(Path::new("/tmp/old.jpg"), &moved).unwrap();
 
-    // ── Group tests ──────────────────────────────────────────────
+        let photos = catalog.list_all_photos().unwrap();
+        assert_eq!(photos.len(), 1, "should update in place, not insert a second row");
+        assert_eq!(photos[0].id, id);
+        assert_eq!(photos[0].path, PathBuf::from("/tmp/new.jpg"));
+        assert_eq!(photos[0].mtime, 2000);
+        assert_eq!(catalog.get_photo_mtime(Path::new("/tmp/old.jpg")).unwrap(), None);
+    }
 
     #[test]
-    fn test_insert_and_get_group() {
+    fn test_rehome_photo_no_op_when_old_path_unknown() {
         let (catalog, source, _tmp) = make_catalog_with_source();
-        let id_a = catalog.upsert_photo(&make_photo(source.id, "/tmp/a.jpg", "aaa")).unwrap();
-        let id_b = catalog.upsert_photo(&make_photo(source.id, "/tmp/b.jpg", "aaa")).unwrap();
+        let photo = make_photo(source.id, "/tmp/new.jpg", "hash");
+        catalog.rehome_photo(Path::new("/tmp/nonexistent.jpg"), &photo).unwrap();
+        assert_eq!(catalog.count_photos().unwrap(), 0);
+    }
 
-        let group_id = catalog.insert_group(id_a, Confidence::Certain, &[id_a, id_b]).unwrap();
-        assert!(group_id > 0);
+    // ── BK-tree hash index tests ─────────────────────────────────
 
-        let group = catalog.get_group(group_id).unwrap();
-        assert_eq!(group.id, group_id);
-        assert_eq!(group.source_of_truth_id, id_a);
-        assert_eq!(group.confidence, Confidence::Certain);
-        assert_eq!(group.members.len(), 2);
+    #[test]
+    fn test_find_similar_finds_upserted_photo_within_distance() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        let mut photo = make_photo(source.id, "/tmp/a.jpg", "hash_a");
+        photo.phash = Some(0b1010);
+        let id = catalog.upsert_photo(&photo).unwrap();
+
+        let matches = catalog.find_similar(0b1011, 1).unwrap();
+        assert_eq!(matches, vec![(id, 1)]);
+        assert!(catalog.find_similar(0b1011, 0).unwrap().is_empty());
     }
 
     #[test]
-    fn test_list_groups() {
+    fn test_find_similar_dhash_is_independent_of_phash_index() {
         let (catalog, source, _tmp) = make_catalog_with_source();
-        let id_a = catalog.upsert_photo(&make_photo(source.id, "/tmp/a.jpg", "aaa")).unwrap();
-        let id_b = catalog.upsert_photo(&make_photo(source.id, "/tmp/b.jpg", "aaa")).unwrap();
-        let id_c = catalog.upsert_photo(&make_photo(source.id, "/tmp/c.jpg", "ccc")).unwrap();
-        let id_d = catalog.upsert_photo(&make_photo(source.id, "/tmp/d.jpg", "ccc")).unwrap();
+        let mut photo = make_photo(source.id, "/tmp/a.jpg", "hash_a");
+        photo.phash = Some(0xAAAA);
+        photo.dhash = Some(0x5555);
+        let id = catalog.upsert_photo(&photo).unwrap();
+
+        assert_eq!(catalog.find_similar_dhash(0x5555, 0).unwrap(), vec![(id, 0)]);
+        assert!(catalog.find_similar_dhash(0xAAAA, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_find_similar_ahash_is_independent_of_phash_and_dhash_indexes() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        let mut photo = make_photo(source.id, "/tmp/a.jpg", "hash_a");
+        photo.phash = Some(0xAAAA);
+        photo.dhash = Some(0x5555);
+        photo.ahash = Some(0xF0F0);
+        let id = catalog.upsert_photo(&photo).unwrap();
+
+        assert_eq!(catalog.find_similar_ahash(0xF0F0, 0).unwrap(), vec![(id, 0)]);
+        assert!(catalog.find_similar_ahash(0xAAAA, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_find_similar_reflects_upsert_update_not_just_insert() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        let mut photo = make_photo(source.id, "/tmp/a.jpg", "hash_a");
+        photo.phash = Some(0b0000);
+        let id = catalog.upsert_photo(&photo).unwrap();
+
+        photo.phash = Some(0b1111);
+        catalog.upsert_photo(&photo).unwrap();
+
+        assert_eq!(catalog.find_similar(0b1111, 0).unwrap(), vec![(id, 0)]);
+    }
+
+    #[test]
+    fn test_find_similar_reflects_rehome_photo() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        let mut photo = make_photo(source.id, "/tmp/old.jpg", "stable_hash");
+        photo.phash = Some(0b0011);
+        let id = catalog.upsert_photo(&photo).unwrap();
+
+        let mut moved = make_photo(source.id, "/tmp/new.jpg", "stable_hash");
+        moved.phash = Some(0b1100);
+        catalog.rehome_photo(Path::new("/tmp/old.jpg"), &moved).unwrap();
+
+        assert_eq!(catalog.find_similar(0b1100, 0).unwrap(), vec![(id, 0)]);
+        assert!(catalog.find_similar(0b0011, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_find_similar_reflects_upsert_photos_batch() {
+        let (mut catalog, source, _tmp) = make_catalog_with_source();
+        let mut photo_a = make_photo(source.id, "/tmp/a.jpg", "a");
+        photo_a.phash = Some(0b0001);
+        let mut photo_b = make_photo(source.id, "/tmp/b.jpg", "b");
+        photo_b.phash = Some(0b1000);
+
+        let ids = catalog.upsert_photos_batch(&[photo_a, photo_b]).unwrap();
+
+        assert_eq!(catalog.find_similar(0b0001, 0).unwrap(), vec![(ids[0], 0)]);
+        assert_eq!(catalog.find_similar(0b1000, 0).unwrap(), vec![(ids[1], 0)]);
+    }
+
+    #[test]
+    fn test_find_similar_index_survives_reopen() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("catalog.db");
+        let id = {
+            let catalog = Catalog::open(&db_path).unwrap();
+            let source_dir = tmp.path().join("photos");
+            std::fs::create_dir_all(&source_dir).unwrap();
+            let source = catalog.add_source(&source_dir).unwrap();
+            let mut photo = make_photo(source.id, "/tmp/a.jpg", "hash_a");
+            photo.phash = Some(0b0110);
+            catalog.upsert_photo(&photo).unwrap()
+        };
+
+        let reopened = Catalog::open(&db_path).unwrap();
+        assert_eq!(reopened.find_similar(0b0110, 0).unwrap(), vec![(id, 0)]);
+    }
+
+    // ── Change feed tests ─────────────────────────────────────────
+
+    #[test]
+    fn test_watch_receives_photo_upserted() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        let rx = catalog.watch();
+
+        let id = catalog.upsert_photo(&make_photo(source.id, "/tmp/a.jpg", "aaa")).unwrap();
+
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            events::CatalogEvent::PhotoUpserted { id }
+        );
+        assert!(rx.try_recv().is_err(), "no further events should be pending");
+    }
+
+    #[test]
+    fn test_watch_since_replays_history_then_streams_live() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        let first_id = catalog.upsert_photo(&make_photo(source.id, "/tmp/a.jpg", "aaa")).unwrap();
+
+        // An offline consumer that only saw up through the first photo's
+        // event (id 2; id 1 is the fixture's `SourceAdded`).
+        let rx = catalog.watch_since(2).unwrap();
+
+        let second_id = catalog.upsert_photo(&make_photo(source.id, "/tmp/b.jpg", "bbb")).unwrap();
+
+        assert_eq!(rx.try_recv().unwrap(), events::CatalogEvent::PhotoUpserted { id: second_id });
+        assert!(rx.try_recv().is_err());
+        assert_ne!(first_id, second_id);
+    }
+
+    #[test]
+    fn test_watch_since_zero_replays_every_event() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        catalog.upsert_photo(&make_photo(source.id, "/tmp/a.jpg", "aaa")).unwrap();
+        catalog.upsert_photo(&make_photo(source.id, "/tmp/b.jpg", "bbb")).unwrap();
+
+        let rx = catalog.watch_since(0).unwrap();
+        let replayed: Vec<_> = rx.try_iter().collect();
+        // The fixture's `SourceAdded` plus the two `PhotoUpserted`s.
+        assert_eq!(replayed.len(), 3);
+    }
+
+    #[test]
+    fn test_watch_receives_group_inserted_and_photo_removed() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        let sot = catalog.upsert_photo(&make_photo(source.id, "/tmp/a.jpg", "aaa")).unwrap();
+        let dup = catalog.upsert_photo(&make_photo(source.id, "/tmp/b.jpg", "bbb")).unwrap();
+
+        let rx = catalog.watch();
+        catalog.insert_group(sot, Confidence::Certain, &[sot, dup]).unwrap();
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            events::CatalogEvent::GroupInserted { sot, members: vec![sot, dup] }
+        );
+
+        catalog.remove_photos_by_paths(&[Path::new("/tmp/b.jpg")]).unwrap();
+        assert_eq!(rx.try_recv().unwrap(), events::CatalogEvent::PhotoRemoved { id: dup });
+    }
+
+    #[test]
+    fn test_watch_subscriber_dropped_receiver_does_not_error_on_emit() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        drop(catalog.watch());
+        // The dropped receiver's sender should be pruned, not cause a panic
+        // or an error, the next time an event fires.
+        catalog.upsert_photo(&make_photo(source.id, "/tmp/a.jpg", "aaa")).unwrap();
+    }
+
+    #[test]
+    fn test_count_photos() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        assert_eq!(catalog.count_photos().unwrap(), 0);
+
+        catalog.upsert_photo(&make_photo(source.id, "/tmp/a.jpg", "aaa")).unwrap();
+        catalog.upsert_photo(&make_photo(source.id, "/tmp/b.jpg", "bbb")).unwrap();
+        assert_eq!(catalog.count_photos().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_find_similar_candidates_pairs_up_close_hashes() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        let mut close_a = make_photo(source.id, "/tmp/a.jpg", "a");
+        close_a.phash = Some(0b0000);
+        let id_a = catalog.upsert_photo(&close_a).unwrap();
+        let mut close_b = make_photo(source.id, "/tmp/b.jpg", "b");
+        close_b.phash = Some(0b0001);
+        let id_b = catalog.upsert_photo(&close_b).unwrap();
+        let mut far = make_photo(source.id, "/tmp/c.jpg", "c");
+        far.phash = Some(u64::MAX);
+        catalog.upsert_photo(&far).unwrap();
+
+        let candidates = catalog.find_similar_candidates(1).unwrap();
+        assert_eq!(candidates.len(), 1);
+        let (low, high, distance) = candidates[0];
+        assert_eq!((low, high), (id_a.min(id_b), id_a.max(id_b)));
+        assert_eq!(distance, 1);
+    }
+
+    #[test]
+    fn test_find_similar_candidates_reports_each_pair_once() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        let mut a = make_photo(source.id, "/tmp/a.jpg", "a");
+        a.phash = Some(0b0000);
+        catalog.upsert_photo(&a).unwrap();
+        let mut b = make_photo(source.id, "/tmp/b.jpg", "b");
+        b.phash = Some(0b0000);
+        catalog.upsert_photo(&b).unwrap();
+
+        let candidates = catalog.find_similar_candidates(0).unwrap();
+        assert_eq!(candidates.len(), 1, "an unordered pair must not be reported twice");
+    }
+
+    #[test]
+    fn test_find_similar_candidates_ignores_photos_without_a_phash() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        let mut no_hash = make_photo(source.id, "/tmp/a.jpg", "a");
+        no_hash.phash = None;
+        catalog.upsert_photo(&no_hash).unwrap();
+        let mut has_hash = make_photo(source.id, "/tmp/b.jpg", "b");
+        has_hash.phash = Some(0b0000);
+        catalog.upsert_photo(&has_hash).unwrap();
+
+        assert!(catalog.find_similar_candidates(64).unwrap().is_empty());
+    }
+
+    // ── Perceptual hash cache tests ───────────────────────────────
+
+    #[test]
+    fn test_get_phashes_by_sha256s_hits_cache_without_a_second_query() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        let mut photo = make_photo(source.id, "/tmp/a.jpg", "hash_a");
+        photo.phash = Some(111);
+        photo.dhash = Some(222);
+        photo.ahash = None;
+        catalog.upsert_photo(&photo).unwrap();
+
+        // Served straight from the cache `upsert_photo` just populated.
+        let first = catalog.get_phashes_by_sha256s(&["hash_a"]).unwrap();
+        assert_eq!(first.get("hash_a"), Some(&(111, Some(222), None)));
+
+        // A second call is still correct even once it's a cache hit.
+        let second = catalog.get_phashes_by_sha256s(&["hash_a"]).unwrap();
+        assert_eq!(second.get("hash_a"), Some(&(111, Some(222), None)));
+    }
+
+    #[test]
+    fn test_get_phashes_by_sha256s_falls_back_to_sql_on_a_cold_cache() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("catalog.db");
+        let id_hash = {
+            let catalog = Catalog::open(&db_path).unwrap();
+            let source_dir = tmp.path().join("photos");
+            std::fs::create_dir_all(&source_dir).unwrap();
+            let source = catalog.add_source(&source_dir).unwrap();
+            let mut photo = make_photo(source.id, "/tmp/a.jpg", "hash_a");
+            photo.phash = Some(333);
+            photo.dhash = None;
+            photo.ahash = None;
+            catalog.upsert_photo(&photo).unwrap();
+            "hash_a"
+        };
+
+        // A freshly reopened catalog has an empty in-memory cache, so this
+        // must fall through to the SQL query rather than missing entirely.
+        let reopened = Catalog::open(&db_path).unwrap();
+        let found = reopened.get_phashes_by_sha256s(&[id_hash]).unwrap();
+        assert_eq!(found.get(id_hash), Some(&(333, None, None)));
+    }
+
+    #[test]
+    fn test_set_phash_cache_capacity_still_preserves_correctness() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        for (i, sha) in ["hash_a", "hash_b", "hash_c"].iter().enumerate() {
+            let mut photo = make_photo(source.id, &format!("/tmp/{sha}.jpg"), sha);
+            photo.phash = Some(i as u64);
+            photo.dhash = None;
+            photo.ahash = None;
+            catalog.upsert_photo(&photo).unwrap();
+        }
+
+        // Shrinking the cache below the working set forces evictions, which
+        // must flush any dirty entry rather than lose it.
+        catalog.set_phash_cache_capacity(1).unwrap();
+
+        let found = catalog
+            .get_phashes_by_sha256s(&["hash_a", "hash_b", "hash_c"])
+            .unwrap();
+        assert_eq!(found.get("hash_a"), Some(&(0, None, None)));
+        assert_eq!(found.get("hash_b"), Some(&(1, None, None)));
+        assert_eq!(found.get("hash_c"), Some(&(2, None, None)));
+    }
+
+    #[test]
+    fn test_flush_phash_cache_backfills_a_duplicate_missing_its_own_phash() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        let mut original = make_photo(source.id, "/tmp/a.jpg", "shared_hash");
+        original.phash = Some(42);
+        catalog.upsert_photo(&original).unwrap();
+
+        // An exact duplicate that never had its own perceptual hash computed.
+        let mut duplicate = make_photo(source.id, "/tmp/b.jpg", "shared_hash");
+        duplicate.phash = None;
+        catalog.upsert_photo(&duplicate).unwrap();
+
+        let flushed = catalog.flush_phash_cache().unwrap();
+        assert_eq!(flushed, 1);
+
+        let photos = catalog.find_photos_by_sha256("shared_hash").unwrap();
+        assert!(photos.iter().all(|p| p.phash == Some(42)));
+    }
+
+    // ── Source diff tests ────────────────────────────────────────
+
+    #[test]
+    fn test_diff_source_classifies_added_modified_deleted() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        let mut unchanged = make_photo(source.id, "/tmp/unchanged.jpg", "u");
+        unchanged.mtime = 1000;
+        catalog.upsert_photo(&unchanged).unwrap();
+        let mut stale = make_photo(source.id, "/tmp/stale.jpg", "s");
+        stale.mtime = 1000;
+        catalog.upsert_photo(&stale).unwrap();
+        let mut gone = make_photo(source.id, "/tmp/gone.jpg", "g");
+        gone.mtime = 1000;
+        catalog.upsert_photo(&gone).unwrap();
+
+        let observed = vec![
+            (PathBuf::from("/tmp/unchanged.jpg"), 1000),
+            (PathBuf::from("/tmp/stale.jpg"), 2000),
+            (PathBuf::from("/tmp/new.jpg"), 3000),
+        ];
+        let excludes = RegexSet::empty();
+        let diff = catalog.diff_source(source.id, &observed, &excludes).unwrap();
+
+        assert_eq!(diff.added, vec![PathBuf::from("/tmp/new.jpg")]);
+        assert_eq!(diff.modified, vec![PathBuf::from("/tmp/stale.jpg")]);
+        assert_eq!(diff.deleted, vec![PathBuf::from("/tmp/gone.jpg")]);
+    }
+
+    #[test]
+    fn test_diff_source_excludes_matching_paths_from_added_and_deleted() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        let mut cached = make_photo(source.id, "/tmp/thumbs/cache.jpg", "c");
+        cached.mtime = 1000;
+        catalog.upsert_photo(&cached).unwrap();
+
+        let observed = vec![(PathBuf::from("/tmp/thumbs/new.jpg"), 3000)];
+        let excludes = RegexSet::new([r"/thumbs/"]).unwrap();
+        let diff = catalog.diff_source(source.id, &observed, &excludes).unwrap();
+
+        assert!(diff.added.is_empty());
+        assert!(diff.modified.is_empty());
+        assert!(diff.deleted.is_empty());
+    }
+
+    #[test]
+    fn test_diff_source_empty_catalog_and_observed_is_empty_diff() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        let excludes = RegexSet::empty();
+        let diff = catalog.diff_source(source.id, &[], &excludes).unwrap();
+        assert_eq!(diff, SourceDiff::default());
+    }
+
+    // ── Search tests ─────────────────────────────────────────────
+
+    fn make_photo_with_exif(source_id: i64, path: &str, sha: &str, exif: ExifData) -> PhotoFile {
+        let mut photo = make_photo(source_id, path, sha);
+        photo.exif = Some(exif);
+        photo
+    }
+
+    #[test]
+    fn test_search_matches_free_text_against_camera_make() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        let canon = make_photo_with_exif(
+            source.id,
+            "/tmp/canon.jpg",
+            "canon_sha",
+            ExifData {
+                date: None,
+                camera_make: Some("Canon".to_string()),
+                camera_model: Some("EOS R5".to_string()),
+                gps_lat: None,
+                gps_lon: None,
+                width: None,
+                height: None,
+            },
+        );
+        let nikon = make_photo_with_exif(
+            source.id,
+            "/tmp/nikon.jpg",
+            "nikon_sha",
+            ExifData {
+                date: None,
+                camera_make: Some("Nikon".to_string()),
+                camera_model: Some("Z9".to_string()),
+                gps_lat: None,
+                gps_lon: None,
+                width: None,
+                height: None,
+            },
+        );
+        catalog.upsert_photo(&canon).unwrap();
+        catalog.upsert_photo(&nikon).unwrap();
+
+        let results = catalog
+            .search(&PhotoQuery {
+                text: Some("Canon".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].sha256, "canon_sha");
+    }
+
+    #[test]
+    fn test_search_text_reflects_upsert_overwriting_camera_make() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        let mut photo = make_photo_with_exif(
+            source.id,
+            "/tmp/a.jpg",
+            "a",
+            ExifData {
+                date: None,
+                camera_make: Some("Canon".to_string()),
+                camera_model: None,
+                gps_lat: None,
+                gps_lon: None,
+                width: None,
+                height: None,
+            },
+        );
+        catalog.upsert_photo(&photo).unwrap();
+
+        photo.exif = Some(ExifData {
+            date: None,
+            camera_make: Some("Fujifilm".to_string()),
+            camera_model: None,
+            gps_lat: None,
+            gps_lon: None,
+            width: None,
+            height: None,
+        });
+        catalog.upsert_photo(&photo).unwrap();
+
+        assert!(catalog
+            .search(&PhotoQuery { text: Some("Canon".to_string()), ..Default::default() })
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            catalog
+                .search(&PhotoQuery { text: Some("Fujifilm".to_string()), ..Default::default() })
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_search_filters_by_exif_date_range() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        let old = make_photo_with_exif(
+            source.id,
+            "/tmp/old.jpg",
+            "old",
+            ExifData {
+                date: Some("2020-01-01".to_string()),
+                camera_make: None,
+                camera_model: None,
+                gps_lat: None,
+                gps_lon: None,
+                width: None,
+                height: None,
+            },
+        );
+        let recent = make_photo_with_exif(
+            source.id,
+            "/tmp/recent.jpg",
+            "recent",
+            ExifData {
+                date: Some("2025-06-15".to_string()),
+                camera_make: None,
+                camera_model: None,
+                gps_lat: None,
+                gps_lon: None,
+                width: None,
+                height: None,
+            },
+        );
+        catalog.upsert_photo(&old).unwrap();
+        catalog.upsert_photo(&recent).unwrap();
+
+        let results = catalog
+            .search(&PhotoQuery {
+                exif_date_from: Some("2024-01-01".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].sha256, "recent");
+    }
+
+    #[test]
+    fn test_search_filters_by_gps_bounding_box() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        let paris = make_photo_with_exif(
+            source.id,
+            "/tmp/paris.jpg",
+            "paris",
+            ExifData {
+                date: None,
+                camera_make: None,
+                camera_model: None,
+                gps_lat: Some(48.8566),
+                gps_lon: Some(2.3522),
+                width: None,
+                height: None,
+            },
+        );
+        let tokyo = make_photo_with_exif(
+            source.id,
+            "/tmp/tokyo.jpg",
+            "tokyo",
+            ExifData {
+                date: None,
+                camera_make: None,
+                camera_model: None,
+                gps_lat: Some(35.6762),
+                gps_lon: Some(139.6503),
+                width: None,
+                height: None,
+            },
+        );
+        catalog.upsert_photo(&paris).unwrap();
+        catalog.upsert_photo(&tokyo).unwrap();
+
+        let results = catalog
+            .search(&PhotoQuery {
+                bbox: Some((48.0, 2.0, 49.0, 3.0)),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].sha256, "paris");
+    }
+
+    #[test]
+    fn test_search_filters_by_format_and_size() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        let mut raw = make_photo(source.id, "/tmp/a.cr2", "raw");
+        raw.format = PhotoFormat::Cr2;
+        raw.size = 20_000_000;
+        let mut jpeg = make_photo(source.id, "/tmp/a.jpg", "jpeg");
+        jpeg.format = PhotoFormat::Jpeg;
+        jpeg.size = 500_000;
+        catalog.upsert_photo(&raw).unwrap();
+        catalog.upsert_photo(&jpeg).unwrap();
+
+        let results = catalog
+            .search(&PhotoQuery {
+                format: Some(PhotoFormat::Cr2),
+                size_min: Some(1_000_000),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].sha256, "raw");
+    }
+
+    #[test]
+    fn test_search_filters_by_exact_camera_make() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        let canon = make_photo_with_exif(
+            source.id,
+            "/tmp/canon.jpg",
+            "canon_sha",
+            ExifData {
+                date: None,
+                camera_make: Some("Canon".to_string()),
+                camera_model: None,
+                gps_lat: None,
+                gps_lon: None,
+                width: None,
+                height: None,
+            },
+        );
+        let canon_rumors = make_photo_with_exif(
+            source.id,
+            "/tmp/canon-rumors.jpg",
+            "rumors_sha",
+            ExifData {
+                date: None,
+                camera_make: Some("Canon Rumors".to_string()),
+                camera_model: None,
+                gps_lat: None,
+                gps_lon: None,
+                width: None,
+                height: None,
+            },
+        );
+        catalog.upsert_photo(&canon).unwrap();
+        catalog.upsert_photo(&canon_rumors).unwrap();
+
+        let results = catalog
+            .search(&PhotoQuery {
+                camera_make: Some("Canon".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].sha256, "canon_sha");
+    }
+
+    #[test]
+    fn test_search_orders_results_by_requested_key() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        let mut small = make_photo(source.id, "/tmp/small.jpg", "small");
+        small.size = 100;
+        let mut large = make_photo(source.id, "/tmp/large.jpg", "large");
+        large.size = 900;
+        catalog.upsert_photo(&large).unwrap();
+        catalog.upsert_photo(&small).unwrap();
+
+        let ascending = catalog
+            .search(&PhotoQuery { order_by: Some(SearchOrderBy::SizeAsc), ..Default::default() })
+            .unwrap();
+        assert_eq!(ascending.iter().map(|p| p.sha256.clone()).collect::<Vec<_>>(), vec!["small", "large"]);
+
+        let descending = catalog
+            .search(&PhotoQuery { order_by: Some(SearchOrderBy::SizeDesc), ..Default::default() })
+            .unwrap();
+        assert_eq!(descending.iter().map(|p| p.sha256.clone()).collect::<Vec<_>>(), vec!["large", "small"]);
+    }
+
+    #[test]
+    fn test_search_text_parses_textual_query_form() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        let canon = make_photo_with_exif(
+            source.id,
+            "/tmp/canon.jpg",
+            "canon_sha",
+            ExifData {
+                date: Some("2022-06-01".to_string()),
+                camera_make: Some("Canon".to_string()),
+                camera_model: None,
+                gps_lat: None,
+                gps_lon: None,
+                width: None,
+                height: None,
+            },
+        );
+        catalog.upsert_photo(&canon).unwrap();
+
+        let results = catalog.search_text(r#"camera:"Canon" date>=2022-01-01"#).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].sha256, "canon_sha");
+    }
+
+    #[test]
+    fn test_search_with_no_criteria_returns_every_photo() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        catalog.upsert_photo(&make_photo(source.id, "/tmp/a.jpg", "a")).unwrap();
+        catalog.upsert_photo(&make_photo(source.id, "/tmp/b.jpg", "b")).unwrap();
+        assert_eq!(catalog.search(&PhotoQuery::default()).unwrap().len(), 2);
+    }
+
+    // ── Group tests ──────────────────────────────────────────────
+
+    #[test]
+    fn test_insert_and_get_group() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        let id_a = catalog.upsert_photo(&make_photo(source.id, "/tmp/a.jpg", "aaa")).unwrap();
+        let id_b = catalog.upsert_photo(&make_photo(source.id, "/tmp/b.jpg", "aaa")).unwrap();
+
+        let group_id = catalog.insert_group(id_a, Confidence::Certain, &[id_a, id_b]).unwrap();
+        assert!(group_id > 0);
+
+        let group = catalog.get_group(group_id).unwrap();
+        assert_eq!(group.id, group_id);
+        assert_eq!(group.source_of_truth_id, id_a);
+        assert_eq!(group.confidence, Confidence::Certain);
+        assert_eq!(group.members.len(), 2);
+    }
+
+    #[test]
+    fn test_list_groups() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        let id_a = catalog.upsert_photo(&make_photo(source.id, "/tmp/a.jpg", "aaa")).unwrap();
+        let id_b = catalog.upsert_photo(&make_photo(source.id, "/tmp/b.jpg", "aaa")).unwrap();
+        let id_c = catalog.upsert_photo(&make_photo(source.id, "/tmp/c.jpg", "ccc")).unwrap();
+        let id_d = catalog.upsert_photo(&make_photo(source.id, "/tmp/d.jpg", "ccc")).unwrap();
 
         catalog.insert_group(id_a, Confidence::Certain, &[id_a, id_b]).unwrap();
         catalog.insert_group(id_c, Confidence::High, &[id_c, id_d]).unwrap();
@@ -1153,6 +3076,29 @@ mod tests {
         );
     }
 
+    // ── Export tracking ──────────────────────────────────────────
+
+    #[test]
+    fn test_record_and_look_up_exported_target() {
+        let catalog = Catalog::open_in_memory().unwrap();
+        assert!(catalog.exported_targets_by_sha256s(&["hash1"]).unwrap().is_empty());
+
+        catalog.record_exported("hash1", Path::new("/export/2024/01/01/a.heic")).unwrap();
+        let found = catalog.exported_targets_by_sha256s(&["hash1", "hash2"]).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found["hash1"], PathBuf::from("/export/2024/01/01/a.heic"));
+    }
+
+    #[test]
+    fn test_record_exported_overwrites_target_for_same_hash() {
+        let catalog = Catalog::open_in_memory().unwrap();
+        catalog.record_exported("hash1", Path::new("/export/old.heic")).unwrap();
+        catalog.record_exported("hash1", Path::new("/export/new.heic")).unwrap();
+
+        let found = catalog.exported_targets_by_sha256s(&["hash1"]).unwrap();
+        assert_eq!(found["hash1"], PathBuf::from("/export/new.heic"));
+    }
+
     // ── Clear perceptual hashes ─────────────────────────────────
 
     #[test]
@@ -1226,13 +3172,272 @@ mod tests {
         assert!(photos[0].phash.is_none());
     }
 
+    #[test]
+    fn test_clear_perceptual_hash_only_clears_selected_column() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        let photo = make_photo(source.id, "/tmp/a.jpg", "aaa");
+        catalog.upsert_photo(&photo).unwrap();
+
+        let count = catalog.clear_perceptual_hash(HashKind::Dhash).unwrap();
+        assert_eq!(count, 1);
+
+        let after = catalog.list_all_photos().unwrap();
+        assert!(after[0].phash.is_some(), "phash should be untouched");
+        assert!(after[0].dhash.is_none(), "dhash should be cleared");
+        assert!(after[0].ahash.is_some(), "ahash should be untouched");
+    }
+
+    #[test]
+    fn test_clear_perceptual_hash_returns_zero_when_column_already_null() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        let mut photo = make_photo(source.id, "/tmp/a.jpg", "aaa");
+        photo.ahash = None;
+        catalog.upsert_photo(&photo).unwrap();
+
+        let count = catalog.clear_perceptual_hash(HashKind::Ahash).unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_clear_perceptual_hashes_also_drops_the_in_memory_phash_cache() {
+        // Left alone, `phash_cache` would go on serving the exact sha256 →
+        // hash pair this just nulled out — a stale hit that silently undoes
+        // the invalidation for any dedup pass reading from the cache before
+        // `upsert_photo` repopulates it.
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        let mut photo = make_photo(source.id, "/tmp/a.jpg", "aaa");
+        photo.phash = Some(999);
+        catalog.upsert_photo(&photo).unwrap();
+
+        // Warms the cache with the pre-invalidation hash.
+        assert_eq!(
+            catalog.get_phashes_by_sha256s(&["aaa"]).unwrap().get("aaa"),
+            Some(&(999, None, None))
+        );
+
+        catalog.clear_perceptual_hashes().unwrap();
+
+        assert!(
+            catalog.get_phashes_by_sha256s(&["aaa"]).unwrap().get("aaa").is_none(),
+            "a cached hit must not survive clear_perceptual_hashes"
+        );
+    }
+
+    #[test]
+    fn test_clear_perceptual_hash_also_drops_the_in_memory_phash_cache() {
+        let (catalog, source, _tmp) = make_catalog_with_source();
+        let photo = make_photo(source.id, "/tmp/a.jpg", "aaa");
+        catalog.upsert_photo(&photo).unwrap();
+
+        // Warms the cache, including the dhash this test is about to clear.
+        catalog.get_phashes_by_sha256s(&["aaa"]).unwrap();
+
+        catalog.clear_perceptual_hash(HashKind::Dhash).unwrap();
+
+        let found = catalog.get_phashes_by_sha256s(&["aaa"]).unwrap();
+        let (phash, dhash, _ahash) = *found.get("aaa").unwrap();
+        assert_eq!(phash, 12345, "phash is untouched, so it should still be reported");
+        assert_eq!(dhash, None, "cached dhash must not survive clear_perceptual_hash");
+    }
+
+    // ── Broken files ──────────────────────────────────────────────
+
+    #[test]
+    fn test_record_and_list_broken_file() {
+        let catalog = Catalog::open_in_memory().unwrap();
+        catalog
+            .record_broken_file(Path::new("/tmp/corrupt.jpg"), "panicked during decode", 1000)
+            .unwrap();
+
+        let broken = catalog.list_broken_files().unwrap();
+        assert_eq!(broken, vec![(PathBuf::from("/tmp/corrupt.jpg"), "panicked during decode".to_string())]);
+        assert_eq!(catalog.broken_file_count().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_record_broken_file_overwrites_reason_on_same_path() {
+        let catalog = Catalog::open_in_memory().unwrap();
+        catalog
+            .record_broken_file(Path::new("/tmp/corrupt.jpg"), "first reason", 1000)
+            .unwrap();
+        catalog
+            .record_broken_file(Path::new("/tmp/corrupt.jpg"), "second reason", 2000)
+            .unwrap();
+
+        let broken = catalog.list_broken_files().unwrap();
+        assert_eq!(broken.len(), 1, "rescans at the same path should overwrite, not accumulate");
+        assert_eq!(broken[0].1, "second reason");
+    }
+
+    #[test]
+    fn test_clear_broken_file_removes_it_from_the_quarantine_list() {
+        let catalog = Catalog::open_in_memory().unwrap();
+        catalog
+            .record_broken_file(Path::new("/tmp/fixed.jpg"), "decode error", 1000)
+            .unwrap();
+
+        catalog.clear_broken_file(Path::new("/tmp/fixed.jpg")).unwrap();
+
+        assert!(catalog.list_broken_files().unwrap().is_empty());
+        assert_eq!(catalog.broken_file_count().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_broken_file_count_empty_by_default() {
+        let catalog = Catalog::open_in_memory().unwrap();
+        assert_eq!(catalog.broken_file_count().unwrap(), 0);
+    }
+
+    // ── Hash cache ────────────────────────────────────────────────
+
+    #[test]
+    fn test_upsert_and_get_cached_hashes_round_trip() {
+        let mut catalog = Catalog::open_in_memory().unwrap();
+        catalog
+            .upsert_hash_cache_batch(&[(PathBuf::from("/tmp/a.jpg"), 1000, 5000, "sha_a".to_string())])
+            .unwrap();
+
+        let cached = catalog
+            .get_cached_hashes(&[Path::new("/tmp/a.jpg"), Path::new("/tmp/missing.jpg")])
+            .unwrap();
+
+        assert_eq!(
+            cached.get(Path::new("/tmp/a.jpg")),
+            Some(&(1000, 5000, "sha_a".to_string()))
+        );
+        assert!(cached.get(Path::new("/tmp/missing.jpg")).is_none());
+    }
+
+    #[test]
+    fn test_upsert_hash_cache_batch_overwrites_on_same_path() {
+        let mut catalog = Catalog::open_in_memory().unwrap();
+        catalog
+            .upsert_hash_cache_batch(&[(PathBuf::from("/tmp/a.jpg"), 1000, 5000, "old_sha".to_string())])
+            .unwrap();
+        catalog
+            .upsert_hash_cache_batch(&[(PathBuf::from("/tmp/a.jpg"), 2000, 6000, "new_sha".to_string())])
+            .unwrap();
+
+        let cached = catalog.get_cached_hashes(&[Path::new("/tmp/a.jpg")]).unwrap();
+        assert_eq!(
+            cached.get(Path::new("/tmp/a.jpg")),
+            Some(&(2000, 6000, "new_sha".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_get_cached_hashes_empty_paths_returns_empty_map() {
+        let catalog = Catalog::open_in_memory().unwrap();
+        assert!(catalog.get_cached_hashes(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_clear_hash_cache_drops_all_entries() {
+        let mut catalog = Catalog::open_in_memory().unwrap();
+        catalog
+            .upsert_hash_cache_batch(&[(PathBuf::from("/tmp/a.jpg"), 1000, 5000, "sha_a".to_string())])
+            .unwrap();
+
+        let cleared = catalog.clear_hash_cache().unwrap();
+
+        assert_eq!(cleared, 1);
+        assert!(catalog
+            .get_cached_hashes(&[Path::new("/tmp/a.jpg")])
+            .unwrap()
+            .is_empty());
+    }
+
+    // ── Garbage collection ───────────────────────────────────────
+
+    #[test]
+    fn test_gc_on_clean_catalog_reports_nothing() {
+        let mut catalog = Catalog::open_in_memory().unwrap();
+        let source_id = catalog.add_source(Path::new(".")).unwrap().id;
+        let a = catalog.upsert_photo(&make_photo(source_id, "/tmp/a.jpg", "sha_a")).unwrap();
+        let b = catalog.upsert_photo(&make_photo(source_id, "/tmp/b.jpg", "sha_b")).unwrap();
+        catalog.insert_group(a, Confidence::High, &[a, b]).unwrap();
+
+        let report = catalog.gc().unwrap();
+        assert_eq!(report, GcReport::default());
+    }
+
+    #[test]
+    fn test_gc_removes_group_members_pointing_at_a_missing_photo() {
+        let mut catalog = Catalog::open_in_memory().unwrap();
+        let source_id = catalog.add_source(Path::new(".")).unwrap().id;
+        let a = catalog.upsert_photo(&make_photo(source_id, "/tmp/a.jpg", "sha_a")).unwrap();
+        let b = catalog.upsert_photo(&make_photo(source_id, "/tmp/b.jpg", "sha_b")).unwrap();
+        let c = catalog.upsert_photo(&make_photo(source_id, "/tmp/c.jpg", "sha_c")).unwrap();
+        catalog.insert_group(a, Confidence::High, &[a, b, c]).unwrap();
+
+        // Simulate a partial/crashed deletion: the photo row is gone but the
+        // membership row referencing it was left behind.
+        catalog.conn.execute("DELETE FROM photos WHERE id = ?1", params![c]).unwrap();
+
+        let report = catalog.gc().unwrap();
+        assert_eq!(report.orphaned_members, 1);
+        assert_eq!(report.orphaned_groups, 0);
+    }
+
+    #[test]
+    fn test_gc_drops_groups_left_with_fewer_than_two_members() {
+        let mut catalog = Catalog::open_in_memory().unwrap();
+        let source_id = catalog.add_source(Path::new(".")).unwrap().id;
+        let a = catalog.upsert_photo(&make_photo(source_id, "/tmp/a.jpg", "sha_a")).unwrap();
+        let b = catalog.upsert_photo(&make_photo(source_id, "/tmp/b.jpg", "sha_b")).unwrap();
+        let group_id = catalog.insert_group(a, Confidence::High, &[a, b]).unwrap();
+
+        // One member vanishes without its group being cleaned up first.
+        catalog.conn.execute("DELETE FROM photos WHERE id = ?1", params![b]).unwrap();
+        catalog.conn.execute(
+            "DELETE FROM group_members WHERE photo_id = ?1",
+            params![b],
+        ).unwrap();
+
+        let report = catalog.gc().unwrap();
+        assert_eq!(report.orphaned_groups, 1);
+        assert!(catalog.list_groups().unwrap().iter().all(|g| g.id != group_id));
+    }
+
+    #[test]
+    fn test_gc_drops_groups_with_dangling_source_of_truth() {
+        let mut catalog = Catalog::open_in_memory().unwrap();
+        let source_id = catalog.add_source(Path::new(".")).unwrap().id;
+        let a = catalog.upsert_photo(&make_photo(source_id, "/tmp/a.jpg", "sha_a")).unwrap();
+        let b = catalog.upsert_photo(&make_photo(source_id, "/tmp/b.jpg", "sha_b")).unwrap();
+        let group_id = catalog.insert_group(a, Confidence::High, &[a, b]).unwrap();
+
+        // The source-of-truth photo is gone, but nothing re-pointed the group.
+        catalog.conn.execute("DELETE FROM photos WHERE id = ?1", params![a]).unwrap();
+        catalog.conn.execute(
+            "DELETE FROM group_members WHERE photo_id = ?1",
+            params![a],
+        ).unwrap();
+
+        let report = catalog.gc().unwrap();
+        assert_eq!(report.orphaned_groups, 1);
+        assert!(catalog.list_groups().unwrap().iter().all(|g| g.id != group_id));
+    }
+
+    #[test]
+    fn test_gc_counts_but_does_not_delete_photos_from_a_missing_source() {
+        let mut catalog = Catalog::open_in_memory().unwrap();
+        let source_id = catalog.add_source(Path::new(".")).unwrap().id;
+        catalog.upsert_photo(&make_photo(source_id, "/tmp/a.jpg", "sha_a")).unwrap();
+        catalog.conn.execute("DELETE FROM sources WHERE id = ?1", params![source_id]).unwrap();
+
+        let report = catalog.gc().unwrap();
+        assert_eq!(report.orphaned_photos, 1);
+        assert_eq!(catalog.count_photos().unwrap(), 1);
+    }
+
     // ── Schema version tracking ─────────────────────────────────
 
     #[test]
     fn test_schema_version_set_on_fresh_db() {
         let catalog = Catalog::open_in_memory().unwrap();
         let version = catalog.get_config("schema_version").unwrap();
-        assert_eq!(version, Some("1".to_string()));
+        assert_eq!(version, Some(schema::SCHEMA_VERSION.to_string()));
     }
 
     #[test]
@@ -1242,16 +3447,16 @@ mod tests {
 
         {
             let catalog = Catalog::open(&db_path).unwrap();
-            assert_eq!(catalog.get_config("schema_version").unwrap(), Some("1".to_string()));
+            assert_eq!(catalog.get_config("schema_version").unwrap(), Some(schema::SCHEMA_VERSION.to_string()));
         }
         {
             let catalog = Catalog::open(&db_path).unwrap();
-            assert_eq!(catalog.get_config("schema_version").unwrap(), Some("1".to_string()));
+            assert_eq!(catalog.get_config("schema_version").unwrap(), Some(schema::SCHEMA_VERSION.to_string()));
         }
     }
 
     #[test]
-    fn test_pre_versioning_db_upgraded_to_v1() {
+    fn test_pre_versioning_db_upgraded_to_current() {
         // Create a DB with schema but no schema_version key.
         let conn = Connection::open_in_memory().unwrap();
         conn.pragma_update(None, "foreign_keys", "ON").unwrap();
@@ -1263,12 +3468,12 @@ mod tests {
             .ok();
         assert!(v.is_none());
 
-        // Running migrate should set it to 1.
+        // Running migrate should set it to 1, then run pending migrations up to current.
         schema::migrate(&conn).unwrap();
         let v: String = conn
             .query_row("SELECT value FROM config WHERE key = 'schema_version'", [], |r| r.get(0))
             .unwrap();
-        assert_eq!(v, "1");
+        assert_eq!(v, schema::SCHEMA_VERSION.to_string());
     }
 
     #[test]
@@ -1285,7 +3490,7 @@ mod tests {
         .unwrap();
 
         let err = schema::migrate(&conn).unwrap_err();
-        assert!(matches!(err, Error::SchemaTooNew { db: 999, code: 1 }));
+        assert!(matches!(err, Error::SchemaTooNew { db: 999, code: 10 }));
     }
 
     #[test]
@@ -1298,7 +3503,110 @@ mod tests {
         let v: String = conn
             .query_row("SELECT value FROM config WHERE key = 'schema_version'", [], |r| r.get(0))
             .unwrap();
-        assert_eq!(v, "1");
+        assert_eq!(v, schema::SCHEMA_VERSION.to_string());
+    }
+
+    #[test]
+    fn test_v1_db_migrates_to_v2_adds_exported_objects_table() {
+        // Simulate a DB that was already on schema v1 before this migration existed.
+        let conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "foreign_keys", "ON").unwrap();
+        schema::initialize(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO config (key, value) VALUES ('schema_version', '1')",
+            [],
+        )
+        .unwrap();
+        conn.execute("DROP TABLE exported_objects", []).unwrap();
+
+        schema::migrate(&conn).unwrap();
+
+        let exists: bool = conn
+            .prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'exported_objects'")
+            .unwrap()
+            .exists([])
+            .unwrap();
+        assert!(exists, "migration should (re)create exported_objects");
+    }
+
+    #[test]
+    fn test_v2_db_migrates_to_v3_adds_ahash_column() {
+        // Simulate a DB that was already on schema v2, before the ahash column existed.
+        let conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "foreign_keys", "ON").unwrap();
+        schema::initialize(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO config (key, value) VALUES ('schema_version', '2')",
+            [],
+        )
+        .unwrap();
+        conn.execute("ALTER TABLE photos DROP COLUMN ahash", []).unwrap();
+
+        schema::migrate(&conn).unwrap();
+
+        let columns: Vec<String> = conn
+            .prepare("SELECT name FROM pragma_table_info('photos')")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert!(columns.contains(&"ahash".to_string()), "migration should add ahash column");
+    }
+
+    #[test]
+    fn test_migrating_a_file_backed_db_leaves_a_pre_migration_backup() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("catalog.db");
+        let conn = Connection::open(&db_path).unwrap();
+        conn.pragma_update(None, "foreign_keys", "ON").unwrap();
+        schema::initialize(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO config (key, value) VALUES ('schema_version', '2')",
+            [],
+        )
+        .unwrap();
+        drop(conn);
+
+        // Reopen so `migrate` runs against a real file-backed connection.
+        let conn = Connection::open(&db_path).unwrap();
+        schema::migrate(&conn).unwrap();
+
+        let backup_path = tmp.path().join("catalog.db.v2.bak");
+        assert!(backup_path.exists(), "migrate should back up the pre-migration file");
+    }
+
+    #[test]
+    fn test_migrating_an_in_memory_db_does_not_attempt_a_backup() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "foreign_keys", "ON").unwrap();
+        schema::initialize(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO config (key, value) VALUES ('schema_version', '2')",
+            [],
+        )
+        .unwrap();
+
+        // Must not error trying to copy a nonexistent path.
+        schema::migrate(&conn).unwrap();
+    }
+
+    #[test]
+    fn test_migration_failure_reports_the_target_version_it_was_heading_towards() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "foreign_keys", "ON").unwrap();
+        schema::initialize(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO config (key, value) VALUES ('schema_version', '2')",
+            [],
+        )
+        .unwrap();
+        // `initialize` already creates the photos table with an ahash
+        // column, so the v2->v3 migration's `ALTER TABLE ADD COLUMN ahash`
+        // fails — migrate should name the version it was migrating *to*,
+        // not just surface the raw SQLite error.
+        let err = schema::migrate(&conn).unwrap_err();
+        assert!(matches!(err, Error::MigrationFailed { version: 3, .. }));
     }
 
     // ── Schema structure pinning ────────────────────────────────
@@ -1315,7 +3623,28 @@ mod tests {
             .unwrap()
             .map(|r| r.unwrap())
             .collect();
-        assert_eq!(tables, vec!["config", "duplicate_groups", "group_members", "photos", "sources"]);
+        assert_eq!(
+            tables,
+            vec![
+                "broken_files",
+                "config",
+                "duplicate_groups",
+                "events",
+                "exported_objects",
+                "group_members",
+                "hash_cache",
+                "photos",
+                // The FTS5 virtual table and its shadow tables.
+                "photos_fts",
+                "photos_fts_config",
+                "photos_fts_data",
+                "photos_fts_docsize",
+                "photos_fts_idx",
+                "sources",
+                "tombstones",
+                "vault_operations",
+            ]
+        );
     }
 
     #[test]
@@ -1333,11 +3662,14 @@ mod tests {
         assert_eq!(
             indexes,
             vec![
+                "idx_duplicate_groups_merge_key",
                 "idx_group_members_photo",
+                "idx_photos_exif_date_source",
                 "idx_photos_path",
                 "idx_photos_sha256",
                 "idx_photos_source",
                 "idx_photos_source_mtime",
+                "idx_vault_operations_run",
             ]
         );
     }
@@ -1358,9 +3690,9 @@ mod tests {
             columns,
             vec![
                 "id", "source_id", "path", "size", "format", "sha256",
-                "phash", "dhash", "mtime", "exif_date", "exif_camera_make",
+                "phash", "dhash", "ahash", "mtime", "exif_date", "exif_camera_make",
                 "exif_camera_model", "exif_gps_lat", "exif_gps_lon",
-                "exif_width", "exif_height",
+                "exif_width", "exif_height", "updated_at",
             ]
         );
     }
@@ -1389,6 +3721,7 @@ mod tests {
         // Tables (sorted alphabetically)
         assert!(normalized.iter().any(|s| s.contains("CREATE TABLE config")));
         assert!(normalized.iter().any(|s| s.contains("CREATE TABLE duplicate_groups")));
+        assert!(normalized.iter().any(|s| s.contains("CREATE TABLE exported_objects")));
         assert!(normalized.iter().any(|s| s.contains("CREATE TABLE group_members")));
         assert!(normalized.iter().any(|s| s.contains("CREATE TABLE photos")));
         assert!(normalized.iter().any(|s| s.contains("CREATE TABLE sources")));
@@ -0,0 +1,364 @@
+//! In-memory write-back LRU cache over `sha256 -> (phash, dhash, ahash)`,
+//! layered in front of the `photos` table's hash columns.
+//!
+//! `Catalog::get_phashes_by_sha256s` is the hot path this exists for: a dedup
+//! pass re-queries the same working set of content hashes over and over, and
+//! without a cache every one of those repeats is a fresh SQLite round trip.
+//! Reads consult the cache first; only misses fall through to a batched
+//! query, which backfills the cache for next time.
+//!
+//! It's write-back rather than write-through: `Catalog::upsert_photo`
+//! records a freshly computed hash here and marks it dirty immediately,
+//! without an extra SQLite write — the hash is already durable on the
+//! photo's own row, but the cache entry also feeds `flush`, which backfills
+//! any *other* row sharing the same content hash that's still missing its
+//! own phash (e.g. an exact duplicate whose hash computation was skipped).
+//! A dirty entry is flushed before it's evicted, so a small `capacity` never
+//! silently drops a pending backfill — it just flushes it sooner.
+
+use std::collections::HashMap;
+
+use rusqlite::{params, Connection};
+
+use crate::error::Result;
+
+/// Capacity used if the caller never calls `Catalog::set_phash_cache_capacity`.
+pub const DEFAULT_CAPACITY: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Entry {
+    phash: u64,
+    dhash: Option<u64>,
+    ahash: Option<u64>,
+    dirty: bool,
+}
+
+/// See module docs.
+#[derive(Debug)]
+pub struct PhashCache {
+    capacity: usize,
+    entries: HashMap<String, Entry>,
+    /// Recency order, front = least recently used, back = most recently used.
+    recency: Vec<String>,
+}
+
+impl PhashCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: HashMap::new(),
+            recency: Vec::new(),
+        }
+    }
+
+    /// Change the capacity, evicting (and flushing, if dirty) down to the
+    /// new size if it's smaller than the current entry count.
+    pub fn set_capacity(&mut self, conn: &Connection, capacity: usize) -> Result<()> {
+        self.capacity = capacity.max(1);
+        self.evict_excess(conn)
+    }
+
+    /// Drop every entry without flushing. Used when the `photos` table's
+    /// hash columns are wiped out from under the cache — e.g. a hash
+    /// algorithm version change — so a stale in-memory entry can't go on
+    /// serving hashes that no longer exist in (or agree with) the database.
+    /// There's nothing to flush: the caller already nulled the backing
+    /// column, so any pending dirty entry would just resurrect a stale value.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+
+    fn touch(&mut self, sha256: &str) {
+        if let Some(pos) = self.recency.iter().position(|k| k == sha256) {
+            let key = self.recency.remove(pos);
+            self.recency.push(key);
+        } else {
+            self.recency.push(sha256.to_string());
+        }
+    }
+
+    /// Look up a cached hash, marking it most-recently-used. `None` means a
+    /// cache miss — the caller should fall back to SQL and backfill via
+    /// `insert_clean`.
+    pub fn get(&mut self, sha256: &str) -> Option<(u64, Option<u64>, Option<u64>)> {
+        let entry = *self.entries.get(sha256)?;
+        self.touch(sha256);
+        Some((entry.phash, entry.dhash, entry.ahash))
+    }
+
+    /// Record a hash just read back from SQL — already durable, so not dirty.
+    pub fn insert_clean(
+        &mut self,
+        conn: &Connection,
+        sha256: &str,
+        phash: u64,
+        dhash: Option<u64>,
+        ahash: Option<u64>,
+    ) -> Result<()> {
+        self.insert(conn, sha256, phash, dhash, ahash, false)
+    }
+
+    /// Record a freshly computed hash from `Catalog::upsert_photo` /
+    /// `upsert_photos_batch`. Marked dirty so `flush` backfills any other
+    /// row sharing this content hash that's still missing its own phash.
+    pub fn insert_dirty(
+        &mut self,
+        conn: &Connection,
+        sha256: &str,
+        phash: u64,
+        dhash: Option<u64>,
+        ahash: Option<u64>,
+    ) -> Result<()> {
+        self.insert(conn, sha256, phash, dhash, ahash, true)
+    }
+
+    fn insert(
+        &mut self,
+        conn: &Connection,
+        sha256: &str,
+        phash: u64,
+        dhash: Option<u64>,
+        ahash: Option<u64>,
+        dirty: bool,
+    ) -> Result<()> {
+        self.entries.insert(
+            sha256.to_string(),
+            Entry {
+                phash,
+                dhash,
+                ahash,
+                dirty,
+            },
+        );
+        self.touch(sha256);
+        self.evict_excess(conn)
+    }
+
+    /// Flush every dirty entry: backfill any `photos` row sharing that
+    /// content hash but still missing its own phash. Never overwrites a row
+    /// that already has one, so this can't clobber an independently-computed
+    /// (and possibly different) hash for the same content. Returns the
+    /// number of entries flushed.
+    pub fn flush(&mut self, conn: &Connection) -> Result<usize> {
+        let mut flushed = 0;
+        for (sha256, entry) in self.entries.iter_mut() {
+            if !entry.dirty {
+                continue;
+            }
+            conn.execute(
+                "UPDATE photos SET phash=?1, dhash=?2, ahash=?3 WHERE sha256=?4 AND phash IS NULL",
+                params![
+                    entry.phash as i64,
+                    entry.dhash.map(|v| v as i64),
+                    entry.ahash.map(|v| v as i64),
+                    sha256,
+                ],
+            )?;
+            entry.dirty = false;
+            flushed += 1;
+        }
+        Ok(flushed)
+    }
+
+    /// Evict least-recently-used entries down to `capacity`, flushing any
+    /// dirty one first so eviction never silently drops a pending backfill.
+    fn evict_excess(&mut self, conn: &Connection) -> Result<()> {
+        while self.entries.len() > self.capacity {
+            let Some(lru_key) = self.recency.first().cloned() else {
+                break;
+            };
+            self.recency.remove(0);
+            if let Some(entry) = self.entries.get(&lru_key) {
+                if entry.dirty {
+                    let entry = *entry;
+                    conn.execute(
+                        "UPDATE photos SET phash=?1, dhash=?2, ahash=?3 WHERE sha256=?4 AND phash IS NULL",
+                        params![
+                            entry.phash as i64,
+                            entry.dhash.map(|v| v as i64),
+                            entry.ahash.map(|v| v as i64),
+                            lru_key,
+                        ],
+                    )?;
+                }
+            }
+            self.entries.remove(&lru_key);
+        }
+        Ok(())
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[cfg(test)]
+    fn is_dirty(&self, sha256: &str) -> bool {
+        self.entries.get(sha256).is_some_and(|e| e.dirty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conn_with_schema() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::catalog::schema::initialize(&conn).unwrap();
+        conn
+    }
+
+    fn insert_photo(conn: &Connection, path: &str, sha256: &str, phash: Option<u64>) {
+        conn.execute(
+            "INSERT INTO sources (id, path) VALUES (1, '/src') ON CONFLICT(id) DO NOTHING",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO photos (source_id, path, size, format, sha256, phash, mtime)
+             VALUES (1, ?1, 100, 'JPEG', ?2, ?3, 1000)",
+            params![path, sha256, phash.map(|v| v as i64)],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_get_is_miss_on_empty_cache() {
+        let mut cache = PhashCache::new(10);
+        assert!(cache.get("sha_a").is_none());
+    }
+
+    #[test]
+    fn test_insert_clean_then_get_is_a_hit_and_not_dirty() {
+        let conn = conn_with_schema();
+        let mut cache = PhashCache::new(10);
+        cache.insert_clean(&conn, "sha_a", 111, Some(222), None).unwrap();
+        assert_eq!(cache.get("sha_a"), Some((111, Some(222), None)));
+        assert!(!cache.is_dirty("sha_a"));
+    }
+
+    #[test]
+    fn test_insert_dirty_marks_entry_dirty() {
+        let conn = conn_with_schema();
+        let mut cache = PhashCache::new(10);
+        cache.insert_dirty(&conn, "sha_a", 111, None, None).unwrap();
+        assert!(cache.is_dirty("sha_a"));
+    }
+
+    #[test]
+    fn test_eviction_drops_least_recently_used_entry() {
+        let conn = conn_with_schema();
+        let mut cache = PhashCache::new(2);
+        cache.insert_clean(&conn, "sha_a", 1, None, None).unwrap();
+        cache.insert_clean(&conn, "sha_b", 2, None, None).unwrap();
+        // Touch "sha_a" so "sha_b" becomes the least-recently-used entry.
+        cache.get("sha_a");
+        cache.insert_clean(&conn, "sha_c", 3, None, None).unwrap();
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("sha_a").is_some());
+        assert!(cache.get("sha_b").is_none());
+        assert!(cache.get("sha_c").is_some());
+    }
+
+    #[test]
+    fn test_eviction_flushes_a_dirty_entry_before_dropping_it() {
+        let conn = conn_with_schema();
+        insert_photo(&conn, "/tmp/dup.jpg", "sha_dup", None);
+
+        let mut cache = PhashCache::new(1);
+        cache.insert_dirty(&conn, "sha_dup", 999, Some(1), Some(2)).unwrap();
+        // Evicts "sha_dup" from the cache, but it was dirty — its backfill
+        // must land in the database before the in-memory copy disappears.
+        cache.insert_clean(&conn, "sha_other", 1, None, None).unwrap();
+
+        let phash: Option<i64> = conn
+            .query_row("SELECT phash FROM photos WHERE sha256 = 'sha_dup'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(phash, Some(999));
+    }
+
+    #[test]
+    fn test_flush_backfills_other_rows_with_the_same_sha256_missing_a_phash() {
+        let conn = conn_with_schema();
+        insert_photo(&conn, "/tmp/a.jpg", "sha_shared", Some(111));
+        insert_photo(&conn, "/tmp/b.jpg", "sha_shared", None);
+
+        let mut cache = PhashCache::new(10);
+        cache.insert_dirty(&conn, "sha_shared", 111, Some(222), None).unwrap();
+        let flushed = cache.flush(&conn).unwrap();
+        assert_eq!(flushed, 1);
+
+        let phash: Option<i64> = conn
+            .query_row(
+                "SELECT phash FROM photos WHERE path = '/tmp/b.jpg'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(phash, Some(111));
+    }
+
+    #[test]
+    fn test_flush_never_overwrites_a_row_that_already_has_a_phash() {
+        let conn = conn_with_schema();
+        insert_photo(&conn, "/tmp/a.jpg", "sha_shared", Some(111));
+        insert_photo(&conn, "/tmp/b.jpg", "sha_shared", Some(555));
+
+        let mut cache = PhashCache::new(10);
+        cache.insert_dirty(&conn, "sha_shared", 111, None, None).unwrap();
+        cache.flush(&conn).unwrap();
+
+        let phash: Option<i64> = conn
+            .query_row(
+                "SELECT phash FROM photos WHERE path = '/tmp/b.jpg'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(phash, Some(555), "an existing phash must never be clobbered by the cache's backfill");
+    }
+
+    #[test]
+    fn test_flush_clears_the_dirty_flag() {
+        let conn = conn_with_schema();
+        let mut cache = PhashCache::new(10);
+        cache.insert_dirty(&conn, "sha_a", 1, None, None).unwrap();
+        cache.flush(&conn).unwrap();
+        assert!(!cache.is_dirty("sha_a"));
+        assert_eq!(cache.flush(&conn).unwrap(), 0, "a second flush has nothing left to do");
+    }
+
+    #[test]
+    fn test_clear_drops_entries_without_flushing_a_dirty_one() {
+        let conn = conn_with_schema();
+        insert_photo(&conn, "/tmp/dup.jpg", "sha_dup", None);
+
+        let mut cache = PhashCache::new(10);
+        cache.insert_dirty(&conn, "sha_dup", 999, Some(1), Some(2)).unwrap();
+        cache.clear();
+
+        assert_eq!(cache.len(), 0);
+        assert!(cache.get("sha_dup").is_none());
+        // The dirty backfill must NOT have landed — the version bump that
+        // triggered the clear already wiped this column on purpose.
+        let phash: Option<i64> = conn
+            .query_row("SELECT phash FROM photos WHERE sha256 = 'sha_dup'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(phash, None);
+    }
+
+    #[test]
+    fn test_set_capacity_shrinks_and_evicts() {
+        let conn = conn_with_schema();
+        let mut cache = PhashCache::new(10);
+        cache.insert_clean(&conn, "sha_a", 1, None, None).unwrap();
+        cache.insert_clean(&conn, "sha_b", 2, None, None).unwrap();
+        cache.insert_clean(&conn, "sha_c", 3, None, None).unwrap();
+
+        cache.set_capacity(&conn, 1).unwrap();
+        assert_eq!(cache.len(), 1);
+        assert!(cache.get("sha_c").is_some(), "most recently used entry should survive");
+    }
+}
@@ -0,0 +1,192 @@
+//! Tiny tokenizer/parser for the textual form of a `PhotoQuery`, so CLI
+//! users can write `camera:"Canon" date>=2022-01-01 bbox:48.8,2.3,48.9,2.4`
+//! instead of constructing the struct by hand. Every recognized term lowers
+//! directly onto a `PhotoQuery` field — there's no separate execution path,
+//! just a different way to build the same filter `Catalog::search` takes.
+
+use crate::catalog::{PhotoQuery, SearchOrderBy};
+use crate::domain::PhotoFormat;
+use crate::error::{Error, Result};
+
+/// Parse a query string into a `PhotoQuery`. Recognized terms, whitespace
+/// separated (a double-quoted span may contain whitespace):
+///
+/// - `camera:"Make"` / `camera:Make` — exact match against `exif_camera_make`
+/// - `date>=YYYY-MM-DD` / `date<=YYYY-MM-DD` — `exif_date` range bounds
+/// - `format:ext` — exact format match (`jpg`, `cr2`, `heic`, ...)
+/// - `bbox:min_lat,min_lon,max_lat,max_lon` — GPS bounding box
+/// - `sort:field` / `sort:field:desc` — `date`, `size`, or `path`
+/// - anything else is added to the free-text `photos_fts` match
+pub fn parse(input: &str) -> Result<PhotoQuery> {
+    let mut query = PhotoQuery::default();
+    let mut text_terms: Vec<String> = Vec::new();
+
+    for token in tokenize(input) {
+        if let Some(value) = token.strip_prefix("camera:") {
+            query.camera_make = Some(unquote(value));
+        } else if let Some(value) = token.strip_prefix("model:") {
+            query.camera_model = Some(unquote(value));
+        } else if let Some(value) = token.strip_prefix("date>=") {
+            query.exif_date_from = Some(value.to_string());
+        } else if let Some(value) = token.strip_prefix("date<=") {
+            query.exif_date_to = Some(value.to_string());
+        } else if let Some(value) = token.strip_prefix("format:") {
+            query.format = Some(parse_format_name(value)?);
+        } else if let Some(value) = token.strip_prefix("bbox:") {
+            query.bbox = Some(parse_bbox(value)?);
+        } else if let Some(value) = token.strip_prefix("sort:") {
+            query.order_by = Some(parse_sort(value)?);
+        } else {
+            text_terms.push(unquote(&token));
+        }
+    }
+
+    if !text_terms.is_empty() {
+        query.text = Some(text_terms.join(" "));
+    }
+    Ok(query)
+}
+
+/// Split `input` on whitespace, except inside a double-quoted span (so
+/// `camera:"Canon EOS"` stays one token).
+fn tokenize(input: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for ch in input.chars() {
+        match ch {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+fn unquote(value: &str) -> String {
+    value.trim_matches('"').to_string()
+}
+
+fn parse_format_name(value: &str) -> Result<PhotoFormat> {
+    match unquote(value).to_ascii_lowercase().as_str() {
+        "jpg" | "jpeg" => Ok(PhotoFormat::Jpeg),
+        "cr2" => Ok(PhotoFormat::Cr2),
+        "cr3" => Ok(PhotoFormat::Cr3),
+        "nef" => Ok(PhotoFormat::Nef),
+        "arw" => Ok(PhotoFormat::Arw),
+        "orf" => Ok(PhotoFormat::Orf),
+        "raf" => Ok(PhotoFormat::Raf),
+        "rw2" => Ok(PhotoFormat::Rw2),
+        "dng" => Ok(PhotoFormat::Dng),
+        "tiff" => Ok(PhotoFormat::Tiff),
+        "png" => Ok(PhotoFormat::Png),
+        "heic" => Ok(PhotoFormat::Heic),
+        "webp" => Ok(PhotoFormat::Webp),
+        other => Err(Error::InvalidQuery(format!("unknown format: {other}"))),
+    }
+}
+
+fn parse_bbox(value: &str) -> Result<(f64, f64, f64, f64)> {
+    let parts: Vec<&str> = value.split(',').collect();
+    let [min_lat, min_lon, max_lat, max_lon]: [&str; 4] = parts.try_into().map_err(|_| {
+        Error::InvalidQuery(format!(
+            "bbox needs 4 comma-separated values (min_lat,min_lon,max_lat,max_lon), got: {value}"
+        ))
+    })?;
+    let parse_coord = |s: &str| {
+        s.trim()
+            .parse::<f64>()
+            .map_err(|_| Error::InvalidQuery(format!("bbox values must be numbers: {value}")))
+    };
+    Ok((
+        parse_coord(min_lat)?,
+        parse_coord(min_lon)?,
+        parse_coord(max_lat)?,
+        parse_coord(max_lon)?,
+    ))
+}
+
+fn parse_sort(value: &str) -> Result<SearchOrderBy> {
+    let (field, descending) = match value.strip_suffix(":desc") {
+        Some(field) => (field, true),
+        None => (value.strip_suffix(":asc").unwrap_or(value), false),
+    };
+    match (field, descending) {
+        ("date", false) => Ok(SearchOrderBy::ExifDateAsc),
+        ("date", true) => Ok(SearchOrderBy::ExifDateDesc),
+        ("size", false) => Ok(SearchOrderBy::SizeAsc),
+        ("size", true) => Ok(SearchOrderBy::SizeDesc),
+        ("path", false) => Ok(SearchOrderBy::PathAsc),
+        ("path", true) => Ok(SearchOrderBy::PathDesc),
+        (other, _) => Err(Error::InvalidQuery(format!("unknown sort field: {other}"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_camera_and_date_range() {
+        let query = parse(r#"camera:"Canon" date>=2022-01-01"#).unwrap();
+        assert_eq!(query.camera_make, Some("Canon".to_string()));
+        assert_eq!(query.exif_date_from, Some("2022-01-01".to_string()));
+        assert_eq!(query.text, None);
+    }
+
+    #[test]
+    fn test_parse_bbox() {
+        let query = parse("bbox:48.8,2.3,48.9,2.4").unwrap();
+        assert_eq!(query.bbox, Some((48.8, 2.3, 48.9, 2.4)));
+    }
+
+    #[test]
+    fn test_parse_bbox_rejects_wrong_arity() {
+        assert!(parse("bbox:48.8,2.3").is_err());
+    }
+
+    #[test]
+    fn test_parse_format() {
+        let query = parse("format:jpg").unwrap();
+        assert_eq!(query.format, Some(PhotoFormat::Jpeg));
+    }
+
+    #[test]
+    fn test_parse_format_rejects_unknown_extension() {
+        assert!(parse("format:bmp").is_err());
+    }
+
+    #[test]
+    fn test_parse_sort_with_explicit_direction() {
+        let query = parse("sort:date:desc").unwrap();
+        assert_eq!(query.order_by, Some(SearchOrderBy::ExifDateDesc));
+    }
+
+    #[test]
+    fn test_parse_sort_defaults_to_ascending() {
+        let query = parse("sort:size").unwrap();
+        assert_eq!(query.order_by, Some(SearchOrderBy::SizeAsc));
+    }
+
+    #[test]
+    fn test_parse_unrecognized_words_become_free_text() {
+        let query = parse("vacation photos").unwrap();
+        assert_eq!(query.text, Some("vacation photos".to_string()));
+    }
+
+    #[test]
+    fn test_parse_combines_structured_terms_with_free_text() {
+        let query = parse(r#"camera:Canon sunset format:jpg"#).unwrap();
+        assert_eq!(query.camera_make, Some("Canon".to_string()));
+        assert_eq!(query.format, Some(PhotoFormat::Jpeg));
+        assert_eq!(query.text, Some("sunset".to_string()));
+    }
+}
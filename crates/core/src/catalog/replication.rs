@@ -0,0 +1,583 @@
+//! Delta export/import for replicating a catalog across machines.
+//!
+//! `export_delta` and `merge_delta` let two independently-scanned catalogs
+//! (e.g. a laptop and a NAS, each scanning their own copy of a library)
+//! reconcile without a shared server. Rows are merged as conflict-free
+//! replicated types rather than a straight table copy, since a local
+//! autoincrement `photos.id`/`duplicate_groups.id` means nothing on a peer's
+//! database:
+//!
+//! - A `photos` row merges last-writer-wins, keyed by `path`: the incoming
+//!   row wins if its `mtime` is newer, with `sha256` (lexicographically
+//!   larger wins) as the tiebreaker for an exact mtime collision.
+//! - `group_members` is an observed-remove set: a group's membership is the
+//!   union of members either side has ever seen, minus whatever the
+//!   `tombstones` table says either side has since removed. A group's
+//!   cross-machine identity is `duplicate_groups.merge_key` — the sha256 of
+//!   its lowest-hash member — set the first time the group takes part in a
+//!   merge, since that value is deterministic and needs no coordination.
+//! - `tombstones` records a deletion so it propagates on the next merge
+//!   instead of the deleted row being silently resurrected by a peer that
+//!   exported its (stale) copy first.
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::Result;
+
+/// One photo row as carried in a `CatalogDelta`. Identified by `path` rather
+/// than a local `id`, since replicated rows cross databases that assign ids
+/// independently; `source_path` is resolved to (or used to create) a local
+/// `sources` row on merge.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhotoRecord {
+    pub source_path: String,
+    pub path: String,
+    pub size: u64,
+    pub format: String,
+    pub sha256: String,
+    pub phash: Option<u64>,
+    pub dhash: Option<u64>,
+    pub ahash: Option<u64>,
+    pub mtime: i64,
+    pub exif_date: Option<String>,
+    pub exif_camera_make: Option<String>,
+    pub exif_camera_model: Option<String>,
+    pub exif_gps_lat: Option<f64>,
+    pub exif_gps_lon: Option<f64>,
+    pub exif_width: Option<u32>,
+    pub exif_height: Option<u32>,
+    pub updated_at: i64,
+}
+
+/// One `group_members` observation as carried in a `CatalogDelta`, keyed by
+/// content hash rather than local ids for the same reason as `PhotoRecord`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupMembershipRecord {
+    pub group_key: String,
+    pub confidence: String,
+    pub member_sha256: String,
+    pub added_at: i64,
+}
+
+/// A deletion recorded by `tombstones`. `kind` is `"photo"` (key = path) or
+/// `"group_member"` (key = `"{group_key}:{member_sha256}"`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tombstone {
+    pub kind: String,
+    pub key: String,
+    pub deleted_at: i64,
+}
+
+/// Everything that changed since a given timestamp, ready to send to a peer.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CatalogDelta {
+    pub photos: Vec<PhotoRecord>,
+    pub group_members: Vec<GroupMembershipRecord>,
+    pub tombstones: Vec<Tombstone>,
+}
+
+/// Counts of what a `merge_delta` call actually did, for reporting to the user.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergeStats {
+    pub photos_added: usize,
+    pub photos_updated: usize,
+    pub photos_skipped_older: usize,
+    pub group_members_added: usize,
+    pub tombstones_applied: usize,
+}
+
+/// Collect every row that changed at or after `since` (a Unix timestamp, as
+/// stamped by callers into `updated_at`/`added_at`/`deleted_at`).
+pub fn export_delta(conn: &Connection, since: i64) -> Result<CatalogDelta> {
+    let mut delta = CatalogDelta::default();
+
+    let mut stmt = conn.prepare(
+        "SELECT s.path, p.path, p.size, p.format, p.sha256, p.phash, p.dhash, p.ahash, p.mtime,
+                p.exif_date, p.exif_camera_make, p.exif_camera_model, p.exif_gps_lat, p.exif_gps_lon,
+                p.exif_width, p.exif_height, p.updated_at
+         FROM photos p JOIN sources s ON s.id = p.source_id
+         WHERE p.updated_at >= ?1",
+    )?;
+    delta.photos = stmt
+        .query_map(params![since], |row| {
+            Ok(PhotoRecord {
+                source_path: row.get(0)?,
+                path: row.get(1)?,
+                size: row.get::<_, i64>(2)? as u64,
+                format: row.get(3)?,
+                sha256: row.get(4)?,
+                phash: row.get::<_, Option<i64>>(5)?.map(|v| v as u64),
+                dhash: row.get::<_, Option<i64>>(6)?.map(|v| v as u64),
+                ahash: row.get::<_, Option<i64>>(7)?.map(|v| v as u64),
+                mtime: row.get(8)?,
+                exif_date: row.get(9)?,
+                exif_camera_make: row.get(10)?,
+                exif_camera_model: row.get(11)?,
+                exif_gps_lat: row.get(12)?,
+                exif_gps_lon: row.get(13)?,
+                exif_width: row.get(14)?,
+                exif_height: row.get(15)?,
+                updated_at: row.get(16)?,
+            })
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    // A group's `merge_key` is the sha256 of its lowest-hash member, computed
+    // over *all* current members (not just those seen since `since`) so the
+    // key a peer gets back matches the key it would compute itself.
+    let mut stmt = conn.prepare(
+        "SELECT dg.confidence, p.sha256, gm.added_at,
+                (SELECT MIN(p2.sha256) FROM group_members gm2
+                 JOIN photos p2 ON p2.id = gm2.photo_id
+                 WHERE gm2.group_id = dg.id)
+         FROM group_members gm
+         JOIN duplicate_groups dg ON dg.id = gm.group_id
+         JOIN photos p ON p.id = gm.photo_id
+         WHERE gm.added_at >= ?1",
+    )?;
+    delta.group_members = stmt
+        .query_map(params![since], |row| {
+            Ok(GroupMembershipRecord {
+                confidence: row.get(0)?,
+                member_sha256: row.get(1)?,
+                added_at: row.get(2)?,
+                group_key: row.get(3)?,
+            })
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let mut stmt = conn.prepare("SELECT kind, key, deleted_at FROM tombstones WHERE deleted_at >= ?1")?;
+    delta.tombstones = stmt
+        .query_map(params![since], |row| {
+            Ok(Tombstone {
+                kind: row.get(0)?,
+                key: row.get(1)?,
+                deleted_at: row.get(2)?,
+            })
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    Ok(delta)
+}
+
+/// Find (or register) the local `sources` row for a path a delta refers to.
+/// Unlike `Catalog::add_source`, this never touches the filesystem — a
+/// replicated source lives on a peer machine and may not exist locally at all.
+fn resolve_source_id(conn: &Connection, path: &str) -> Result<i64> {
+    if let Some(id) = conn
+        .query_row("SELECT id FROM sources WHERE path = ?1", params![path], |row| row.get(0))
+        .optional()?
+    {
+        return Ok(id);
+    }
+    conn.execute("INSERT INTO sources (path, role) VALUES (?1, 'standard')", params![path])?;
+    Ok(conn.last_insert_rowid())
+}
+
+fn photo_id_by_sha256(conn: &Connection, sha256: &str) -> Result<Option<i64>> {
+    Ok(conn
+        .query_row("SELECT id FROM photos WHERE sha256 = ?1 LIMIT 1", params![sha256], |row| row.get(0))
+        .optional()?)
+}
+
+/// Find (or create) the local group for `group_key`, the sha256 of its
+/// lowest-hash member: a group already stamped with that `merge_key`, else a
+/// not-yet-stamped local group whose lowest-hash member matches (stamped
+/// now), else a new group. Creation is skipped if that member isn't
+/// cataloged locally yet — the group is created on a later merge once it arrives.
+fn resolve_group_id(conn: &Connection, group_key: &str, confidence: &str) -> Result<Option<i64>> {
+    if let Some(id) = conn
+        .query_row(
+            "SELECT id FROM duplicate_groups WHERE merge_key = ?1",
+            params![group_key],
+            |row| row.get(0),
+        )
+        .optional()?
+    {
+        return Ok(Some(id));
+    }
+    if let Some(id) = conn
+        .query_row(
+            "SELECT dg.id FROM duplicate_groups dg
+             WHERE dg.merge_key IS NULL
+               AND (SELECT MIN(p.sha256) FROM group_members gm
+                    JOIN photos p ON p.id = gm.photo_id WHERE gm.group_id = dg.id) = ?1",
+            params![group_key],
+            |row| row.get::<_, i64>(0),
+        )
+        .optional()?
+    {
+        conn.execute("UPDATE duplicate_groups SET merge_key = ?1 WHERE id = ?2", params![group_key, id])?;
+        return Ok(Some(id));
+    }
+    let Some(source_of_truth_id) = photo_id_by_sha256(conn, group_key)? else {
+        return Ok(None);
+    };
+    conn.execute(
+        "INSERT INTO duplicate_groups (source_of_truth_id, confidence, merge_key) VALUES (?1, ?2, ?3)",
+        params![source_of_truth_id, confidence, group_key],
+    )?;
+    Ok(Some(conn.last_insert_rowid()))
+}
+
+/// Apply a peer's delta, merging photos and group memberships as
+/// conflict-free types and recording each photo and membership change under
+/// `applied_at` so a later `export_delta` call can forward it to a third peer.
+/// Merged tombstones keep their own `deleted_at` rather than `applied_at`, so
+/// `export_delta` forwards one only when `since` is at or before that time.
+pub fn merge_delta(conn: &Connection, delta: &CatalogDelta, applied_at: i64) -> Result<MergeStats> {
+    let mut stats = MergeStats::default();
+
+    for photo in &delta.photos {
+        // A local tombstone at or after the incoming row's last change wins
+        // over it, so a peer's stale copy doesn't resurrect a deleted photo.
+        let tombstoned: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM tombstones WHERE kind = 'photo' AND key = ?1 AND deleted_at >= ?2)",
+            params![photo.path, photo.updated_at],
+            |row| row.get(0),
+        )?;
+        if tombstoned {
+            stats.photos_skipped_older += 1;
+            continue;
+        }
+
+        let existing: Option<(i64, i64, String)> = conn
+            .query_row(
+                "SELECT id, mtime, sha256 FROM photos WHERE path = ?1",
+                params![photo.path],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()?;
+
+        match existing {
+            None => {
+                let source_id = resolve_source_id(conn, &photo.source_path)?;
+                conn.execute(
+                    "INSERT INTO photos (source_id, path, size, format, sha256, phash, dhash, ahash, mtime,
+                     exif_date, exif_camera_make, exif_camera_model, exif_gps_lat, exif_gps_lon,
+                     exif_width, exif_height, updated_at)
+                     VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11,?12,?13,?14,?15,?16,?17)",
+                    params![
+                        source_id,
+                        photo.path,
+                        photo.size as i64,
+                        photo.format,
+                        photo.sha256,
+                        photo.phash.map(|v| v as i64),
+                        photo.dhash.map(|v| v as i64),
+                        photo.ahash.map(|v| v as i64),
+                        photo.mtime,
+                        photo.exif_date,
+                        photo.exif_camera_make,
+                        photo.exif_camera_model,
+                        photo.exif_gps_lat,
+                        photo.exif_gps_lon,
+                        photo.exif_width,
+                        photo.exif_height,
+                        applied_at,
+                    ],
+                )?;
+                stats.photos_added += 1;
+            }
+            Some((id, stored_mtime, stored_sha256)) => {
+                let incoming_wins = photo.mtime > stored_mtime
+                    || (photo.mtime == stored_mtime && photo.sha256 > stored_sha256);
+                if incoming_wins {
+                    let source_id = resolve_source_id(conn, &photo.source_path)?;
+                    conn.execute(
+                        "UPDATE photos SET source_id=?1, size=?2, format=?3, sha256=?4, phash=?5, dhash=?6,
+                         ahash=?7, mtime=?8, exif_date=?9, exif_camera_make=?10, exif_camera_model=?11,
+                         exif_gps_lat=?12, exif_gps_lon=?13, exif_width=?14, exif_height=?15, updated_at=?16
+                         WHERE id=?17",
+                        params![
+                            source_id,
+                            photo.size as i64,
+                            photo.format,
+                            photo.sha256,
+                            photo.phash.map(|v| v as i64),
+                            photo.dhash.map(|v| v as i64),
+                            photo.ahash.map(|v| v as i64),
+                            photo.mtime,
+                            photo.exif_date,
+                            photo.exif_camera_make,
+                            photo.exif_camera_model,
+                            photo.exif_gps_lat,
+                            photo.exif_gps_lon,
+                            photo.exif_width,
+                            photo.exif_height,
+                            applied_at,
+                            id,
+                        ],
+                    )?;
+                    stats.photos_updated += 1;
+                } else {
+                    stats.photos_skipped_older += 1;
+                }
+            }
+        }
+    }
+
+    for membership in &delta.group_members {
+        let tombstone_key = format!("{}:{}", membership.group_key, membership.member_sha256);
+        let tombstoned: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM tombstones WHERE kind = 'group_member' AND key = ?1 AND deleted_at >= ?2)",
+            params![tombstone_key, membership.added_at],
+            |row| row.get(0),
+        )?;
+        if tombstoned {
+            continue;
+        }
+        let Some(group_id) = resolve_group_id(conn, &membership.group_key, &membership.confidence)? else {
+            continue;
+        };
+        let Some(photo_id) = photo_id_by_sha256(conn, &membership.member_sha256)? else {
+            continue;
+        };
+        let added = conn.execute(
+            "INSERT OR IGNORE INTO group_members (group_id, photo_id, added_at) VALUES (?1, ?2, ?3)",
+            params![group_id, photo_id, applied_at],
+        )?;
+        stats.group_members_added += added;
+    }
+
+    for tombstone in &delta.tombstones {
+        conn.execute(
+            "INSERT INTO tombstones (kind, key, deleted_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(kind, key) DO UPDATE SET deleted_at = MAX(deleted_at, excluded.deleted_at)",
+            params![tombstone.kind, tombstone.key, tombstone.deleted_at],
+        )?;
+
+        match tombstone.kind.as_str() {
+            "photo" => {
+                conn.execute("DELETE FROM group_members WHERE photo_id IN (SELECT id FROM photos WHERE path = ?1)", params![tombstone.key])?;
+                conn.execute("DELETE FROM photos WHERE path = ?1", params![tombstone.key])?;
+            }
+            "group_member" => {
+                if let Some((group_key, member_sha256)) = tombstone.key.split_once(':') {
+                    if let Some(group_id) = conn
+                        .query_row(
+                            "SELECT id FROM duplicate_groups WHERE merge_key = ?1",
+                            params![group_key],
+                            |row| row.get::<_, i64>(0),
+                        )
+                        .optional()?
+                    {
+                        if let Some(photo_id) = photo_id_by_sha256(conn, member_sha256)? {
+                            conn.execute(
+                                "DELETE FROM group_members WHERE group_id = ?1 AND photo_id = ?2",
+                                params![group_id, photo_id],
+                            )?;
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        stats.tombstones_applied += 1;
+    }
+
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conn_with_schema() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::catalog::schema::initialize(&conn).unwrap();
+        conn
+    }
+
+    fn insert_source(conn: &Connection, path: &str) -> i64 {
+        conn.execute("INSERT INTO sources (path, role) VALUES (?1, 'standard')", params![path]).unwrap();
+        conn.last_insert_rowid()
+    }
+
+    fn insert_photo(conn: &Connection, source_id: i64, path: &str, sha256: &str, mtime: i64, updated_at: i64) {
+        conn.execute(
+            "INSERT INTO photos (source_id, path, size, format, sha256, mtime, updated_at)
+             VALUES (?1, ?2, 10, 'JPEG', ?3, ?4, ?5)",
+            params![source_id, path, sha256, mtime, updated_at],
+        )
+        .unwrap();
+    }
+
+    fn photo_record(source_path: &str, path: &str, sha256: &str, mtime: i64, updated_at: i64) -> PhotoRecord {
+        PhotoRecord {
+            source_path: source_path.to_string(),
+            path: path.to_string(),
+            size: 99,
+            format: "JPEG".to_string(),
+            sha256: sha256.to_string(),
+            phash: None,
+            dhash: None,
+            ahash: None,
+            mtime,
+            exif_date: None,
+            exif_camera_make: None,
+            exif_camera_model: None,
+            exif_gps_lat: None,
+            exif_gps_lon: None,
+            exif_width: None,
+            exif_height: None,
+            updated_at,
+        }
+    }
+
+    #[test]
+    fn test_export_delta_only_returns_rows_changed_since() {
+        let conn = conn_with_schema();
+        let source = insert_source(&conn, "/laptop/photos");
+        insert_photo(&conn, source, "/laptop/photos/a.jpg", "aaa", 100, 5);
+        insert_photo(&conn, source, "/laptop/photos/b.jpg", "bbb", 100, 20);
+
+        let delta = export_delta(&conn, 10).unwrap();
+        assert_eq!(delta.photos.len(), 1);
+        assert_eq!(delta.photos[0].sha256, "bbb");
+    }
+
+    #[test]
+    fn test_merge_delta_inserts_new_photo_and_creates_source() {
+        let conn = conn_with_schema();
+        let delta = CatalogDelta {
+            photos: vec![photo_record("/nas/photos", "/nas/photos/a.jpg", "aaa", 100, 5)],
+            group_members: vec![],
+            tombstones: vec![],
+        };
+
+        let stats = merge_delta(&conn, &delta, 1).unwrap();
+        assert_eq!(stats.photos_added, 1);
+
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM photos", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 1);
+        let sources: i64 = conn.query_row("SELECT COUNT(*) FROM sources", [], |row| row.get(0)).unwrap();
+        assert_eq!(sources, 1);
+    }
+
+    #[test]
+    fn test_merge_delta_last_writer_wins_on_newer_mtime() {
+        let conn = conn_with_schema();
+        let source = insert_source(&conn, "/laptop/photos");
+        insert_photo(&conn, source, "/laptop/photos/a.jpg", "old_hash", 100, 1);
+
+        let delta = CatalogDelta {
+            photos: vec![photo_record("/laptop/photos", "/laptop/photos/a.jpg", "new_hash", 200, 10)],
+            group_members: vec![],
+            tombstones: vec![],
+        };
+        let stats = merge_delta(&conn, &delta, 1).unwrap();
+        assert_eq!(stats.photos_updated, 1);
+
+        let sha: String = conn
+            .query_row("SELECT sha256 FROM photos WHERE path = '/laptop/photos/a.jpg'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(sha, "new_hash");
+    }
+
+    #[test]
+    fn test_merge_delta_skips_older_mtime() {
+        let conn = conn_with_schema();
+        let source = insert_source(&conn, "/laptop/photos");
+        insert_photo(&conn, source, "/laptop/photos/a.jpg", "current", 200, 1);
+
+        let delta = CatalogDelta {
+            photos: vec![photo_record("/laptop/photos", "/laptop/photos/a.jpg", "stale", 100, 10)],
+            group_members: vec![],
+            tombstones: vec![],
+        };
+        let stats = merge_delta(&conn, &delta, 1).unwrap();
+        assert_eq!(stats.photos_skipped_older, 1);
+
+        let sha: String = conn
+            .query_row("SELECT sha256 FROM photos WHERE path = '/laptop/photos/a.jpg'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(sha, "current");
+    }
+
+    #[test]
+    fn test_merge_delta_applies_photo_tombstone() {
+        let conn = conn_with_schema();
+        let source = insert_source(&conn, "/laptop/photos");
+        insert_photo(&conn, source, "/laptop/photos/a.jpg", "aaa", 100, 1);
+
+        let delta = CatalogDelta {
+            photos: vec![],
+            group_members: vec![],
+            tombstones: vec![Tombstone {
+                kind: "photo".to_string(),
+                key: "/laptop/photos/a.jpg".to_string(),
+                deleted_at: 50,
+            }],
+        };
+        let stats = merge_delta(&conn, &delta, 1).unwrap();
+        assert_eq!(stats.tombstones_applied, 1);
+
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM photos", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_merge_delta_tombstone_wins_even_if_photo_arrives_in_the_same_delta() {
+        // A peer that deleted a file and one that's still re-exporting a
+        // stale copy of it can legitimately land in the same merge batch —
+        // the tombstone must not be resurrected by the add it's paired with.
+        let conn = conn_with_schema();
+        let delta = CatalogDelta {
+            photos: vec![photo_record("/laptop/photos", "/laptop/photos/a.jpg", "aaa", 100, 5)],
+            group_members: vec![],
+            tombstones: vec![Tombstone {
+                kind: "photo".to_string(),
+                key: "/laptop/photos/a.jpg".to_string(),
+                deleted_at: 50,
+            }],
+        };
+        merge_delta(&conn, &delta, 1).unwrap();
+
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM photos", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_merge_delta_group_member_creates_group_once_photo_present() {
+        let conn = conn_with_schema();
+        let source = insert_source(&conn, "/laptop/photos");
+        insert_photo(&conn, source, "/laptop/photos/a.jpg", "aaa", 100, 1);
+
+        let delta = CatalogDelta {
+            photos: vec![],
+            group_members: vec![GroupMembershipRecord {
+                group_key: "aaa".to_string(),
+                confidence: "Certain".to_string(),
+                member_sha256: "aaa".to_string(),
+                added_at: 5,
+            }],
+            tombstones: vec![],
+        };
+        let stats = merge_delta(&conn, &delta, 1).unwrap();
+        assert_eq!(stats.group_members_added, 1);
+
+        let groups: i64 = conn.query_row("SELECT COUNT(*) FROM duplicate_groups", [], |row| row.get(0)).unwrap();
+        assert_eq!(groups, 1);
+    }
+
+    #[test]
+    fn test_merge_delta_group_member_skipped_when_member_photo_unknown() {
+        let conn = conn_with_schema();
+        let delta = CatalogDelta {
+            photos: vec![],
+            group_members: vec![GroupMembershipRecord {
+                group_key: "aaa".to_string(),
+                confidence: "Certain".to_string(),
+                member_sha256: "aaa".to_string(),
+                added_at: 5,
+            }],
+            tombstones: vec![],
+        };
+        let stats = merge_delta(&conn, &delta, 1).unwrap();
+        assert_eq!(stats.group_members_added, 0);
+
+        let groups: i64 = conn.query_row("SELECT COUNT(*) FROM duplicate_groups", [], |row| row.get(0)).unwrap();
+        assert_eq!(groups, 0);
+    }
+}
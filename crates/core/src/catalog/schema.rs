@@ -7,27 +7,201 @@
 //! version against [`SCHEMA_VERSION`]:
 //!
 //! - **DB version == code version** → no-op.
-//! - **DB version < code version** → run pending migrations in a transaction.
+//! - **DB version < code version** → run every pending step in ascending
+//!   order, each in its own transaction.
 //! - **DB version > code version** → fail with [`Error::SchemaTooNew`] so the
 //!   user knows to upgrade photopack.
 //! - **No version key** (pre-versioning DB) → auto-set to 1.
 //!
+//! Before the first mutating step, [`migrate`] runs a `PRAGMA
+//! integrity_check` and (for a file-backed database) copies the file aside
+//! to `<path>.v{db_version}.bak`, so a failed `ALTER TABLE` partway through
+//! an upgrade still leaves the pre-migration catalog recoverable rather than
+//! only a half-migrated one.
+//!
 //! ## Adding a migration
 //!
 //! 1. Increment [`SCHEMA_VERSION`].
 //! 2. Write a `fn(conn: &Connection) -> Result<()>` that performs the DDL/DML.
-//! 3. Append it to [`MIGRATIONS`]. The array index maps to the transition:
-//!    `MIGRATIONS[0]` = v1→v2, `MIGRATIONS[1]` = v2→v3, etc.
+//! 3. Append `(target_version, the_fn)` to [`MIGRATIONS`], where
+//!    `target_version` is the schema version the database is at *after* that
+//!    step runs. [`migrate`] persists `target_version` the moment that step's
+//!    own transaction commits, so a failure partway through a multi-step
+//!    migration reports exactly which version it failed heading towards (see
+//!    [`Error::MigrationFailed`]) while every earlier step's progress stays
+//!    committed rather than being rolled back with it.
 
 use rusqlite::{params, Connection};
 
 use crate::error::{Error, Result};
 
 /// Current schema version. Bump when adding a migration.
-pub const SCHEMA_VERSION: i64 = 1;
+pub const SCHEMA_VERSION: i64 = 10;
+
+/// Ordered list of `(target_version, migration)` steps, applied in order
+/// starting from whatever version the database is currently at.
+pub const MIGRATIONS: &[(i64, fn(&Connection) -> Result<()>)] = &[
+    (2, add_exported_objects_table),
+    (3, add_ahash_column),
+    (4, add_broken_files_table),
+    (5, add_hash_cache_table),
+    (6, add_vault_operations_table),
+    (7, add_replication_support),
+    (8, add_fts_search),
+    (9, add_events_table),
+    (10, add_exif_date_source_index),
+];
+
+/// v1 -> v2: track which content hashes have already been exported, and
+/// where, so `Vault::export` can recognize a moved/renamed source-of-truth
+/// as already done instead of re-converting it under its new name.
+fn add_exported_objects_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS exported_objects (
+            sha256 TEXT PRIMARY KEY,
+            target TEXT NOT NULL
+        );",
+    )?;
+    Ok(())
+}
+
+/// v2 -> v3: a third, fixed average-hash (aHash) fingerprint alongside the
+/// existing configurable `phash` and fixed `dhash` columns, so grouping can
+/// fall back on a third vote when the other two disagree.
+fn add_ahash_column(conn: &Connection) -> Result<()> {
+    conn.execute_batch("ALTER TABLE photos ADD COLUMN ahash INTEGER;")?;
+    Ok(())
+}
+
+/// v3 -> v4: quarantine list for files `scan` couldn't decode — either a
+/// hard decode error or a panic inside a third-party codec — so one corrupt
+/// file no longer risks aborting the whole run. See `Catalog::record_broken_file`.
+fn add_broken_files_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS broken_files (
+            path        TEXT PRIMARY KEY,
+            reason      TEXT NOT NULL,
+            detected_at INTEGER NOT NULL
+        );",
+    )?;
+    Ok(())
+}
+
+/// v4 -> v5: a standalone hash cache keyed by absolute path, independent of
+/// the `photos` table rows a scan's move/delete detection can rewrite
+/// mid-run. Unlike `get_mtimes_and_sizes_for_source` (which only recognizes
+/// a path already cataloged under the *same* source), this survives a file
+/// being re-added under a different source or after its catalog row was
+/// dropped, so a repeat scan of mostly-unchanged content never re-hashes it.
+fn add_hash_cache_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS hash_cache (
+            path   TEXT PRIMARY KEY,
+            size   INTEGER NOT NULL,
+            mtime  INTEGER NOT NULL,
+            sha256 TEXT NOT NULL
+        );",
+    )?;
+    Ok(())
+}
 
-/// Ordered list of migrations. `MIGRATIONS[i]` migrates from version `i+1` to `i+2`.
-pub const MIGRATIONS: &[fn(&Connection) -> Result<()>] = &[];
+/// v5 -> v6: a journal of in-flight vault-save operations, so a save
+/// interrupted mid-run (crash, kill -9, power loss) leaves a durable record
+/// of exactly which copies/removals/links were planned and which had
+/// already completed, instead of an unknown on-disk state with no way to
+/// tell what `VaultSaveProgress::Complete`'s counts would have been. See
+/// `catalog::journal`.
+fn add_vault_operations_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS vault_operations (
+            id       INTEGER PRIMARY KEY AUTOINCREMENT,
+            run_id   TEXT NOT NULL,
+            seq      INTEGER NOT NULL,
+            op_type  TEXT NOT NULL,
+            path_a   TEXT NOT NULL,
+            path_b   TEXT,
+            sha256   TEXT,
+            size     INTEGER,
+            done     INTEGER NOT NULL DEFAULT 0
+        );
+        CREATE INDEX IF NOT EXISTS idx_vault_operations_run ON vault_operations(run_id);",
+    )?;
+    Ok(())
+}
+
+/// v6 -> v7: columns and a tombstone table for `Catalog::export_delta` /
+/// `merge_delta` to replicate a catalog across machines. `photos.updated_at`
+/// and `group_members.added_at` let a delta export select only what changed
+/// since a given timestamp; `duplicate_groups.merge_key` gives a group a
+/// cross-machine-stable identity (the sha256 of its lowest-hash member, set
+/// the first time the group takes part in a merge) since its local
+/// autoincrement `id` means nothing on a peer's database; `tombstones`
+/// records deletions so they propagate on the next merge instead of being
+/// resurrected by a peer that never saw them.
+fn add_replication_support(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE photos ADD COLUMN updated_at INTEGER NOT NULL DEFAULT 0;
+         ALTER TABLE group_members ADD COLUMN added_at INTEGER NOT NULL DEFAULT 0;
+         ALTER TABLE duplicate_groups ADD COLUMN merge_key TEXT;
+         CREATE UNIQUE INDEX IF NOT EXISTS idx_duplicate_groups_merge_key ON duplicate_groups(merge_key);
+
+         CREATE TABLE IF NOT EXISTS tombstones (
+             kind       TEXT NOT NULL,
+             key        TEXT NOT NULL,
+             deleted_at INTEGER NOT NULL,
+             PRIMARY KEY (kind, key)
+         );",
+    )?;
+    Ok(())
+}
+
+/// v7 -> v8: an FTS5 virtual table over camera make/model so
+/// `Catalog::search` can push a free-text filter down to SQL instead of
+/// loading every photo into Rust to grep through it. `content='photos'`
+/// keeps it an index only — the real data stays in `photos`, so there's
+/// nothing to reconcile on conflict — but that also means it isn't kept in
+/// sync automatically; `Catalog::upsert_photo`/`upsert_photos_batch`
+/// maintain it going forward, and this migration backfills whatever's
+/// already cataloged.
+fn add_fts_search(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS photos_fts USING fts5(
+             camera_make, camera_model, content='photos', content_rowid='id'
+         );
+         INSERT INTO photos_fts(rowid, camera_make, camera_model)
+         SELECT id, exif_camera_make, exif_camera_model FROM photos;",
+    )?;
+    Ok(())
+}
+
+/// v8 -> v9: a change feed so an incremental UI or background indexer can
+/// observe catalog mutations without re-reading `list_all_photos` — see
+/// `catalog::events`. `id` is the replay cursor consumers persist and pass
+/// back to `Catalog::watch_since`.
+fn add_events_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS events (
+            id         INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind       TEXT NOT NULL,
+            entity_id  INTEGER,
+            sot_id     INTEGER,
+            members    TEXT,
+            created_at INTEGER NOT NULL DEFAULT 0
+         );",
+    )?;
+    Ok(())
+}
+
+/// v9 -> v10: a composite index on `(exif_date, source_id)` for
+/// `Catalog::search`'s date-range-plus-source access pattern — the existing
+/// `idx_photos_source_mtime` covers a scan's own mtime lookups, but a date
+/// range filter on its own fell back to a full table scan.
+fn add_exif_date_source_index(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_photos_exif_date_source ON photos(exif_date, source_id);",
+    )?;
+    Ok(())
+}
 
 pub fn initialize(conn: &Connection) -> Result<()> {
     conn.execute_batch(
@@ -35,7 +209,8 @@ pub fn initialize(conn: &Connection) -> Result<()> {
         CREATE TABLE IF NOT EXISTS sources (
             id          INTEGER PRIMARY KEY AUTOINCREMENT,
             path        TEXT NOT NULL UNIQUE,
-            last_scanned INTEGER
+            last_scanned INTEGER,
+            role        TEXT NOT NULL DEFAULT 'standard'
         );
 
         CREATE TABLE IF NOT EXISTS photos (
@@ -47,6 +222,7 @@ pub fn initialize(conn: &Connection) -> Result<()> {
             sha256      TEXT NOT NULL,
             phash       INTEGER,
             dhash       INTEGER,
+            ahash       INTEGER,
             mtime       INTEGER NOT NULL,
             exif_date       TEXT,
             exif_camera_make  TEXT,
@@ -54,32 +230,90 @@ pub fn initialize(conn: &Connection) -> Result<()> {
             exif_gps_lat     REAL,
             exif_gps_lon     REAL,
             exif_width       INTEGER,
-            exif_height      INTEGER
+            exif_height      INTEGER,
+            updated_at       INTEGER NOT NULL DEFAULT 0
         );
 
         CREATE INDEX IF NOT EXISTS idx_photos_sha256 ON photos(sha256);
         CREATE INDEX IF NOT EXISTS idx_photos_source ON photos(source_id);
         CREATE INDEX IF NOT EXISTS idx_photos_path ON photos(path);
         CREATE INDEX IF NOT EXISTS idx_photos_source_mtime ON photos(source_id, mtime);
+        CREATE INDEX IF NOT EXISTS idx_photos_exif_date_source ON photos(exif_date, source_id);
 
         CREATE TABLE IF NOT EXISTS duplicate_groups (
             id              INTEGER PRIMARY KEY AUTOINCREMENT,
             source_of_truth_id INTEGER NOT NULL REFERENCES photos(id),
-            confidence      TEXT NOT NULL
+            confidence      TEXT NOT NULL,
+            merge_key       TEXT
         );
 
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_duplicate_groups_merge_key ON duplicate_groups(merge_key);
+
         CREATE TABLE IF NOT EXISTS group_members (
             group_id    INTEGER NOT NULL REFERENCES duplicate_groups(id),
             photo_id    INTEGER NOT NULL REFERENCES photos(id),
+            added_at    INTEGER NOT NULL DEFAULT 0,
             PRIMARY KEY (group_id, photo_id)
         );
 
         CREATE INDEX IF NOT EXISTS idx_group_members_photo ON group_members(photo_id);
 
+        CREATE TABLE IF NOT EXISTS tombstones (
+            kind       TEXT NOT NULL,
+            key        TEXT NOT NULL,
+            deleted_at INTEGER NOT NULL,
+            PRIMARY KEY (kind, key)
+        );
+
+        CREATE VIRTUAL TABLE IF NOT EXISTS photos_fts USING fts5(
+            camera_make, camera_model, content='photos', content_rowid='id'
+        );
+
         CREATE TABLE IF NOT EXISTS config (
             key   TEXT PRIMARY KEY,
             value TEXT NOT NULL
         );
+
+        CREATE TABLE IF NOT EXISTS exported_objects (
+            sha256 TEXT PRIMARY KEY,
+            target TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS broken_files (
+            path        TEXT PRIMARY KEY,
+            reason      TEXT NOT NULL,
+            detected_at INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS hash_cache (
+            path   TEXT PRIMARY KEY,
+            size   INTEGER NOT NULL,
+            mtime  INTEGER NOT NULL,
+            sha256 TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS vault_operations (
+            id       INTEGER PRIMARY KEY AUTOINCREMENT,
+            run_id   TEXT NOT NULL,
+            seq      INTEGER NOT NULL,
+            op_type  TEXT NOT NULL,
+            path_a   TEXT NOT NULL,
+            path_b   TEXT,
+            sha256   TEXT,
+            size     INTEGER,
+            done     INTEGER NOT NULL DEFAULT 0
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_vault_operations_run ON vault_operations(run_id);
+
+        CREATE TABLE IF NOT EXISTS events (
+            id         INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind       TEXT NOT NULL,
+            entity_id  INTEGER,
+            sot_id     INTEGER,
+            members    TEXT,
+            created_at INTEGER NOT NULL DEFAULT 0
+        );
         ",
     )?;
     Ok(())
@@ -131,15 +365,40 @@ pub fn migrate(conn: &Connection) -> Result<()> {
         });
     }
 
-    // Run pending migrations inside a transaction.
+    // `MIGRATIONS[i]` targets version `i+2` (a db at `db_version` needs
+    // `MIGRATIONS` starting at index `db_version - 1`, since `db_version` is
+    // always >= 1 here). Each step runs in its own transaction and bumps the
+    // stored version the moment it commits, so re-running `migrate` after a
+    // failure resumes from the last version that actually landed instead of
+    // redoing (or losing) already-applied steps.
     if db_version < SCHEMA_VERSION {
-        let tx = conn.unchecked_transaction()?;
-        for migration in MIGRATIONS.iter().skip(db_version as usize) {
-            migration(&tx)?;
+        backup_before_migrating(conn, db_version)?;
+        for (target_version, migration) in MIGRATIONS.iter().skip((db_version - 1) as usize) {
+            let tx = conn.unchecked_transaction()?;
+            migration(&tx).map_err(|e| Error::MigrationFailed {
+                version: *target_version,
+                message: e.to_string(),
+            })?;
+            set_schema_version(&tx, *target_version)?;
+            tx.commit()?;
         }
-        set_schema_version(&tx, SCHEMA_VERSION)?;
-        tx.commit()?;
     }
 
     Ok(())
 }
+
+/// Run `PRAGMA integrity_check` and, for a file-backed database, copy it
+/// aside to `<path>.v{db_version}.bak` before the first mutating migration
+/// step runs — see the module docs. A no-op for in-memory connections
+/// (`Connection::path` returns `None` for those), since there's no file to
+/// back up.
+fn backup_before_migrating(conn: &Connection, db_version: i64) -> Result<()> {
+    let integrity: String = conn.query_row("PRAGMA integrity_check", [], |row| row.get(0))?;
+    if integrity != "ok" {
+        return Err(Error::CatalogIntegrityCheckFailed(integrity));
+    }
+    if let Some(path) = conn.path().filter(|p| !p.is_empty()) {
+        std::fs::copy(path, format!("{path}.v{db_version}.bak"))?;
+    }
+    Ok(())
+}
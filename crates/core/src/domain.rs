@@ -0,0 +1,111 @@
+use std::path::PathBuf;
+
+/// A single indexed photo: one row in the catalog's `photos` table, joined
+/// with the EXIF columns into `exif`. `id` and `source_id` are catalog
+/// primary/foreign keys; everything else is read straight off disk or out
+/// of the file's own metadata during a scan.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhotoFile {
+    pub id: i64,
+    pub source_id: i64,
+    pub path: PathBuf,
+    pub size: u64,
+    pub format: PhotoFormat,
+    pub sha256: String,
+    pub phash: Option<u64>,
+    pub dhash: Option<u64>,
+    pub ahash: Option<u64>,
+    pub exif: Option<ExifData>,
+    pub mtime: i64,
+}
+
+/// The container formats this crate recognizes, by filename extension (see
+/// `catalog::parse_format` for the on-disk string mapping) and, for the
+/// common cases, by magic-number sniffing (`format_sniff::sniff_format`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhotoFormat {
+    Jpeg,
+    Png,
+    Tiff,
+    Webp,
+    Heic,
+    Cr2,
+    Cr3,
+    Nef,
+    Arw,
+    Orf,
+    Raf,
+    Rw2,
+    Dng,
+}
+
+impl PhotoFormat {
+    /// Canonical string form stored in the catalog — see `catalog::parse_format`
+    /// for the reverse mapping.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PhotoFormat::Jpeg => "JPEG",
+            PhotoFormat::Png => "PNG",
+            PhotoFormat::Tiff => "TIFF",
+            PhotoFormat::Webp => "WebP",
+            PhotoFormat::Heic => "HEIC",
+            PhotoFormat::Cr2 => "CR2",
+            PhotoFormat::Cr3 => "CR3",
+            PhotoFormat::Nef => "NEF",
+            PhotoFormat::Arw => "ARW",
+            PhotoFormat::Orf => "ORF",
+            PhotoFormat::Raf => "RAF",
+            PhotoFormat::Rw2 => "RW2",
+            PhotoFormat::Dng => "DNG",
+        }
+    }
+}
+
+/// EXIF metadata extracted by `exif::extract_exif`. Every field is best-effort:
+/// a camera or export pipeline that stripped EXIF, or a RAW/HEIC file this
+/// build can't decode, leaves some or all of these `None` rather than failing
+/// the whole scan.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExifData {
+    /// `DateTimeOriginal`, formatted `"YYYY:MM:DD HH:MM:SS"`. When the camera
+    /// also recorded `SubSecTimeOriginal`/`SubSecTimeDigitized`,
+    /// `exif::extract_exif` appends it as a decimal fraction (e.g.
+    /// `"2024:12:24 10:00:00.123"`) so `matching::is_sequential_shot` can tell
+    /// a same-second burst from a true duplicate — see
+    /// `matching::exif_precise_seconds`.
+    pub date: Option<String>,
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub gps_lat: Option<f64>,
+    pub gps_lon: Option<f64>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+/// How strongly two (or more) photos are believed to be the same shot.
+/// Ordered loosest-to-strictest so `a < b` picks the more conservative of
+/// two independently-derived confidences (see
+/// `matching::confidence::combine_confidence`) — `Low` is the weakest signal,
+/// `Certain` (an exact byte-for-byte match) the strongest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Confidence {
+    Low,
+    Probable,
+    High,
+    NearCertain,
+    Certain,
+}
+
+impl Confidence {
+    /// Canonical string form stored in the catalog — see `catalog::parse_confidence`
+    /// for the reverse mapping.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Confidence::Certain => "Certain",
+            Confidence::NearCertain => "Near-Certain",
+            Confidence::High => "High",
+            Confidence::Probable => "Probable",
+            Confidence::Low => "Low",
+        }
+    }
+}
@@ -35,6 +35,9 @@ pub enum Error {
     #[error("unsupported file format: {}", .0.display())]
     UnsupportedFormat(PathBuf),
 
+    #[error("unsupported source archive format: {} (expected .zip, .tar, or .tar.gz)", .0.display())]
+    UnsupportedArchiveFormat(PathBuf),
+
     #[error("vault path not configured — run `photopack pack <path>` first")]
     VaultPathNotSet,
 
@@ -52,6 +55,68 @@ pub enum Error {
 
     #[error("sips command not available — this feature requires macOS")]
     SipsNotAvailable,
+
+    #[error("HEIC export requires building with the `heif` feature")]
+    HeifFeatureNotBuilt,
+
+    #[error("this export format requires building with the `sips` feature (macOS only)")]
+    SipsFeatureNotBuilt,
+
+    #[error("archive member corrupt: {} (expected {expected}, found {actual})", .path.display())]
+    ArchiveCorrupt {
+        path: PathBuf,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("archive entry path is unsafe: {0} (escapes the destination directory)")]
+    ArchiveUnsafePath(String),
+
+    #[error("archive exceeds configured limits: {0}")]
+    ArchiveLimitExceeded(String),
+
+    #[error("refusing to save {}: escapes its source root via a symlink", .0.display())]
+    VaultSaveSymlinkEscape(PathBuf),
+
+    #[error("vault save exceeds configured limits: {0}")]
+    VaultSaveLimitExceeded(String),
+
+    #[error("refusing to link {} to its source of truth: file sizes differ", .0.display())]
+    VaultSaveContentMismatch(PathBuf),
+
+    #[error("cannot hard-link {} across filesystems — use SymLink or MoveTo instead", .path.display())]
+    CrossFilesystemLink { path: PathBuf },
+
+    #[error("cannot symlink {} — symlinks are only supported on unix, use HardLink or MoveTo instead", .path.display())]
+    SymlinkUnsupported { path: PathBuf },
+
+    #[error(
+        "hash size {size} not supported — the catalog stores phash/dhash as 64-bit integers, so only size 8 (8x8 = 64 bits) is available"
+    )]
+    HashSizeUnsupported { size: u32 },
+
+    #[error(
+        "manifest schema version {on_disk} is newer than this build supports (up to {supported}) — upgrade photopack to open this pack"
+    )]
+    ManifestVersionTooNew { on_disk: u32, supported: u32 },
+
+    #[error("catalog schema version {db} is newer than this build supports (up to {code}) — upgrade photopack to open this catalog")]
+    SchemaTooNew { db: i64, code: i64 },
+
+    #[error("migration to schema version {version} failed: {message}")]
+    MigrationFailed { version: i64, message: String },
+
+    #[error("catalog failed its integrity check before migrating, refusing to proceed: {0}")]
+    CatalogIntegrityCheckFailed(String),
+
+    #[error("pack snapshot archive does not exist: {}", .0.display())]
+    SnapshotArchiveNotFound(PathBuf),
+
+    #[error("invalid search query: {0}")]
+    InvalidQuery(String),
+
+    #[error("manifest connection pool error: {0}")]
+    Pool(#[from] r2d2::Error),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,114 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use exif::{In, Reader, Tag, Value};
+
+use crate::domain::ExifData;
+
+/// Read the EXIF tags this crate cares about out of `path`: capture date
+/// (with sub-second resolution folded in — see `ExifData::date`), camera
+/// make/model, GPS fix, and pixel dimensions. Returns `None` if the file
+/// can't be opened, has no EXIF segment at all, or its EXIF carries none of
+/// the capture date, make, model, GPS latitude or pixel width; otherwise
+/// individual fields are `None` when only some tags are present.
+pub fn extract_exif(path: &Path) -> Option<ExifData> {
+    let file = File::open(path).ok()?;
+    let mut reader = BufReader::new(&file);
+    let exif = Reader::new().read_from_container(&mut reader).ok()?;
+
+    let date = date_with_subsec(&exif);
+    let camera_make = field_as_string(&exif, Tag::Make);
+    let camera_model = field_as_string(&exif, Tag::Model);
+    let (gps_lat, gps_lon) = gps_coordinates(&exif);
+    let width = field_as_u32(&exif, Tag::PixelXDimension);
+    let height = field_as_u32(&exif, Tag::PixelYDimension);
+
+    if date.is_none()
+        && camera_make.is_none()
+        && camera_model.is_none()
+        && gps_lat.is_none()
+        && width.is_none()
+    {
+        return None;
+    }
+
+    Some(ExifData {
+        date,
+        camera_make,
+        camera_model,
+        gps_lat,
+        gps_lon,
+        width,
+        height,
+    })
+}
+
+/// `DateTimeOriginal` (`"YYYY:MM:DD HH:MM:SS"`) with `SubSecTimeOriginal`
+/// (falling back to `SubSecTimeDigitized`) appended as a decimal fraction —
+/// e.g. `"2024:12:24 10:00:00.123"` — when the camera recorded one. Folded
+/// into the same string rather than a new `ExifData` field so it round-trips
+/// through the catalog's existing `exif_date` column unchanged; see
+/// `matching::is_sequential_shot` for why the sub-second part matters.
+fn date_with_subsec(exif: &exif::Exif) -> Option<String> {
+    let base = field_as_string(exif, Tag::DateTimeOriginal)?;
+    let subsec = field_as_string(exif, Tag::SubSecTimeOriginal)
+        .or_else(|| field_as_string(exif, Tag::SubSecTimeDigitized));
+    match subsec {
+        Some(s) if !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()) => {
+            Some(format!("{base}.{s}"))
+        }
+        _ => Some(base),
+    }
+}
+
+fn field_as_string(exif: &exif::Exif, tag: Tag) -> Option<String> {
+    let field = exif.get_field(tag, In::PRIMARY)?;
+    let value = field.display_value().with_unit(exif).to_string();
+    let value = value.trim();
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+fn field_as_u32(exif: &exif::Exif, tag: Tag) -> Option<u32> {
+    let field = exif.get_field(tag, In::PRIMARY)?;
+    match &field.value {
+        Value::Long(v) => v.first().copied(),
+        Value::Short(v) => v.first().map(|&n| u32::from(n)),
+        _ => field.display_value().to_string().trim().parse().ok(),
+    }
+}
+
+fn gps_coordinates(exif: &exif::Exif) -> (Option<f64>, Option<f64>) {
+    let lat = gps_coordinate(exif, Tag::GPSLatitude, Tag::GPSLatitudeRef, "S");
+    let lon = gps_coordinate(exif, Tag::GPSLongitude, Tag::GPSLongitudeRef, "W");
+    (lat, lon)
+}
+
+/// Degrees/minutes/seconds rational triplet → signed decimal degrees, using
+/// `ref_tag` (`"N"/"S"` or `"E"/"W"`) to decide the sign — `negative_ref` is
+/// the letter that flips it negative (south latitudes, west longitudes).
+fn gps_coordinate(exif: &exif::Exif, tag: Tag, ref_tag: Tag, negative_ref: &str) -> Option<f64> {
+    let field = exif.get_field(tag, In::PRIMARY)?;
+    let Value::Rational(rationals) = &field.value else {
+        return None;
+    };
+    if rationals.len() < 3 {
+        return None;
+    }
+
+    let degrees = rationals[0].to_f64();
+    let minutes = rationals[1].to_f64();
+    let seconds = rationals[2].to_f64();
+    let mut value = degrees + minutes / 60.0 + seconds / 3600.0;
+
+    if let Some(r) = exif.get_field(ref_tag, In::PRIMARY) {
+        if r.display_value().to_string().trim() == negative_ref {
+            value = -value;
+        }
+    }
+    Some(value)
+}
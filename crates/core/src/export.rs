@@ -0,0 +1,371 @@
+use std::path::{Path, PathBuf};
+#[cfg(feature = "sips")]
+use std::process::Command;
+
+use crate::error::{Error, Result};
+
+/// Codec to convert photos into when exporting, each with its own sensible
+/// default quality and file extension. Mirrors the `sips -s format <name>`
+/// values; quality is only meaningful for the lossy formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Heic,
+    Avif,
+    WebP,
+    JpegXl,
+}
+
+impl ExportFormat {
+    /// File extension used for converted output, without the leading dot.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Heic => "heic",
+            ExportFormat::Avif => "avif",
+            ExportFormat::WebP => "webp",
+            ExportFormat::JpegXl => "jxl",
+        }
+    }
+
+    /// The `-s format <name>` value passed to `sips`.
+    fn sips_format_name(&self) -> &'static str {
+        match self {
+            ExportFormat::Heic => "heic",
+            ExportFormat::Avif => "avif",
+            ExportFormat::WebP => "webp",
+            ExportFormat::JpegXl => "jxl",
+        }
+    }
+
+    /// Default encoder quality (0-100) when the user doesn't specify one.
+    /// AVIF and JPEG-XL hold visually lossless results at lower numbers than
+    /// HEIC/WebP, so each format gets its own default rather than one global 85.
+    pub fn default_quality(&self) -> u8 {
+        match self {
+            ExportFormat::Heic => 85,
+            ExportFormat::Avif => 70,
+            ExportFormat::WebP => 80,
+            ExportFormat::JpegXl => 75,
+        }
+    }
+
+    /// Parse a `--format` CLI value. Case-insensitive.
+    pub fn parse(name: &str) -> Option<ExportFormat> {
+        match name.to_ascii_lowercase().as_str() {
+            "heic" => Some(ExportFormat::Heic),
+            "avif" => Some(ExportFormat::Avif),
+            "webp" => Some(ExportFormat::WebP),
+            "jpegxl" | "jxl" => Some(ExportFormat::JpegXl),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.extension())
+    }
+}
+
+/// Callback for reporting export progress.
+pub enum ExportProgress {
+    /// Starting export with total count.
+    Start { total: usize },
+    /// A file was converted.
+    Converted {
+        source: PathBuf,
+        target: PathBuf,
+        format: ExportFormat,
+    },
+    /// A file was skipped — already exported, or (when `reason` is set)
+    /// its source couldn't be decoded by the active backend.
+    Skipped {
+        path: PathBuf,
+        reason: Option<String>,
+    },
+    /// Export completed.
+    Complete { converted: usize, skipped: usize },
+}
+
+/// Outcome of converting a single photo, distinguishing a fresh conversion
+/// from the two ways a conversion can be skipped without failing the whole
+/// export — see `ExportProgress::Skipped`.
+pub enum ExportOutcome {
+    /// No file existed yet at the target path; it was converted.
+    Converted,
+    /// `target` already existed; nothing was touched.
+    AlreadyExists,
+    /// The source format couldn't be decoded by the active backend.
+    Undecodable(String),
+}
+
+/// Confirm the `sips` command is available. Only built with the `sips`
+/// feature — macOS's `sips` is a fallback encoder for formats the portable
+/// `libheif`-based backend doesn't cover, not a hard requirement of the core
+/// crate; see `export_photo_to_heic_portable`.
+#[cfg(feature = "sips")]
+pub fn check_sips_available() -> Result<()> {
+    Command::new("sips")
+        .arg("--version")
+        .output()
+        .map_err(|_| Error::SipsNotAvailable)?;
+    Ok(())
+}
+
+#[cfg(not(feature = "sips"))]
+pub fn check_sips_available() -> Result<()> {
+    Err(Error::SipsFeatureNotBuilt)
+}
+
+/// Build the export target path: export_path/YYYY/MM/DD/filename.<ext>
+pub fn build_export_path(export_path: &Path, date: (u32, u32, u32), original_path: &Path) -> PathBuf {
+    build_export_path_with_format(export_path, date, original_path, ExportFormat::Heic)
+}
+
+/// Build the export target path using the given format's extension:
+/// export_path/YYYY/MM/DD/filename.<ext>
+pub fn build_export_path_with_format(
+    export_path: &Path,
+    date: (u32, u32, u32),
+    original_path: &Path,
+    format: ExportFormat,
+) -> PathBuf {
+    let (year, month, day) = date;
+    let dir = export_path
+        .join(format!("{:04}", year))
+        .join(format!("{:02}", month))
+        .join(format!("{:02}", day));
+
+    let file_stem = original_path
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy();
+
+    dir.join(format!("{}.{}", file_stem, format.extension()))
+}
+
+/// Convert `source` to HEIC at `target` via `sips`. Kept for backward
+/// compatibility; prefer `export_photo_to_format`.
+#[cfg(feature = "sips")]
+pub fn export_photo_to_heic(source: &Path, target: &Path, quality: u8) -> Result<bool> {
+    export_photo_to_format(source, target, ExportFormat::Heic, quality)
+}
+
+#[cfg(not(feature = "sips"))]
+pub fn export_photo_to_heic(_source: &Path, _target: &Path, _quality: u8) -> Result<bool> {
+    Err(Error::SipsFeatureNotBuilt)
+}
+
+/// Convert `source` to `format` at `target` via `sips`, skipping the
+/// conversion if `target` already exists. Returns whether a conversion
+/// actually ran. Only built with the `sips` feature — AVIF/WebP/JPEG-XL
+/// still fall back to this macOS-only path, since the portable backend only
+/// covers HEIC; see `export_photo_to_heic_portable`.
+#[cfg(feature = "sips")]
+pub fn export_photo_to_format(
+    source: &Path,
+    target: &Path,
+    format: ExportFormat,
+    quality: u8,
+) -> Result<bool> {
+    if target.exists() {
+        return Ok(false);
+    }
+
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let output = Command::new("sips")
+        .args([
+            "-s",
+            "format",
+            format.sips_format_name(),
+            "-s",
+            "formatOptions",
+            &quality.to_string(),
+            source.to_string_lossy().as_ref(),
+            "--out",
+            target.to_string_lossy().as_ref(),
+        ])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(Error::ConversionFailed {
+            path: source.to_path_buf(),
+            message: String::from_utf8_lossy(&output.stderr).to_string(),
+        });
+    }
+
+    Ok(true)
+}
+
+#[cfg(not(feature = "sips"))]
+pub fn export_photo_to_format(
+    _source: &Path,
+    _target: &Path,
+    _format: ExportFormat,
+    _quality: u8,
+) -> Result<bool> {
+    Err(Error::SipsFeatureNotBuilt)
+}
+
+/// Convert `source` to HEIC at `target` without shelling out to `sips`, so
+/// export works on Linux/Windows too. Decodes `source` through
+/// `hasher::decode::decode_to_rgb8` — the same dispatcher the hashing
+/// pipeline uses, so a RAW source-of-truth (CR2/NEF/...) is demosaiced via
+/// `rawloader`/`imagepipe` exactly as it would be for perceptual hashing —
+/// then re-encodes the decoded RGB8 buffer with `libheif_rs`. Skips the
+/// conversion if `target` already exists, and reports an undecodable source
+/// as `ExportOutcome::Undecodable` instead of an error, so one bad file
+/// doesn't abort the whole export.
+#[cfg(feature = "heif")]
+pub fn export_photo_to_heic_portable(
+    source: &Path,
+    target: &Path,
+    quality: u8,
+) -> Result<ExportOutcome> {
+    use libheif_rs::{Channel, ColorSpace, CompressionFormat, EncoderQuality, HeifContext, Image, RgbChroma};
+
+    if target.exists() {
+        return Ok(ExportOutcome::AlreadyExists);
+    }
+
+    let Some(rgb) = crate::hasher::decode::decode_to_rgb8(source) else {
+        return Ok(ExportOutcome::Undecodable(format!(
+            "{} could not be decoded by the portable HEIC backend",
+            source.display()
+        )));
+    };
+
+    let to_conversion_error = |message: String| Error::ConversionFailed {
+        path: source.to_path_buf(),
+        message,
+    };
+
+    let (width, height) = (rgb.width(), rgb.height());
+    let mut image = Image::new(width, height, ColorSpace::Rgb(RgbChroma::Rgb))
+        .map_err(|e| to_conversion_error(e.to_string()))?;
+    image
+        .create_plane(Channel::Interleaved, width, height, 24)
+        .map_err(|e| to_conversion_error(e.to_string()))?;
+    let plane = image
+        .planes_mut()
+        .interleaved
+        .ok_or_else(|| to_conversion_error("encoder produced no interleaved plane".to_string()))?;
+    let stride = plane.stride;
+    for (row, src_row) in rgb.rows().enumerate() {
+        let row_start = row * stride;
+        for (col, pixel) in src_row.enumerate() {
+            let offset = row_start + col * 3;
+            plane.data[offset..offset + 3].copy_from_slice(&pixel.0);
+        }
+    }
+
+    let mut context = HeifContext::new().map_err(|e| to_conversion_error(e.to_string()))?;
+    let mut encoder = context
+        .encoder_for_format(CompressionFormat::Hevc)
+        .map_err(|e| to_conversion_error(e.to_string()))?;
+    encoder
+        .set_quality(EncoderQuality::Lossy(quality))
+        .map_err(|e| to_conversion_error(e.to_string()))?;
+    context
+        .encode_image(&image, &mut encoder, None)
+        .map_err(|e| to_conversion_error(e.to_string()))?;
+
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    context
+        .write_to_file(&target.to_string_lossy())
+        .map_err(|e| to_conversion_error(e.to_string()))?;
+
+    Ok(ExportOutcome::Converted)
+}
+
+#[cfg(not(feature = "heif"))]
+pub fn export_photo_to_heic_portable(
+    _source: &Path,
+    _target: &Path,
+    _quality: u8,
+) -> Result<ExportOutcome> {
+    Err(Error::HeifFeatureNotBuilt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "heif")]
+    #[test]
+    fn test_export_photo_to_heic_portable_skips_existing() {
+        let tmp = tempfile::tempdir().unwrap();
+        let source = tmp.path().join("source.jpg");
+        std::fs::write(&source, b"irrelevant, never decoded").unwrap();
+        let target = tmp.path().join("target.heic");
+        std::fs::write(&target, b"already here").unwrap();
+
+        let outcome = export_photo_to_heic_portable(&source, &target, 80).unwrap();
+        assert!(matches!(outcome, ExportOutcome::AlreadyExists));
+    }
+
+    #[cfg(feature = "heif")]
+    #[test]
+    fn test_export_photo_to_heic_portable_reports_undecodable_source() {
+        let tmp = tempfile::tempdir().unwrap();
+        let source = tmp.path().join("source.jpg");
+        std::fs::write(&source, b"not actually a jpeg").unwrap();
+        let target = tmp.path().join("target.heic");
+
+        let outcome = export_photo_to_heic_portable(&source, &target, 80).unwrap();
+        assert!(matches!(outcome, ExportOutcome::Undecodable(_)));
+        assert!(!target.exists());
+    }
+
+    #[cfg(feature = "heif")]
+    #[test]
+    fn test_export_photo_to_heic_portable_reports_undecodable_raw_source() {
+        // A RAW-extension source is routed to `hasher::decode::decode_raw`
+        // (see `decode.rs`) the same as any other source; without the `raw`
+        // feature (or with corrupt sensor data), it should fail the same
+        // graceful way as an unrecognized JPEG rather than erroring the export.
+        let tmp = tempfile::tempdir().unwrap();
+        let source = tmp.path().join("source.cr2");
+        std::fs::write(&source, b"not actually a CR2 file").unwrap();
+        let target = tmp.path().join("target.heic");
+
+        let outcome = export_photo_to_heic_portable(&source, &target, 80).unwrap();
+        assert!(matches!(outcome, ExportOutcome::Undecodable(_)));
+        assert!(!target.exists());
+    }
+
+    #[cfg(not(feature = "heif"))]
+    #[test]
+    fn test_export_photo_to_heic_portable_without_feature_errors() {
+        let tmp = tempfile::tempdir().unwrap();
+        let source = tmp.path().join("source.jpg");
+        let target = tmp.path().join("target.heic");
+
+        let result = export_photo_to_heic_portable(&source, &target, 80);
+        assert!(matches!(result, Err(Error::HeifFeatureNotBuilt)));
+    }
+
+    #[cfg(not(feature = "sips"))]
+    #[test]
+    fn test_check_sips_available_without_feature_errors() {
+        assert!(matches!(
+            check_sips_available(),
+            Err(Error::SipsFeatureNotBuilt)
+        ));
+    }
+
+    #[cfg(not(feature = "sips"))]
+    #[test]
+    fn test_export_photo_to_format_without_sips_feature_errors() {
+        let tmp = tempfile::tempdir().unwrap();
+        let source = tmp.path().join("source.jpg");
+        let target = tmp.path().join("target.avif");
+
+        let result = export_photo_to_format(&source, &target, ExportFormat::Avif, 70);
+        assert!(matches!(result, Err(Error::SipsFeatureNotBuilt)));
+    }
+}
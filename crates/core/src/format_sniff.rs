@@ -0,0 +1,157 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::domain::PhotoFormat;
+
+/// Sniff `path`'s true container format from its leading magic bytes,
+/// independent of whatever `PhotoFormat` its filename extension implies.
+/// Returns `None` if the file can't be opened or its header doesn't match
+/// any recognized magic number, rather than guessing — an unreadable or
+/// exotic file should never produce a false mismatch.
+pub fn sniff_format(path: &Path) -> Option<PhotoFormat> {
+    let mut header = [0u8; 12];
+    let mut file = File::open(path).ok()?;
+    let read = file.read(&mut header).ok()?;
+    detect_format_from_header(&header[..read])
+}
+
+/// Magic-number detection for the container formats this crate's format
+/// auditing cares about. RAW formats (CR2/CR3/NEF/ARW/ORF/RAF/RW2/DNG) are
+/// all TIFF- or ISOBMFF-based containers that can't be told apart from a
+/// dozen header bytes without deeper parsing, so this only distinguishes
+/// the cases a phone or editor commonly mislabels: JPEG, PNG, HEIC/HEIF,
+/// WebP, and plain TIFF.
+pub fn detect_format_from_header(header: &[u8]) -> Option<PhotoFormat> {
+    if header.len() >= 3 && header[0..3] == [0xFF, 0xD8, 0xFF] {
+        return Some(PhotoFormat::Jpeg);
+    }
+    if header.len() >= 8 && header[0..8] == [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A] {
+        return Some(PhotoFormat::Png);
+    }
+    if header.len() >= 12 && &header[4..8] == b"ftyp" {
+        let brand = &header[8..12];
+        if matches!(
+            brand,
+            b"heic" | b"heix" | b"hevc" | b"heim" | b"heis" | b"hevx" | b"mif1" | b"msf1"
+        ) {
+            return Some(PhotoFormat::Heic);
+        }
+    }
+    if header.len() >= 12 && &header[0..4] == b"RIFF" && &header[8..12] == b"WEBP" {
+        return Some(PhotoFormat::Webp);
+    }
+    if header.len() >= 4
+        && (header[0..4] == [0x49, 0x49, 0x2A, 0x00] || header[0..4] == [0x4D, 0x4D, 0x00, 0x2A])
+    {
+        return Some(PhotoFormat::Tiff);
+    }
+    None
+}
+
+/// Whether `declared` (usually derived from the filename extension) doesn't
+/// match the format sniffed from `path`'s actual bytes. A sniff that
+/// produces `None` (unrecognized or unreadable) is never reported as a
+/// mismatch — only a confident, conflicting detection counts.
+pub fn is_mismatched(path: &Path, declared: PhotoFormat) -> bool {
+    sniff_format(path)
+        .map(|detected| detected != declared)
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_format_from_header_jpeg() {
+        let header = [0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10];
+        assert_eq!(detect_format_from_header(&header), Some(PhotoFormat::Jpeg));
+    }
+
+    #[test]
+    fn test_detect_format_from_header_png() {
+        let header = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+        assert_eq!(detect_format_from_header(&header), Some(PhotoFormat::Png));
+    }
+
+    #[test]
+    fn test_detect_format_from_header_heic() {
+        let mut header = [0u8; 12];
+        header[4..8].copy_from_slice(b"ftyp");
+        header[8..12].copy_from_slice(b"heic");
+        assert_eq!(detect_format_from_header(&header), Some(PhotoFormat::Heic));
+    }
+
+    #[test]
+    fn test_detect_format_from_header_webp() {
+        let mut header = [0u8; 12];
+        header[0..4].copy_from_slice(b"RIFF");
+        header[8..12].copy_from_slice(b"WEBP");
+        assert_eq!(detect_format_from_header(&header), Some(PhotoFormat::Webp));
+    }
+
+    #[test]
+    fn test_detect_format_from_header_tiff_little_endian() {
+        let header = [0x49, 0x49, 0x2A, 0x00];
+        assert_eq!(detect_format_from_header(&header), Some(PhotoFormat::Tiff));
+    }
+
+    #[test]
+    fn test_detect_format_from_header_tiff_big_endian() {
+        let header = [0x4D, 0x4D, 0x00, 0x2A];
+        assert_eq!(detect_format_from_header(&header), Some(PhotoFormat::Tiff));
+    }
+
+    #[test]
+    fn test_detect_format_from_header_unrecognized() {
+        let header = [0x00, 0x01, 0x02, 0x03];
+        assert_eq!(detect_format_from_header(&header), None);
+    }
+
+    #[test]
+    fn test_detect_format_from_header_too_short() {
+        assert_eq!(detect_format_from_header(&[0xFF]), None);
+        assert_eq!(detect_format_from_header(&[]), None);
+    }
+
+    #[test]
+    fn test_sniff_format_reads_real_jpeg() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("photo.png"); // misleading extension
+        image::RgbImage::new(4, 4)
+            .save_with_format(&path, image::ImageFormat::Jpeg)
+            .unwrap();
+
+        assert_eq!(sniff_format(&path), Some(PhotoFormat::Jpeg));
+    }
+
+    #[test]
+    fn test_sniff_format_missing_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert_eq!(sniff_format(&tmp.path().join("ghost.jpg")), None);
+    }
+
+    #[test]
+    fn test_is_mismatched_true_when_sniff_disagrees() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("photo.png");
+        image::RgbImage::new(4, 4)
+            .save_with_format(&path, image::ImageFormat::Jpeg)
+            .unwrap();
+
+        assert!(is_mismatched(&path, PhotoFormat::Png));
+        assert!(!is_mismatched(&path, PhotoFormat::Jpeg));
+    }
+
+    #[test]
+    fn test_is_mismatched_false_when_sniff_is_inconclusive() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("raw.cr2");
+        std::fs::write(&path, b"not a real CR2, sniff will fail").unwrap();
+
+        // An unrecognized header is never reported as a mismatch, even
+        // though it clearly isn't a valid CR2 either.
+        assert!(!is_mismatched(&path, PhotoFormat::Cr2));
+    }
+}
@@ -0,0 +1,138 @@
+use std::path::Path;
+
+use image::RgbImage;
+
+/// Decode any file the hashing pipeline can be pointed at into an 8-bit RGB
+/// image, dispatching on extension to a format-specific decoder before
+/// falling back to the `image` crate (JPEG/PNG/WebP/TIFF/... — whatever
+/// `image` itself supports).
+///
+/// RAW (CR2/CR3/NEF/ARW/ORF/RAF/RW2/DNG) and HEIC/HEIF are not decodable by
+/// `image`, so they're routed through `raw`/`heif`-feature-gated decoders.
+/// With the relevant feature disabled, those extensions simply fail to
+/// decode (`None`) — the same outcome as any other unsupported format —
+/// rather than the default build pulling in the heavier dependencies.
+pub fn decode_to_rgb8(path: &Path) -> Option<RgbImage> {
+    match extension_lower(path).as_deref() {
+        Some("cr2" | "cr3" | "nef" | "arw" | "orf" | "raf" | "rw2" | "dng") => decode_raw(path),
+        Some("heic" | "heif") => decode_heif(path),
+        _ => image::open(path).ok().map(|img| img.to_rgb8()),
+    }
+}
+
+fn extension_lower(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+}
+
+/// Decode a RAW camera file via `imagepipe` (which wraps `rawloader` for the
+/// sensor demosaic, then applies white balance, color space conversion, and
+/// gamma) into RGB8.
+#[cfg(feature = "raw")]
+fn decode_raw(path: &Path) -> Option<RgbImage> {
+    let decoded = imagepipe::simple_decode_8bit(path, 0, 0).ok()?;
+    RgbImage::from_raw(decoded.width as u32, decoded.height as u32, decoded.data)
+}
+
+#[cfg(not(feature = "raw"))]
+fn decode_raw(_path: &Path) -> Option<RgbImage> {
+    None
+}
+
+/// Decode a HEIC/HEIF file via `libheif-rs`, taking the primary image and
+/// converting to interleaved RGB8.
+#[cfg(feature = "heif")]
+fn decode_heif(path: &Path) -> Option<RgbImage> {
+    use libheif_rs::{ColorSpace, HeifContext, LibHeif, RgbChroma};
+
+    let lib_heif = LibHeif::new();
+    let ctx = HeifContext::read_from_file(path.to_str()?).ok()?;
+    let handle = ctx.primary_image_handle().ok()?;
+    let image = lib_heif
+        .decode(&handle, ColorSpace::Rgb(RgbChroma::Rgb), None)
+        .ok()?;
+    let plane = image.planes().interleaved?;
+    RgbImage::from_raw(
+        handle.width(),
+        handle.height(),
+        plane.data.to_vec(),
+    )
+}
+
+#[cfg(not(feature = "heif"))]
+fn decode_heif(_path: &Path) -> Option<RgbImage> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nonexistent_file_returns_none() {
+        assert!(decode_to_rgb8(Path::new("/nonexistent/photo.jpg")).is_none());
+    }
+
+    #[test]
+    fn test_unrecognized_extension_falls_back_to_image_crate() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("not_an_image.jpg");
+        std::fs::write(&path, b"not actually a jpeg").unwrap();
+        assert!(decode_to_rgb8(&path).is_none());
+    }
+
+    #[cfg(not(feature = "raw"))]
+    #[test]
+    fn test_raw_extension_without_feature_returns_none() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("photo.cr2");
+        std::fs::write(&path, b"fake raw bytes").unwrap();
+        assert!(decode_to_rgb8(&path).is_none());
+    }
+
+    #[cfg(not(feature = "heif"))]
+    #[test]
+    fn test_heif_extension_without_feature_returns_none() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("photo.heic");
+        std::fs::write(&path, b"fake heif bytes").unwrap();
+        assert!(decode_to_rgb8(&path).is_none());
+    }
+
+    #[cfg(not(feature = "heif"))]
+    #[test]
+    fn test_heif_extension_and_case_both_route_to_the_heif_decoder() {
+        // `.heif` (not just `.heic`) and an uppercase extension must both hit
+        // `decode_heif` rather than falling through to `image::open`, which
+        // would "succeed" on garbage bytes for some inputs and corrupt the
+        // perceptual hash — same rationale as the RAW routing test below.
+        let tmp = tempfile::tempdir().unwrap();
+        for name in ["photo.heif", "photo.HEIC"] {
+            let path = tmp.path().join(name);
+            std::fs::write(&path, b"fake heif bytes").unwrap();
+            assert!(
+                decode_to_rgb8(&path).is_none(),
+                "{name} should be recognized as HEIF/HEIC and gated behind the `heif` feature"
+            );
+        }
+    }
+
+    #[cfg(not(feature = "raw"))]
+    #[test]
+    fn test_every_raw_extension_is_routed_to_the_raw_decoder() {
+        // All of these must hit `decode_raw` (and so fail the same way
+        // without the `raw` feature) rather than silently falling through
+        // to the `image` crate, which would "succeed" on garbage bytes for
+        // some of these extensions and corrupt the perceptual hash.
+        let tmp = tempfile::tempdir().unwrap();
+        for ext in ["cr2", "cr3", "nef", "arw", "orf", "raf", "rw2", "dng"] {
+            let path = tmp.path().join(format!("photo.{ext}"));
+            std::fs::write(&path, b"fake raw bytes").unwrap();
+            assert!(
+                decode_to_rgb8(&path).is_none(),
+                "{ext} should be recognized as RAW and gated behind the `raw` feature"
+            );
+        }
+    }
+}
@@ -1,3 +1,4 @@
+pub mod decode;
 pub mod perceptual;
 
 use std::io::Read;
@@ -25,6 +26,122 @@ pub fn compute_sha256(path: &Path) -> std::io::Result<String> {
     Ok(format!("{:x}", result))
 }
 
+/// How much of a file `compute_prehash` reads — enough to distinguish almost
+/// any two genuinely different files while staying far cheaper than hashing
+/// the whole thing. A file whose total size is within this many bytes has
+/// its entire content covered by the prehash, so the prehash is usable as
+/// that file's real sha256 — see `Vault::scan`'s Phase 1a.
+pub const PREHASH_BYTES: usize = 16 * 1024;
+
+/// Hash only the leading [`PREHASH_BYTES`] of a file's contents, in the same
+/// hex-digest format as [`compute_sha256`]. Two files can only be exact
+/// duplicates if their size and this leading block agree, so a scan can use
+/// `(size, prehash)` to cheaply rule out the vast majority of candidates as
+/// definitely-unique before paying for a full-file hash — see `Vault::scan`.
+pub fn compute_prehash(path: &Path) -> std::io::Result<String> {
+    let file = std::fs::File::open(path)?;
+    let mut reader = std::io::BufReader::with_capacity(PREHASH_BYTES, file);
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; PREHASH_BYTES];
+    let mut filled = 0;
+
+    while filled < buf.len() {
+        let n = reader.read(&mut buf[filled..])?;
+        if n == 0 {
+            break;
+        }
+        filled += n;
+    }
+    hasher.update(&buf[..filled]);
+
+    let result = hasher.finalize();
+    Ok(format!("{:x}", result))
+}
+
+/// Which algorithm backs an exact-duplicate digest (the choice is kept in
+/// the catalog config under `dedup_hash_algorithm`; the catalog has no
+/// per-row algorithm column, so a stored digest does not record which
+/// algorithm produced it). `Sha256` is cryptographically strong but the slowest;
+/// `Xxh3` is the fast default for dedup, where collision resistance against
+/// an adversary isn't the concern; `Blake3` sits in between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashType {
+    Sha256,
+    Blake3,
+    Xxh3,
+}
+
+impl HashType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HashType::Sha256 => "sha256",
+            HashType::Blake3 => "blake3",
+            HashType::Xxh3 => "xxh3",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "sha256" => Some(HashType::Sha256),
+            "blake3" => Some(HashType::Blake3),
+            "xxh3" => Some(HashType::Xxh3),
+            _ => None,
+        }
+    }
+}
+
+impl Default for HashType {
+    /// Xxh3 is the fast default for exact-duplicate grouping — see the
+    /// type's own docs for why cryptographic strength isn't needed there.
+    fn default() -> Self {
+        HashType::Xxh3
+    }
+}
+
+/// Compute a file's content digest using `hash_type`, in the same streaming,
+/// chunked-read style as [`compute_sha256`] regardless of algorithm.
+pub fn compute_digest(path: &Path, hash_type: HashType) -> std::io::Result<String> {
+    match hash_type {
+        HashType::Sha256 => compute_sha256(path),
+        HashType::Blake3 => compute_blake3(path),
+        HashType::Xxh3 => compute_xxh3(path),
+    }
+}
+
+fn compute_blake3(path: &Path) -> std::io::Result<String> {
+    let file = std::fs::File::open(path)?;
+    let mut reader = std::io::BufReader::with_capacity(64 * 1024, file);
+    let mut hasher = blake3::Hasher::new();
+    let mut buf = [0u8; 64 * 1024];
+
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+fn compute_xxh3(path: &Path) -> std::io::Result<String> {
+    let file = std::fs::File::open(path)?;
+    let mut reader = std::io::BufReader::with_capacity(64 * 1024, file);
+    let mut hasher = xxhash_rust::xxh3::Xxh3::new();
+    let mut buf = [0u8; 64 * 1024];
+
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+
+    Ok(format!("{:016x}", hasher.digest()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -78,4 +195,121 @@ mod tests {
         let result = compute_sha256(Path::new("/nonexistent/file.bin"));
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_prehash_matches_sha256_for_files_under_the_prehash_size() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("small.bin");
+        fs::write(&path, b"hello world").unwrap();
+
+        assert_eq!(
+            compute_prehash(&path).unwrap(),
+            compute_sha256(&path).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_prehash_ignores_content_past_the_prehash_size() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path_a = tmp.path().join("a.bin");
+        let path_b = tmp.path().join("b.bin");
+        let mut content_a = vec![0u8; PREHASH_BYTES];
+        let mut content_b = content_a.clone();
+        content_a.extend_from_slice(b"tail A");
+        content_b.extend_from_slice(b"tail B");
+        fs::write(&path_a, &content_a).unwrap();
+        fs::write(&path_b, &content_b).unwrap();
+
+        assert_eq!(
+            compute_prehash(&path_a).unwrap(),
+            compute_prehash(&path_b).unwrap()
+        );
+        assert_ne!(
+            compute_sha256(&path_a).unwrap(),
+            compute_sha256(&path_b).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_prehash_differs_for_content_within_the_prehash_size() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path_a = tmp.path().join("a.bin");
+        let path_b = tmp.path().join("b.bin");
+        fs::write(&path_a, b"content A").unwrap();
+        fs::write(&path_b, b"content B").unwrap();
+
+        assert_ne!(
+            compute_prehash(&path_a).unwrap(),
+            compute_prehash(&path_b).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_prehash_nonexistent_file() {
+        let result = compute_prehash(Path::new("/nonexistent/file.bin"));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_hash_type_as_str_and_parse_round_trip() {
+        for hash_type in [HashType::Sha256, HashType::Blake3, HashType::Xxh3] {
+            assert_eq!(HashType::parse(hash_type.as_str()), Some(hash_type));
+        }
+    }
+
+    #[test]
+    fn test_hash_type_parse_rejects_unknown_tag() {
+        assert_eq!(HashType::parse("md5"), None);
+    }
+
+    #[test]
+    fn test_hash_type_default_is_xxh3() {
+        assert_eq!(HashType::default(), HashType::Xxh3);
+    }
+
+    #[test]
+    fn test_compute_digest_dispatches_to_the_requested_algorithm() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("test.bin");
+        fs::write(&path, b"hello world").unwrap();
+
+        assert_eq!(
+            compute_digest(&path, HashType::Sha256).unwrap(),
+            compute_sha256(&path).unwrap()
+        );
+        assert_ne!(
+            compute_digest(&path, HashType::Blake3).unwrap(),
+            compute_digest(&path, HashType::Xxh3).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_compute_digest_is_consistent_across_calls() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("test.bin");
+        fs::write(&path, b"hello world").unwrap();
+
+        for hash_type in [HashType::Sha256, HashType::Blake3, HashType::Xxh3] {
+            assert_eq!(
+                compute_digest(&path, hash_type).unwrap(),
+                compute_digest(&path, hash_type).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_compute_digest_differs_for_different_content() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path_a = tmp.path().join("a.bin");
+        let path_b = tmp.path().join("b.bin");
+        fs::write(&path_a, b"content A").unwrap();
+        fs::write(&path_b, b"content B").unwrap();
+
+        for hash_type in [HashType::Sha256, HashType::Blake3, HashType::Xxh3] {
+            assert_ne!(
+                compute_digest(&path_a, hash_type).unwrap(),
+                compute_digest(&path_b, hash_type).unwrap()
+            );
+        }
+    }
 }
@@ -10,7 +10,9 @@ use fast_image_resize::{self as fir, images::Image as FirImage};
 ///
 /// Uses a hybrid decode strategy:
 /// - JPEG: `turbojpeg` full-resolution grayscale decode (feature-gated, skips chroma)
-/// - Other formats: `image` crate decode, RGB resize to 9x8, then grayscale conversion
+/// - RAW (CR2/CR3/NEF/ARW/ORF/RAF/RW2/DNG): `imagepipe`-decoded RGB (feature-gated `raw`)
+/// - HEIC/HEIF: `libheif-rs`-decoded RGB (feature-gated `heif`)
+/// - Everything else: `image` crate decode, RGB resize to 9x8, then grayscale conversion
 ///
 /// Both paths apply EXIF orientation before resizing, so photos with rotation tags
 /// (common on iPhone originals) produce the same hash as physically-rotated exports.
@@ -19,24 +21,183 @@ use fast_image_resize::{self as fir, images::Image as FirImage};
 /// Full-resolution decode is critical — DCT scaling changes frequency-domain coefficients
 /// differently for recompressed JPEGs, causing hash divergence beyond threshold.
 pub fn compute_perceptual_hashes(path: &Path) -> Option<(u64, u64)> {
-    let pixels = load_9x8_grayscale(path)?;
-    let ahash = compute_ahash(&pixels);
+    compute_perceptual_hashes_with_alg(path, HashAlg::Mean)
+}
+
+/// Which algorithm computes the primary (`phash` column) hash. The secondary
+/// consensus hash (`dhash` column) is always the horizontal gradient hash,
+/// regardless of `alg` — the dual-hash check this pipeline relies on compares
+/// a configurable primary signal against that fixed baseline.
+///
+/// All five variants are 64-bit, matching the catalog's `INTEGER` phash
+/// columns; wider hash sizes (16x16, 32x32, 64x64) aren't supported yet since
+/// that would need `domain::PhotoFile`'s `phash`/`dhash` fields to widen past
+/// `u64` and the `photos` table's `INTEGER` columns to become `BLOB` — see
+/// `Vault::set_hash_size`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HashAlg {
+    /// Average hash: each bit = 1 if the pixel is at/above the block mean.
+    #[default]
+    Mean,
+    /// Difference hash: each bit = 1 if a pixel is brighter than its
+    /// horizontal neighbor (this is the same computation as the `dhash` column).
+    Gradient,
+    /// Gradient's bits XORed with a vertical companion gradient, to catch
+    /// structure a purely-horizontal gradient can miss.
+    DoubleGradient,
+    /// Mean hash over a horizontally pre-smoothed block, approximating
+    /// block-averaging without access to the pre-resize full-resolution image.
+    Blockhash,
+    /// Classic DCT-based pHash: a separable 2-D DCT-II over a 32x32
+    /// downscale, thresholding the low-frequency 8x8 block against its
+    /// median (DC term excluded from the median). Unlike the other variants, which all
+    /// hash the shared 9x8 buffer, this one decodes its own 32x32 buffer —
+    /// the larger input is what makes it tolerant of gamma shifts,
+    /// brightness changes, and JPEG recompression.
+    Dct,
+}
+
+/// Which `fast_image_resize` algorithm downscales the decoded image to the
+/// 9x8 hashing buffer. Nearest is fastest and most tolerant of
+/// recompression noise (cheap, blocky); Lanczos3 (the default) is sharpest
+/// and most sensitive to fine structural differences; Triangle sits between
+/// the two.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResizeFilter {
+    Nearest,
+    /// `fast_image_resize` has no dedicated triangle/bilinear-interpolation
+    /// filter distinct from its convolution kernels — this maps to its
+    /// closest equivalent, `FilterType::Bilinear`.
+    Triangle,
+    #[default]
+    Lanczos3,
+}
+
+impl ResizeFilter {
+    fn to_resize_alg(self) -> fir::ResizeAlg {
+        match self {
+            ResizeFilter::Nearest => fir::ResizeAlg::Nearest,
+            ResizeFilter::Triangle => fir::ResizeAlg::Convolution(fir::FilterType::Bilinear),
+            ResizeFilter::Lanczos3 => fir::ResizeAlg::Convolution(fir::FilterType::Lanczos3),
+        }
+    }
+}
+
+/// Compute the primary perceptual hash and the dhash consensus hash for an
+/// image, using `alg` for the primary hash and the default (Lanczos3)
+/// resize filter. See `HashAlg` for the tradeoffs.
+pub fn compute_perceptual_hashes_with_alg(path: &Path, alg: HashAlg) -> Option<(u64, u64)> {
+    compute_perceptual_hashes_with_config(path, alg, ResizeFilter::default())
+}
+
+/// Compute the primary perceptual hash and the dhash consensus hash for an
+/// image, using `alg` for the primary hash and `filter` to downscale to the
+/// 9x8 hashing buffer. See `HashAlg`/`ResizeFilter` for the tradeoffs.
+pub fn compute_perceptual_hashes_with_config(
+    path: &Path,
+    alg: HashAlg,
+    filter: ResizeFilter,
+) -> Option<(u64, u64)> {
+    let pixels = load_9x8_grayscale(path, filter)?;
+    let primary = compute_primary_hash(path, alg, filter, &pixels)?;
+    let dhash = compute_dhash(&pixels);
+    Some((primary, dhash))
+}
+
+/// Like `compute_perceptual_hashes_with_config`, but also returns the aHash
+/// (average hash) as a third, fixed fingerprint alongside the configurable
+/// primary hash and the fixed dHash consensus hash — used by the catalog's
+/// `ahash` column so grouping can fall back on a third signal when the
+/// primary and dHash disagree. Reuses the same decoded 9x8 buffer, so this
+/// costs nothing beyond one extra mean-hash pass over 64 bytes.
+pub fn compute_triple_hash_with_config(
+    path: &Path,
+    alg: HashAlg,
+    filter: ResizeFilter,
+) -> Option<(u64, u64, u64)> {
+    let pixels = load_9x8_grayscale(path, filter)?;
+    let primary = compute_primary_hash(path, alg, filter, &pixels)?;
     let dhash = compute_dhash(&pixels);
-    Some((ahash, dhash))
+    let ahash = compute_ahash(&pixels);
+    Some((primary, dhash, ahash))
+}
+
+/// Dispatch to the algorithm `alg` selects. Every variant but `Dct` hashes
+/// the already-decoded 9x8 buffer; `Dct` needs a separate, larger 32x32
+/// decode of `path` to have enough low-frequency structure to threshold.
+fn compute_primary_hash(path: &Path, alg: HashAlg, filter: ResizeFilter, pixels_9x8: &[u8]) -> Option<u64> {
+    if alg == HashAlg::Dct {
+        let dct_pixels = load_32x32_grayscale_with_filter(path, filter)?;
+        return Some(compute_phash_from_pixels(&dct_pixels));
+    }
+    Some(compute_hash_with_alg(pixels_9x8, alg))
+}
+
+fn compute_hash_with_alg(pixels: &[u8], alg: HashAlg) -> u64 {
+    match alg {
+        HashAlg::Mean => compute_ahash(pixels),
+        HashAlg::Gradient => compute_dhash(pixels),
+        HashAlg::DoubleGradient => compute_dhash(pixels) ^ compute_vertical_gradient(pixels),
+        HashAlg::Blockhash => compute_blockhash(pixels),
+        HashAlg::Dct => unreachable!("Dct is handled by compute_primary_hash before reaching here"),
+    }
+}
+
+/// Difference hash computed vertically instead of horizontally: each bit = 1
+/// if a pixel is brighter than the pixel one row below (wrapping past the
+/// last row, since the 9x8 buffer has no 9th row to compare against).
+fn compute_vertical_gradient(pixels: &[u8]) -> u64 {
+    let mut hash: u64 = 0;
+    let mut bit = 0;
+    for row in 0..8 {
+        let next_row = (row + 1) % 8;
+        for col in 0..8 {
+            let top = pixels[row * 9 + col];
+            let bottom = pixels[next_row * 9 + col];
+            if top > bottom {
+                hash |= 1 << bit;
+            }
+            bit += 1;
+        }
+    }
+    hash
+}
+
+/// Mean hash over a horizontally-smoothed 8x8 block (each pixel averaged with
+/// its right neighbor first), which softens single-pixel noise the way a true
+/// blockhash would by averaging over regions of the original full-resolution image.
+fn compute_blockhash(pixels: &[u8]) -> u64 {
+    let mut smoothed = [0u8; 64];
+    for row in 0..8 {
+        for col in 0..8 {
+            let left = pixels[row * 9 + col] as u16;
+            let right = pixels[row * 9 + col + 1] as u16;
+            smoothed[row * 8 + col] = ((left + right) / 2) as u8;
+        }
+    }
+
+    let mean: u64 = smoothed.iter().map(|&p| p as u64).sum::<u64>() / 64;
+    let mut hash: u64 = 0;
+    for (i, &pixel) in smoothed.iter().enumerate() {
+        if pixel as u64 >= mean {
+            hash |= 1 << i;
+        }
+    }
+    hash
 }
 
 /// Load image and produce a 9x8 grayscale pixel buffer ready for hashing.
-fn load_9x8_grayscale(path: &Path) -> Option<[u8; 72]> {
+fn load_9x8_grayscale(path: &Path, filter: ResizeFilter) -> Option<[u8; 72]> {
     // JPEG: turbojpeg full-res grayscale → orientation → resize to 9x8
     #[cfg(feature = "turbojpeg")]
     if is_jpeg(path) {
-        if let Some(buf) = load_jpeg_9x8(path) {
+        if let Some(buf) = load_jpeg_9x8(path, filter) {
             return Some(buf);
         }
     }
 
     // Other formats: image crate → orientation → RGB resize to 9x8 → grayscale
-    load_image_crate_9x8(path)
+    load_image_crate_9x8(path, filter)
 }
 
 /// Check if a file is JPEG by extension.
@@ -152,7 +313,7 @@ fn apply_orientation(buf: &[u8], w: usize, h: usize, orientation: u8) -> (Vec<u8
 /// Full-resolution decode is required — DCT scaling produces different
 /// intermediate pixels for recompressed JPEGs, causing hash divergence.
 #[cfg(feature = "turbojpeg")]
-fn load_jpeg_9x8(path: &Path) -> Option<[u8; 72]> {
+fn load_jpeg_9x8(path: &Path, filter: ResizeFilter) -> Option<[u8; 72]> {
     let jpeg_data = std::fs::read(path).ok()?;
     let mut decompressor = turbojpeg::Decompressor::new().ok()?;
     let header = decompressor.read_header(&jpeg_data).ok()?;
@@ -177,7 +338,8 @@ fn load_jpeg_9x8(path: &Path) -> Option<[u8; 72]> {
     // SIMD resize grayscale to 9x8
     let src = FirImage::from_vec_u8(w as u32, h as u32, buf, fir::PixelType::U8).ok()?;
     let mut dst = FirImage::new(9, 8, fir::PixelType::U8);
-    fir::Resizer::new().resize(&src, &mut dst, None).ok()?;
+    let options = fir::ResizeOptions::new().resize_alg(filter.to_resize_alg());
+    fir::Resizer::new().resize(&src, &mut dst, Some(&options)).ok()?;
 
     let mut pixels = [0u8; 72];
     pixels.copy_from_slice(&dst.buffer()[..72]);
@@ -217,9 +379,8 @@ fn apply_orientation_rgb(buf: &[u8], w: usize, h: usize, orientation: u8) -> (Ve
 /// Decode any supported format using the `image` crate, apply EXIF orientation,
 /// resize RGB to 9x8, then convert only those 72 pixels to grayscale.
 /// Avoids full-resolution grayscale conversion (e.g., 12MP × BT.601 per pixel).
-fn load_image_crate_9x8(path: &Path) -> Option<[u8; 72]> {
-    let img = image::open(path).ok()?;
-    let rgb = img.to_rgb8();
+fn load_image_crate_9x8(path: &Path, filter: ResizeFilter) -> Option<[u8; 72]> {
+    let rgb = super::decode::decode_to_rgb8(path)?;
     let (w, h) = (rgb.width() as usize, rgb.height() as usize);
 
     // Apply EXIF orientation before resize
@@ -229,7 +390,8 @@ fn load_image_crate_9x8(path: &Path) -> Option<[u8; 72]> {
     // SIMD resize RGB to 9x8 (216 bytes output instead of millions)
     let src = FirImage::from_vec_u8(w as u32, h as u32, rgb_data, fir::PixelType::U8x3).ok()?;
     let mut dst = FirImage::new(9, 8, fir::PixelType::U8x3);
-    fir::Resizer::new().resize(&src, &mut dst, None).ok()?;
+    let options = fir::ResizeOptions::new().resize_alg(filter.to_resize_alg());
+    fir::Resizer::new().resize(&src, &mut dst, Some(&options)).ok()?;
 
     // Convert 72 RGB pixels to grayscale using BT.601
     let rgb_buf = dst.buffer();
@@ -287,6 +449,169 @@ pub fn hamming_distance(a: u64, b: u64) -> u32 {
     (a ^ b).count_ones()
 }
 
+/// Byte-vector counterpart to `hamming_distance`, for hashes wider than 64
+/// bits (see `HashAlg`'s note on `Vault::set_hash_size` — the catalog's
+/// `phash`/`dhash` columns are 64-bit today, so nothing produces a hash this
+/// compares yet, but a BLOB-backed BK-tree over a widened hash would need
+/// this rather than the fixed-width `u64` version). Mismatched lengths count
+/// every extra byte in the longer hash as fully different, since there's no
+/// sane way to align two hashes of different bit widths bit-for-bit.
+pub fn hamming_distance_bytes(a: &[u8], b: &[u8]) -> u32 {
+    let common = a.len().min(b.len());
+    let mut distance: u32 = a[..common]
+        .iter()
+        .zip(&b[..common])
+        .map(|(x, y)| (x ^ y).count_ones())
+        .sum();
+    distance += (a.len() - common) as u32 * 8;
+    distance += (b.len() - common) as u32 * 8;
+    distance
+}
+
+/// Union-find clustering of `(id, hash)` pairs into groups whose pairwise
+/// Hamming distance is all within `threshold` of some other member (clusters
+/// merge transitively: a~b and b~c puts a, b, c in one group even if a and c
+/// aren't directly within range). O(n^2) over hashes, never over pixels.
+/// Singletons (no near-duplicate found) are not returned.
+pub fn cluster_by_hamming<T: Clone>(entries: &[(T, u64)], threshold: u32) -> Vec<Vec<T>> {
+    let n = entries.len();
+    let mut parent: Vec<usize> = (0..n).collect();
+    fn find(parent: &mut [usize], x: usize) -> usize {
+        if parent[x] != x {
+            parent[x] = find(parent, parent[x]);
+        }
+        parent[x]
+    }
+
+    for i in 0..n {
+        for j in (i + 1)..n {
+            if hamming_distance(entries[i].1, entries[j].1) <= threshold {
+                let (ri, rj) = (find(&mut parent, i), find(&mut parent, j));
+                if ri != rj {
+                    parent[ri] = rj;
+                }
+            }
+        }
+    }
+
+    let mut clusters: std::collections::HashMap<usize, Vec<T>> = std::collections::HashMap::new();
+    for i in 0..n {
+        let root = find(&mut parent, i);
+        clusters.entry(root).or_default().push(entries[i].0.clone());
+    }
+
+    clusters.into_values().filter(|c| c.len() > 1).collect()
+}
+
+/// Default Hamming distance threshold below which two pHash values are
+/// considered a near-duplicate (re-encoded or resized copy of the same photo).
+pub const PHASH_NEAR_DUPLICATE_THRESHOLD: u32 = 10;
+
+/// Compute a DCT-based perceptual hash (pHash) for an image.
+///
+/// Unlike `compute_perceptual_hashes` (aHash/dHash, cheap and tuned for the main
+/// matching pipeline's dual-hash consensus), pHash operates in the frequency
+/// domain and tolerates re-encoding/resizing better, at higher compute cost.
+/// It is used for `vault_save` / pack-time near-duplicate clustering, not the
+/// SHA-256 → EXIF → perceptual-hash matching pipeline.
+///
+/// Pipeline: decode → orientation correction → resize to 32x32 grayscale →
+/// 2D DCT → top-left 8x8 low-frequency block → threshold against the median
+/// of those 64 coefficients (DC term excluded from the median) → 64-bit hash.
+pub fn compute_phash(path: &Path) -> Option<u64> {
+    let pixels = load_32x32_grayscale(path)?;
+    Some(compute_phash_from_pixels(&pixels))
+}
+
+/// Decode any supported format, apply EXIF orientation, and resize RGB to
+/// 32x32, converting to grayscale for the DCT pass. Uses the default
+/// (Lanczos3) resize filter — see `load_32x32_grayscale_with_filter` for the
+/// configurable version used by `HashAlg::Dct`.
+fn load_32x32_grayscale(path: &Path) -> Option<[u8; 1024]> {
+    load_32x32_grayscale_with_filter(path, ResizeFilter::default())
+}
+
+/// Like `load_32x32_grayscale`, but lets the caller pick the downscale
+/// filter, matching the 9x8 path's `filter` knob so `HashAlg::Dct` respects
+/// the configured `ResizeFilter` instead of always using the library default.
+fn load_32x32_grayscale_with_filter(path: &Path, filter: ResizeFilter) -> Option<[u8; 1024]> {
+    let rgb = super::decode::decode_to_rgb8(path)?;
+    let (w, h) = (rgb.width() as usize, rgb.height() as usize);
+
+    let orientation = read_exif_orientation(path);
+    let (rgb_data, w, h) = apply_orientation_rgb(rgb.as_raw(), w, h, orientation);
+
+    let src = FirImage::from_vec_u8(w as u32, h as u32, rgb_data, fir::PixelType::U8x3).ok()?;
+    let mut dst = FirImage::new(32, 32, fir::PixelType::U8x3);
+    let options = fir::ResizeOptions::new().resize_alg(filter.to_resize_alg());
+    fir::Resizer::new().resize(&src, &mut dst, Some(&options)).ok()?;
+
+    let rgb_buf = dst.buffer();
+    let mut gray = [0u8; 1024];
+    for i in 0..1024 {
+        let r = rgb_buf[i * 3] as f32;
+        let g = rgb_buf[i * 3 + 1] as f32;
+        let b = rgb_buf[i * 3 + 2] as f32;
+        gray[i] = (0.299 * r + 0.587 * g + 0.114 * b) as u8;
+    }
+    Some(gray)
+}
+
+/// Naive O(n^4) 2D DCT-II over a 32x32 block. Fine for pack-time hashing —
+/// this runs once per photo, not in the hot scan path.
+fn dct_2d_32(pixels: &[u8; 1024]) -> [[f64; 32]; 32] {
+    const N: usize = 32;
+    let mut samples = [[0f64; N]; N];
+    for y in 0..N {
+        for x in 0..N {
+            samples[y][x] = pixels[y * N + x] as f64;
+        }
+    }
+
+    let mut coeffs = [[0f64; N]; N];
+    for v in 0..N {
+        for u in 0..N {
+            let mut sum = 0f64;
+            for y in 0..N {
+                for x in 0..N {
+                    sum += samples[y][x]
+                        * ((std::f64::consts::PI / N as f64) * (x as f64 + 0.5) * u as f64).cos()
+                        * ((std::f64::consts::PI / N as f64) * (y as f64 + 0.5) * v as f64).cos();
+                }
+            }
+            let cu = if u == 0 { std::f64::consts::FRAC_1_SQRT_2 } else { 1.0 };
+            let cv = if v == 0 { std::f64::consts::FRAC_1_SQRT_2 } else { 1.0 };
+            coeffs[v][u] = 0.25 * cu * cv * sum;
+        }
+    }
+    coeffs
+}
+
+/// Take the top-left 8x8 low-frequency block, threshold each of the 64
+/// coefficients against the median (DC term excluded from the median itself).
+fn compute_phash_from_pixels(pixels: &[u8; 1024]) -> u64 {
+    let dct = dct_2d_32(pixels);
+
+    let mut block = [0f64; 64];
+    for v in 0..8 {
+        for u in 0..8 {
+            block[v * 8 + u] = dct[v][u];
+        }
+    }
+
+    let mut without_dc: Vec<f64> = block[1..].to_vec();
+    without_dc.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let median = without_dc[without_dc.len() / 2];
+
+    let mut hash: u64 = 0;
+    for (i, &coeff) in block.iter().enumerate() {
+        if coeff > median {
+            hash |= 1 << i;
+        }
+    }
+    hash
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -309,6 +634,26 @@ mod tests {
         assert_eq!(hamming_distance(0, u64::MAX), 64);
     }
 
+    #[test]
+    fn test_hamming_distance_bytes_matches_u64_version_at_equal_width() {
+        let a = 0b1010_u64.to_be_bytes();
+        let b = 0b1111_u64.to_be_bytes();
+        assert_eq!(hamming_distance_bytes(&a, &b), hamming_distance(0b1010, 0b1111));
+    }
+
+    #[test]
+    fn test_hamming_distance_bytes_identical() {
+        let a = [0xFFu8; 32];
+        assert_eq!(hamming_distance_bytes(&a, &a), 0);
+    }
+
+    #[test]
+    fn test_hamming_distance_bytes_penalizes_mismatched_length() {
+        // The extra byte has no counterpart to compare against, so it counts
+        // as fully different rather than being silently ignored.
+        assert_eq!(hamming_distance_bytes(&[0x00], &[0x00, 0xFF]), 8);
+    }
+
     #[test]
     fn test_compute_perceptual_hashes_returns_values() {
         let tmp = tempfile::tempdir().unwrap();
@@ -333,6 +678,67 @@ mod tests {
         assert_eq!(dhash_a, dhash_b);
     }
 
+    #[test]
+    fn test_compute_perceptual_hashes_with_alg_identical_images_same_hash() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path_a = tmp.path().join("a.jpg");
+        let path_b = tmp.path().join("b.jpg");
+        create_test_jpeg(&path_a, 200, 100, 50);
+        create_test_jpeg(&path_b, 200, 100, 50);
+
+        for alg in [
+            HashAlg::Mean,
+            HashAlg::Gradient,
+            HashAlg::DoubleGradient,
+            HashAlg::Blockhash,
+            HashAlg::Dct,
+        ] {
+            let (phash_a, _) = compute_perceptual_hashes_with_alg(&path_a, alg).unwrap();
+            let (phash_b, _) = compute_perceptual_hashes_with_alg(&path_b, alg).unwrap();
+            assert_eq!(phash_a, phash_b, "{alg:?} should hash identical images identically");
+        }
+    }
+
+    #[test]
+    fn test_gradient_alg_matches_dhash_column() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("a.jpg");
+        create_test_jpeg(&path, 90, 60, 30);
+
+        let (_, dhash) = compute_perceptual_hashes(&path).unwrap();
+        let (gradient_primary, _) =
+            compute_perceptual_hashes_with_alg(&path, HashAlg::Gradient).unwrap();
+        assert_eq!(gradient_primary, dhash);
+    }
+
+    #[test]
+    fn test_dct_alg_matches_compute_phash() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("a.jpg");
+        create_test_jpeg(&path, 90, 60, 30);
+
+        // `HashAlg::Dct` with the default filter is the same DCT pipeline as
+        // the standalone `compute_phash` used by vault_save/pack-time.
+        let (dct_primary, _) = compute_perceptual_hashes_with_alg(&path, HashAlg::Dct).unwrap();
+        let phash = compute_phash(&path).unwrap();
+        assert_eq!(dct_primary, phash);
+    }
+
+    #[test]
+    fn test_triple_hash_ahash_matches_mean_alg_primary() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("a.jpg");
+        create_test_jpeg(&path, 90, 60, 30);
+
+        // With HashAlg::Gradient as primary, the third value (ahash) should
+        // still equal the dedicated Mean-alg hash, independent of `alg`.
+        let (_, _, ahash) =
+            compute_triple_hash_with_config(&path, HashAlg::Gradient, ResizeFilter::default())
+                .unwrap();
+        let (mean_primary, _) = compute_perceptual_hashes_with_alg(&path, HashAlg::Mean).unwrap();
+        assert_eq!(ahash, mean_primary);
+    }
+
     #[test]
     fn test_different_images_different_hash() {
         let tmp = tempfile::tempdir().unwrap();
@@ -448,4 +854,121 @@ mod tests {
         assert_eq!((w, h), (2, 3));
         assert_eq!(out, vec![3, 6, 2, 5, 1, 4]);
     }
+
+    // ── pHash (DCT) ──────────────────────────────────────────────
+
+    #[test]
+    fn test_compute_phash_returns_value() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("test.jpg");
+        create_test_jpeg(&path, 128, 128, 128);
+
+        assert!(compute_phash(&path).is_some());
+    }
+
+    #[test]
+    fn test_phash_identical_images_same_hash() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path_a = tmp.path().join("a.jpg");
+        let path_b = tmp.path().join("b.jpg");
+        create_test_jpeg(&path_a, 200, 100, 50);
+        create_test_jpeg(&path_b, 200, 100, 50);
+
+        let hash_a = compute_phash(&path_a).unwrap();
+        let hash_b = compute_phash(&path_b).unwrap();
+        assert_eq!(hash_a, hash_b);
+    }
+
+    #[test]
+    fn test_phash_different_images_different_hash() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path_a = tmp.path().join("gradient.jpg");
+        let path_b = tmp.path().join("checkerboard.jpg");
+
+        let img_a = image::RgbImage::from_fn(64, 64, |x, _| {
+            let v = (x * 4) as u8;
+            image::Rgb([v, 0, 0])
+        });
+        img_a.save(&path_a).unwrap();
+
+        let img_b = image::RgbImage::from_fn(64, 64, |x, y| {
+            if (x / 8 + y / 8) % 2 == 0 {
+                image::Rgb([255, 255, 255])
+            } else {
+                image::Rgb([0, 0, 0])
+            }
+        });
+        img_b.save(&path_b).unwrap();
+
+        let hash_a = compute_phash(&path_a).unwrap();
+        let hash_b = compute_phash(&path_b).unwrap();
+        assert_ne!(hash_a, hash_b);
+    }
+
+    #[test]
+    fn test_phash_nonexistent_file_returns_none() {
+        assert!(compute_phash(Path::new("/nonexistent/image.jpg")).is_none());
+    }
+
+    // ── cluster_by_hamming ───────────────────────────────────────
+
+    #[test]
+    fn test_cluster_by_hamming_groups_close_hashes() {
+        let entries = vec![
+            ("a".to_string(), 0u64),
+            ("b".to_string(), 1u64),
+            ("c".to_string(), u64::MAX),
+        ];
+        let clusters = cluster_by_hamming(&entries, 10);
+        assert_eq!(clusters.len(), 1);
+        let mut cluster = clusters[0].clone();
+        cluster.sort();
+        assert_eq!(cluster, vec!["a".to_string(), "b".to_string()]);
+    }
+
+    #[test]
+    fn test_cluster_by_hamming_no_clusters_when_all_distinct() {
+        let entries = vec![("a".to_string(), 0u64), ("b".to_string(), u64::MAX)];
+        assert!(cluster_by_hamming(&entries, 10).is_empty());
+    }
+
+    #[test]
+    fn test_cluster_by_hamming_transitive_chain() {
+        let entries = vec![
+            ("a".to_string(), 0b0000u64),
+            ("b".to_string(), 0b0001u64),
+            ("c".to_string(), 0b0011u64),
+        ];
+        let clusters = cluster_by_hamming(&entries, 1);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].len(), 3);
+    }
+
+    #[test]
+    fn test_phash_resized_copy_is_near_duplicate() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path_a = tmp.path().join("full.png");
+        let path_b = tmp.path().join("resized.png");
+
+        let img = image::RgbImage::from_fn(256, 256, |x, y| {
+            if (x / 16 + y / 16) % 2 == 0 {
+                image::Rgb([230, 60, 30])
+            } else {
+                image::Rgb([20, 90, 200])
+            }
+        });
+        img.save(&path_a).unwrap();
+
+        let resized = image::imageops::resize(
+            &img,
+            96,
+            96,
+            image::imageops::FilterType::Lanczos3,
+        );
+        resized.save(&path_b).unwrap();
+
+        let hash_a = compute_phash(&path_a).unwrap();
+        let hash_b = compute_phash(&path_b).unwrap();
+        assert!(hamming_distance(hash_a, hash_b) <= PHASH_NEAR_DUPLICATE_THRESHOLD);
+    }
 }
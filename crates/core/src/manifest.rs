@@ -1,62 +1,139 @@
+use std::collections::HashSet;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use r2d2_sqlite::SqliteConnectionManager;
 use rusqlite::Connection;
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
 
-use crate::error::Result;
+use crate::error::{Error, Result};
+use crate::hasher::compute_sha256;
+use crate::tar_archive::{self, TarCompression};
+use crate::vault_save::object_path_for;
+
+/// Schema migrations, each upgrading the database *to* the given version.
+/// Applied in ascending order starting from `current_version + 1`, each
+/// inside its own transaction committed before moving to the next step, so
+/// a crash mid-migration leaves the database at a consistent (if behind)
+/// version rather than half-applied. Version 1 is the baseline schema
+/// created by `open`'s `CREATE TABLE IF NOT EXISTS` and isn't listed here.
+const MIGRATIONS: &[(u32, &str)] = &[
+    (2, "ALTER TABLE pack_files ADD COLUMN phash INTEGER"),
+    (
+        3,
+        "CREATE TABLE IF NOT EXISTS generations (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            started_at  TEXT NOT NULL,
+            finished_at TEXT,
+            label       TEXT
+        );
+
+        CREATE TABLE IF NOT EXISTS pack_file_events (
+            id            INTEGER PRIMARY KEY AUTOINCREMENT,
+            generation_id INTEGER NOT NULL REFERENCES generations(id),
+            sha256        TEXT NOT NULL,
+            reason        TEXT NOT NULL,
+            recorded_at   TEXT NOT NULL
+        );",
+    ),
+];
+
+/// Highest schema version this build knows how to read and migrate to.
+const CURRENT_VERSION: u32 = 3;
+
+/// Pooled connection handle returned by `Manifest::conn`.
+type PooledConnection = r2d2::PooledConnection<SqliteConnectionManager>;
 
 /// Embedded manifest stored inside the pack directory at `.photopack/manifest.sqlite`.
 /// Maps SHA-256 hashes to file metadata, enabling integrity verification and cleanup.
+///
+/// Backed by an r2d2 connection pool rather than a single `Connection`: WAL
+/// mode already lets SQLite serve concurrent readers, and pooling lets
+/// concurrent writers (e.g. a rayon-parallel import hashing many files at
+/// once) each check out their own connection instead of serializing behind
+/// one shared one.
 pub struct Manifest {
-    conn: Connection,
+    pool: r2d2::Pool<SqliteConnectionManager>,
+    pack_path: PathBuf,
 }
 
 impl Manifest {
     /// Open (or create) the manifest database inside `pack_path/.photopack/`.
-    /// Creates the `.photopack/` directory, `manifest.sqlite`, and `version` file.
+    /// Creates the `.photopack/` directory, `manifest.sqlite`, and `version` file,
+    /// then runs any pending migrations up to `CURRENT_VERSION`, and finally
+    /// builds the connection pool callers check out from.
     pub fn open(pack_path: &Path) -> Result<Self> {
         let meta_dir = pack_path.join(".photopack");
         fs::create_dir_all(&meta_dir)?;
 
         let db_path = meta_dir.join("manifest.sqlite");
-        let conn = Connection::open(&db_path)?;
-        conn.execute_batch("PRAGMA journal_mode = WAL;")?;
 
-        conn.execute_batch(
-            "CREATE TABLE IF NOT EXISTS metadata (
-                key   TEXT PRIMARY KEY,
-                value TEXT NOT NULL
-            );
+        // Schema creation and migrations run through a single plain
+        // connection, opened and dropped before the pool exists — letting
+        // several pooled connections race through "CREATE TABLE IF NOT
+        // EXISTS" and the migration ladder concurrently would be asking for
+        // trouble.
+        {
+            let mut conn = Connection::open(&db_path)?;
+            conn.execute_batch("PRAGMA journal_mode = WAL;")?;
 
-            CREATE TABLE IF NOT EXISTS pack_files (
-                sha256            TEXT PRIMARY KEY,
-                original_filename TEXT NOT NULL,
-                format            TEXT NOT NULL,
-                size              INTEGER NOT NULL,
-                exif_date         TEXT,
-                camera_make       TEXT,
-                camera_model      TEXT,
-                added_at          TEXT NOT NULL
-            );",
-        )?;
+            conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS metadata (
+                    key   TEXT PRIMARY KEY,
+                    value TEXT NOT NULL
+                );
 
-        // Seed version metadata if missing
-        conn.execute(
-            "INSERT OR IGNORE INTO metadata (key, value) VALUES ('version', '1')",
-            [],
-        )?;
-        conn.execute(
-            "INSERT OR IGNORE INTO metadata (key, value) VALUES ('created_at', datetime('now'))",
-            [],
-        )?;
+                CREATE TABLE IF NOT EXISTS pack_files (
+                    sha256            TEXT PRIMARY KEY,
+                    original_filename TEXT NOT NULL,
+                    format            TEXT NOT NULL,
+                    size              INTEGER NOT NULL,
+                    exif_date         TEXT,
+                    camera_make       TEXT,
+                    camera_model      TEXT,
+                    added_at          TEXT NOT NULL
+                );",
+            )?;
 
-        // Write version text file
-        fs::write(meta_dir.join("version"), "1")?;
+            // Seed version metadata if missing — a brand-new database starts at
+            // the baseline schema version and is migrated up below like any
+            // older pack would be.
+            conn.execute(
+                "INSERT OR IGNORE INTO metadata (key, value) VALUES ('version', '1')",
+                [],
+            )?;
+            conn.execute(
+                "INSERT OR IGNORE INTO metadata (key, value) VALUES ('created_at', datetime('now'))",
+                [],
+            )?;
 
-        Ok(Self { conn })
+            run_migrations(&mut conn, &meta_dir)?;
+        }
+
+        let manager = SqliteConnectionManager::file(&db_path)
+            .with_init(|conn| conn.execute_batch("PRAGMA journal_mode = WAL;"));
+        let pool = r2d2::Pool::new(manager)?;
+
+        Ok(Self {
+            pool,
+            pack_path: pack_path.to_path_buf(),
+        })
+    }
+
+    /// Check out a pooled connection. Every method below calls this once at
+    /// the top rather than holding a connection on `self`, since the pool —
+    /// not any single connection — is what lets concurrent callers work the
+    /// manifest at once.
+    fn conn(&self) -> Result<PooledConnection> {
+        Ok(self.pool.get()?)
     }
 
-    /// Insert or replace a pack file entry.
+    /// Insert or replace a pack file entry, recording a `pack_file_events` row
+    /// against `generation_id` explaining why. `phash` is the DCT perceptual
+    /// hash (see `hasher::perceptual::compute_phash`), stored so
+    /// near-duplicate clustering only ever compares hashes, not pixels.
     #[allow(clippy::too_many_arguments)]
     pub fn insert_file(
         &self,
@@ -67,19 +144,146 @@ impl Manifest {
         exif_date: Option<&str>,
         camera_make: Option<&str>,
         camera_model: Option<&str>,
+        phash: Option<u64>,
+        generation_id: GenerationId,
+        reason: IngestReason,
     ) -> Result<()> {
-        self.conn.execute(
+        let conn = self.conn()?;
+        conn.execute(
             "INSERT OR REPLACE INTO pack_files
-                (sha256, original_filename, format, size, exif_date, camera_make, camera_model, added_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, datetime('now'))",
-            rusqlite::params![sha256, original_filename, format, size as i64, exif_date, camera_make, camera_model],
+                (sha256, original_filename, format, size, exif_date, camera_make, camera_model, added_at, phash)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, datetime('now'), ?8)",
+            rusqlite::params![
+                sha256,
+                original_filename,
+                format,
+                size as i64,
+                exif_date,
+                camera_make,
+                camera_model,
+                phash.map(|h| h as i64),
+            ],
+        )?;
+        conn.execute(
+            "INSERT INTO pack_file_events (generation_id, sha256, reason, recorded_at)
+             VALUES (?1, ?2, ?3, datetime('now'))",
+            rusqlite::params![generation_id, sha256, reason.as_str()],
         )?;
         Ok(())
     }
 
+    /// Insert a batch of pack file entries, all logged against the same
+    /// `generation_id`, in a single transaction — amortizing commit cost
+    /// versus one `insert_file` call per file when ingesting many at once.
+    pub fn insert_files(&self, generation_id: GenerationId, entries: &[FileEntry]) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        for entry in entries {
+            tx.execute(
+                "INSERT OR REPLACE INTO pack_files
+                    (sha256, original_filename, format, size, exif_date, camera_make, camera_model, added_at, phash)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, datetime('now'), ?8)",
+                rusqlite::params![
+                    entry.sha256,
+                    entry.original_filename,
+                    entry.format,
+                    entry.size as i64,
+                    entry.exif_date,
+                    entry.camera_make,
+                    entry.camera_model,
+                    entry.phash.map(|h| h as i64),
+                ],
+            )?;
+            tx.execute(
+                "INSERT INTO pack_file_events (generation_id, sha256, reason, recorded_at)
+                 VALUES (?1, ?2, ?3, datetime('now'))",
+                rusqlite::params![generation_id, entry.sha256, entry.reason.as_str()],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Start a new generation — one ingest run — returning its id for
+    /// `insert_file` and, once the run completes, `finish_generation`.
+    /// `label` is a free-form note (e.g. the command that triggered it).
+    pub fn begin_generation(&self, label: Option<&str>) -> Result<GenerationId> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO generations (started_at, finished_at, label) VALUES (datetime('now'), NULL, ?1)",
+            rusqlite::params![label],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Mark a generation as finished.
+    pub fn finish_generation(&self, id: GenerationId) -> Result<()> {
+        self.conn()?.execute(
+            "UPDATE generations SET finished_at = datetime('now') WHERE id = ?1",
+            rusqlite::params![id],
+        )?;
+        Ok(())
+    }
+
+    /// List every `pack_file_events` row recorded under `id`, in the order
+    /// they were inserted.
+    pub fn list_generation(&self, id: GenerationId) -> Result<Vec<GenerationEvent>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT sha256, reason, recorded_at FROM pack_file_events
+             WHERE generation_id = ?1 ORDER BY id",
+        )?;
+        let events = stmt
+            .query_map([id], |row| {
+                Ok(GenerationEvent {
+                    sha256: row.get(0)?,
+                    reason: row.get(1)?,
+                    recorded_at: row.get(2)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(events)
+    }
+
+    /// Compare the SHA-256 hashes touched by generation `a` against those
+    /// touched by generation `b`: `added` appeared only in `b`, `removed`
+    /// only in `a`, `unchanged` in both. This compares *event history*, not
+    /// a full pack snapshot — `pack_files` only tracks current state, so a
+    /// hash can't be reconstructed as "present as of generation N" once a
+    /// later generation has removed it.
+    pub fn diff_generations(&self, a: GenerationId, b: GenerationId) -> Result<GenerationDiff> {
+        let conn = self.conn()?;
+        let hashes_in = |id: GenerationId| -> Result<HashSet<String>> {
+            let mut stmt = conn
+                .prepare("SELECT DISTINCT sha256 FROM pack_file_events WHERE generation_id = ?1")?;
+            let hashes = stmt
+                .query_map([id], |row| row.get::<_, String>(0))?
+                .filter_map(|r| r.ok())
+                .collect();
+            Ok(hashes)
+        };
+
+        let set_a = hashes_in(a)?;
+        let set_b = hashes_in(b)?;
+
+        let mut added: Vec<String> = set_b.difference(&set_a).cloned().collect();
+        let mut removed: Vec<String> = set_a.difference(&set_b).cloned().collect();
+        let mut unchanged: Vec<String> = set_a.intersection(&set_b).cloned().collect();
+        added.sort();
+        removed.sort();
+        unchanged.sort();
+
+        Ok(GenerationDiff {
+            added,
+            removed,
+            unchanged,
+        })
+    }
+
     /// Check if a SHA-256 hash exists in the manifest.
     pub fn contains(&self, sha256: &str) -> Result<bool> {
-        let count: i64 = self.conn.query_row(
+        let count: i64 = self.conn()?.query_row(
             "SELECT COUNT(*) FROM pack_files WHERE sha256 = ?1",
             [sha256],
             |row| row.get(0),
@@ -89,18 +293,27 @@ impl Manifest {
 
     /// Remove a pack file entry. Returns true if a row was deleted.
     pub fn remove(&self, sha256: &str) -> Result<bool> {
-        let deleted = self.conn.execute(
-            "DELETE FROM pack_files WHERE sha256 = ?1",
-            [sha256],
-        )?;
+        let deleted = self
+            .conn()?
+            .execute("DELETE FROM pack_files WHERE sha256 = ?1", [sha256])?;
         Ok(deleted > 0)
     }
 
     /// List all entries as `(sha256, format)` pairs.
     pub fn list_entries(&self) -> Result<Vec<(String, String)>> {
-        let mut stmt = self
-            .conn
-            .prepare("SELECT sha256, format FROM pack_files")?;
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare("SELECT sha256, format FROM pack_files")?;
+        let entries = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(entries)
+    }
+
+    /// List all entries as `(sha256, original_filename)` pairs.
+    pub fn list_filenames(&self) -> Result<Vec<(String, String)>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare("SELECT sha256, original_filename FROM pack_files")?;
         let entries = stmt
             .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
             .filter_map(|r| r.ok())
@@ -108,15 +321,477 @@ impl Manifest {
         Ok(entries)
     }
 
+    /// List `(sha256, phash)` pairs for entries that have a pHash recorded.
+    pub fn list_phashes(&self) -> Result<Vec<(String, u64)>> {
+        let conn = self.conn()?;
+        let mut stmt =
+            conn.prepare("SELECT sha256, phash FROM pack_files WHERE phash IS NOT NULL")?;
+        let entries = stmt
+            .query_map([], |row| {
+                let sha256: String = row.get(0)?;
+                let phash: i64 = row.get(1)?;
+                Ok((sha256, phash as u64))
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(entries)
+    }
+
+    /// Find clusters of near-duplicate photos: groups of SHA-256 hashes whose
+    /// pHash values are all within `threshold` Hamming distance of each other.
+    /// Singletons (no near-duplicate found) are not returned.
+    pub fn find_near_duplicate_clusters(&self, threshold: u32) -> Result<Vec<Vec<String>>> {
+        let entries = self.list_phashes()?;
+        Ok(crate::hasher::perceptual::cluster_by_hamming(
+            &entries, threshold,
+        ))
+    }
+
+    /// Filter `pack_files` by `filter`, translating its populated fields into
+    /// a parameterized `WHERE` clause rather than pulling every row into
+    /// memory and filtering in Rust — the catalog does the equivalent for
+    /// `photos` with its own `IN (...)` placeholder building, which this
+    /// mirrors. Returns full `FileRecord`s (every column) so a caller can
+    /// build a gallery or a selective restore directly from the result.
+    pub fn query(&self, filter: &ManifestQuery) -> Result<Vec<FileRecord>> {
+        let mut clauses: Vec<String> = Vec::new();
+        let mut params: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
+
+        if let Some(format) = &filter.format {
+            clauses.push(format!("format = ?{}", params.len() + 1));
+            params.push(Box::new(format.clone()));
+        }
+        if let Some(camera_make) = &filter.camera_make {
+            clauses.push(format!("camera_make = ?{}", params.len() + 1));
+            params.push(Box::new(camera_make.clone()));
+        }
+        if let Some(camera_model) = &filter.camera_model {
+            clauses.push(format!("camera_model = ?{}", params.len() + 1));
+            params.push(Box::new(camera_model.clone()));
+        }
+        if let Some(from) = &filter.exif_date_from {
+            clauses.push(format!("exif_date >= ?{}", params.len() + 1));
+            params.push(Box::new(from.clone()));
+        }
+        if let Some(to) = &filter.exif_date_to {
+            clauses.push(format!("exif_date <= ?{}", params.len() + 1));
+            params.push(Box::new(to.clone()));
+        }
+        if let Some(min) = filter.size_min {
+            clauses.push(format!("size >= ?{}", params.len() + 1));
+            params.push(Box::new(min as i64));
+        }
+        if let Some(max) = filter.size_max {
+            clauses.push(format!("size <= ?{}", params.len() + 1));
+            params.push(Box::new(max as i64));
+        }
+
+        let mut sql = String::from(
+            "SELECT sha256, original_filename, format, size, exif_date, camera_make, camera_model, added_at, phash
+             FROM pack_files",
+        );
+        if !clauses.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&clauses.join(" AND "));
+        }
+        if let Some(order_by) = filter.order_by {
+            sql.push_str(match order_by {
+                ManifestQueryOrder::ExifDateAsc => " ORDER BY exif_date ASC",
+                ManifestQueryOrder::ExifDateDesc => " ORDER BY exif_date DESC",
+                ManifestQueryOrder::SizeAsc => " ORDER BY size ASC",
+                ManifestQueryOrder::SizeDesc => " ORDER BY size DESC",
+            });
+        }
+        if let Some(limit) = filter.limit {
+            sql.push_str(&format!(" LIMIT {limit}"));
+            if let Some(offset) = filter.offset {
+                sql.push_str(&format!(" OFFSET {offset}"));
+            }
+        }
+
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&sql)?;
+        let param_refs: Vec<&dyn rusqlite::types::ToSql> =
+            params.iter().map(|p| p.as_ref()).collect();
+        let records = stmt
+            .query_map(param_refs.as_slice(), |row| {
+                let phash: Option<i64> = row.get(8)?;
+                Ok(FileRecord {
+                    sha256: row.get(0)?,
+                    original_filename: row.get(1)?,
+                    format: row.get(2)?,
+                    size: row.get::<_, i64>(3)? as u64,
+                    exif_date: row.get(4)?,
+                    camera_make: row.get(5)?,
+                    camera_model: row.get(6)?,
+                    added_at: row.get(7)?,
+                    phash: phash.map(|h| h as u64),
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(records)
+    }
+
     /// Get the manifest version string.
     pub fn version(&self) -> Result<String> {
-        let version: String = self.conn.query_row(
+        let version: String = self.conn()?.query_row(
             "SELECT value FROM metadata WHERE key = 'version'",
             [],
             |row| row.get(0),
         )?;
         Ok(version)
     }
+
+    /// Canonical digest over every `pack_files` row: sorted by `sha256` so
+    /// row order never affects the result, each row rendered as
+    /// `sha256|size|format`, newline-joined, then hashed with SHA-256. Both
+    /// `sign` and `verify_signature` hash this same representation, so
+    /// inserting, removing, or editing any entry changes the digest.
+    fn canonical_digest(&self) -> Result<[u8; 32]> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare("SELECT sha256, size, format FROM pack_files ORDER BY sha256")?;
+        let rows = stmt
+            .query_map([], |row| {
+                let sha256: String = row.get(0)?;
+                let size: i64 = row.get(1)?;
+                let format: String = row.get(2)?;
+                Ok((sha256, size, format))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let mut hasher = Sha256::new();
+        for (sha256, size, format) in &rows {
+            hasher.update(format!("{sha256}|{size}|{format}\n").as_bytes());
+        }
+        Ok(hasher.finalize().into())
+    }
+
+    /// Sign the manifest's canonical digest with `signing_key`, writing the
+    /// detached signature to `.photopack/manifest.sig` and the matching
+    /// public key to `.photopack/manifest.pub`, so a downstream consumer can
+    /// confirm the pack was produced by a trusted party — independent of
+    /// SQLite-level integrity, which only proves the file isn't corrupt.
+    pub fn sign(&self, signing_key: &SigningKey) -> Result<()> {
+        let digest = self.canonical_digest()?;
+        let signature = signing_key.sign(&digest);
+
+        let meta_dir = self.pack_path.join(".photopack");
+        fs::write(meta_dir.join("manifest.sig"), signature.to_bytes())?;
+        fs::write(
+            meta_dir.join("manifest.pub"),
+            signing_key.verifying_key().to_bytes(),
+        )?;
+        Ok(())
+    }
+
+    /// Recompute the canonical digest and check it against the detached
+    /// signature written by `sign`. Returns `Ok(false)` — not an error — for
+    /// a missing, truncated, or mismatched signature; only I/O failures
+    /// reading the manifest itself surface as `Err`.
+    pub fn verify_signature(&self, verifying_key: &VerifyingKey) -> Result<bool> {
+        let sig_path = self.pack_path.join(".photopack").join("manifest.sig");
+        let sig_bytes = match fs::read(&sig_path) {
+            Ok(bytes) => bytes,
+            Err(_) => return Ok(false),
+        };
+        let sig_bytes: [u8; 64] = match sig_bytes.try_into() {
+            Ok(bytes) => bytes,
+            Err(_) => return Ok(false),
+        };
+        let signature = Signature::from_bytes(&sig_bytes);
+
+        let digest = self.canonical_digest()?;
+        Ok(verifying_key.verify(&digest, &signature).is_ok())
+    }
+
+    /// Recompute the SHA-256 (and check the recorded `size`) of every object
+    /// a `pack_files` row points at under `pack_path`'s `objects/` tree (see
+    /// `vault_save::object_path_for`), and tally on-disk objects no entry
+    /// references. Turns the manifest from a passive index into an
+    /// auditable source of truth, independent of SQLite-level integrity
+    /// (which only proves the database file itself isn't corrupt).
+    pub fn verify(&self, pack_path: &Path) -> Result<VerifyReport> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare("SELECT sha256, size FROM pack_files")?;
+        let rows = stmt
+            .query_map([], |row| {
+                let sha256: String = row.get(0)?;
+                let size: i64 = row.get(1)?;
+                Ok((sha256, size))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let mut report = VerifyReport::default();
+        let mut known_hashes: HashSet<String> = HashSet::new();
+
+        for (sha256, size) in &rows {
+            known_hashes.insert(sha256.clone());
+            let object_path = object_path_for(pack_path, sha256);
+
+            match compute_sha256(&object_path) {
+                Ok(actual) if &actual == sha256 => {
+                    match fs::metadata(&object_path) {
+                        Ok(meta) if meta.len() == *size as u64 => report.ok += 1,
+                        _ => report.corrupt += 1,
+                    }
+                }
+                Ok(_) => report.corrupt += 1,
+                Err(_) => report.missing += 1,
+            }
+        }
+
+        let objects_dir = pack_path.join("objects");
+        if objects_dir.is_dir() {
+            for entry in WalkDir::new(&objects_dir).into_iter().filter_map(|e| e.ok()) {
+                if !entry.file_type().is_file() {
+                    continue;
+                }
+                let relative = entry
+                    .path()
+                    .strip_prefix(&objects_dir)
+                    .unwrap_or(entry.path());
+                let hash: String = relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect();
+                if !known_hashes.contains(&hash) {
+                    report.untracked += 1;
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Bundle `pack_path` (its object tree plus `.photopack/manifest.sqlite`)
+    /// into a single gzip tar at `dest`, so the whole pack can be moved or
+    /// backed up atomically as one file. See `import_snapshot` for the
+    /// reverse operation.
+    pub fn export_snapshot(pack_path: &Path, dest: &Path) -> Result<()> {
+        tar_archive::pack_vault_tar(pack_path, dest, TarCompression::Gzip, None)?;
+        Ok(())
+    }
+
+    /// Restore a snapshot written by `export_snapshot` into `dest_pack`.
+    /// Re-opens the manifest afterward, which runs the same migration and
+    /// `ManifestVersionTooNew` check any other `open` does — an archive from
+    /// a newer build is rejected rather than left half-imported on disk.
+    ///
+    /// `options.ignore_missing` turns a nonexistent `archive` into a no-op
+    /// instead of an error; `options.skip_if_populated` turns an already
+    /// populated `dest_pack` manifest into a no-op instead of overwriting it.
+    /// Both exist so callers can script this idempotently (e.g. a restore
+    /// step that only matters the first time a pack is set up).
+    pub fn import_snapshot(
+        archive: &Path,
+        dest_pack: &Path,
+        options: ImportSnapshotOptions,
+    ) -> Result<ImportSnapshotOutcome> {
+        if !archive.exists() {
+            if options.ignore_missing {
+                return Ok(ImportSnapshotOutcome::SkippedArchiveMissing);
+            }
+            return Err(Error::SnapshotArchiveNotFound(archive.to_path_buf()));
+        }
+
+        if options.skip_if_populated
+            && dest_pack.join(".photopack").join("manifest.sqlite").exists()
+        {
+            let existing = Manifest::open(dest_pack)?;
+            if !existing.list_entries()?.is_empty() {
+                return Ok(ImportSnapshotOutcome::SkippedAlreadyPopulated);
+            }
+        }
+
+        fs::create_dir_all(dest_pack)?;
+        tar_archive::unpack_vault_tar(archive, dest_pack, None)?;
+        Manifest::open(dest_pack)?;
+
+        Ok(ImportSnapshotOutcome::Imported)
+    }
+}
+
+/// A `generations` row id, returned by `Manifest::begin_generation`.
+pub type GenerationId = i64;
+
+/// One file to insert via `Manifest::insert_files`, mirroring `insert_file`'s
+/// parameters. `generation_id` is passed separately to `insert_files` rather
+/// than per-entry, since a single batch naturally shares one generation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileEntry {
+    pub sha256: String,
+    pub original_filename: String,
+    pub format: String,
+    pub size: u64,
+    pub exif_date: Option<String>,
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub phash: Option<u64>,
+    pub reason: IngestReason,
+}
+
+/// Filter criteria for `Manifest::query`. Every field is optional; an unset
+/// field places no constraint on the result. `order_by`/`limit`/`offset`
+/// support paginating a large pack instead of pulling every row into memory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ManifestQuery {
+    pub format: Option<String>,
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    /// Inclusive lower bound on `exif_date` (string comparison, so dates
+    /// should be in the same sortable format EXIF already stores them in).
+    pub exif_date_from: Option<String>,
+    /// Inclusive upper bound on `exif_date`.
+    pub exif_date_to: Option<String>,
+    pub size_min: Option<u64>,
+    pub size_max: Option<u64>,
+    pub order_by: Option<ManifestQueryOrder>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+/// Sort order for `Manifest::query` results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestQueryOrder {
+    ExifDateAsc,
+    ExifDateDesc,
+    SizeAsc,
+    SizeDesc,
+}
+
+/// A full `pack_files` row, returned by `Manifest::query`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileRecord {
+    pub sha256: String,
+    pub original_filename: String,
+    pub format: String,
+    pub size: u64,
+    pub exif_date: Option<String>,
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub added_at: String,
+    pub phash: Option<u64>,
+}
+
+/// Why a file entered the pack during a given generation, recorded per
+/// `pack_file_events` row so `Manifest::diff_generations` can explain not
+/// just *what* changed but *why*.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IngestReason {
+    /// First time this content has been seen.
+    New,
+    /// The entry already existed; this call refreshes its recorded metadata.
+    Changed,
+    /// The object already existed in the pack under a different name; this
+    /// file was linked to it rather than copied again.
+    DuplicateSkipped,
+    /// Ingested by explicit request despite being otherwise skippable.
+    Forced,
+}
+
+impl IngestReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IngestReason::New => "new",
+            IngestReason::Changed => "changed",
+            IngestReason::DuplicateSkipped => "duplicate-skipped",
+            IngestReason::Forced => "forced",
+        }
+    }
+}
+
+/// One `pack_file_events` row: a file touched during a generation, and why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenerationEvent {
+    pub sha256: String,
+    pub reason: String,
+    pub recorded_at: String,
+}
+
+/// Result of `Manifest::diff_generations`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GenerationDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub unchanged: Vec<String>,
+}
+
+/// Controls `Manifest::import_snapshot`'s handling of a destination that may
+/// already be populated, or an archive that may not exist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ImportSnapshotOptions {
+    /// Leave `dest_pack` untouched instead of overwriting it when its
+    /// manifest already has at least one entry.
+    pub skip_if_populated: bool,
+    /// Return `SkippedArchiveMissing` instead of erroring when `archive`
+    /// doesn't exist.
+    pub ignore_missing: bool,
+}
+
+/// What `Manifest::import_snapshot` actually did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportSnapshotOutcome {
+    /// The archive was extracted into `dest_pack`.
+    Imported,
+    /// Skipped: `dest_pack` already had a populated manifest.
+    SkippedAlreadyPopulated,
+    /// Skipped: the archive didn't exist.
+    SkippedArchiveMissing,
+}
+
+/// Result of `Manifest::verify`: counts of entries whose object matches on
+/// disk (`ok`), has no object file at all (`missing`), re-hashes or
+/// re-sizes differently than recorded (`corrupt`), and on-disk objects no
+/// manifest entry references (`untracked`).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct VerifyReport {
+    pub ok: usize,
+    pub missing: usize,
+    pub corrupt: usize,
+    pub untracked: usize,
+}
+
+/// Read the on-disk schema version, refuse to proceed if it's newer than
+/// `CURRENT_VERSION` (an old binary must never touch a newer pack), then
+/// apply each pending step from `MIGRATIONS` in order. Each step runs in its
+/// own transaction that bumps the `version` metadata row and rewrites
+/// `.photopack/version` before committing, so a crash mid-run leaves the
+/// database at a valid, fully-applied version rather than a torn one.
+fn run_migrations(conn: &mut Connection, meta_dir: &Path) -> Result<()> {
+    let on_disk: u32 = conn
+        .query_row(
+            "SELECT value FROM metadata WHERE key = 'version'",
+            [],
+            |row| row.get::<_, String>(0),
+        )?
+        .parse()
+        .unwrap_or(1);
+
+    if on_disk > CURRENT_VERSION {
+        return Err(Error::ManifestVersionTooNew {
+            on_disk,
+            supported: CURRENT_VERSION,
+        });
+    }
+
+    for &(target_version, sql) in MIGRATIONS {
+        if target_version <= on_disk {
+            continue;
+        }
+
+        let tx = conn.transaction()?;
+        tx.execute_batch(sql)?;
+        tx.execute(
+            "UPDATE metadata SET value = ?1 WHERE key = 'version'",
+            rusqlite::params![target_version.to_string()],
+        )?;
+        tx.commit()?;
+
+        fs::write(meta_dir.join("version"), target_version.to_string())?;
+    }
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -135,17 +810,21 @@ mod tests {
     fn test_manifest_version() {
         let tmp = tempfile::tempdir().unwrap();
         let manifest = Manifest::open(tmp.path()).unwrap();
-        assert_eq!(manifest.version().unwrap(), "1");
+        assert_eq!(manifest.version().unwrap(), "3");
     }
 
     #[test]
     fn test_manifest_insert_and_contains() {
         let tmp = tempfile::tempdir().unwrap();
         let manifest = Manifest::open(tmp.path()).unwrap();
+        let gen = manifest.begin_generation(None).unwrap();
 
         assert!(!manifest.contains("abc123").unwrap());
         manifest
-            .insert_file("abc123", "photo.jpg", "JPEG", 1024, None, None, None)
+            .insert_file(
+                "abc123", "photo.jpg", "JPEG", 1024, None, None, None, None, gen,
+                IngestReason::New,
+            )
             .unwrap();
         assert!(manifest.contains("abc123").unwrap());
     }
@@ -154,9 +833,13 @@ mod tests {
     fn test_manifest_remove() {
         let tmp = tempfile::tempdir().unwrap();
         let manifest = Manifest::open(tmp.path()).unwrap();
+        let gen = manifest.begin_generation(None).unwrap();
 
         manifest
-            .insert_file("abc123", "photo.jpg", "JPEG", 1024, None, None, None)
+            .insert_file(
+                "abc123", "photo.jpg", "JPEG", 1024, None, None, None, None, gen,
+                IngestReason::New,
+            )
             .unwrap();
         assert!(manifest.contains("abc123").unwrap());
 
@@ -173,12 +856,17 @@ mod tests {
     fn test_manifest_list_entries() {
         let tmp = tempfile::tempdir().unwrap();
         let manifest = Manifest::open(tmp.path()).unwrap();
+        let gen = manifest.begin_generation(None).unwrap();
 
         manifest
-            .insert_file("aaa", "a.jpg", "JPEG", 100, None, None, None)
+            .insert_file(
+                "aaa", "a.jpg", "JPEG", 100, None, None, None, None, gen, IngestReason::New,
+            )
             .unwrap();
         manifest
-            .insert_file("bbb", "b.cr2", "CR2", 200, None, None, None)
+            .insert_file(
+                "bbb", "b.cr2", "CR2", 200, None, None, None, None, gen, IngestReason::New,
+            )
             .unwrap();
 
         let entries = manifest.list_entries().unwrap();
@@ -194,9 +882,13 @@ mod tests {
     fn test_manifest_insert_idempotent() {
         let tmp = tempfile::tempdir().unwrap();
         let manifest = Manifest::open(tmp.path()).unwrap();
+        let gen = manifest.begin_generation(None).unwrap();
 
         manifest
-            .insert_file("abc123", "photo.jpg", "JPEG", 1024, None, None, None)
+            .insert_file(
+                "abc123", "photo.jpg", "JPEG", 1024, None, None, None, None, gen,
+                IngestReason::New,
+            )
             .unwrap();
         // Insert again with different metadata — should succeed (OR REPLACE)
         manifest
@@ -208,6 +900,9 @@ mod tests {
                 Some("2024-01-01"),
                 Some("Canon"),
                 Some("EOS R5"),
+                Some(42),
+                gen,
+                IngestReason::Changed,
             )
             .unwrap();
 
@@ -221,8 +916,8 @@ mod tests {
     fn test_manifest_tables_exist() {
         let tmp = tempfile::tempdir().unwrap();
         let manifest = Manifest::open(tmp.path()).unwrap();
-        let mut stmt = manifest
-            .conn
+        let conn = manifest.conn().unwrap();
+        let mut stmt = conn
             .prepare("SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name")
             .unwrap();
         let tables: Vec<String> = stmt
@@ -230,15 +925,18 @@ mod tests {
             .unwrap()
             .map(|r| r.unwrap())
             .collect();
-        assert_eq!(tables, vec!["metadata", "pack_files"]);
+        assert_eq!(
+            tables,
+            vec!["generations", "metadata", "pack_file_events", "pack_files"]
+        );
     }
 
     #[test]
     fn test_manifest_pack_files_columns() {
         let tmp = tempfile::tempdir().unwrap();
         let manifest = Manifest::open(tmp.path()).unwrap();
-        let mut stmt = manifest
-            .conn
+        let conn = manifest.conn().unwrap();
+        let mut stmt = conn
             .prepare("SELECT name FROM pragma_table_info('pack_files') ORDER BY cid")
             .unwrap();
         let columns: Vec<String> = stmt
@@ -250,7 +948,7 @@ mod tests {
             columns,
             vec![
                 "sha256", "original_filename", "format", "size",
-                "exif_date", "camera_make", "camera_model", "added_at",
+                "exif_date", "camera_make", "camera_model", "added_at", "phash",
             ]
         );
     }
@@ -260,8 +958,12 @@ mod tests {
         let tmp = tempfile::tempdir().unwrap();
         {
             let manifest = Manifest::open(tmp.path()).unwrap();
+            let gen = manifest.begin_generation(None).unwrap();
             manifest
-                .insert_file("abc123", "photo.jpg", "JPEG", 1024, None, None, None)
+                .insert_file(
+                    "abc123", "photo.jpg", "JPEG", 1024, None, None, None, None, gen,
+                    IngestReason::New,
+                )
                 .unwrap();
         }
         {
@@ -272,4 +974,674 @@ mod tests {
             assert_eq!(entries[0].0, "abc123");
         }
     }
+
+    // ── pHash / near-duplicate clustering ───────────────────────
+
+    #[test]
+    fn test_list_phashes_excludes_missing() {
+        let tmp = tempfile::tempdir().unwrap();
+        let manifest = Manifest::open(tmp.path()).unwrap();
+        let gen = manifest.begin_generation(None).unwrap();
+
+        manifest
+            .insert_file(
+                "aaa", "a.jpg", "JPEG", 100, None, None, None, Some(0b1010), gen,
+                IngestReason::New,
+            )
+            .unwrap();
+        manifest
+            .insert_file(
+                "bbb", "b.jpg", "JPEG", 100, None, None, None, None, gen, IngestReason::New,
+            )
+            .unwrap();
+
+        let phashes = manifest.list_phashes().unwrap();
+        assert_eq!(phashes.len(), 1);
+        assert_eq!(phashes[0], ("aaa".to_string(), 0b1010));
+    }
+
+    #[test]
+    fn test_find_near_duplicate_clusters_groups_close_hashes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let manifest = Manifest::open(tmp.path()).unwrap();
+        let gen = manifest.begin_generation(None).unwrap();
+
+        manifest
+            .insert_file(
+                "a", "a.jpg", "JPEG", 100, None, None, None, Some(0), gen, IngestReason::New,
+            )
+            .unwrap();
+        // within threshold of "a" (1 bit different)
+        manifest
+            .insert_file(
+                "b", "b.jpg", "JPEG", 100, None, None, None, Some(1), gen, IngestReason::New,
+            )
+            .unwrap();
+        // far from everything
+        manifest
+            .insert_file(
+                "c", "c.jpg", "JPEG", 100, None, None, None, Some(u64::MAX), gen,
+                IngestReason::New,
+            )
+            .unwrap();
+
+        let clusters = manifest.find_near_duplicate_clusters(10).unwrap();
+        assert_eq!(clusters.len(), 1);
+        let mut cluster = clusters[0].clone();
+        cluster.sort();
+        assert_eq!(cluster, vec!["a".to_string(), "b".to_string()]);
+    }
+
+    #[test]
+    fn test_find_near_duplicate_clusters_empty_when_all_distinct() {
+        let tmp = tempfile::tempdir().unwrap();
+        let manifest = Manifest::open(tmp.path()).unwrap();
+        let gen = manifest.begin_generation(None).unwrap();
+
+        manifest
+            .insert_file(
+                "a", "a.jpg", "JPEG", 100, None, None, None, Some(0), gen, IngestReason::New,
+            )
+            .unwrap();
+        manifest
+            .insert_file(
+                "b", "b.jpg", "JPEG", 100, None, None, None, Some(u64::MAX), gen,
+                IngestReason::New,
+            )
+            .unwrap();
+
+        let clusters = manifest.find_near_duplicate_clusters(10).unwrap();
+        assert!(clusters.is_empty());
+    }
+
+    #[test]
+    fn test_find_near_duplicate_clusters_transitive_chain() {
+        let tmp = tempfile::tempdir().unwrap();
+        let manifest = Manifest::open(tmp.path()).unwrap();
+        let gen = manifest.begin_generation(None).unwrap();
+
+        // a~b (distance 1), b~c (distance 1 from b, but far from a) — should merge into one cluster
+        manifest
+            .insert_file(
+                "a", "a.jpg", "JPEG", 100, None, None, None, Some(0b0000), gen,
+                IngestReason::New,
+            )
+            .unwrap();
+        manifest
+            .insert_file(
+                "b", "b.jpg", "JPEG", 100, None, None, None, Some(0b0001), gen,
+                IngestReason::New,
+            )
+            .unwrap();
+        manifest
+            .insert_file(
+                "c", "c.jpg", "JPEG", 100, None, None, None, Some(0b0011), gen,
+                IngestReason::New,
+            )
+            .unwrap();
+
+        let clusters = manifest.find_near_duplicate_clusters(1).unwrap();
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].len(), 3);
+    }
+
+    #[test]
+    fn test_manifest_add_phash_column_to_legacy_db() {
+        let tmp = tempfile::tempdir().unwrap();
+        let meta_dir = tmp.path().join(".photopack");
+        fs::create_dir_all(&meta_dir).unwrap();
+        let conn = Connection::open(meta_dir.join("manifest.sqlite")).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE pack_files (
+                sha256            TEXT PRIMARY KEY,
+                original_filename TEXT NOT NULL,
+                format            TEXT NOT NULL,
+                size              INTEGER NOT NULL,
+                exif_date         TEXT,
+                camera_make       TEXT,
+                camera_model      TEXT,
+                added_at          TEXT NOT NULL
+            );",
+        )
+        .unwrap();
+        drop(conn);
+
+        // Opening a legacy manifest (no phash column) should migrate it in place.
+        let manifest = Manifest::open(tmp.path()).unwrap();
+        let gen = manifest.begin_generation(None).unwrap();
+        manifest
+            .insert_file(
+                "abc", "a.jpg", "JPEG", 1, None, None, None, Some(7), gen, IngestReason::New,
+            )
+            .unwrap();
+        assert_eq!(manifest.list_phashes().unwrap(), vec![("abc".to_string(), 7)]);
+    }
+
+    #[test]
+    fn test_migration_updates_version_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let meta_dir = tmp.path().join(".photopack");
+        fs::create_dir_all(&meta_dir).unwrap();
+        let conn = Connection::open(meta_dir.join("manifest.sqlite")).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE pack_files (
+                sha256            TEXT PRIMARY KEY,
+                original_filename TEXT NOT NULL,
+                format            TEXT NOT NULL,
+                size              INTEGER NOT NULL,
+                exif_date         TEXT,
+                camera_make       TEXT,
+                camera_model      TEXT,
+                added_at          TEXT NOT NULL
+            );",
+        )
+        .unwrap();
+        drop(conn);
+
+        let manifest = Manifest::open(tmp.path()).unwrap();
+        assert_eq!(manifest.version().unwrap(), "3");
+        let version_file = fs::read_to_string(meta_dir.join("version")).unwrap();
+        assert_eq!(version_file, "3");
+    }
+
+    #[test]
+    fn test_reopening_an_up_to_date_manifest_does_not_reapply_migrations() {
+        let tmp = tempfile::tempdir().unwrap();
+        // Opening twice re-runs `run_migrations`; if the version-1->2 step
+        // (adding the phash column) or the version-2->3 step (adding the
+        // generations tables) were applied again, this would fail with
+        // "duplicate column name" or "table already exists".
+        let _first = Manifest::open(tmp.path()).unwrap();
+        let second = Manifest::open(tmp.path()).unwrap();
+        assert_eq!(second.version().unwrap(), "3");
+    }
+
+    #[test]
+    fn test_open_refuses_a_manifest_newer_than_this_build_supports() {
+        let tmp = tempfile::tempdir().unwrap();
+        {
+            let manifest = Manifest::open(tmp.path()).unwrap();
+            manifest
+                .conn()
+                .unwrap()
+                .execute(
+                    "UPDATE metadata SET value = '99' WHERE key = 'version'",
+                    [],
+                )
+                .unwrap();
+        }
+
+        let err = Manifest::open(tmp.path()).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::ManifestVersionTooNew { on_disk: 99, supported: CURRENT_VERSION }
+        ));
+    }
+
+    // ── sign / verify_signature ─────────────────────────────────
+
+    #[test]
+    fn test_sign_and_verify_signature_roundtrip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let manifest = Manifest::open(tmp.path()).unwrap();
+        let gen = manifest.begin_generation(None).unwrap();
+        manifest
+            .insert_file(
+                "abc123", "photo.jpg", "JPEG", 1024, None, None, None, None, gen,
+                IngestReason::New,
+            )
+            .unwrap();
+
+        let signing_key = SigningKey::from_bytes(&[7u8; 32]);
+        manifest.sign(&signing_key).unwrap();
+
+        assert!(manifest
+            .verify_signature(&signing_key.verifying_key())
+            .unwrap());
+    }
+
+    #[test]
+    fn test_verify_signature_fails_with_wrong_key() {
+        let tmp = tempfile::tempdir().unwrap();
+        let manifest = Manifest::open(tmp.path()).unwrap();
+        let gen = manifest.begin_generation(None).unwrap();
+        manifest
+            .insert_file(
+                "abc123", "photo.jpg", "JPEG", 1024, None, None, None, None, gen,
+                IngestReason::New,
+            )
+            .unwrap();
+
+        let signing_key = SigningKey::from_bytes(&[7u8; 32]);
+        manifest.sign(&signing_key).unwrap();
+
+        let other_key = SigningKey::from_bytes(&[9u8; 32]);
+        assert!(!manifest
+            .verify_signature(&other_key.verifying_key())
+            .unwrap());
+    }
+
+    #[test]
+    fn test_verify_signature_detects_tampering_after_signing() {
+        let tmp = tempfile::tempdir().unwrap();
+        let manifest = Manifest::open(tmp.path()).unwrap();
+        let gen = manifest.begin_generation(None).unwrap();
+        manifest
+            .insert_file(
+                "abc123", "photo.jpg", "JPEG", 1024, None, None, None, None, gen,
+                IngestReason::New,
+            )
+            .unwrap();
+
+        let signing_key = SigningKey::from_bytes(&[7u8; 32]);
+        manifest.sign(&signing_key).unwrap();
+
+        // Mutate the manifest after signing — the digest no longer matches.
+        manifest
+            .insert_file(
+                "def456", "other.jpg", "JPEG", 2048, None, None, None, None, gen,
+                IngestReason::New,
+            )
+            .unwrap();
+
+        assert!(!manifest
+            .verify_signature(&signing_key.verifying_key())
+            .unwrap());
+    }
+
+    #[test]
+    fn test_verify_signature_without_a_signature_file_returns_false() {
+        let tmp = tempfile::tempdir().unwrap();
+        let manifest = Manifest::open(tmp.path()).unwrap();
+        let signing_key = SigningKey::from_bytes(&[7u8; 32]);
+
+        assert!(!manifest
+            .verify_signature(&signing_key.verifying_key())
+            .unwrap());
+    }
+
+    // ── verify ───────────────────────────────────────────────────
+
+    /// Plants `content` at its object path (as `vault_save` would) and
+    /// records a matching manifest entry, mirroring the object-store layout
+    /// `Manifest::verify` audits against.
+    fn pack_with_object(content: &[u8]) -> (tempfile::TempDir, Manifest, String) {
+        let tmp = tempfile::tempdir().unwrap();
+        let scratch = tmp.path().join("scratch.bin");
+        fs::write(&scratch, content).unwrap();
+        let sha256 = crate::hasher::compute_sha256(&scratch).unwrap();
+        fs::remove_file(&scratch).unwrap();
+
+        let manifest = Manifest::open(tmp.path()).unwrap();
+        let gen = manifest.begin_generation(None).unwrap();
+        let object_path = object_path_for(tmp.path(), &sha256);
+        fs::create_dir_all(object_path.parent().unwrap()).unwrap();
+        fs::write(&object_path, content).unwrap();
+        manifest
+            .insert_file(
+                &sha256,
+                "photo.jpg",
+                "JPEG",
+                content.len() as u64,
+                None,
+                None,
+                None,
+                None,
+                gen,
+                IngestReason::New,
+            )
+            .unwrap();
+        (tmp, manifest, sha256)
+    }
+
+    #[test]
+    fn test_verify_all_ok() {
+        let (tmp, manifest, _) = pack_with_object(b"hello pack");
+        let report = manifest.verify(tmp.path()).unwrap();
+        assert_eq!(
+            report,
+            VerifyReport {
+                ok: 1,
+                missing: 0,
+                corrupt: 0,
+                untracked: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_verify_detects_missing_object() {
+        let (tmp, manifest, sha256) = pack_with_object(b"hello pack");
+        fs::remove_file(object_path_for(tmp.path(), &sha256)).unwrap();
+
+        let report = manifest.verify(tmp.path()).unwrap();
+        assert_eq!(report.ok, 0);
+        assert_eq!(report.missing, 1);
+    }
+
+    #[test]
+    fn test_verify_detects_corrupt_object() {
+        let (tmp, manifest, sha256) = pack_with_object(b"hello pack");
+        fs::write(object_path_for(tmp.path(), &sha256), b"tampered bytes").unwrap();
+
+        let report = manifest.verify(tmp.path()).unwrap();
+        assert_eq!(report.ok, 0);
+        assert_eq!(report.corrupt, 1);
+    }
+
+    #[test]
+    fn test_verify_detects_untracked_object() {
+        let (tmp, manifest, _) = pack_with_object(b"hello pack");
+        let scratch = tmp.path().join("scratch2.bin");
+        fs::write(&scratch, b"untracked content").unwrap();
+        let untracked_sha = crate::hasher::compute_sha256(&scratch).unwrap();
+        let untracked_path = object_path_for(tmp.path(), &untracked_sha);
+        fs::create_dir_all(untracked_path.parent().unwrap()).unwrap();
+        fs::write(&untracked_path, b"untracked content").unwrap();
+
+        let report = manifest.verify(tmp.path()).unwrap();
+        assert_eq!(report.ok, 1);
+        assert_eq!(report.untracked, 1);
+    }
+
+    // ── export_snapshot / import_snapshot ───────────────────────
+
+    #[test]
+    fn test_export_then_import_snapshot_roundtrip() {
+        let (pack, _manifest, sha256) = pack_with_object(b"snapshot me");
+
+        let archive_dir = tempfile::tempdir().unwrap();
+        let archive_path = archive_dir.path().join("pack.tar.gz");
+        Manifest::export_snapshot(pack.path(), &archive_path).unwrap();
+
+        let dest = tempfile::tempdir().unwrap();
+        let outcome = Manifest::import_snapshot(
+            &archive_path,
+            dest.path(),
+            ImportSnapshotOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(outcome, ImportSnapshotOutcome::Imported);
+
+        let restored = Manifest::open(dest.path()).unwrap();
+        assert!(restored.contains(&sha256).unwrap());
+        assert!(fs::metadata(object_path_for(dest.path(), &sha256)).is_ok());
+    }
+
+    #[test]
+    fn test_import_snapshot_ignores_missing_archive() {
+        let dest = tempfile::tempdir().unwrap();
+        let outcome = Manifest::import_snapshot(
+            &dest.path().join("nonexistent.tar.gz"),
+            dest.path(),
+            ImportSnapshotOptions {
+                ignore_missing: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(outcome, ImportSnapshotOutcome::SkippedArchiveMissing);
+    }
+
+    #[test]
+    fn test_import_snapshot_errors_on_missing_archive_by_default() {
+        let dest = tempfile::tempdir().unwrap();
+        let result = Manifest::import_snapshot(
+            &dest.path().join("nonexistent.tar.gz"),
+            dest.path(),
+            ImportSnapshotOptions::default(),
+        );
+        assert!(matches!(result, Err(Error::SnapshotArchiveNotFound(_))));
+    }
+
+    #[test]
+    fn test_import_snapshot_skips_already_populated_destination() {
+        let (pack, _manifest, _) = pack_with_object(b"snapshot me");
+        let archive_dir = tempfile::tempdir().unwrap();
+        let archive_path = archive_dir.path().join("pack.tar.gz");
+        Manifest::export_snapshot(pack.path(), &archive_path).unwrap();
+
+        let (dest, dest_manifest, dest_sha256) = pack_with_object(b"already here");
+
+        let outcome = Manifest::import_snapshot(
+            &archive_path,
+            dest.path(),
+            ImportSnapshotOptions {
+                skip_if_populated: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(outcome, ImportSnapshotOutcome::SkippedAlreadyPopulated);
+        assert!(dest_manifest.contains(&dest_sha256).unwrap());
+    }
+
+    // ── generations ──────────────────────────────────────────────
+
+    #[test]
+    fn test_insert_file_records_a_pack_file_event() {
+        let tmp = tempfile::tempdir().unwrap();
+        let manifest = Manifest::open(tmp.path()).unwrap();
+        let gen = manifest.begin_generation(Some("initial import")).unwrap();
+        manifest
+            .insert_file(
+                "abc123", "photo.jpg", "JPEG", 1024, None, None, None, None, gen,
+                IngestReason::New,
+            )
+            .unwrap();
+
+        let events = manifest.list_generation(gen).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].sha256, "abc123");
+        assert_eq!(events[0].reason, "new");
+    }
+
+    #[test]
+    fn test_list_generation_is_scoped_to_its_own_generation() {
+        let tmp = tempfile::tempdir().unwrap();
+        let manifest = Manifest::open(tmp.path()).unwrap();
+
+        let gen_a = manifest.begin_generation(None).unwrap();
+        manifest
+            .insert_file(
+                "a", "a.jpg", "JPEG", 1, None, None, None, None, gen_a, IngestReason::New,
+            )
+            .unwrap();
+
+        let gen_b = manifest.begin_generation(None).unwrap();
+        manifest
+            .insert_file(
+                "b", "b.jpg", "JPEG", 1, None, None, None, None, gen_b, IngestReason::New,
+            )
+            .unwrap();
+
+        assert_eq!(manifest.list_generation(gen_a).unwrap().len(), 1);
+        assert_eq!(manifest.list_generation(gen_b).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_finish_generation_sets_finished_at() {
+        let tmp = tempfile::tempdir().unwrap();
+        let manifest = Manifest::open(tmp.path()).unwrap();
+        let gen = manifest.begin_generation(None).unwrap();
+
+        let finished_before: Option<String> = manifest
+            .conn()
+            .unwrap()
+            .query_row(
+                "SELECT finished_at FROM generations WHERE id = ?1",
+                [gen],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(finished_before.is_none());
+
+        manifest.finish_generation(gen).unwrap();
+
+        let finished_after: Option<String> = manifest
+            .conn()
+            .unwrap()
+            .query_row(
+                "SELECT finished_at FROM generations WHERE id = ?1",
+                [gen],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(finished_after.is_some());
+    }
+
+    #[test]
+    fn test_diff_generations_reports_added_removed_and_unchanged() {
+        let tmp = tempfile::tempdir().unwrap();
+        let manifest = Manifest::open(tmp.path()).unwrap();
+
+        let gen_a = manifest.begin_generation(None).unwrap();
+        manifest
+            .insert_file(
+                "kept", "kept.jpg", "JPEG", 1, None, None, None, None, gen_a, IngestReason::New,
+            )
+            .unwrap();
+        manifest
+            .insert_file(
+                "dropped", "dropped.jpg", "JPEG", 1, None, None, None, None, gen_a,
+                IngestReason::New,
+            )
+            .unwrap();
+
+        let gen_b = manifest.begin_generation(None).unwrap();
+        manifest
+            .insert_file(
+                "kept", "kept.jpg", "JPEG", 1, None, None, None, None, gen_b,
+                IngestReason::Changed,
+            )
+            .unwrap();
+        manifest
+            .insert_file(
+                "fresh", "fresh.jpg", "JPEG", 1, None, None, None, None, gen_b,
+                IngestReason::New,
+            )
+            .unwrap();
+
+        let diff = manifest.diff_generations(gen_a, gen_b).unwrap();
+        assert_eq!(diff.added, vec!["fresh".to_string()]);
+        assert_eq!(diff.removed, vec!["dropped".to_string()]);
+        assert_eq!(diff.unchanged, vec!["kept".to_string()]);
+    }
+
+    #[test]
+    fn test_insert_files_batch_inserts_all_entries_under_one_generation() {
+        let tmp = tempfile::tempdir().unwrap();
+        let manifest = Manifest::open(tmp.path()).unwrap();
+        let gen = manifest.begin_generation(None).unwrap();
+
+        let entries = vec![
+            FileEntry {
+                sha256: "aaa".to_string(),
+                original_filename: "a.jpg".to_string(),
+                format: "JPEG".to_string(),
+                size: 100,
+                exif_date: None,
+                camera_make: None,
+                camera_model: None,
+                phash: Some(0b1010),
+                reason: IngestReason::New,
+            },
+            FileEntry {
+                sha256: "bbb".to_string(),
+                original_filename: "b.jpg".to_string(),
+                format: "JPEG".to_string(),
+                size: 200,
+                exif_date: None,
+                camera_make: None,
+                camera_model: None,
+                phash: None,
+                reason: IngestReason::DuplicateSkipped,
+            },
+        ];
+        manifest.insert_files(gen, &entries).unwrap();
+
+        assert!(manifest.contains("aaa").unwrap());
+        assert!(manifest.contains("bbb").unwrap());
+
+        let events = manifest.list_generation(gen).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].reason, "duplicate-skipped");
+    }
+
+    // ── query ────────────────────────────────────────────────────
+
+    fn manifest_with_catalog() -> (tempfile::TempDir, Manifest) {
+        let tmp = tempfile::tempdir().unwrap();
+        let manifest = Manifest::open(tmp.path()).unwrap();
+        let gen = manifest.begin_generation(None).unwrap();
+        manifest
+            .insert_file(
+                "raw1", "img1.cr2", "CR2", 30_000_000, Some("2024-03-01"), Some("Canon"),
+                Some("EOS R5"), None, gen, IngestReason::New,
+            )
+            .unwrap();
+        manifest
+            .insert_file(
+                "raw2", "img2.cr2", "CR2", 32_000_000, Some("2024-06-15"), Some("Canon"),
+                Some("EOS R5"), None, gen, IngestReason::New,
+            )
+            .unwrap();
+        manifest
+            .insert_file(
+                "jpeg1", "img3.jpg", "JPEG", 4_000_000, Some("2023-12-25"), Some("Nikon"),
+                Some("Z9"), None, gen, IngestReason::New,
+            )
+            .unwrap();
+        (tmp, manifest)
+    }
+
+    #[test]
+    fn test_query_filters_by_format() {
+        let (_tmp, manifest) = manifest_with_catalog();
+        let records = manifest
+            .query(&ManifestQuery {
+                format: Some("CR2".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(records.len(), 2);
+        assert!(records.iter().all(|r| r.format == "CR2"));
+    }
+
+    #[test]
+    fn test_query_filters_by_camera_and_date_range() {
+        let (_tmp, manifest) = manifest_with_catalog();
+        let records = manifest
+            .query(&ManifestQuery {
+                camera_make: Some("Canon".to_string()),
+                exif_date_from: Some("2024-01-01".to_string()),
+                exif_date_to: Some("2024-12-31".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        let mut shas: Vec<&str> = records.iter().map(|r| r.sha256.as_str()).collect();
+        shas.sort();
+        assert_eq!(shas, vec!["raw1", "raw2"]);
+    }
+
+    #[test]
+    fn test_query_orders_and_paginates() {
+        let (_tmp, manifest) = manifest_with_catalog();
+        let records = manifest
+            .query(&ManifestQuery {
+                order_by: Some(ManifestQueryOrder::SizeDesc),
+                limit: Some(1),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].sha256, "raw2");
+    }
+
+    #[test]
+    fn test_query_with_no_filter_returns_everything() {
+        let (_tmp, manifest) = manifest_with_catalog();
+        let records = manifest.query(&ManifestQuery::default()).unwrap();
+        assert_eq!(records.len(), 3);
+    }
 }
@@ -1,4 +1,5 @@
 use crate::domain::Confidence;
+use crate::hasher::perceptual::HashAlg;
 
 /// Perceptual hash Hamming distance thresholds (for 64-bit hashes).
 /// Super-safe thresholds: true cross-format duplicates (RAW↔JPEG of the SAME photo)
@@ -8,19 +9,330 @@ pub const PHASH_NEAR_CERTAIN_THRESHOLD: u32 = 2;
 pub const PHASH_HIGH_THRESHOLD: u32 = 2;
 pub const PHASH_PROBABLE_THRESHOLD: u32 = 3;
 
-/// Determine confidence from a perceptual hash Hamming distance.
+/// Thresholds for the phash slot specifically when it holds a DCT hash
+/// (`HashAlg::Dct`) instead of aHash/dHash/blockhash. The DCT hash's 32x32
+/// low-frequency block tolerates scaling, brightness shifts, and JPEG
+/// requantization at distances that would be false positives for the other
+/// algorithms, so true duplicates typically land at distance 5-10 rather
+/// than 0-2 — these thresholds only ever apply to the phash slot itself;
+/// dHash/aHash stay on the tighter constants above regardless of which
+/// algorithm produced phash.
+pub const PHASH_DCT_NEAR_CERTAIN_THRESHOLD: u32 = 6;
+pub const PHASH_DCT_HIGH_THRESHOLD: u32 = 8;
+
+/// Runtime-configurable Hamming-distance thresholds for confidence banding —
+/// see `MatchingConfig::phash_confidence`/`confidence_from_hamming`. Replaces
+/// reading the fixed `PHASH_*` constants directly so a user with a library of
+/// scanned film or heavily edited exports can trade precision for recall (via
+/// `--near-certain`/`--high`/`--probable` on `lsvault sources scan`, persisted
+/// so a later scan reproduces the same grouping) without recompiling.
+///
+/// `near_certain_threshold`/`high_threshold` are `None` by default, meaning
+/// "use the algorithm-appropriate default" — the phash slot gets the
+/// DCT-aware bands when `phash_alg` is `HashAlg::Dct`, dHash/aHash always get
+/// the tight, zero-false-positive constants (dHash/aHash are never DCT, so
+/// they'd otherwise inherit a band sized for a hash they don't use — see
+/// `phash_confidence`/`confidence_from_hamming`). Once the user explicitly
+/// sets one of these fields (`Some(n)`), it overrides phash, dHash, and aHash
+/// alike, per the CLI flags' own framing of a single number for "how strict."
+/// `probable_threshold` widens or narrows the pure-phash matching phase's
+/// (`group_by_perceptual_hash`) outer cutoff — and, correspondingly, the
+/// `Probable` band's upper edge.
+///
+/// `phash_alg` mirrors the `HashAlg` the catalog's `phash` column was
+/// computed with (see `Vault::hash_alg`) — it only feeds the phash slot's
+/// default threshold selection; it has no effect on dHash/aHash, and no
+/// effect on phash either once `near_certain_threshold`/`high_threshold` are
+/// overridden by hand.
+///
+/// `required_votes` overrides Phase 3's N-of-M hash consensus (see
+/// `group_by_perceptual_hash`): `None` keeps the default rule (both present
+/// hashes must agree when only 2 are available, 2-of-3 once aHash joins
+/// dHash/pHash). `Some(n)` requires exactly `n` of the available hashes to
+/// independently fall within threshold, regardless of how many are present —
+/// e.g. `Some(1)` accepts any single agreeing hash, `Some(3)` demands all
+/// three agree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchingConfig {
+    pub near_certain_threshold: Option<u32>,
+    pub high_threshold: Option<u32>,
+    pub probable_threshold: u32,
+    pub phash_alg: HashAlg,
+    pub required_votes: Option<u32>,
+}
+
+impl Default for MatchingConfig {
+    fn default() -> Self {
+        Self::for_alg(HashAlg::default())
+    }
+}
+
+impl MatchingConfig {
+    /// Build a config for `alg`'s phash slot, with no user override yet — see
+    /// the struct doc comment for how `near_certain_threshold`/`high_threshold`
+    /// behave while unset. Start from this (rather than `Default::default()`,
+    /// which assumes `HashAlg::default()`) whenever the caller knows which
+    /// algorithm actually produced the stored phash — e.g. `Vault::scan`.
+    pub fn for_alg(alg: HashAlg) -> Self {
+        Self {
+            near_certain_threshold: None,
+            high_threshold: None,
+            probable_threshold: PHASH_PROBABLE_THRESHOLD,
+            phash_alg: alg,
+            required_votes: None,
+        }
+    }
+
+    /// Default thresholds for this config's phash slot: the DCT hash's 32x32
+    /// low-frequency block tolerates scaling, brightness shifts, and JPEG
+    /// requantization at distances (5-10) that would be false positives for
+    /// the other algorithms (0-2), so it gets its own, looser near-certain/
+    /// high bands. Any other algorithm uses the fixed, zero-false-positive
+    /// constants.
+    fn phash_defaults(&self) -> (u32, u32) {
+        match self.phash_alg {
+            HashAlg::Dct => (PHASH_DCT_NEAR_CERTAIN_THRESHOLD, PHASH_DCT_HIGH_THRESHOLD),
+            _ => (PHASH_NEAR_CERTAIN_THRESHOLD, PHASH_HIGH_THRESHOLD),
+        }
+    }
+
+    /// Determine confidence from a phash Hamming distance using this config's
+    /// thresholds: a user override if one was set, otherwise the
+    /// algorithm-appropriate default (see `phash_defaults`). Use this for the
+    /// phash slot specifically — see `confidence_from_hamming` for dHash/aHash.
+    pub fn phash_confidence(&self, distance: u32) -> Option<Confidence> {
+        let (default_near_certain, default_high) = self.phash_defaults();
+        confidence_with_thresholds(
+            distance,
+            self.near_certain_threshold.unwrap_or(default_near_certain),
+            self.high_threshold.unwrap_or(default_high),
+            self.probable_threshold,
+        )
+    }
+
+    /// Determine confidence from a dHash/aHash Hamming distance using this
+    /// config's thresholds: a user override if one was set, otherwise the
+    /// fixed `PHASH_NEAR_CERTAIN_THRESHOLD`/`PHASH_HIGH_THRESHOLD` constants —
+    /// dHash/aHash are never DCT-based, so `phash_alg`'s default never applies
+    /// here regardless of which algorithm produced the phash slot.
+    pub fn confidence_from_hamming(&self, distance: u32) -> Option<Confidence> {
+        confidence_with_thresholds(
+            distance,
+            self.near_certain_threshold.unwrap_or(PHASH_NEAR_CERTAIN_THRESHOLD),
+            self.high_threshold.unwrap_or(PHASH_HIGH_THRESHOLD),
+            self.probable_threshold,
+        )
+    }
+
+    /// This config's resolved phash `high_threshold` (override, or the
+    /// algorithm-appropriate default) — for call sites that need the raw
+    /// number rather than a banded `Confidence`, e.g. the cross-format
+    /// "phash is the only corroborating hash" branch in
+    /// `group_by_perceptual_hash`.
+    pub fn phash_high_threshold(&self) -> u32 {
+        self.high_threshold.unwrap_or(self.phash_defaults().1)
+    }
+
+    /// This config's resolved phash `near_certain_threshold` (override, or
+    /// the algorithm-appropriate default) — for call sites that need the raw
+    /// number rather than a banded `Confidence`, e.g. `hash_votes`'
+    /// `phash_threshold` argument in `validate_with_perceptual_hash`.
+    pub fn phash_near_certain_threshold(&self) -> u32 {
+        self.near_certain_threshold.unwrap_or(self.phash_defaults().0)
+    }
+
+    /// This config's resolved dHash/aHash `near_certain_threshold` (override,
+    /// or the fixed constant) — for call sites that need the raw number
+    /// rather than a banded `Confidence`, e.g. `hash_votes`' `other_threshold`
+    /// argument in `validate_with_perceptual_hash`.
+    pub fn other_near_certain_threshold(&self) -> u32 {
+        self.near_certain_threshold.unwrap_or(PHASH_NEAR_CERTAIN_THRESHOLD)
+    }
+}
+
+/// Determine confidence from a perceptual hash Hamming distance, using the
+/// default (fixed) thresholds. See `MatchingConfig::confidence_from_hamming`
+/// for a version that honors user-configured thresholds.
 pub fn confidence_from_hamming(distance: u32) -> Option<Confidence> {
-    if distance <= PHASH_NEAR_CERTAIN_THRESHOLD {
+    MatchingConfig::default().confidence_from_hamming(distance)
+}
+
+/// Free-function form of `MatchingConfig::confidence_from_hamming`, for call
+/// sites that don't otherwise need the config object in scope. Used for
+/// dHash/aHash comparisons only — see `phash_confidence_with_config` for the
+/// phash-specific counterpart, which defaults to a different (DCT-aware) band.
+pub fn confidence_from_hamming_with_config(
+    distance: u32,
+    config: &MatchingConfig,
+) -> Option<Confidence> {
+    config.confidence_from_hamming(distance)
+}
+
+/// Free-function form of `MatchingConfig::phash_confidence`, for call sites
+/// that don't otherwise need the config object in scope. Use for the phash
+/// slot specifically — unlike `confidence_from_hamming_with_config`, this
+/// defaults to the wider DCT bands when `config.phash_alg` is `HashAlg::Dct`.
+pub fn phash_confidence_with_config(distance: u32, config: &MatchingConfig) -> Option<Confidence> {
+    config.phash_confidence(distance)
+}
+
+fn confidence_with_thresholds(
+    distance: u32,
+    near_certain: u32,
+    high: u32,
+    probable: u32,
+) -> Option<Confidence> {
+    if distance <= near_certain {
         Some(Confidence::NearCertain)
-    } else if distance <= PHASH_HIGH_THRESHOLD {
+    } else if distance <= high {
         Some(Confidence::High)
-    } else if distance <= PHASH_PROBABLE_THRESHOLD {
+    } else if distance <= probable {
         Some(Confidence::Probable)
     } else {
         None
     }
 }
 
+/// Friendly presets over `MatchingConfig::probable_threshold`, for callers
+/// who'd rather pick a named tolerance than reason about raw Hamming
+/// distances. See `threshold_for_bits`.
+///
+/// Ordered loosest-tolerance-last: `Minimal` barely widens past the fixed
+/// zero-false-positive bands, `Maximum` is tuned for aggressively cropped,
+/// gamma-shifted, or re-compressed near-duplicates where more false
+/// positives are an acceptable trade for fewer missed matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimilarityLevel {
+    Minimal,
+    Low,
+    Medium,
+    High,
+    VeryHigh,
+    Maximum,
+}
+
+impl SimilarityLevel {
+    /// Default `probable_threshold` for this level at a given hash bit-size.
+    /// A fixed Hamming distance means something different at 8 bits than at
+    /// 64 — the same strictness needs a narrower budget on a smaller hash,
+    /// since each bit carries more weight. Only 64-bit hashes are wired up
+    /// today (`Vault::set_hash_size` rejects anything else), but the table
+    /// is keyed by bit-size so it needs no changes once that widens.
+    pub fn threshold_for_bits(self, hash_bits: u32) -> u32 {
+        let row: [u32; 6] = match hash_bits {
+            0..=8 => [1, 2, 5, 7, 14, 20],
+            9..=16 => [2, 5, 15, 30, 40, 40],
+            17..=32 => [4, 10, 20, 40, 40, 40],
+            _ => [6, 20, 40, 40, 40, 40],
+        };
+        match self {
+            SimilarityLevel::Minimal => row[0],
+            SimilarityLevel::Low => row[1],
+            SimilarityLevel::Medium => row[2],
+            SimilarityLevel::High => row[3],
+            SimilarityLevel::VeryHigh => row[4],
+            SimilarityLevel::Maximum => row[5],
+        }
+    }
+
+    /// Friendly label for CLI output — see `Vault::similarity_level`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SimilarityLevel::Minimal => "Minimal",
+            SimilarityLevel::Low => "Low",
+            SimilarityLevel::Medium => "Medium",
+            SimilarityLevel::High => "High",
+            SimilarityLevel::VeryHigh => "Very High",
+            SimilarityLevel::Maximum => "Maximum",
+        }
+    }
+
+    /// Reverse of `threshold_for_bits`: the named level whose preset exactly
+    /// matches `threshold` at `hash_bits`, or `None` if the configured
+    /// threshold doesn't line up with any preset — e.g. it was set directly
+    /// via `Vault::set_similarity_threshold` rather than a named level.
+    pub fn from_threshold_for_bits(threshold: u32, hash_bits: u32) -> Option<Self> {
+        [
+            SimilarityLevel::Minimal,
+            SimilarityLevel::Low,
+            SimilarityLevel::Medium,
+            SimilarityLevel::High,
+            SimilarityLevel::VeryHigh,
+            SimilarityLevel::Maximum,
+        ]
+        .into_iter()
+        .find(|level| level.threshold_for_bits(hash_bits) == threshold)
+    }
+}
+
+/// Per-hash-size Hamming distance cutoffs for `Confidence::from_evidence`:
+/// `[near_certain, high, probable]` inclusive cutoffs, analogous to
+/// `SimilarityLevel::threshold_for_bits`'s staged table — a fixed distance
+/// carries more weight on a smaller hash, so the bands widen with
+/// `hash_bits`. Anything past `probable` falls through to `Low`.
+fn confidence_bands_for_bits(hash_bits: u32) -> [u32; 3] {
+    match hash_bits {
+        0..=8 => [0, 1, 2],
+        9..=16 => [1, 2, 5],
+        17..=32 => [2, 4, 10],
+        33..=64 => [2, 4, 8],
+        _ => [4, 8, 16],
+    }
+}
+
+/// `confidence_from_hamming`, but scaled to `hash_bits` instead of assuming
+/// the fixed 64-bit `PHASH_*_THRESHOLD` constants — the same distance means
+/// something different on an 8-bit hash than a 256-bit one, so a caller
+/// comparing hashes of a non-default width (once `hasher::perceptual`
+/// produces one — see `hamming_distance_bytes`) should use this instead.
+/// Shares `confidence_bands_for_bits`'s staged table with `Confidence::from_evidence`.
+pub fn confidence_from_hamming_for_bits(distance: u32, hash_bits: u32) -> Option<Confidence> {
+    let [near_certain, high, probable] = confidence_bands_for_bits(hash_bits);
+    confidence_with_thresholds(distance, near_certain, high, probable)
+}
+
+/// One step up `Confidence`'s ladder — `Certain`/`NearCertain` are already at
+/// the top, so they're left alone. See `Confidence::from_evidence`.
+fn tighten_one_band(confidence: Confidence) -> Confidence {
+    match confidence {
+        Confidence::Low => Confidence::Probable,
+        Confidence::Probable => Confidence::High,
+        Confidence::High => Confidence::NearCertain,
+        Confidence::NearCertain => Confidence::NearCertain,
+        Confidence::Certain => Confidence::Certain,
+    }
+}
+
+impl Confidence {
+    /// Derive a `Confidence` from the actual matching evidence instead of a
+    /// caller-picked label. An exact SHA-256 match is always `Certain`;
+    /// otherwise the minimum Hamming distance among the group's members maps
+    /// onto `confidence_bands_for_bits`' staged cutoffs for `hash_bits`, and
+    /// `exif_match` — capture date, camera make/model, and dimensions all
+    /// agreeing across members — tightens the result by one band, since
+    /// corroborating metadata makes the same distance more trustworthy.
+    pub fn from_evidence(sha_match: bool, hamming: u32, hash_bits: u32, exif_match: bool) -> Confidence {
+        if sha_match {
+            return Confidence::Certain;
+        }
+        let [near_certain, high, probable] = confidence_bands_for_bits(hash_bits);
+        let band = if hamming <= near_certain {
+            Confidence::NearCertain
+        } else if hamming <= high {
+            Confidence::High
+        } else if hamming <= probable {
+            Confidence::Probable
+        } else {
+            Confidence::Low
+        };
+        if exif_match {
+            tighten_one_band(band)
+        } else {
+            band
+        }
+    }
+}
+
 /// Combine two confidence levels, taking the lower (more conservative) one.
 pub fn combine_confidence(a: Confidence, b: Confidence) -> Confidence {
     if a < b { a } else { b }
@@ -41,9 +353,211 @@ mod tests {
         assert_eq!(confidence_from_hamming(10), None);
     }
 
+    #[test]
+    fn test_confidence_from_hamming_with_config_widens_probable_band() {
+        let config = MatchingConfig {
+            probable_threshold: 6,
+            ..MatchingConfig::default()
+        };
+        assert_eq!(
+            confidence_from_hamming_with_config(5, &config),
+            Some(Confidence::Probable)
+        );
+        assert_eq!(confidence_from_hamming_with_config(7, &config), None);
+        // Default config still matches the fixed constants.
+        assert_eq!(
+            confidence_from_hamming_with_config(5, &MatchingConfig::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_phash_confidence_with_config_uses_fixed_thresholds_for_non_dct_algs() {
+        let config = MatchingConfig::default();
+        assert_eq!(config.phash_alg, HashAlg::Mean);
+        assert_eq!(phash_confidence_with_config(2, &config), Some(Confidence::NearCertain));
+        assert_eq!(phash_confidence_with_config(3, &config), Some(Confidence::Probable));
+        assert_eq!(phash_confidence_with_config(6, &config), None);
+    }
+
+    #[test]
+    fn test_phash_confidence_with_config_widens_for_dct() {
+        let config = MatchingConfig::for_alg(HashAlg::Dct);
+        assert_eq!(phash_confidence_with_config(6, &config), Some(Confidence::NearCertain));
+        assert_eq!(phash_confidence_with_config(8, &config), Some(Confidence::High));
+        assert_eq!(phash_confidence_with_config(9, &config), None);
+        // dHash/aHash are never DCT-based, so the generic alias stays on the
+        // tight fixed constants even when the phash slot is widened — a
+        // distance of 6 is past its fixed `PHASH_HIGH_THRESHOLD` of 2.
+        assert_eq!(confidence_from_hamming_with_config(6, &config), None);
+    }
+
+    #[test]
+    fn test_explicit_override_applies_to_phash_and_generic_alike() {
+        let config = MatchingConfig {
+            near_certain_threshold: Some(6),
+            high_threshold: Some(8),
+            ..MatchingConfig::for_alg(HashAlg::Mean)
+        };
+        assert_eq!(phash_confidence_with_config(6, &config), Some(Confidence::NearCertain));
+        assert_eq!(
+            confidence_from_hamming_with_config(6, &config),
+            Some(Confidence::NearCertain)
+        );
+        assert_eq!(phash_confidence_with_config(8, &config), Some(Confidence::High));
+        assert_eq!(confidence_from_hamming_with_config(8, &config), Some(Confidence::High));
+    }
+
+    #[test]
+    fn test_similarity_level_scales_with_hash_bits() {
+        assert_eq!(SimilarityLevel::Minimal.threshold_for_bits(64), 6);
+        assert_eq!(SimilarityLevel::Medium.threshold_for_bits(64), 40);
+        assert_eq!(SimilarityLevel::Maximum.threshold_for_bits(64), 40);
+        assert!(
+            SimilarityLevel::Medium.threshold_for_bits(8) < SimilarityLevel::Medium.threshold_for_bits(64)
+        );
+    }
+
+    #[test]
+    fn test_confidence_from_hamming_for_bits_widens_with_hash_size() {
+        // Distance 10: unrelated at 64 bits, but probable at 256+ bits.
+        assert_eq!(confidence_from_hamming_for_bits(10, 64), None);
+        assert_eq!(
+            confidence_from_hamming_for_bits(10, 256),
+            Some(Confidence::Probable)
+        );
+        // Matches Confidence::from_evidence's own table at the same hash_bits.
+        assert_eq!(
+            confidence_from_hamming_for_bits(8, 64),
+            Some(Confidence::Probable)
+        );
+        assert_eq!(confidence_from_hamming_for_bits(9, 64), None);
+    }
+
+    #[test]
+    fn test_similarity_level_is_monotonically_non_decreasing() {
+        let levels = [
+            SimilarityLevel::Minimal,
+            SimilarityLevel::Low,
+            SimilarityLevel::Medium,
+            SimilarityLevel::High,
+            SimilarityLevel::VeryHigh,
+            SimilarityLevel::Maximum,
+        ];
+        for bits in [8, 16, 32, 64] {
+            let thresholds: Vec<u32> = levels.iter().map(|l| l.threshold_for_bits(bits)).collect();
+            assert!(
+                thresholds.windows(2).all(|w| w[0] <= w[1]),
+                "thresholds for {bits}-bit hashes should never decrease: {thresholds:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_similarity_level_as_str() {
+        assert_eq!(SimilarityLevel::Minimal.as_str(), "Minimal");
+        assert_eq!(SimilarityLevel::VeryHigh.as_str(), "Very High");
+    }
+
+    #[test]
+    fn test_from_threshold_for_bits_round_trips_low_tolerance_levels() {
+        // `Minimal`/`Low` sit below where any bit-size's preset table
+        // collapses distinct levels onto the same threshold, so they always
+        // round-trip to themselves; higher levels can tie (at 64 bits
+        // `Medium` through `Maximum` all map to 40).
+        for bits in [8, 16, 32, 64] {
+            for level in [SimilarityLevel::Minimal, SimilarityLevel::Low] {
+                let threshold = level.threshold_for_bits(bits);
+                assert_eq!(SimilarityLevel::from_threshold_for_bits(threshold, bits), Some(level));
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_threshold_for_bits_returns_none_for_a_custom_threshold() {
+        assert_eq!(SimilarityLevel::from_threshold_for_bits(3, 64), None);
+    }
+
     #[test]
     fn test_combine_confidence() {
         assert_eq!(combine_confidence(Confidence::Certain, Confidence::High), Confidence::High);
         assert_eq!(combine_confidence(Confidence::Low, Confidence::Certain), Confidence::Low);
     }
+
+    #[test]
+    fn test_from_evidence_sha_match_is_always_certain() {
+        assert_eq!(
+            Confidence::from_evidence(true, 40, 64, false),
+            Confidence::Certain,
+            "an exact SHA-256 match should win over any Hamming distance"
+        );
+    }
+
+    #[test]
+    fn test_from_evidence_bands_by_hamming_distance_at_64_bits() {
+        assert_eq!(Confidence::from_evidence(false, 0, 64, false), Confidence::NearCertain);
+        assert_eq!(Confidence::from_evidence(false, 2, 64, false), Confidence::NearCertain);
+        assert_eq!(Confidence::from_evidence(false, 4, 64, false), Confidence::High);
+        assert_eq!(Confidence::from_evidence(false, 8, 64, false), Confidence::Probable);
+        assert_eq!(Confidence::from_evidence(false, 9, 64, false), Confidence::Low);
+    }
+
+    #[test]
+    fn test_from_evidence_bands_scale_with_hash_bits() {
+        // The same raw distance means less on a smaller hash, so it should
+        // land in a lower band than it would at 64 bits.
+        assert_eq!(Confidence::from_evidence(false, 4, 8, false), Confidence::Low);
+        assert_eq!(Confidence::from_evidence(false, 4, 64, false), Confidence::High);
+    }
+
+    #[test]
+    fn test_from_evidence_confidence_ladder_at_every_configured_hash_size() {
+        // Exercise every row of `confidence_bands_for_bits`'s table (8/16/32/64
+        // bits) at its exact boundaries, confirming the ladder is
+        // Certain (sha match) > NearCertain > High > Probable > Low and that
+        // each band's cutoff is inclusive.
+        let cases: [(u32, [u32; 3]); 4] = [
+            (8, [0, 1, 2]),
+            (16, [1, 2, 5]),
+            (32, [2, 4, 10]),
+            (64, [2, 4, 8]),
+        ];
+        for (hash_bits, [near_certain, high, probable]) in cases {
+            assert_eq!(
+                Confidence::from_evidence(false, near_certain, hash_bits, false),
+                Confidence::NearCertain,
+                "hash_bits={hash_bits}: distance at the near-certain cutoff"
+            );
+            assert_eq!(
+                Confidence::from_evidence(false, high, hash_bits, false),
+                Confidence::High,
+                "hash_bits={hash_bits}: distance at the high cutoff"
+            );
+            assert_eq!(
+                Confidence::from_evidence(false, probable, hash_bits, false),
+                Confidence::Probable,
+                "hash_bits={hash_bits}: distance at the probable cutoff"
+            );
+            assert_eq!(
+                Confidence::from_evidence(false, probable + 1, hash_bits, false),
+                Confidence::Low,
+                "hash_bits={hash_bits}: just past the probable cutoff falls through to Low"
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_evidence_exif_match_tightens_by_one_band() {
+        assert_eq!(Confidence::from_evidence(false, 8, 64, true), Confidence::High);
+        assert_eq!(Confidence::from_evidence(false, 9, 64, true), Confidence::Probable);
+    }
+
+    #[test]
+    fn test_from_evidence_exif_match_cannot_push_past_near_certain() {
+        assert_eq!(
+            Confidence::from_evidence(false, 0, 64, true),
+            Confidence::NearCertain,
+            "NearCertain is the ceiling for a non-exact match regardless of EXIF corroboration"
+        );
+    }
 }
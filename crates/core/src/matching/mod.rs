@@ -2,9 +2,11 @@ pub mod confidence;
 
 use std::collections::{HashMap, HashSet};
 
-use crate::domain::{Confidence, PhotoFile};
+use rayon::prelude::*;
+
+use crate::domain::{Confidence, ExifData, PhotoFile};
 use crate::hasher::perceptual::hamming_distance;
-use confidence::confidence_from_hamming;
+use confidence::{phash_confidence_with_config, MatchingConfig};
 
 /// BK-tree for efficient Hamming distance nearest-neighbor search.
 /// Allows finding all items within a given distance in O(n^α) where α < 1,
@@ -83,9 +85,23 @@ pub struct MatchGroup {
     pub confidence: Confidence,
 }
 
-/// Run the full matching pipeline on a set of photos.
+/// Run the full matching pipeline on a set of photos, using the default
+/// similarity tolerance. See `find_duplicates_with_config` to tune it.
 /// Returns groups of duplicate photos with confidence levels.
 pub fn find_duplicates(photos: &[PhotoFile]) -> Vec<MatchGroup> {
+    find_duplicates_with_config(photos, &MatchingConfig::default())
+}
+
+/// Run the full matching pipeline on a set of photos.
+/// `config.probable_threshold` controls how far apart two photos' perceptual
+/// hashes can be and still be grouped by the pure-phash phase (Phase 3) —
+/// widen it to catch more aggressively edited near-duplicates at the cost of
+/// more false positives.
+/// Returns groups of duplicate photos with confidence levels.
+pub fn find_duplicates_with_config(
+    photos: &[PhotoFile],
+    config: &MatchingConfig,
+) -> Vec<MatchGroup> {
     if photos.len() < 2 {
         return Vec::new();
     }
@@ -111,7 +127,7 @@ pub fn find_duplicates(photos: &[PhotoFile]) -> Vec<MatchGroup> {
     let exif_groups = group_by_exif(photos, &empty_set);
     let photo_map: HashMap<i64, &PhotoFile> = photos.iter().map(|p| (p.id, p)).collect();
     for group in exif_groups {
-        let validated = validate_with_perceptual_hash(&group.member_ids, photos);
+        let validated = validate_with_perceptual_hash(&group.member_ids, photos, config);
 
         // Filter: keep members that either (a) passed visual validation, or
         // (b) lack perceptual hashes entirely (HEIC/RAW — EXIF is our best signal), or
@@ -171,7 +187,7 @@ pub fn find_duplicates(photos: &[PhotoFile]) -> Vec<MatchGroup> {
     }
 
     // Phase 3: pHash/dHash Hamming distance → Probable
-    let perceptual_groups = group_by_perceptual_hash(photos, &grouped_ids);
+    let perceptual_groups = group_by_perceptual_hash(photos, &grouped_ids, config);
     for group in perceptual_groups {
         for &id in &group.member_ids {
             grouped_ids.insert(id);
@@ -179,8 +195,21 @@ pub fn find_duplicates(photos: &[PhotoFile]) -> Vec<MatchGroup> {
         groups.push(group);
     }
 
+    // Phase 3.5: identical filename stem + byte size → Low. Catches obvious
+    // copies (e.g. the same IMG_1234 exported to several folders) that the
+    // phases above miss entirely — no shared EXIF, and no perceptual hash at
+    // all for formats we can't decode. Only considers photos none of the
+    // stronger phases already placed.
+    let name_size_groups = group_by_name_and_size(photos, &grouped_ids);
+    for group in name_size_groups {
+        for &id in &group.member_ids {
+            grouped_ids.insert(id);
+        }
+        groups.push(group);
+    }
+
     // Phase 4: Merge overlapping groups (with cross-group visual validation)
-    merge_overlapping(&mut groups, photos)
+    merge_overlapping(&mut groups, photos, config)
 }
 
 /// Phase 1: Group photos by identical SHA-256 hash.
@@ -223,13 +252,78 @@ fn group_by_exif(photos: &[PhotoFile], excluded: &HashSet<i64>) -> Vec<MatchGrou
         .collect()
 }
 
-/// Validate a group of photo IDs using perceptual hash distance (strict dual-hash consensus).
-/// Returns IDs of photos that are perceptually close to at least one other member.
-/// Uses NEAR_CERTAIN threshold (≤2 bits) for EXIF validation — only true duplicates pass.
-/// Sequential/burst shots (distance 3+) are rejected.
-fn validate_with_perceptual_hash(ids: &[i64], photos: &[PhotoFile]) -> HashSet<i64> {
-    use confidence::PHASH_NEAR_CERTAIN_THRESHOLD;
+/// Phase 3.5: Group photos by identical filename stem + byte size. A cheap,
+/// content-agnostic fallback for obvious copies (the same file exported to
+/// multiple folders) that carry no shared EXIF and, for undecodable formats,
+/// no perceptual hash either. `excluded` is every ID already placed by a
+/// stronger phase — only photos still unmatched are considered here.
+fn group_by_name_and_size(photos: &[PhotoFile], excluded: &HashSet<i64>) -> Vec<MatchGroup> {
+    let mut stem_size_map: HashMap<(String, u64), Vec<i64>> = HashMap::new();
+
+    for photo in photos {
+        if excluded.contains(&photo.id) {
+            continue;
+        }
+
+        let stem = match photo.path.file_stem().and_then(|s| s.to_str()) {
+            Some(stem) => stem.to_string(),
+            None => continue,
+        };
+        stem_size_map
+            .entry((stem, photo.size))
+            .or_default()
+            .push(photo.id);
+    }
+
+    stem_size_map
+        .into_values()
+        .filter(|ids| ids.len() >= 2)
+        .map(|member_ids| MatchGroup {
+            member_ids,
+            confidence: Confidence::Low,
+        })
+        .collect()
+}
+
+/// Count how many of the three fingerprints (phash, dhash, ahash) a pair of
+/// photos both carry (`compared`), and how many of those fall within
+/// threshold Hamming distance of each other (`matched`). phash is always
+/// counted first since Phase 2/3 only ever reach here once it's present, and
+/// uses `phash_threshold` (which widens for a DCT-based phash — see
+/// `confidence::phash_confidence_with_config`) while dHash/aHash stay on the
+/// fixed `other_threshold`, since those are never DCT hashes regardless of
+/// which algorithm produced phash.
+/// Callers require `matched == compared` when `compared <= 2` (the original
+/// all-available-hashes-must-agree rule) and `matched >= 2` once a third
+/// (aHash) vote is in play, so aHash can rescue a case where dHash alone
+/// would have disagreed.
+fn hash_votes(a: &PhotoFile, b: &PhotoFile, phash_dist: u32, phash_threshold: u32, other_threshold: u32) -> (u32, u32) {
+    let mut matched = u32::from(phash_dist <= phash_threshold);
+    let mut compared = 1;
+
+    if let (Some(da), Some(db)) = (a.dhash, b.dhash) {
+        compared += 1;
+        matched += u32::from(hamming_distance(da, db) <= other_threshold);
+    }
+    if let (Some(ha), Some(hb)) = (a.ahash, b.ahash) {
+        compared += 1;
+        matched += u32::from(hamming_distance(ha, hb) <= other_threshold);
+    }
+
+    (matched, compared)
+}
 
+/// Validate a group of photo IDs using perceptual hash distance (triple-hash
+/// consensus). Returns IDs of photos that are perceptually close to at least
+/// one other member.
+/// Uses `config.phash_near_certain_threshold()` for the phash slot (already
+/// widened if `config.phash_alg` is DCT-based) and `config.other_near_certain_threshold()`
+/// for dhash/ahash (always the fixed, tight constant unless the user
+/// overrode it) — only true duplicates pass either way. Sequential/burst
+/// shots (distance past that threshold) are rejected.
+/// When all three of phash/dhash/ahash are available, only 2 of the 3 need
+/// to agree — see `hash_votes`.
+fn validate_with_perceptual_hash(ids: &[i64], photos: &[PhotoFile], config: &MatchingConfig) -> HashSet<i64> {
     let photo_map: HashMap<i64, &PhotoFile> = photos.iter().map(|p| (p.id, p)).collect();
     let mut valid = HashSet::new();
 
@@ -238,14 +332,14 @@ fn validate_with_perceptual_hash(ids: &[i64], photos: &[PhotoFile]) -> HashSet<i
             if let (Some(pa), Some(pb)) = (photo_map.get(&id_a), photo_map.get(&id_b)) {
                 if let (Some(phash_a), Some(phash_b)) = (pa.phash, pb.phash) {
                     let phash_dist = hamming_distance(phash_a, phash_b);
-                    let is_match = match (pa.dhash, pb.dhash) {
-                        (Some(da), Some(db)) => {
-                            let dhash_dist = hamming_distance(da, db);
-                            phash_dist <= PHASH_NEAR_CERTAIN_THRESHOLD
-                                && dhash_dist <= PHASH_NEAR_CERTAIN_THRESHOLD
-                        }
-                        _ => phash_dist <= PHASH_NEAR_CERTAIN_THRESHOLD,
-                    };
+                    let (matched, compared) = hash_votes(
+                        pa,
+                        pb,
+                        phash_dist,
+                        config.phash_near_certain_threshold(),
+                        config.other_near_certain_threshold(),
+                    );
+                    let is_match = if compared >= 3 { matched >= 2 } else { matched == compared };
                     if is_match {
                         valid.insert(id_a);
                         valid.insert(id_b);
@@ -261,6 +355,9 @@ fn validate_with_perceptual_hash(ids: &[i64], photos: &[PhotoFile]) -> HashSet<i
 /// Parse an EXIF datetime string into an approximate seconds value (for comparison only).
 /// Handles "YYYY:MM:DD HH:MM:SS" and "YYYY-MM-DD HH:MM:SS".
 fn parse_exif_seconds(date_str: &str) -> Option<i64> {
+    // Strip any subsecond fraction (see `exif_precise_seconds`) before
+    // splitting on ':' — "00.123" isn't a valid integer seconds field.
+    let date_str = date_str.split('.').next().unwrap_or(date_str);
     let parts: Vec<&str> = date_str.split_whitespace().collect();
     let date_part = parts.first()?;
     let time_part = parts.get(1)?;
@@ -280,9 +377,23 @@ fn parse_exif_seconds(date_str: &str) -> Option<i64> {
     Some(days * 86400 + tp[0] * 3600 + tp[1] * 60 + tp[2])
 }
 
+/// GPS fixes this far apart (in degrees, on either axis) are treated as
+/// "different locations" rather than noise from repeated fix rounding.
+const GPS_DISTINCT_LOCATION_EPSILON_DEGREES: f64 = 0.0001;
+
 /// Check if two photos are sequential shots from the same camera.
 /// Sequential shots: same camera model, EXIF dates 1-60 seconds apart (not identical).
-/// True duplicates always have identical EXIF dates.
+/// True duplicates always have identical EXIF dates — unless their GPS fixes disagree,
+/// in which case the identical timestamp is a whole-second rounding artifact hiding two
+/// distinct captures (e.g. a burst spanning a sub-second GPS update), so treat those as
+/// sequential too.
+///
+/// `exif::extract_exif` folds `SubSecTimeOriginal`/`SubSecTimeDigitized` into `date` as
+/// a decimal fraction (e.g. `"2024:12:24 10:00:00.123"`) when the camera recorded one,
+/// so two photos that share a whole-second timestamp but land in different subsecond
+/// fractions are *not* treated as an identical-date true duplicate here — they fall
+/// through to the seconds-apart check below via `exif_precise_seconds`, which catches
+/// exactly this case: a burst shot faster than one second apart.
 fn is_sequential_shot(a: &PhotoFile, b: &PhotoFile) -> bool {
     let (exif_a, exif_b) = match (&a.exif, &b.exif) {
         (Some(ea), Some(eb)) => (ea, eb),
@@ -301,16 +412,50 @@ fn is_sequential_shot(a: &PhotoFile, b: &PhotoFile) -> bool {
         _ => return false,
     };
 
-    // Identical dates = true duplicate, not sequential
+    // Identical dates (including subsecond fraction, if present) = true
+    // duplicate, not sequential — unless GPS disagrees.
     if date_a == date_b {
-        return false;
+        return gps_locations_differ(exif_a, exif_b);
+    }
+
+    // Parse and check time difference, sub-second precision included.
+    match (exif_precise_seconds(date_a), exif_precise_seconds(date_b)) {
+        (Some(ta), Some(tb)) => (ta - tb).abs() <= 60.0,
+        _ => false,
     }
+}
+
+/// `date_str` with any subsecond fraction (see `is_sequential_shot`) as
+/// floating-point seconds, so two shots in the same whole second but
+/// different subsecond fractions still register a (small) nonzero gap.
+fn exif_precise_seconds(date_str: &str) -> Option<f64> {
+    let whole = parse_exif_seconds(date_str)? as f64;
+    let millis = parse_exif_subsec_millis(date_str).unwrap_or(0);
+    Some(whole + f64::from(millis) / 1000.0)
+}
 
-    // Parse and check time difference
-    match (parse_exif_seconds(date_a), parse_exif_seconds(date_b)) {
-        (Some(sa), Some(sb)) => {
-            let diff = (sa - sb).unsigned_abs();
-            diff <= 60
+/// The subsecond fraction appended to an EXIF date string by
+/// `exif::extract_exif` (the `123` in `"2024:12:24 10:00:00.123"`),
+/// normalized to milliseconds regardless of how many digits the camera
+/// recorded (EXIF `SubSecTime*` tags are free-form digit strings, not a
+/// fixed width). `None` if the camera didn't record one.
+fn parse_exif_subsec_millis(date_str: &str) -> Option<u32> {
+    let frac = date_str.split('.').nth(1)?;
+    if frac.is_empty() || !frac.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let padded: String = frac.chars().chain(std::iter::repeat('0')).take(3).collect();
+    padded.parse().ok()
+}
+
+/// True when both photos carry a GPS fix and the fixes disagree by more than
+/// rounding noise — a same-timestamp pair with different coordinates can't be the
+/// same capture. Absent or matching GPS data is not evidence of a difference.
+fn gps_locations_differ(a: &ExifData, b: &ExifData) -> bool {
+    match (a.gps_lat, a.gps_lon, b.gps_lat, b.gps_lon) {
+        (Some(lat_a), Some(lon_a), Some(lat_b), Some(lon_b)) => {
+            (lat_a - lat_b).abs() > GPS_DISTINCT_LOCATION_EPSILON_DEGREES
+                || (lon_a - lon_b).abs() > GPS_DISTINCT_LOCATION_EPSILON_DEGREES
         }
         _ => false,
     }
@@ -328,8 +473,17 @@ fn is_sequential_shot(a: &PhotoFile, b: &PhotoFile) -> bool {
 /// Sequential shot filter: rejects matches where both photos have the same camera
 /// model and EXIF dates 1-60 seconds apart (but not identical). True duplicates
 /// always have identical EXIF dates.
-fn group_by_perceptual_hash(photos: &[PhotoFile], excluded: &HashSet<i64>) -> Vec<MatchGroup> {
-    use confidence::{PHASH_HIGH_THRESHOLD, PHASH_PROBABLE_THRESHOLD};
+///
+/// `config.probable_threshold` sets the BK-tree search radius and the
+/// `Probable` confidence band's outer cutoff (see `MatchingConfig`).
+fn group_by_perceptual_hash(
+    photos: &[PhotoFile],
+    excluded: &HashSet<i64>,
+    config: &MatchingConfig,
+) -> Vec<MatchGroup> {
+    use confidence::confidence_from_hamming_with_config;
+
+    let phash_high_threshold = config.phash_high_threshold();
 
     // Build lookup map for dhash access
     let photo_map: HashMap<i64, &PhotoFile> = photos.iter().map(|p| (p.id, p)).collect();
@@ -348,17 +502,26 @@ fn group_by_perceptual_hash(photos: &[PhotoFile], excluded: &HashSet<i64>) -> Ve
         .filter(|p| !excluded.contains(&p.id) && p.phash.is_some())
         .collect();
 
+    // BK-tree lookups are read-only and independent per photo, so the expensive
+    // part of this phase — the candidate search — is parallelized across the
+    // rayon pool (see `Vault::set_scan_thread_limit`/`run_with_thread_limit`).
+    // The neighbor lists are collected in `ungrouped` order so that the actual
+    // grouping below stays a single-threaded pass over a fixed sequence,
+    // keeping group membership and confidence assignment deterministic
+    // regardless of how the lookups were scheduled.
+    let neighbor_lists: Vec<Vec<(i64, u32)>> = ungrouped
+        .par_iter()
+        .map(|photo_a| tree.find_within(photo_a.phash.unwrap(), config.probable_threshold))
+        .collect();
+
     let mut groups: Vec<MatchGroup> = Vec::new();
     let mut used: HashSet<i64> = HashSet::new();
 
-    for &photo_a in &ungrouped {
+    for (&photo_a, neighbors) in ungrouped.iter().zip(neighbor_lists.iter()) {
         if used.contains(&photo_a.id) {
             continue;
         }
 
-        let phash_a = photo_a.phash.unwrap();
-        let neighbors = tree.find_within(phash_a, PHASH_PROBABLE_THRESHOLD);
-
         let mut members = vec![photo_a.id];
         let mut worst_confidence = Confidence::Certain;
 
@@ -367,30 +530,55 @@ fn group_by_perceptual_hash(photos: &[PhotoFile], excluded: &HashSet<i64>) -> Ve
                 continue;
             }
 
-            let phash_conf = match confidence_from_hamming(*phash_dist) {
+            let phash_conf = match phash_confidence_with_config(*phash_dist, config) {
                 Some(c) => c,
                 None => continue,
             };
 
-            // Dual-hash consensus: check dhash when both photos have it
+            // Triple-hash consensus: phash already passed via the BK-tree
+            // lookup above. Count how many of dhash/ahash the pair both
+            // carry and agree on; when all three are present, only 2 of 3
+            // need to agree (so ahash can rescue a dhash disagreement) —
+            // otherwise every hash that's present must agree, same as the
+            // original dual-hash rule.
             let neighbor = photo_map.get(neighbor_id);
-            let conf = match (photo_a.dhash, neighbor.and_then(|p| p.dhash)) {
-                (Some(da), Some(db)) => {
-                    let dhash_dist = hamming_distance(da, db);
-                    match confidence_from_hamming(dhash_dist) {
-                        Some(dc) => confidence::combine_confidence(phash_conf, dc),
-                        None => continue, // dhash too far → reject
-                    }
+            let mut matched = 1u32;
+            let mut compared = 1u32;
+            let mut worst_hash_confidence = phash_conf;
+
+            if let (Some(da), Some(db)) = (photo_a.dhash, neighbor.and_then(|p| p.dhash)) {
+                compared += 1;
+                let dhash_dist = hamming_distance(da, db);
+                if let Some(dc) = confidence_from_hamming_with_config(dhash_dist, config) {
+                    matched += 1;
+                    worst_hash_confidence = confidence::combine_confidence(worst_hash_confidence, dc);
                 }
-                _ => {
-                    // One or both lack dhash (cross-format) — require stricter phash
-                    if *phash_dist > PHASH_HIGH_THRESHOLD {
-                        continue;
-                    }
-                    phash_conf
+            }
+            if let (Some(ha), Some(hb)) = (photo_a.ahash, neighbor.and_then(|p| p.ahash)) {
+                compared += 1;
+                let ahash_dist = hamming_distance(ha, hb);
+                if let Some(ac) = confidence_from_hamming_with_config(ahash_dist, config) {
+                    matched += 1;
+                    worst_hash_confidence = confidence::combine_confidence(worst_hash_confidence, ac);
                 }
+            }
+
+            let is_match = if let Some(required) = config.required_votes {
+                matched >= required
+            } else if compared == 1 {
+                // No dhash/ahash to corroborate (cross-format) — require stricter phash
+                *phash_dist <= phash_high_threshold
+            } else if compared == 2 {
+                matched == 2 // original dual-hash rule: both present hashes must agree
+            } else {
+                matched >= 2 // 2-of-3 consensus once ahash is in play
             };
 
+            if !is_match {
+                continue;
+            }
+            let conf = worst_hash_confidence;
+
             // Sequential shot filter: reject matches from the same camera
             // with EXIF dates 1-60 seconds apart (not identical).
             if let Some(neighbor_photo) = neighbor {
@@ -423,7 +611,7 @@ fn group_by_perceptual_hash(photos: &[PhotoFile], excluded: &HashSet<i64>) -> Ve
 /// Before merging, validates that the groups are visually related — at least one
 /// pair of exclusive members (one from each group) must have perceptual hashes
 /// within threshold. This prevents cascading false merges through bridge photos.
-fn merge_overlapping(groups: &mut Vec<MatchGroup>, photos: &[PhotoFile]) -> Vec<MatchGroup> {
+fn merge_overlapping(groups: &mut Vec<MatchGroup>, photos: &[PhotoFile], config: &MatchingConfig) -> Vec<MatchGroup> {
     let photo_map: HashMap<i64, &PhotoFile> = photos.iter().map(|p| (p.id, p)).collect();
     let mut merged: Vec<MatchGroup> = Vec::new();
 
@@ -446,7 +634,7 @@ fn merge_overlapping(groups: &mut Vec<MatchGroup>, photos: &[PhotoFile]) -> Vec<
             // exclusive members (one from each side) are perceptually close.
             let mut to_merge: Vec<usize> = Vec::new();
             for &idx in &overlap_indices {
-                if cross_group_validated(&group_set, &merged[idx], &photo_map) {
+                if cross_group_validated(&group_set, &merged[idx], &photo_map, config) {
                     to_merge.push(idx);
                 }
             }
@@ -484,6 +672,7 @@ fn cross_group_validated(
     new_set: &HashSet<i64>,
     existing: &MatchGroup,
     photo_map: &HashMap<i64, &PhotoFile>,
+    config: &MatchingConfig,
 ) -> bool {
     let existing_set: HashSet<i64> = existing.member_ids.iter().copied().collect();
 
@@ -513,7 +702,7 @@ fn cross_group_validated(
             if let (Some(pa), Some(pb)) = (photo_map.get(&id_a), photo_map.get(&id_b)) {
                 if let (Some(phash_a), Some(phash_b)) = (pa.phash, pb.phash) {
                     let dist = hamming_distance(phash_a, phash_b);
-                    if confidence_from_hamming(dist).is_some() {
+                    if phash_confidence_with_config(dist, config).is_some() {
                         return true;
                     }
                 }
@@ -527,7 +716,8 @@ fn cross_group_validated(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::{ExifData, PhotoFormat};
+    use crate::domain::PhotoFormat;
+    use crate::hasher::perceptual::HashAlg;
     use std::path::PathBuf;
 
     fn make_photo(id: i64, sha: &str, phash: Option<u64>) -> PhotoFile {
@@ -549,6 +739,7 @@ mod tests {
             sha256: sha.to_string(),
             phash,
             dhash,
+            ahash: None,
             exif: None,
             mtime: 1000,
         }
@@ -596,6 +787,125 @@ mod tests {
         p
     }
 
+    /// Like `make_photo_with_exif`, but with an explicit GPS fix.
+    fn make_photo_with_exif_gps(
+        id: i64,
+        sha: &str,
+        phash: Option<u64>,
+        date: &str,
+        camera: &str,
+        gps: (f64, f64),
+    ) -> PhotoFile {
+        let mut p = make_photo_with_exif(id, sha, phash, date, camera);
+        if let Some(exif) = p.exif.as_mut() {
+            exif.gps_lat = Some(gps.0);
+            exif.gps_lon = Some(gps.1);
+        }
+        p
+    }
+
+    // ── BK-tree ──────────────────────────────────────────────────
+
+    #[test]
+    fn test_bktree_find_within_returns_entries_at_or_under_the_radius() {
+        let mut tree = BkTree::new();
+        tree.insert(0b0000, 1);
+        tree.insert(0b0001, 2); // distance 1 from the root
+        tree.insert(0b0111, 3); // distance 3 from the root
+        tree.insert(0b1111, 4); // distance 4 from the root
+
+        let mut hits = tree.find_within(0b0000, 1);
+        hits.sort();
+        assert_eq!(hits, vec![(1, 0), (2, 1)]);
+    }
+
+    #[test]
+    fn test_bktree_find_within_empty_tree_returns_nothing() {
+        let tree = BkTree::new();
+        assert_eq!(tree.find_within(0, 5), Vec::new());
+    }
+
+    #[test]
+    fn test_bktree_find_within_excludes_entries_outside_the_radius() {
+        let mut tree = BkTree::new();
+        tree.insert(0b0000, 1);
+        tree.insert(u64::MAX, 2); // 64 bits away — never a near neighbor
+
+        let hits = tree.find_within(0b0000, 3);
+        assert_eq!(hits, vec![(1, 0)]);
+    }
+
+    #[test]
+    fn test_bktree_finds_neighbor_reached_through_an_intermediate_node() {
+        // node3 sits two levels deep (child of node2, itself a child of the
+        // root). Neither the root nor node2 is within range of the query on
+        // its own — only recursing through both, per the triangle-inequality
+        // window at each level, reaches node3.
+        let mut tree = BkTree::new();
+        tree.insert(0b0000, 1); // root
+        tree.insert(0b1111, 2); // distance 4 from root -> child of root at key 4
+        tree.insert(0b10111, 3); // distance 4 from root (same bucket as node 2) -> recurses
+                                  // into node 2, then distance 2 from node 2 -> its child
+
+        let hits = tree.find_within(0b10111, 1);
+        assert_eq!(hits, vec![(3, 0)]);
+    }
+
+    #[test]
+    fn test_bktree_find_within_matches_brute_force_over_many_hashes() {
+        // The triangle-inequality pruning in `search` is an optimization —
+        // it must never change which entries are found, only how many nodes
+        // get visited getting there. Build a tree from a large, deterministic
+        // set of hashes (a simple LCG, not `rand`, so the test has no
+        // external dependency) and confirm every radius query agrees exactly
+        // with a brute-force scan over the same set.
+        let mut hashes: Vec<u64> = Vec::new();
+        let mut state: u64 = 0x1234_5678_9abc_def0;
+        for _ in 0..300 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            hashes.push(state);
+        }
+
+        let mut tree = BkTree::new();
+        for (i, &h) in hashes.iter().enumerate() {
+            tree.insert(h, i as i64);
+        }
+
+        for (i, &query) in hashes.iter().enumerate().step_by(7) {
+            for radius in [0, 1, 5, 10, 20] {
+                let mut via_tree = tree.find_within(query, radius);
+                via_tree.sort();
+
+                let mut via_brute_force: Vec<(i64, u32)> = hashes
+                    .iter()
+                    .enumerate()
+                    .map(|(j, &h)| (j as i64, hamming_distance(query, h)))
+                    .filter(|&(_, d)| d <= radius)
+                    .collect();
+                via_brute_force.sort();
+
+                assert_eq!(
+                    via_tree, via_brute_force,
+                    "query {i} at radius {radius} diverged from brute force"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_bktree_insert_same_distance_bucket_chains_into_subtree() {
+        // Two inserts landing in the same child distance bucket from the
+        // root must chain into a subtree rather than overwrite each other.
+        let mut tree = BkTree::new();
+        tree.insert(0b0000, 1); // root
+        tree.insert(0b0011, 2); // distance 2 from root
+        tree.insert(0b1100, 3); // also distance 2 from root — chains under node 2
+
+        let mut hits = tree.find_within(0b1100, 0);
+        hits.sort();
+        assert_eq!(hits, vec![(3, 0)]);
+    }
+
     // ── Phase 1: SHA-256 ─────────────────────────────────────────
 
     #[test]
@@ -717,6 +1027,26 @@ mod tests {
         assert!(groups.is_empty());
     }
 
+    #[test]
+    fn test_configurable_threshold_widens_probable_band() {
+        // Distance 5: beyond the default PHASH_PROBABLE_THRESHOLD (3), so the
+        // default config finds nothing...
+        let photos = vec![
+            make_photo(1, "aaa", Some(0b0000_0000)),
+            make_photo(2, "bbb", Some(0b0001_1111)),
+        ];
+        assert!(find_duplicates(&photos).is_empty());
+
+        // ...but a caller who widens the tolerance via MatchingConfig catches it.
+        let config = MatchingConfig {
+            probable_threshold: 5,
+            ..MatchingConfig::default()
+        };
+        let groups = find_duplicates_with_config(&photos, &config);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].confidence, Confidence::Probable);
+    }
+
     #[test]
     fn test_dual_hash_consensus_rejects_single_hash_match() {
         // phash close (distance 1) but dhash far → should NOT group
@@ -818,6 +1148,99 @@ mod tests {
         );
     }
 
+    fn make_photo_triple(
+        id: i64,
+        sha: &str,
+        phash: Option<u64>,
+        dhash: Option<u64>,
+        ahash: Option<u64>,
+    ) -> PhotoFile {
+        let mut p = make_photo_full(id, sha, phash, dhash);
+        p.ahash = ahash;
+        p
+    }
+
+    #[test]
+    fn test_triple_hash_ahash_rescues_dhash_disagreement() {
+        // phash close (1), dhash far (64), but ahash close (1) too — 2 of 3
+        // hashes agree, so triple-hash consensus groups them even though the
+        // old dual-hash rule (phash+dhash only) would have rejected this pair.
+        let photos = vec![
+            make_photo_triple(1, "aaa", Some(0b1111_0000), Some(0), Some(0b1010_0000)),
+            make_photo_triple(2, "bbb", Some(0b1111_0001), Some(u64::MAX), Some(0b1010_0001)),
+        ];
+
+        let groups = find_duplicates(&photos);
+        assert_eq!(groups.len(), 1, "aHash agreeing with phash should rescue a dHash disagreement");
+    }
+
+    #[test]
+    fn test_triple_hash_rejects_when_only_phash_agrees() {
+        // phash close, but both dhash and ahash far — only 1 of 3 hashes
+        // agrees, which isn't enough even with a third vote available.
+        let photos = vec![
+            make_photo_triple(1, "aaa", Some(0b1111_0000), Some(0), Some(0)),
+            make_photo_triple(2, "bbb", Some(0b1111_0001), Some(u64::MAX), Some(u64::MAX)),
+        ];
+
+        let groups = find_duplicates(&photos);
+        assert!(groups.is_empty(), "Only phash agreeing out of three hashes should not be enough");
+    }
+
+    #[test]
+    fn test_triple_hash_all_three_distances_zero_is_near_certain() {
+        // phash, dhash, and ahash all identical (distance 0) between the
+        // pair — the strongest triple-hash evidence short of an exact SHA
+        // match — must earn NearCertain, not just pass the vote.
+        let photos = vec![
+            make_photo_triple(1, "aaa", Some(0b1111_0000), Some(0b0000_1111), Some(0b1010_1010)),
+            make_photo_triple(2, "bbb", Some(0b1111_0000), Some(0b0000_1111), Some(0b1010_1010)),
+        ];
+
+        let groups = find_duplicates(&photos);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(
+            groups[0].confidence,
+            Confidence::NearCertain,
+            "all three hashes agreeing exactly should yield NearCertain"
+        );
+    }
+
+    #[test]
+    fn test_required_votes_override_accepts_single_agreeing_hash() {
+        // Same pair as test_triple_hash_rejects_when_only_phash_agrees — only
+        // phash agrees, dhash and ahash are both far — but a config that
+        // only requires 1 of 3 votes should accept it.
+        let photos = vec![
+            make_photo_triple(1, "aaa", Some(0b1111_0000), Some(0), Some(0)),
+            make_photo_triple(2, "bbb", Some(0b1111_0001), Some(u64::MAX), Some(u64::MAX)),
+        ];
+
+        let config = MatchingConfig {
+            required_votes: Some(1),
+            ..MatchingConfig::default()
+        };
+        let groups = find_duplicates_with_config(&photos, &config);
+        assert_eq!(groups.len(), 1, "required_votes: Some(1) should accept a single agreeing hash");
+    }
+
+    #[test]
+    fn test_required_votes_override_rejects_below_threshold() {
+        // Same 2-of-3 agreement as test_triple_hash_ahash_rescues_dhash_disagreement,
+        // but a config that demands all 3 votes should reject it.
+        let photos = vec![
+            make_photo_triple(1, "aaa", Some(0b1111_0000), Some(0), Some(0b1010_0000)),
+            make_photo_triple(2, "bbb", Some(0b1111_0001), Some(u64::MAX), Some(0b1010_0001)),
+        ];
+
+        let config = MatchingConfig {
+            required_votes: Some(3),
+            ..MatchingConfig::default()
+        };
+        let groups = find_duplicates_with_config(&photos, &config);
+        assert!(groups.is_empty(), "required_votes: Some(3) should reject 2-of-3 agreement");
+    }
+
     #[test]
     fn test_exif_filters_visually_different_members() {
         // 3 photos: same EXIF. Photos 1 and 2 visually similar, photo 3 visually different.
@@ -846,6 +1269,24 @@ mod tests {
         assert!(groups.is_empty());
     }
 
+    #[test]
+    fn test_decode_failure_falls_back_to_sha_only_grouping() {
+        // A photo whose perceptual hash couldn't be computed (corrupt file,
+        // unsupported codec) still groups with its exact byte-identical
+        // duplicate via Phase 1, even though it can never enter the BK-tree
+        // built by `group_by_perceptual_hash` (phash is None).
+        let photos = vec![
+            make_photo(1, "aaa", None),
+            make_photo(2, "aaa", None),
+            make_photo(3, "bbb", Some(100)), // unrelated, has a phash
+        ];
+
+        let groups = find_duplicates(&photos);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].confidence, Confidence::Certain);
+        assert_eq!(groups[0].member_ids, vec![1, 2]);
+    }
+
     // ── Phase 4: Merge ───────────────────────────────────────────
 
     #[test]
@@ -867,7 +1308,7 @@ mod tests {
             },
         ];
 
-        let merged = merge_overlapping(&mut groups, &photos);
+        let merged = merge_overlapping(&mut groups, &photos, &MatchingConfig::default());
         assert_eq!(merged.len(), 1);
         assert_eq!(merged[0].member_ids.len(), 3);
         assert_eq!(merged[0].confidence, Confidence::High);
@@ -892,7 +1333,7 @@ mod tests {
             },
         ];
 
-        let merged = merge_overlapping(&mut groups, &photos);
+        let merged = merge_overlapping(&mut groups, &photos, &MatchingConfig::default());
         assert_eq!(merged.len(), 2);
     }
 
@@ -977,6 +1418,27 @@ mod tests {
         assert_eq!(groups[0].member_ids.len(), 4);
     }
 
+    #[test]
+    fn test_cross_format_cross_directory_raw_jpeg_pairs_all_merge() {
+        // Same scenario as the HEIC test above, but with a camera RAW
+        // sidecar instead: IMG_3234.jpeg and IMG_3234.cr2 in both test/ and
+        // test2/ — 4 files, two SHA pairs, one shared EXIF group, no phash
+        // on either RAW (this build's decoder can't hash it) — should still
+        // merge into one group of 4 on EXIF alone.
+        let p1 = make_photo_with_exif(1, "sha_jpeg", Some(100), "2024-01-15 12:00:00", "Canon EOS R5");
+        let p2 = make_photo_with_exif(2, "sha_jpeg", Some(100), "2024-01-15 12:00:00", "Canon EOS R5");
+        let mut p3 = make_photo_with_exif(3, "sha_raw", None, "2024-01-15 12:00:00", "Canon EOS R5");
+        let mut p4 = make_photo_with_exif(4, "sha_raw", None, "2024-01-15 12:00:00", "Canon EOS R5");
+        p3.format = PhotoFormat::Cr2;
+        p4.format = PhotoFormat::Cr2;
+
+        let photos = vec![p1, p2, p3, p4];
+        let groups = find_duplicates(&photos);
+
+        assert_eq!(groups.len(), 1, "All 4 files should merge into one group");
+        assert_eq!(groups[0].member_ids.len(), 4);
+    }
+
     #[test]
     fn test_three_image_pairs_three_groups() {
         // 3 different photos, each with a JPEG+HEIC pair → 3 separate groups of 2.
@@ -1038,7 +1500,7 @@ mod tests {
             },
         ];
 
-        let merged = merge_overlapping(&mut groups, &photos);
+        let merged = merge_overlapping(&mut groups, &photos, &MatchingConfig::default());
         assert_eq!(merged.len(), 1, "Transitive chain should collapse to 1 group");
         assert_eq!(merged[0].member_ids.len(), 4);
         assert_eq!(merged[0].confidence, Confidence::High, "Worst confidence wins");
@@ -1067,7 +1529,7 @@ mod tests {
             },
         ];
 
-        let merged = merge_overlapping(&mut groups, &photos);
+        let merged = merge_overlapping(&mut groups, &photos, &MatchingConfig::default());
         assert_eq!(merged.len(), 1, "Bridge group should merge the two disjoint groups");
         assert_eq!(merged[0].member_ids.len(), 4);
     }
@@ -1101,7 +1563,7 @@ mod tests {
             },
         ];
 
-        let merged = merge_overlapping(&mut groups, &photos);
+        let merged = merge_overlapping(&mut groups, &photos, &MatchingConfig::default());
         assert_eq!(merged.len(), 1, "Single bridge touching all groups should merge everything");
         assert_eq!(merged[0].member_ids.len(), 6);
         assert_eq!(merged[0].confidence, Confidence::Probable);
@@ -1136,7 +1598,7 @@ mod tests {
             },
         ];
 
-        let merged = merge_overlapping(&mut groups, &photos);
+        let merged = merge_overlapping(&mut groups, &photos, &MatchingConfig::default());
         assert_eq!(merged.len(), 2, "Two independent chains should stay separate");
     }
 
@@ -1160,7 +1622,7 @@ mod tests {
             },
         ];
 
-        let merged = merge_overlapping(&mut groups, &photos);
+        let merged = merge_overlapping(&mut groups, &photos, &MatchingConfig::default());
         assert_eq!(merged.len(), 2, "Visually unrelated groups should NOT merge");
     }
 
@@ -1180,6 +1642,54 @@ mod tests {
         assert!(!is_sequential_shot(&a, &b), "Identical dates = true duplicate, not sequential");
     }
 
+    #[test]
+    fn test_is_sequential_shot_identical_dates_different_gps_is_sequential() {
+        let a = make_photo_with_exif_gps(
+            1,
+            "a",
+            Some(0),
+            "2024-12-24 20:43:45",
+            "iPhone 16 Pro Max",
+            (40.7128, -74.0060),
+        );
+        let b = make_photo_with_exif_gps(
+            2,
+            "b",
+            Some(0),
+            "2024-12-24 20:43:45",
+            "iPhone 16 Pro Max",
+            (34.0522, -118.2437),
+        );
+        assert!(
+            is_sequential_shot(&a, &b),
+            "Same whole-second timestamp but different GPS fix → distinct captures, not a duplicate"
+        );
+    }
+
+    #[test]
+    fn test_is_sequential_shot_identical_dates_same_gps_not_sequential() {
+        let a = make_photo_with_exif_gps(
+            1,
+            "a",
+            Some(0),
+            "2024-12-24 20:43:45",
+            "iPhone 16 Pro Max",
+            (40.7128, -74.0060),
+        );
+        let b = make_photo_with_exif_gps(
+            2,
+            "b",
+            Some(0),
+            "2024-12-24 20:43:45",
+            "iPhone 16 Pro Max",
+            (40.7128, -74.0060),
+        );
+        assert!(
+            !is_sequential_shot(&a, &b),
+            "Identical date and GPS fix → still a true duplicate, not sequential"
+        );
+    }
+
     #[test]
     fn test_is_sequential_shot_different_cameras_not_sequential() {
         let a = make_photo_with_exif(1, "a", Some(0), "2024-12-24 20:43:45", "iPhone 16 Pro Max");
@@ -1274,6 +1784,51 @@ mod tests {
         assert!(!is_sequential_shot(&a, &b), "24h apart → not sequential");
     }
 
+    #[test]
+    fn test_is_sequential_shot_identical_subsecond_is_true_duplicate() {
+        // Same whole second AND same subsecond fraction (as `exif::extract_exif`
+        // would produce for two copies of the same exact capture) → identical
+        // dates, not sequential.
+        let a = make_photo_with_exif(1, "a", Some(0), "2024-12-24 20:43:00.500", "iPhone");
+        let b = make_photo_with_exif(2, "b", Some(0), "2024-12-24 20:43:00.500", "iPhone");
+        assert!(!is_sequential_shot(&a, &b), "identical timestamp incl. subsecond → true duplicate");
+    }
+
+    #[test]
+    fn test_is_sequential_shot_same_second_different_subsecond_is_sequential() {
+        // Same whole-second EXIF timestamp but different subsecond fraction —
+        // a burst frame faster than one second apart, not a true duplicate.
+        let a = make_photo_with_exif(1, "a", Some(0), "2024-12-24 20:43:00.100", "iPhone");
+        let b = make_photo_with_exif(2, "b", Some(0), "2024-12-24 20:43:00.900", "iPhone");
+        assert!(is_sequential_shot(&a, &b), "same second, different subsecond → sequential burst");
+    }
+
+    #[test]
+    fn test_is_sequential_shot_subsecond_does_not_break_boundary_math() {
+        // Exactly 60s apart at the whole-second level, identical subsecond
+        // fraction on both sides → the fraction shouldn't shift the total.
+        let a = make_photo_with_exif(1, "a", Some(0), "2024-12-24 20:43:00.500", "iPhone");
+        let b = make_photo_with_exif(2, "b", Some(0), "2024-12-24 20:44:00.500", "iPhone");
+        assert!(is_sequential_shot(&a, &b), "60s apart (subsecond fractions cancel out) → sequential");
+    }
+
+    #[test]
+    fn test_is_sequential_shot_subsecond_pushes_just_past_60s_boundary() {
+        // 60s apart at the whole-second level, but the subsecond fractions
+        // add another 0.4s — 60.4s total should fall outside the window.
+        let a = make_photo_with_exif(1, "a", Some(0), "2024-12-24 20:43:00.100", "iPhone");
+        let b = make_photo_with_exif(2, "b", Some(0), "2024-12-24 20:44:00.500", "iPhone");
+        assert!(!is_sequential_shot(&a, &b), "60.4s apart → NOT sequential");
+    }
+
+    #[test]
+    fn test_parse_exif_subsec_millis_normalizes_digit_width() {
+        assert_eq!(parse_exif_subsec_millis("2024:12:24 10:00:00.5"), Some(500));
+        assert_eq!(parse_exif_subsec_millis("2024:12:24 10:00:00.50"), Some(500));
+        assert_eq!(parse_exif_subsec_millis("2024:12:24 10:00:00.500"), Some(500));
+        assert_eq!(parse_exif_subsec_millis("2024:12:24 10:00:00"), None);
+    }
+
     // ── parse_exif_seconds unit tests ───────────────────────────────
 
     #[test]
@@ -1616,6 +2171,58 @@ mod tests {
             "1 phash, 0 comparison partners → NearCertain");
     }
 
+    #[test]
+    fn test_exif_phash_validates_cross_format_with_raw() {
+        // JPEG has phash, RAW (CR2) has no phash — e.g. a RAW format this
+        // build's decoder doesn't support. Same EXIF. Should group at
+        // NearCertain, same as the HEIC case above: the single phash has no
+        // comparison partner, so EXIF alone decides.
+        let jpeg = make_photo_with_exif(1, "sha_j", Some(100), "2024-01-15 12:00:00", "Canon EOS R5");
+        let mut raw = make_photo_with_exif(2, "sha_r", None, "2024-01-15 12:00:00", "Canon EOS R5");
+        raw.format = PhotoFormat::Cr2;
+
+        let photos = vec![jpeg, raw];
+        let groups = find_duplicates(&photos);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].member_ids.len(), 2);
+        assert_eq!(groups[0].confidence, Confidence::NearCertain,
+            "1 phash, 0 comparison partners → NearCertain");
+    }
+
+    #[test]
+    fn test_heic_with_phash_groups_with_jpeg_on_perceptual_hash_alone() {
+        // With the `heif` feature decoding HEIC originals, a HEIC now carries
+        // a phash like any other format — so a JPEG export and its HEIC
+        // original with no shared EXIF/SHA still merge on Phase 3 alone,
+        // the same path JPEG+PNG+TIFF already merge through.
+        let jpeg = make_photo(1, "sha_jpeg", Some(0b1111_0000));
+        let mut heic = make_photo(2, "sha_heic", Some(0b1111_0001));
+        heic.format = PhotoFormat::Heic;
+
+        let photos = vec![jpeg, heic];
+        let groups = find_duplicates(&photos);
+
+        assert_eq!(groups.len(), 1, "JPEG+HEIC should group on phash alone");
+        assert_eq!(groups[0].member_ids.len(), 2);
+    }
+
+    #[test]
+    fn test_raw_with_phash_groups_with_jpeg_on_perceptual_hash_alone() {
+        // With the `raw` feature decoding the sensor data to pixels, a RAW
+        // original carries a phash like any other format — so a JPEG export
+        // and its RAW original with no shared EXIF/SHA still merge on Phase
+        // 3 alone, the same cross-format path HEIC already takes above.
+        let jpeg = make_photo(1, "sha_jpeg", Some(0b1111_0000));
+        let mut raw = make_photo(2, "sha_raw", Some(0b1111_0001));
+        raw.format = PhotoFormat::Cr2;
+
+        let photos = vec![jpeg, raw];
+        let groups = find_duplicates(&photos);
+
+        assert_eq!(groups.len(), 1, "JPEG+RAW should group on phash alone");
+        assert_eq!(groups[0].member_ids.len(), 2);
+    }
+
     #[test]
     fn test_exif_2_jpegs_1_heic_same_date() {
         // 2 JPEGs (close phash) + 1 HEIC (no phash), all same EXIF.
@@ -1676,6 +2283,47 @@ mod tests {
         assert_eq!(groups.len(), 1, "Cross-format phash dist 2 = HIGH → accepted");
     }
 
+    #[test]
+    fn test_phase3_dct_phash_distance_6_rejected_under_default_thresholds() {
+        // Distance 6 is well past the fixed HIGH threshold (2) that every
+        // non-DCT algorithm shares, so the default config must reject it
+        // even though it's a legitimate DCT near-duplicate.
+        let photos = vec![
+            make_photo_full(1, "aaa", Some(0b0000_0000), None),
+            make_photo_full(2, "bbb", Some(0b0011_1111), None), // phash dist 6, no dhash
+        ];
+
+        let groups = find_duplicates(&photos);
+        assert!(
+            groups.is_empty(),
+            "Cross-format phash dist 6 > fixed HIGH (2) → rejected under default thresholds"
+        );
+    }
+
+    #[test]
+    fn test_phase3_dct_phash_distance_6_accepted_with_dct_config() {
+        // Same pair, but matched with a DCT-aware config: distance 6 sits
+        // within PHASH_DCT_HIGH_THRESHOLD (8), so it should group.
+        let photos = vec![
+            make_photo_full(1, "aaa", Some(0b0000_0000), None),
+            make_photo_full(2, "bbb", Some(0b0011_1111), None), // phash dist 6, no dhash
+        ];
+
+        let config = MatchingConfig {
+            // Widened so the BK-tree search radius itself reaches the
+            // distance-6 neighbor; PHASH_DCT_HIGH_THRESHOLD governs the
+            // stricter cross-format confidence check below it.
+            probable_threshold: confidence::PHASH_DCT_HIGH_THRESHOLD,
+            ..MatchingConfig::for_alg(HashAlg::Dct)
+        };
+        let groups = find_duplicates_with_config(&photos, &config);
+        assert_eq!(
+            groups.len(),
+            1,
+            "Cross-format phash dist 6 <= PHASH_DCT_HIGH_THRESHOLD → accepted under DCT config"
+        );
+    }
+
     #[test]
     fn test_phase3_both_dhash_none_uses_high_threshold() {
         // Both photos lack dhash → cross-format path, HIGH threshold.
@@ -1767,7 +2415,7 @@ mod tests {
             MatchGroup { member_ids: vec![1, 2], confidence: Confidence::Certain },
         ];
 
-        let merged = merge_overlapping(&mut groups, &photos);
+        let merged = merge_overlapping(&mut groups, &photos, &MatchingConfig::default());
         assert_eq!(merged.len(), 1);
         assert_eq!(merged[0].member_ids.len(), 3);
     }
@@ -1787,7 +2435,7 @@ mod tests {
             MatchGroup { member_ids: vec![2, 4], confidence: Confidence::NearCertain },
         ];
 
-        let merged = merge_overlapping(&mut groups, &photos);
+        let merged = merge_overlapping(&mut groups, &photos, &MatchingConfig::default());
         assert_eq!(merged.len(), 1, "Single bridge photo merges all");
         assert_eq!(merged[0].member_ids.len(), 4);
     }
@@ -1806,7 +2454,7 @@ mod tests {
             MatchGroup { member_ids: vec![2, 3], confidence: Confidence::High },
         ];
 
-        let merged = merge_overlapping(&mut groups, &photos);
+        let merged = merge_overlapping(&mut groups, &photos, &MatchingConfig::default());
         assert_eq!(merged.len(), 1, "No phash on exclusive side → allow merge");
     }
 
@@ -2073,6 +2721,44 @@ mod tests {
         assert_eq!(groups.len(), 1, "Recompressed JPEG should group by perceptual hash");
     }
 
+    #[test]
+    fn test_full_pipeline_recompressed_jpeg_grouped_via_dct_config() {
+        // Same recompressed-JPEG setup as
+        // `test_full_pipeline_recompressed_jpeg_different_sha`, but pushed to
+        // a phash distance (5) that the default (Mean/aHash-style) thresholds
+        // would reject outright — a "borderline" recompression this request
+        // calls out — while the DCT-aware config still accepts it, since a
+        // DCT pHash tolerates JPEG requantization at much larger distances.
+        let photos = vec![
+            {
+                let mut p = make_photo_full(1, "sha_hq", Some(0b0000_0000), None);
+                p.size = 5_000_000;
+                p
+            },
+            {
+                let mut p = make_photo_full(2, "sha_lq", Some(0b0001_1111), None); // phash dist 5
+                p.size = 1_000_000;
+                p
+            },
+        ];
+
+        assert!(
+            find_duplicates(&photos).is_empty(),
+            "distance 5 should be rejected under the default (non-DCT) thresholds"
+        );
+
+        let config = MatchingConfig {
+            probable_threshold: confidence::PHASH_DCT_HIGH_THRESHOLD,
+            ..MatchingConfig::for_alg(HashAlg::Dct)
+        };
+        let groups = find_duplicates_with_config(&photos, &config);
+        assert_eq!(
+            groups.len(),
+            1,
+            "a DCT-aware config should still group a recompressed JPEG at distance 5"
+        );
+    }
+
     #[test]
     fn test_full_pipeline_no_false_merge_across_visually_different_groups() {
         // Group A: photos 1,2 (same SHA). Group B: photos 3,4 (same SHA).
@@ -2089,4 +2775,98 @@ mod tests {
         let groups = find_duplicates(&photos);
         assert_eq!(groups.len(), 2, "Visually unrelated SHA groups must stay separate");
     }
+
+    #[test]
+    fn test_full_pipeline_large_library_many_distinct_clusters() {
+        // 60 widely-spaced near-duplicate pairs (no SHA-256 or EXIF overlap), so
+        // every group is found purely by the BK-tree-backed Phase 3 lookup.
+        // Exercises that the tree finds each pair correctly at a scale where a
+        // mis-pruned search (wrong `[dist-d, dist+d]` child range) would start
+        // dropping or merging clusters, even though brute-force pairwise would
+        // still happen to work.
+        let mut photos = Vec::new();
+        for i in 0..60u64 {
+            let base = i * 1000; // spaced far apart: no cross-cluster collisions
+            photos.push(make_photo(i as i64 * 2 + 1, &format!("sha_{i}_a"), Some(base)));
+            photos.push(make_photo(i as i64 * 2 + 2, &format!("sha_{i}_b"), Some(base + 1)));
+        }
+
+        let groups = find_duplicates(&photos);
+        assert_eq!(groups.len(), 60, "Should find all 60 near-duplicate pairs");
+        for group in &groups {
+            assert_eq!(group.member_ids.len(), 2);
+            assert_eq!(group.confidence, Confidence::NearCertain);
+        }
+    }
+
+    #[test]
+    fn test_full_pipeline_same_name_and_size_no_hash_groups_as_low() {
+        // No SHA overlap, no EXIF, no perceptual hash at all (e.g. an
+        // undecodable format) — only the filename stem and byte size match.
+        // Phase 3.5 should catch this as a Low-confidence group.
+        let photos = vec![
+            {
+                let mut p = make_photo(1, "sha_a", None);
+                p.path = "photos/IMG_1234.jpeg".into();
+                p.size = 2_000_000;
+                p
+            },
+            {
+                let mut p = make_photo(2, "sha_b", None);
+                p.path = "backup/IMG_1234.jpeg".into();
+                p.size = 2_000_000;
+                p
+            },
+        ];
+
+        let groups = find_duplicates(&photos);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].confidence, Confidence::Low);
+        assert_eq!(groups[0].member_ids.len(), 2);
+    }
+
+    #[test]
+    fn test_full_pipeline_same_name_different_size_does_not_group() {
+        let photos = vec![
+            {
+                let mut p = make_photo(1, "sha_a", None);
+                p.path = "photos/IMG_1234.jpeg".into();
+                p.size = 2_000_000;
+                p
+            },
+            {
+                let mut p = make_photo(2, "sha_b", None);
+                p.path = "backup/IMG_1234.jpeg".into();
+                p.size = 3_000_000;
+                p
+            },
+        ];
+
+        assert!(find_duplicates(&photos).is_empty());
+    }
+
+    #[test]
+    fn test_full_pipeline_name_size_fallback_skips_already_grouped_photos() {
+        // Same stem + size AND same SHA-256 — Phase 1 already groups these as
+        // Certain, so Phase 3.5 must not also emit a separate Low-confidence
+        // group for the same pair.
+        let photos = vec![
+            {
+                let mut p = make_photo(1, "same_sha", None);
+                p.path = "photos/IMG_1234.jpeg".into();
+                p.size = 2_000_000;
+                p
+            },
+            {
+                let mut p = make_photo(2, "same_sha", None);
+                p.path = "backup/IMG_1234.jpeg".into();
+                p.size = 2_000_000;
+                p
+            },
+        ];
+
+        let groups = find_duplicates(&photos);
+        assert_eq!(groups.len(), 1, "must not double-group the same pair");
+        assert_eq!(groups[0].confidence, Confidence::Certain);
+    }
 }
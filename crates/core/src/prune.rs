@@ -0,0 +1,368 @@
+//! Retention/prune policy for the vault's `YYYY/MM/DD` display tree.
+//!
+//! Modeled on the classic keep-daily/weekly/monthly/yearly backup rotation
+//! scheme (e.g. `restic --keep-daily`): walking newest-to-oldest, the first
+//! file encountered in each not-yet-filled period bucket is kept, and
+//! whatever's left over is a removal candidate. `plan_prune` only computes
+//! the plan; nothing is deleted until it's handed to `apply_prune`.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::Datelike;
+use walkdir::WalkDir;
+
+use crate::error::Result;
+use crate::vault_save::VaultSaveProgress;
+
+/// How many of the most recent buckets at each granularity to keep. A zero
+/// field disables that granularity entirely. `keep_last` keeps the N most
+/// recently dated files outright, independent of the bucket rules below.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PrunePolicy {
+    pub keep_last: usize,
+    pub keep_daily: usize,
+    pub keep_weekly: usize,
+    pub keep_monthly: usize,
+    pub keep_yearly: usize,
+}
+
+/// One file's fate under a `PrunePolicy`, as computed by `plan_prune`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrunePlanEntry {
+    pub path: PathBuf,
+    pub date: (u32, u32, u32),
+    /// Whether this file survives the policy.
+    pub keep: bool,
+    /// Which rule kept it (e.g. `"keep_daily"`), or `None` for a removal candidate.
+    pub reason: Option<String>,
+}
+
+/// Parse (year, month, day) from the first three path components under
+/// `vault_path` — the way `vault_save::build_target_path` writes them.
+/// Returns `None` if they don't parse as a clean `YYYY/MM/DD` folder (e.g.
+/// a stray file dropped directly under the vault root), in which case the
+/// caller falls back to the file's own mtime as the date authority.
+fn date_from_path(vault_path: &Path, path: &Path) -> Option<(u32, u32, u32)> {
+    let rel = path.strip_prefix(vault_path).ok()?;
+    let mut components = rel.components();
+    let year: u32 = components.next()?.as_os_str().to_str()?.parse().ok()?;
+    let month: u32 = components.next()?.as_os_str().to_str()?.parse().ok()?;
+    let day: u32 = components.next()?.as_os_str().to_str()?.parse().ok()?;
+    if !(1970..=2100).contains(&year) || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    Some((year, month, day))
+}
+
+/// Mirrors `vault_save::date_for_photo`'s own mtime fallback: the authority
+/// of last resort when a file's date can't be read from its path.
+fn date_from_mtime(mtime: i64) -> (u32, u32, u32) {
+    let dt = chrono::DateTime::from_timestamp(mtime, 0)
+        .unwrap_or_else(|| chrono::DateTime::from_timestamp(0, 0).unwrap());
+    (dt.year() as u32, dt.month(), dt.day())
+}
+
+/// ISO (year, week) for a (year, month, day) triple, used as the weekly
+/// bucket key so a week spanning a year boundary buckets correctly.
+fn iso_week(date: (u32, u32, u32)) -> (i32, u32) {
+    let (year, month, day) = date;
+    chrono::NaiveDate::from_ymd_opt(year as i32, month, day)
+        .map(|d| {
+            let week = d.iso_week();
+            (week.year(), week.week())
+        })
+        .unwrap_or((year as i32, 0))
+}
+
+/// Walk `vault_path`'s display tree (`objects/` excluded — it holds the
+/// content-addressed blobs, not display files) and compute which files a
+/// `policy` would keep. A dry run: nothing on disk is touched. Pass the
+/// result to `apply_prune` to actually delete the removal candidates.
+pub fn plan_prune(vault_path: &Path, policy: &PrunePolicy) -> Result<Vec<PrunePlanEntry>> {
+    let objects_dir = vault_path.join("objects");
+
+    let mut entries: Vec<(PathBuf, (u32, u32, u32), i64)> = Vec::new();
+    for entry in WalkDir::new(vault_path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+    {
+        let path = entry.path();
+        if path.starts_with(&objects_dir) {
+            continue;
+        }
+
+        let mtime = entry
+            .metadata()
+            .ok()
+            .and_then(|m| m.modified().ok())
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        let date = date_from_path(vault_path, path).unwrap_or_else(|| date_from_mtime(mtime));
+
+        entries.push((path.to_path_buf(), date, mtime));
+    }
+
+    // Newest first — date is the primary order, mtime breaks ties within a day.
+    entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| b.2.cmp(&a.2)));
+
+    let mut kept: HashSet<PathBuf> = HashSet::new();
+    let mut reason: HashMap<PathBuf, String> = HashMap::new();
+
+    for (path, _, _) in entries.iter().take(policy.keep_last) {
+        kept.insert(path.clone());
+        reason.entry(path.clone()).or_insert_with(|| "keep_last".to_string());
+    }
+
+    let mut seen_days: HashSet<(u32, u32, u32)> = HashSet::new();
+    let mut quota = policy.keep_daily;
+    for (path, date, _) in &entries {
+        if quota == 0 {
+            break;
+        }
+        if seen_days.insert(*date) {
+            kept.insert(path.clone());
+            reason.entry(path.clone()).or_insert_with(|| "keep_daily".to_string());
+            quota -= 1;
+        }
+    }
+
+    let mut seen_weeks: HashSet<(i32, u32)> = HashSet::new();
+    let mut quota = policy.keep_weekly;
+    for (path, date, _) in &entries {
+        if quota == 0 {
+            break;
+        }
+        if seen_weeks.insert(iso_week(*date)) {
+            kept.insert(path.clone());
+            reason.entry(path.clone()).or_insert_with(|| "keep_weekly".to_string());
+            quota -= 1;
+        }
+    }
+
+    let mut seen_months: HashSet<(u32, u32)> = HashSet::new();
+    let mut quota = policy.keep_monthly;
+    for (path, date, _) in &entries {
+        if quota == 0 {
+            break;
+        }
+        if seen_months.insert((date.0, date.1)) {
+            kept.insert(path.clone());
+            reason.entry(path.clone()).or_insert_with(|| "keep_monthly".to_string());
+            quota -= 1;
+        }
+    }
+
+    let mut seen_years: HashSet<u32> = HashSet::new();
+    let mut quota = policy.keep_yearly;
+    for (path, date, _) in &entries {
+        if quota == 0 {
+            break;
+        }
+        if seen_years.insert(date.0) {
+            kept.insert(path.clone());
+            reason.entry(path.clone()).or_insert_with(|| "keep_yearly".to_string());
+            quota -= 1;
+        }
+    }
+
+    Ok(entries
+        .into_iter()
+        .map(|(path, date, _)| {
+            let keep = kept.contains(&path);
+            let reason = if keep { reason.remove(&path) } else { None };
+            PrunePlanEntry {
+                path,
+                date,
+                keep,
+                reason,
+            }
+        })
+        .collect())
+}
+
+/// Delete every `plan` entry not marked `keep`, emitting the same
+/// `VaultSaveProgress::Removed` event `cleanup_superseded_vault_files` uses
+/// for its removals, so a progress bar wired to `vault sync` can drive
+/// `vault prune` too. Returns the number of files actually removed.
+pub fn apply_prune(
+    plan: &[PrunePlanEntry],
+    mut progress_cb: Option<&mut dyn FnMut(VaultSaveProgress)>,
+) -> Result<usize> {
+    let mut removed = 0;
+    for entry in plan {
+        if entry.keep {
+            continue;
+        }
+        if fs::remove_file(&entry.path).is_ok() {
+            removed += 1;
+            if let Some(ref mut cb) = progress_cb {
+                cb(VaultSaveProgress::Removed {
+                    path: entry.path.clone(),
+                });
+            }
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(path: &Path) {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        fs::write(path, b"x").unwrap();
+    }
+
+    #[test]
+    fn test_date_from_path_parses_clean_ymd() {
+        let vault = PathBuf::from("/vault");
+        let path = vault.join("2024/06/15/photo.jpg");
+        assert_eq!(date_from_path(&vault, &path), Some((2024, 6, 15)));
+    }
+
+    #[test]
+    fn test_date_from_path_rejects_non_numeric_component() {
+        let vault = PathBuf::from("/vault");
+        let path = vault.join("objects/ab/cdef");
+        assert_eq!(date_from_path(&vault, &path), None);
+    }
+
+    #[test]
+    fn test_date_from_path_rejects_out_of_range_month() {
+        let vault = PathBuf::from("/vault");
+        let path = vault.join("2024/13/01/photo.jpg");
+        assert_eq!(date_from_path(&vault, &path), None);
+    }
+
+    #[test]
+    fn test_plan_prune_keeps_newest_under_keep_last() {
+        let tmp = tempfile::tempdir().unwrap();
+        let vault = tmp.path();
+        touch(&vault.join("2024/01/01/a.jpg"));
+        touch(&vault.join("2024/01/02/b.jpg"));
+        touch(&vault.join("2024/01/03/c.jpg"));
+
+        let policy = PrunePolicy {
+            keep_last: 1,
+            ..Default::default()
+        };
+        let plan = plan_prune(vault, &policy).unwrap();
+
+        let kept: Vec<&PrunePlanEntry> = plan.iter().filter(|e| e.keep).collect();
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].date, (2024, 1, 3));
+        assert_eq!(kept[0].reason.as_deref(), Some("keep_last"));
+    }
+
+    #[test]
+    fn test_plan_prune_keep_daily_keeps_one_per_day() {
+        let tmp = tempfile::tempdir().unwrap();
+        let vault = tmp.path();
+        touch(&vault.join("2024/01/01/morning.jpg"));
+        touch(&vault.join("2024/01/01/evening.jpg"));
+        touch(&vault.join("2024/01/02/a.jpg"));
+
+        let policy = PrunePolicy {
+            keep_daily: 2,
+            ..Default::default()
+        };
+        let plan = plan_prune(vault, &policy).unwrap();
+
+        let kept: Vec<&PrunePlanEntry> = plan.iter().filter(|e| e.keep).collect();
+        assert_eq!(kept.len(), 2, "one kept per distinct day, up to the quota");
+        let kept_days: HashSet<(u32, u32, u32)> = kept.iter().map(|e| e.date).collect();
+        assert_eq!(kept_days.len(), 2);
+    }
+
+    #[test]
+    fn test_plan_prune_keep_monthly_keeps_newest_per_month() {
+        let tmp = tempfile::tempdir().unwrap();
+        let vault = tmp.path();
+        touch(&vault.join("2024/01/05/a.jpg"));
+        touch(&vault.join("2024/01/20/b.jpg"));
+        touch(&vault.join("2024/02/10/c.jpg"));
+
+        let policy = PrunePolicy {
+            keep_monthly: 10,
+            ..Default::default()
+        };
+        let plan = plan_prune(vault, &policy).unwrap();
+
+        let jan_kept: Vec<&PrunePlanEntry> = plan
+            .iter()
+            .filter(|e| e.keep && e.date.0 == 2024 && e.date.1 == 1)
+            .collect();
+        assert_eq!(jan_kept.len(), 1, "only the newest January file is kept");
+        assert_eq!(jan_kept[0].date, (2024, 1, 20));
+    }
+
+    #[test]
+    fn test_plan_prune_excludes_objects_dir() {
+        let tmp = tempfile::tempdir().unwrap();
+        let vault = tmp.path();
+        touch(&vault.join("objects/ab/cdefabc"));
+        touch(&vault.join("2024/01/01/a.jpg"));
+
+        let plan = plan_prune(vault, &PrunePolicy::default()).unwrap();
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan[0].date, (2024, 1, 1));
+    }
+
+    #[test]
+    fn test_plan_prune_default_policy_keeps_nothing() {
+        let tmp = tempfile::tempdir().unwrap();
+        let vault = tmp.path();
+        touch(&vault.join("2024/01/01/a.jpg"));
+
+        let plan = plan_prune(vault, &PrunePolicy::default()).unwrap();
+        assert!(plan.iter().all(|e| !e.keep));
+    }
+
+    #[test]
+    fn test_apply_prune_deletes_only_non_kept_entries() {
+        let tmp = tempfile::tempdir().unwrap();
+        let vault = tmp.path();
+        let keep_path = vault.join("2024/01/02/keep.jpg");
+        let remove_path = vault.join("2024/01/01/remove.jpg");
+        touch(&keep_path);
+        touch(&remove_path);
+
+        let plan = vec![
+            PrunePlanEntry {
+                path: keep_path.clone(),
+                date: (2024, 1, 2),
+                keep: true,
+                reason: Some("keep_last".to_string()),
+            },
+            PrunePlanEntry {
+                path: remove_path.clone(),
+                date: (2024, 1, 1),
+                keep: false,
+                reason: None,
+            },
+        ];
+
+        let mut removed_events: Vec<PathBuf> = Vec::new();
+        let removed = apply_prune(
+            &plan,
+            Some(&mut |progress| {
+                if let VaultSaveProgress::Removed { path } = progress {
+                    removed_events.push(path);
+                }
+            }),
+        )
+        .unwrap();
+
+        assert_eq!(removed, 1);
+        assert!(keep_path.exists());
+        assert!(!remove_path.exists());
+        assert_eq!(removed_events, vec![remove_path]);
+    }
+}
@@ -0,0 +1,277 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::domain::PhotoFile;
+use crate::error::{Error, Result};
+
+/// What to do with a duplicate group's non-canonical members. The
+/// `source_of_truth_id` member is always left untouched.
+#[derive(Debug, Clone)]
+pub enum Resolution {
+    /// Delete the file from disk (and its catalog row).
+    Delete,
+    /// Relocate the file under `target`, preserving its original absolute
+    /// path layout so members from different sources never collide.
+    MoveTo(PathBuf),
+    /// Replace the file with a hard link to the source of truth. Fails with
+    /// `Error::CrossFilesystemLink` if the two paths aren't on the same
+    /// filesystem — unlike `vault_save::copy_photo_to_vault`, this never
+    /// silently falls back to a copy, since the whole point is reclaiming space.
+    /// The catalog's recorded hash for the file is left as-is; a later `scan`
+    /// naturally resyncs it once the new (identical) bytes are rehashed.
+    HardLink,
+    /// Replace the file with a symlink to the source of truth. Same catalog
+    /// resync caveat as `HardLink`.
+    SymLink,
+}
+
+/// Tally of a resolution run (real or dry-run), returned per-group and
+/// summed across a batch by `Vault::resolve_all`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResolutionReport {
+    pub files_affected: usize,
+    pub bytes_reclaimed: u64,
+}
+
+impl ResolutionReport {
+    pub fn merge(&mut self, other: ResolutionReport) {
+        self.files_affected += other.files_affected;
+        self.bytes_reclaimed += other.bytes_reclaimed;
+    }
+}
+
+/// Destination path for `MoveTo`: `member_path` with its leading root
+/// stripped and rejoined under `target`.
+pub fn move_target_path(target: &Path, member_path: &Path) -> PathBuf {
+    let relative = member_path.strip_prefix("/").unwrap_or(member_path);
+    target.join(relative)
+}
+
+/// Apply `resolution` to a single non-source-of-truth member, returning the
+/// report entry for it (and, for `MoveTo`, the path it ended up at). A no-op
+/// on disk when `dry_run` is set — the report is still computed so callers
+/// can preview the outcome.
+pub fn resolve_member(
+    member: &PhotoFile,
+    sot_path: &Path,
+    resolution: &Resolution,
+    dry_run: bool,
+) -> Result<(ResolutionReport, Option<PathBuf>)> {
+    let mut new_path = None;
+
+    if !dry_run {
+        match resolution {
+            Resolution::Delete => {
+                fs::remove_file(&member.path)?;
+            }
+            Resolution::MoveTo(target) => {
+                let dest = move_target_path(target, &member.path);
+                if let Some(parent) = dest.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                if fs::rename(&member.path, &dest).is_err() {
+                    fs::copy(&member.path, &dest)?;
+                    fs::remove_file(&member.path)?;
+                }
+                new_path = Some(dest);
+            }
+            Resolution::HardLink => {
+                // Link into a sibling temp name, then rename into place, so the
+                // original is never removed until the link actually exists — a
+                // failed cross-filesystem link (the case this is built to
+                // detect) or a crash mid-resolve leaves `member.path` intact
+                // rather than destroying the file. Same idiom as
+                // `vault_save::make_hard_link`, but without its copy fallback:
+                // a "failed" resolve must not silently turn into a copy.
+                let tmp_path = member.path.with_extension("lsvault-resolve-tmp");
+                if fs::hard_link(sot_path, &tmp_path).is_err() {
+                    let _ = fs::remove_file(&tmp_path);
+                    return Err(Error::CrossFilesystemLink {
+                        path: member.path.clone(),
+                    });
+                }
+                fs::rename(&tmp_path, &member.path)?;
+            }
+            Resolution::SymLink => {
+                // Same tmp-then-rename ordering as `HardLink`: the original is
+                // only replaced once the symlink exists. On non-unix targets
+                // there's no symlink to create at all, so fail loudly instead
+                // of deleting the file and reporting a bogus success.
+                #[cfg(unix)]
+                {
+                    let tmp_path = member.path.with_extension("lsvault-resolve-tmp");
+                    std::os::unix::fs::symlink(sot_path, &tmp_path)?;
+                    fs::rename(&tmp_path, &member.path)?;
+                }
+                #[cfg(not(unix))]
+                {
+                    return Err(Error::SymlinkUnsupported {
+                        path: member.path.clone(),
+                    });
+                }
+            }
+        }
+    } else if let Resolution::MoveTo(target) = resolution {
+        new_path = Some(move_target_path(target, &member.path));
+    }
+
+    Ok((
+        ResolutionReport {
+            files_affected: 1,
+            bytes_reclaimed: member.size,
+        },
+        new_path,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_member(id: i64, path: &Path, size: u64) -> PhotoFile {
+        PhotoFile {
+            id,
+            source_id: 1,
+            path: path.to_path_buf(),
+            size,
+            format: crate::domain::PhotoFormat::Jpeg,
+            sha256: "abc".to_string(),
+            phash: None,
+            dhash: None,
+            ahash: None,
+            exif: None,
+            mtime: 0,
+        }
+    }
+
+    #[test]
+    fn test_move_target_path_preserves_layout() {
+        let target = Path::new("/dest");
+        let member = Path::new("/a/b/c/photo.jpg");
+        assert_eq!(move_target_path(target, member), PathBuf::from("/dest/a/b/c/photo.jpg"));
+    }
+
+    #[test]
+    fn test_resolve_delete_removes_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("dup.jpg");
+        fs::write(&path, b"dup bytes").unwrap();
+        let member = make_member(2, &path, 9);
+
+        let (report, new_path) =
+            resolve_member(&member, Path::new("/sot.jpg"), &Resolution::Delete, false).unwrap();
+
+        assert!(!path.exists());
+        assert_eq!(report.files_affected, 1);
+        assert_eq!(report.bytes_reclaimed, 9);
+        assert!(new_path.is_none());
+    }
+
+    #[test]
+    fn test_resolve_dry_run_does_not_touch_disk() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("dup.jpg");
+        fs::write(&path, b"dup bytes").unwrap();
+        let member = make_member(2, &path, 9);
+
+        let (report, _) =
+            resolve_member(&member, Path::new("/sot.jpg"), &Resolution::Delete, true).unwrap();
+
+        assert!(path.exists(), "dry run must not delete the file");
+        assert_eq!(report.files_affected, 1);
+    }
+
+    #[test]
+    fn test_resolve_move_to_preserves_layout_and_creates_dirs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let source_dir = tmp.path().join("source_root");
+        fs::create_dir_all(source_dir.join("nested")).unwrap();
+        let path = source_dir.join("nested").join("dup.jpg");
+        fs::write(&path, b"dup bytes").unwrap();
+        let member = make_member(2, &path, 9);
+
+        let target = tmp.path().join("archive");
+        let (_, new_path) = resolve_member(
+            &member,
+            Path::new("/sot.jpg"),
+            &Resolution::MoveTo(target.clone()),
+            false,
+        )
+        .unwrap();
+
+        let dest = new_path.unwrap();
+        assert!(dest.exists());
+        assert!(!path.exists());
+        assert!(dest.starts_with(&target));
+    }
+
+    #[test]
+    fn test_resolve_hard_link_replaces_duplicate() {
+        let tmp = tempfile::tempdir().unwrap();
+        let sot_path = tmp.path().join("sot.jpg");
+        fs::write(&sot_path, b"canonical bytes").unwrap();
+        let dup_path = tmp.path().join("dup.jpg");
+        fs::write(&dup_path, b"dup bytes").unwrap();
+        let member = make_member(2, &dup_path, 9);
+
+        resolve_member(&member, &sot_path, &Resolution::HardLink, false).unwrap();
+
+        let sot_meta = fs::metadata(&sot_path).unwrap();
+        let dup_meta = fs::metadata(&dup_path).unwrap();
+        assert_eq!(sot_meta.len(), dup_meta.len());
+        assert_eq!(fs::read(&dup_path).unwrap(), b"canonical bytes");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_sym_link_replaces_duplicate() {
+        let tmp = tempfile::tempdir().unwrap();
+        let sot_path = tmp.path().join("sot.jpg");
+        fs::write(&sot_path, b"canonical bytes").unwrap();
+        let dup_path = tmp.path().join("dup.jpg");
+        fs::write(&dup_path, b"dup bytes").unwrap();
+        let member = make_member(2, &dup_path, 9);
+
+        resolve_member(&member, &sot_path, &Resolution::SymLink, false).unwrap();
+
+        assert_eq!(fs::read_link(&dup_path).unwrap(), sot_path);
+    }
+
+    #[test]
+    fn test_resolve_hard_link_failure_leaves_duplicate_untouched() {
+        // sot_path doesn't exist, so fs::hard_link fails — this stands in for
+        // the cross-filesystem case without needing two real filesystems in
+        // the test sandbox. The duplicate must survive a failed link.
+        let tmp = tempfile::tempdir().unwrap();
+        let sot_path = tmp.path().join("missing_sot.jpg");
+        let dup_path = tmp.path().join("dup.jpg");
+        fs::write(&dup_path, b"dup bytes").unwrap();
+        let member = make_member(2, &dup_path, 9);
+
+        let result = resolve_member(&member, &sot_path, &Resolution::HardLink, false);
+
+        assert!(result.is_err());
+        assert!(dup_path.exists(), "a failed link must not destroy the original file");
+        assert_eq!(fs::read(&dup_path).unwrap(), b"dup bytes");
+        assert!(
+            !tmp.path().join("dup.lsvault-resolve-tmp").exists(),
+            "a failed link must not leave a tmp artifact behind"
+        );
+    }
+
+    #[cfg(not(unix))]
+    #[test]
+    fn test_resolve_sym_link_errors_on_non_unix_instead_of_deleting() {
+        let tmp = tempfile::tempdir().unwrap();
+        let sot_path = tmp.path().join("sot.jpg");
+        fs::write(&sot_path, b"canonical bytes").unwrap();
+        let dup_path = tmp.path().join("dup.jpg");
+        fs::write(&dup_path, b"dup bytes").unwrap();
+        let member = make_member(2, &dup_path, 9);
+
+        let result = resolve_member(&member, &sot_path, &Resolution::SymLink, false);
+
+        assert!(result.is_err());
+        assert!(dup_path.exists(), "a non-unix SymLink attempt must not delete the original file");
+    }
+}
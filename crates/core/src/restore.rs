@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+use crate::hasher::compute_sha256;
+use crate::manifest::Manifest;
+use crate::vault_save::object_path_for;
+
+/// Progress callback events for `restore_vault`, paralleling
+/// `vault_save::VaultSaveProgress` for the inverse operation.
+pub enum RestoreProgress {
+    /// Starting restore with the number of manifest entries to process.
+    Start { total: usize },
+    /// An object was re-hashed, matched its recorded digest, and copied out.
+    Restored { hash: String, target: PathBuf },
+    /// An object's content no longer matches its recorded digest — it was
+    /// not restored, but the rest of the vault may still be intact.
+    Corrupt { hash: String },
+    /// Restore completed.
+    Complete { restored: usize, corrupt: usize },
+}
+
+/// Result of a restore pass.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RestoreReport {
+    /// Files written into `target_dir`.
+    pub restored: Vec<PathBuf>,
+    /// Hashes whose object no longer matches its recorded digest.
+    pub corrupt: Vec<String>,
+    /// Destination paths that already existed and were left untouched.
+    pub skipped: Vec<PathBuf>,
+}
+
+/// Rebuild `target_dir` from a vault's content-addressed object store and
+/// manifest — the inverse of `vault_save`. For every entry in `manifest`,
+/// re-hash the object at `object_path_for(vault_path, sha256)` and, if it
+/// matches, copy it into `target_dir` under its original filename (from
+/// `manifest.list_filenames`, falling back to the bare hash when no
+/// filename was recorded). An object whose content no longer matches its
+/// recorded digest is reported via `RestoreProgress::Corrupt` and skipped
+/// rather than aborting the whole restore.
+///
+/// Idempotent like `export`: a file already present at the destination path
+/// is left untouched. Every destination path is resolved under `target_dir`
+/// and refused if a reconstructed filename would somehow escape it.
+pub fn restore_vault(
+    vault_path: &Path,
+    target_dir: &Path,
+    manifest: &Manifest,
+    mut progress_cb: Option<&mut dyn FnMut(RestoreProgress)>,
+) -> Result<RestoreReport> {
+    fs::create_dir_all(target_dir)?;
+    let target_dir = target_dir
+        .canonicalize()
+        .unwrap_or_else(|_| target_dir.to_path_buf());
+
+    let entries = manifest.list_entries()?;
+    let filenames: HashMap<String, String> = manifest.list_filenames()?.into_iter().collect();
+
+    let mut report = RestoreReport::default();
+
+    if let Some(ref mut cb) = progress_cb {
+        cb(RestoreProgress::Start {
+            total: entries.len(),
+        });
+    }
+
+    for (sha256, _format) in &entries {
+        let object_path = object_path_for(&vault_path, sha256);
+
+        let matches = matches!(compute_sha256(&object_path), Ok(actual) if &actual == sha256);
+        if !matches {
+            if let Some(ref mut cb) = progress_cb {
+                cb(RestoreProgress::Corrupt {
+                    hash: sha256.clone(),
+                });
+            }
+            report.corrupt.push(sha256.clone());
+            continue;
+        }
+
+        let name = filenames
+            .get(sha256)
+            .and_then(|f| Path::new(f).file_name())
+            .map(|f| f.to_string_lossy().to_string())
+            .unwrap_or_else(|| sha256.clone());
+        let target = target_dir.join(&name);
+        if !target.starts_with(&target_dir) {
+            return Err(Error::ArchiveUnsafePath(name));
+        }
+
+        if target.exists() {
+            report.skipped.push(target);
+            continue;
+        }
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(&object_path, &target)?;
+
+        report.restored.push(target.clone());
+        if let Some(ref mut cb) = progress_cb {
+            cb(RestoreProgress::Restored {
+                hash: sha256.clone(),
+                target,
+            });
+        }
+    }
+
+    if let Some(ref mut cb) = progress_cb {
+        cb(RestoreProgress::Complete {
+            restored: report.restored.len(),
+            corrupt: report.corrupt.len(),
+        });
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::IngestReason;
+
+    /// Hash `content` the same way `vault_save` does, then plant it directly
+    /// at its object path with `filename` recorded in the manifest.
+    fn vault_with_object(content: &[u8], filename: &str) -> (tempfile::TempDir, Manifest, String) {
+        let tmp = tempfile::tempdir().unwrap();
+        let scratch = tmp.path().join("scratch.bin");
+        fs::write(&scratch, content).unwrap();
+        let sha256 = compute_sha256(&scratch).unwrap();
+        fs::remove_file(&scratch).unwrap();
+
+        let manifest = Manifest::open(tmp.path()).unwrap();
+        let gen = manifest.begin_generation(None).unwrap();
+        let object_path = object_path_for(tmp.path(), &sha256);
+        fs::create_dir_all(object_path.parent().unwrap()).unwrap();
+        fs::write(&object_path, content).unwrap();
+        manifest
+            .insert_file(
+                &sha256,
+                filename,
+                "JPEG",
+                content.len() as u64,
+                None,
+                None,
+                None,
+                None,
+                gen,
+                IngestReason::New,
+            )
+            .unwrap();
+        (tmp, manifest, sha256)
+    }
+
+    #[test]
+    fn test_restore_vault_writes_original_filename() {
+        let (tmp, manifest, _) = vault_with_object(b"hello vault", "photo.jpg");
+        let dest = tempfile::tempdir().unwrap();
+
+        let report = restore_vault(tmp.path(), dest.path(), &manifest, None).unwrap();
+        assert_eq!(report.restored, vec![dest.path().join("photo.jpg")]);
+        assert!(report.corrupt.is_empty());
+        assert_eq!(fs::read(dest.path().join("photo.jpg")).unwrap(), b"hello vault");
+    }
+
+    #[test]
+    fn test_restore_vault_detects_corruption() {
+        let (tmp, manifest, sha256) = vault_with_object(b"original bytes", "a.jpg");
+        let object_path = object_path_for(tmp.path(), &sha256);
+        fs::write(&object_path, b"corrupted!").unwrap();
+
+        let dest = tempfile::tempdir().unwrap();
+        let report = restore_vault(tmp.path(), dest.path(), &manifest, None).unwrap();
+        assert!(report.restored.is_empty());
+        assert_eq!(report.corrupt, vec![sha256]);
+        assert!(!dest.path().join("a.jpg").exists());
+    }
+
+    #[test]
+    fn test_restore_vault_is_idempotent() {
+        let (tmp, manifest, _) = vault_with_object(b"same content", "a.jpg");
+        let dest = tempfile::tempdir().unwrap();
+
+        restore_vault(tmp.path(), dest.path(), &manifest, None).unwrap();
+        // Tamper with the restored copy to prove a second run leaves it alone.
+        fs::write(dest.path().join("a.jpg"), b"locally edited").unwrap();
+
+        let report = restore_vault(tmp.path(), dest.path(), &manifest, None).unwrap();
+        assert!(report.restored.is_empty());
+        assert_eq!(report.skipped, vec![dest.path().join("a.jpg")]);
+        assert_eq!(fs::read(dest.path().join("a.jpg")).unwrap(), b"locally edited");
+    }
+
+    #[test]
+    fn test_restore_vault_confines_traversal_filename_to_target_dir() {
+        // The manifest's `original_filename` is attacker-influenced if the
+        // pack itself came from an untrusted source — a directory-traversal
+        // payload must collapse to a plain basename rather than escaping
+        // `target_dir`.
+        let (tmp, manifest, _) = vault_with_object(b"escape me", "../../etc/passwd");
+        let dest = tempfile::tempdir().unwrap();
+
+        let report = restore_vault(tmp.path(), dest.path(), &manifest, None).unwrap();
+        assert_eq!(report.restored, vec![dest.path().join("passwd")]);
+        assert!(dest.path().join("passwd").exists());
+    }
+
+    #[test]
+    fn test_restore_vault_empty_manifest() {
+        let tmp = tempfile::tempdir().unwrap();
+        let manifest = Manifest::open(tmp.path()).unwrap();
+        let dest = tempfile::tempdir().unwrap();
+
+        let report = restore_vault(tmp.path(), dest.path(), &manifest, None).unwrap();
+        assert_eq!(report, RestoreReport::default());
+    }
+}
@@ -0,0 +1,388 @@
+//! Hardened ingestion of `.zip`/`.tar`/`.tar.gz` archives as photo sources.
+//!
+//! `Vault::add_source` accepts a path to an archive file in addition to a
+//! plain directory: the archive is extracted once into a managed directory
+//! next to the catalog database, and that directory is registered as the
+//! actual source `scan` walks. Archives are attacker-controllable (a camera
+//! or phone export someone handed you, say), so the same discipline
+//! `archive::unpack_archive_with_limits` and
+//! `tar_archive::unpack_vault_tar_with_limits` apply to vault restores
+//! applies here: path traversal, symlinks, and runaway sizes are all
+//! rejected before a single byte is written, and only regular files and
+//! directories are extracted.
+
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+use std::path::{Component, Path, PathBuf};
+
+use tar::{Archive as TarArchiveReader, EntryType};
+use zip::ZipArchive;
+
+use crate::error::{Error, Result};
+
+/// Ceilings `ingest_source_archive_with_limits` enforces against the
+/// archive's untrusted contents before writing a single byte — the same
+/// approach `archive::ArchiveLimits` and `tar_archive::TarArchiveLimits`
+/// take for the vault's own pack formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceArchiveLimits {
+    /// Reject the archive once the running sum of entries' sizes would
+    /// exceed this.
+    pub max_total_uncompressed_size: u64,
+    /// Reject the archive once more than this many entries have been seen.
+    pub max_entries: usize,
+    /// Reject any single entry whose size exceeds this.
+    pub max_entry_uncompressed_size: u64,
+}
+
+impl Default for SourceArchiveLimits {
+    fn default() -> Self {
+        Self {
+            max_total_uncompressed_size: 500 * 1024 * 1024 * 1024,
+            max_entries: 1_000_000,
+            max_entry_uncompressed_size: 10 * 1024 * 1024 * 1024,
+        }
+    }
+}
+
+/// Whether `path`'s extension marks it as a source archive `add_source`
+/// should unpack rather than register directly.
+pub fn is_source_archive(path: &Path) -> bool {
+    let name = path.to_string_lossy().to_lowercase();
+    name.ends_with(".zip") || name.ends_with(".tar") || name.ends_with(".tar.gz") || name.ends_with(".tgz")
+}
+
+/// Extract `archive_path` into a fresh subdirectory of `dest_root` under
+/// `SourceArchiveLimits::default()`. See `ingest_source_archive_with_limits`.
+pub fn ingest_source_archive(archive_path: &Path, dest_root: &Path) -> Result<PathBuf> {
+    ingest_source_archive_with_limits(archive_path, dest_root, SourceArchiveLimits::default())
+}
+
+/// Extract `archive_path` (`.zip`, `.tar`, or `.tar.gz`/`.tgz`) into a fresh
+/// subdirectory of `dest_root` named after the archive's file stem, enforcing
+/// `limits` and rejecting unsafe entries before any bytes are written.
+/// Returns the directory the files were extracted into, ready to hand to
+/// `Catalog::add_source`.
+pub fn ingest_source_archive_with_limits(
+    archive_path: &Path,
+    dest_root: &Path,
+    limits: SourceArchiveLimits,
+) -> Result<PathBuf> {
+    let name = archive_path.to_string_lossy().to_lowercase();
+    let stem = archive_stem(archive_path);
+    let dest = unique_dest_dir(dest_root, &stem);
+    fs::create_dir_all(&dest)?;
+
+    if name.ends_with(".zip") {
+        extract_zip(archive_path, &dest, limits)?;
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        extract_tar_gz(archive_path, &dest, limits)?;
+    } else if name.ends_with(".tar") {
+        extract_tar(archive_path, &dest, limits)?;
+    } else {
+        return Err(Error::UnsupportedArchiveFormat(archive_path.to_path_buf()));
+    }
+
+    Ok(dest)
+}
+
+/// The archive's file stem with any compression suffix stripped, e.g.
+/// `"holiday.tar.gz"` -> `"holiday"`, `"holiday.zip"` -> `"holiday"`.
+fn archive_stem(archive_path: &Path) -> String {
+    let file_name = archive_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "archive".to_string());
+    for suffix in [".tar.gz", ".tgz", ".tar", ".zip"] {
+        if let Some(stripped) = file_name.to_lowercase().strip_suffix(suffix) {
+            return file_name[..stripped.len()].to_string();
+        }
+    }
+    file_name
+}
+
+/// Pick `dest_root/stem`, or `dest_root/stem-N` for the first `N` that
+/// doesn't already exist, so importing the same archive twice (or two
+/// archives sharing a stem) doesn't silently merge their contents.
+fn unique_dest_dir(dest_root: &Path, stem: &str) -> PathBuf {
+    let mut candidate = dest_root.join(stem);
+    let mut n = 1u32;
+    while candidate.exists() {
+        candidate = dest_root.join(format!("{stem}-{n}"));
+        n += 1;
+    }
+    candidate
+}
+
+/// Resolve `relative` against `dest`, rejecting a `..` component, an
+/// absolute path, or anything else besides a plain relative path confined
+/// to `dest` — the same check `archive::safe_extraction_target` and
+/// `tar_archive::unpack_entries` apply.
+fn safe_target(dest: &Path, relative: &Path) -> Result<PathBuf> {
+    if relative
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(Error::ArchiveUnsafePath(relative.display().to_string()));
+    }
+    let target = dest.join(relative);
+    if !target.starts_with(dest) {
+        return Err(Error::ArchiveUnsafePath(relative.display().to_string()));
+    }
+    Ok(target)
+}
+
+fn extract_zip(archive_path: &Path, dest: &Path, limits: SourceArchiveLimits) -> Result<()> {
+    let file = File::open(archive_path)?;
+    let mut zip = ZipArchive::new(BufReader::new(file))
+        .map_err(|e| Error::ArchiveUnsafePath(format!("not a valid zip archive: {e}")))?;
+
+    if zip.len() > limits.max_entries {
+        return Err(Error::ArchiveLimitExceeded(format!(
+            "{} entries exceeds the limit of {}",
+            zip.len(),
+            limits.max_entries
+        )));
+    }
+
+    let mut total_uncompressed = 0u64;
+    for i in 0..zip.len() {
+        let mut entry = zip
+            .by_index(i)
+            .map_err(|e| Error::ArchiveUnsafePath(format!("corrupt zip entry: {e}")))?;
+
+        // `enclosed_name` already refuses `..` components and absolute
+        // paths; `safe_target` below re-checks so both archive formats go
+        // through one path-safety gate.
+        let Some(relative) = entry.enclosed_name().map(|p| p.to_path_buf()) else {
+            return Err(Error::ArchiveUnsafePath(entry.name().to_string()));
+        };
+
+        if entry.is_symlink() {
+            return Err(Error::ArchiveUnsafePath(relative.display().to_string()));
+        }
+
+        let target = safe_target(dest, &relative)?;
+
+        let entry_size = entry.size();
+        if entry_size > limits.max_entry_uncompressed_size {
+            return Err(Error::ArchiveLimitExceeded(format!(
+                "entry {} is {entry_size} bytes, exceeding the per-entry limit of {}",
+                relative.display(),
+                limits.max_entry_uncompressed_size
+            )));
+        }
+        total_uncompressed = total_uncompressed.saturating_add(entry_size);
+        if total_uncompressed > limits.max_total_uncompressed_size {
+            return Err(Error::ArchiveLimitExceeded(format!(
+                "total uncompressed size exceeds the limit of {} bytes",
+                limits.max_total_uncompressed_size
+            )));
+        }
+
+        if entry.is_dir() {
+            fs::create_dir_all(&target)?;
+            continue;
+        }
+        if !entry.is_file() {
+            return Err(Error::ArchiveUnsafePath(relative.display().to_string()));
+        }
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut out = File::create(&target)?;
+        std::io::copy(&mut entry, &mut out)?;
+    }
+
+    Ok(())
+}
+
+fn extract_tar_gz(archive_path: &Path, dest: &Path, limits: SourceArchiveLimits) -> Result<()> {
+    let file = File::open(archive_path)?;
+    let decoder = flate2::read::GzDecoder::new(BufReader::new(file));
+    let mut archive = TarArchiveReader::new(decoder);
+    extract_tar_entries(&mut archive, dest, limits)
+}
+
+fn extract_tar(archive_path: &Path, dest: &Path, limits: SourceArchiveLimits) -> Result<()> {
+    let file = File::open(archive_path)?;
+    let mut archive = TarArchiveReader::new(BufReader::new(file));
+    extract_tar_entries(&mut archive, dest, limits)
+}
+
+fn extract_tar_entries<R: Read>(
+    archive: &mut TarArchiveReader<R>,
+    dest: &Path,
+    limits: SourceArchiveLimits,
+) -> Result<()> {
+    let mut seen = 0usize;
+    let mut total_uncompressed = 0u64;
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_type = entry.header().entry_type();
+        let relative = entry.path()?.into_owned();
+
+        if !matches!(entry_type, EntryType::Regular | EntryType::Directory) {
+            return Err(Error::ArchiveUnsafePath(relative.display().to_string()));
+        }
+
+        let target = safe_target(dest, &relative)?;
+
+        seen += 1;
+        if seen > limits.max_entries {
+            return Err(Error::ArchiveLimitExceeded(format!(
+                "archive exceeds the limit of {} entries",
+                limits.max_entries
+            )));
+        }
+
+        let entry_size = entry.header().size()?;
+        if entry_size > limits.max_entry_uncompressed_size {
+            return Err(Error::ArchiveLimitExceeded(format!(
+                "entry {} is {entry_size} bytes, exceeding the per-entry limit of {}",
+                relative.display(),
+                limits.max_entry_uncompressed_size
+            )));
+        }
+        total_uncompressed = total_uncompressed.saturating_add(entry_size);
+        if total_uncompressed > limits.max_total_uncompressed_size {
+            return Err(Error::ArchiveLimitExceeded(format!(
+                "total uncompressed size exceeds the limit of {} bytes",
+                limits.max_total_uncompressed_size
+            )));
+        }
+
+        if entry_type.is_dir() {
+            fs::create_dir_all(&target)?;
+            continue;
+        }
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        entry.unpack(&target)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn test_is_source_archive_recognizes_supported_extensions() {
+        assert!(is_source_archive(Path::new("export.zip")));
+        assert!(is_source_archive(Path::new("export.tar")));
+        assert!(is_source_archive(Path::new("export.tar.gz")));
+        assert!(is_source_archive(Path::new("export.tgz")));
+        assert!(!is_source_archive(Path::new("photos")));
+        assert!(!is_source_archive(Path::new("photo.jpg")));
+    }
+
+    #[test]
+    fn test_archive_stem_strips_compression_suffix() {
+        assert_eq!(archive_stem(Path::new("holiday.zip")), "holiday");
+        assert_eq!(archive_stem(Path::new("holiday.tar")), "holiday");
+        assert_eq!(archive_stem(Path::new("holiday.tar.gz")), "holiday");
+        assert_eq!(archive_stem(Path::new("holiday.tgz")), "holiday");
+    }
+
+    #[test]
+    fn test_unique_dest_dir_avoids_collisions() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir(tmp.path().join("holiday")).unwrap();
+        fs::create_dir(tmp.path().join("holiday-1")).unwrap();
+
+        let dest = unique_dest_dir(tmp.path(), "holiday");
+        assert_eq!(dest, tmp.path().join("holiday-2"));
+    }
+
+    #[test]
+    fn test_ingest_zip_extracts_regular_files() {
+        let archive_dir = tempfile::tempdir().unwrap();
+        let archive_path = archive_dir.path().join("export.zip");
+        {
+            let file = File::create(&archive_path).unwrap();
+            let mut zip = zip::ZipWriter::new(file);
+            zip.start_file("a.jpg", zip::write::FileOptions::<()>::default())
+                .unwrap();
+            zip.write_all(b"photo a bytes").unwrap();
+            zip.start_file("nested/b.jpg", zip::write::FileOptions::<()>::default())
+                .unwrap();
+            zip.write_all(b"photo b bytes").unwrap();
+            zip.finish().unwrap();
+        }
+
+        let dest_root = tempfile::tempdir().unwrap();
+        let dest = ingest_source_archive(&archive_path, dest_root.path()).unwrap();
+
+        assert_eq!(fs::read(dest.join("a.jpg")).unwrap(), b"photo a bytes");
+        assert_eq!(fs::read(dest.join("nested/b.jpg")).unwrap(), b"photo b bytes");
+    }
+
+    #[test]
+    fn test_ingest_zip_rejects_path_traversal() {
+        let archive_dir = tempfile::tempdir().unwrap();
+        let archive_path = archive_dir.path().join("evil.zip");
+        {
+            let file = File::create(&archive_path).unwrap();
+            let mut zip = zip::ZipWriter::new(file);
+            zip.start_file("../escape.jpg", zip::write::FileOptions::<()>::default())
+                .unwrap();
+            zip.write_all(b"nope").unwrap();
+            zip.finish().unwrap();
+        }
+
+        let dest_root = tempfile::tempdir().unwrap();
+        let result = ingest_source_archive(&archive_path, dest_root.path());
+        assert!(matches!(result, Err(Error::ArchiveUnsafePath(_))));
+    }
+
+    #[test]
+    fn test_ingest_zip_rejects_over_total_size_limit() {
+        let archive_dir = tempfile::tempdir().unwrap();
+        let archive_path = archive_dir.path().join("big.zip");
+        {
+            let file = File::create(&archive_path).unwrap();
+            let mut zip = zip::ZipWriter::new(file);
+            zip.start_file("a.jpg", zip::write::FileOptions::<()>::default())
+                .unwrap();
+            zip.write_all(b"0123456789").unwrap();
+            zip.finish().unwrap();
+        }
+
+        let dest_root = tempfile::tempdir().unwrap();
+        let limits = SourceArchiveLimits {
+            max_total_uncompressed_size: 5,
+            ..SourceArchiveLimits::default()
+        };
+        let result = ingest_source_archive_with_limits(&archive_path, dest_root.path(), limits);
+        assert!(matches!(result, Err(Error::ArchiveLimitExceeded(_))));
+    }
+
+    #[test]
+    fn test_ingest_tar_gz_extracts_regular_files() {
+        let archive_dir = tempfile::tempdir().unwrap();
+        let archive_path = archive_dir.path().join("export.tar.gz");
+        {
+            let file = File::create(&archive_path).unwrap();
+            let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+            let mut builder = tar::Builder::new(encoder);
+            let src_dir = tempfile::tempdir().unwrap();
+            fs::write(src_dir.path().join("a.jpg"), b"photo a bytes").unwrap();
+            builder
+                .append_path_with_name(src_dir.path().join("a.jpg"), "a.jpg")
+                .unwrap();
+            builder.into_inner().unwrap().finish().unwrap();
+        }
+
+        let dest_root = tempfile::tempdir().unwrap();
+        let dest = ingest_source_archive(&archive_path, dest_root.path()).unwrap();
+        assert_eq!(fs::read(dest.join("a.jpg")).unwrap(), b"photo a bytes");
+    }
+}
@@ -0,0 +1,254 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use walkdir::WalkDir;
+
+use crate::error::Result;
+use crate::hasher::compute_sha256;
+use crate::hasher::perceptual;
+use crate::manifest::Manifest;
+
+/// Per-extension rollup: how many files and how many bytes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtensionStats {
+    pub count: usize,
+    pub bytes: u64,
+}
+
+/// A set of display-tree files that hash to the same SHA-256: exact content
+/// duplicates. `bytes_reclaimable` is the size of every member past the first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExactDuplicateGroup {
+    pub sha256: String,
+    pub paths: Vec<PathBuf>,
+    pub bytes_reclaimable: u64,
+}
+
+/// Vault-wide storage and duplication report, as produced by `compute_vault_stats`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VaultStats {
+    /// Total files under the vault's YYYY/MM/DD display tree.
+    pub total_photos: usize,
+    /// Sum of file sizes across the display tree (logical size, ignoring dedup).
+    pub total_bytes: u64,
+    /// Sum of one representative size per distinct SHA-256 (physical size after dedup).
+    pub unique_bytes: u64,
+    /// `total_bytes - unique_bytes`: space already reclaimable by collapsing exact dupes.
+    pub bytes_reclaimed: u64,
+    /// Groups of display files sharing identical content.
+    pub exact_duplicate_groups: Vec<ExactDuplicateGroup>,
+    /// Clusters of visually-similar singletons (distinct content, close pHash),
+    /// as original filenames. Singletons with no match are omitted.
+    pub near_duplicate_clusters: Vec<Vec<String>>,
+    /// File count and byte total per extension (lowercased, no leading dot).
+    pub by_extension: BTreeMap<String, ExtensionStats>,
+}
+
+/// Scan the vault's display tree (`objects/` and `.photopack/` excluded) and
+/// build a storage/duplication report.
+///
+/// Exact duplicates are found by grouping files by `compute_sha256`: since
+/// `vault_save` hardlinks every display path to one content-addressed object,
+/// two display files with the same hash represent the same photo imported
+/// under different names. Whatever content remains a singleton after that
+/// pass is then clustered by perceptual hash (pulled from the manifest
+/// recorded at `vault_save` time) within `threshold` Hamming distance, to
+/// surface visually similar photos that exact hashing can't catch.
+pub fn compute_vault_stats(
+    vault_path: &Path,
+    manifest: &Manifest,
+    threshold: u32,
+) -> Result<VaultStats> {
+    let objects_dir = vault_path.join("objects");
+    let meta_dir = vault_path.join(".photopack");
+
+    let mut by_sha: HashMap<String, Vec<(PathBuf, u64)>> = HashMap::new();
+    let mut by_extension: BTreeMap<String, ExtensionStats> = BTreeMap::new();
+
+    for entry in WalkDir::new(vault_path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+    {
+        let path = entry.path();
+        if path.starts_with(&objects_dir) || path.starts_with(&meta_dir) {
+            continue;
+        }
+
+        let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+        let ext = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let ext_stats = by_extension.entry(ext).or_default();
+        ext_stats.count += 1;
+        ext_stats.bytes += size;
+
+        if let Ok(sha256) = compute_sha256(path) {
+            by_sha.entry(sha256).or_default().push((path.to_path_buf(), size));
+        }
+    }
+
+    let total_photos = by_sha.values().map(|v| v.len()).sum();
+    let total_bytes: u64 = by_sha.values().flatten().map(|(_, size)| size).sum();
+
+    let mut unique_bytes = 0u64;
+    let mut exact_duplicate_groups = Vec::new();
+    let mut singleton_shas: Vec<String> = Vec::new();
+
+    for (sha256, members) in &by_sha {
+        unique_bytes += members[0].1;
+        if members.len() > 1 {
+            let bytes_reclaimable: u64 = members[1..].iter().map(|(_, size)| size).sum();
+            exact_duplicate_groups.push(ExactDuplicateGroup {
+                sha256: sha256.clone(),
+                paths: members.iter().map(|(p, _)| p.clone()).collect(),
+                bytes_reclaimable,
+            });
+        } else {
+            singleton_shas.push(sha256.clone());
+        }
+    }
+    exact_duplicate_groups.sort_by(|a, b| b.bytes_reclaimable.cmp(&a.bytes_reclaimable));
+
+    let filenames: HashMap<String, String> = manifest.list_filenames()?.into_iter().collect();
+    let phashes: HashMap<String, u64> = manifest.list_phashes()?.into_iter().collect();
+    let singleton_phashes: Vec<(String, u64)> = singleton_shas
+        .iter()
+        .filter_map(|sha256| phashes.get(sha256).map(|&phash| (sha256.clone(), phash)))
+        .collect();
+
+    let near_duplicate_clusters = perceptual::cluster_by_hamming(&singleton_phashes, threshold)
+        .into_iter()
+        .map(|cluster| {
+            cluster
+                .into_iter()
+                .map(|sha256| filenames.get(&sha256).cloned().unwrap_or(sha256))
+                .collect()
+        })
+        .collect();
+
+    Ok(VaultStats {
+        total_photos,
+        total_bytes,
+        unique_bytes,
+        bytes_reclaimed: total_bytes - unique_bytes,
+        exact_duplicate_groups,
+        near_duplicate_clusters,
+        by_extension,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::IngestReason;
+    use std::fs;
+
+    fn vault_with_manifest() -> (tempfile::TempDir, Manifest) {
+        let tmp = tempfile::tempdir().unwrap();
+        let manifest = Manifest::open(tmp.path()).unwrap();
+        (tmp, manifest)
+    }
+
+    #[test]
+    fn test_empty_vault() {
+        let (tmp, manifest) = vault_with_manifest();
+        let stats = compute_vault_stats(tmp.path(), &manifest, 10).unwrap();
+        assert_eq!(stats, VaultStats::default());
+    }
+
+    #[test]
+    fn test_counts_total_photos_and_bytes() {
+        let (tmp, manifest) = vault_with_manifest();
+        let dir = tmp.path().join("2024/01/01");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.jpg"), b"hello").unwrap();
+        fs::write(dir.join("b.jpg"), b"world!").unwrap();
+
+        let stats = compute_vault_stats(tmp.path(), &manifest, 10).unwrap();
+        assert_eq!(stats.total_photos, 2);
+        assert_eq!(stats.total_bytes, 11);
+        assert_eq!(stats.unique_bytes, 11);
+        assert_eq!(stats.bytes_reclaimed, 0);
+        assert!(stats.exact_duplicate_groups.is_empty());
+    }
+
+    #[test]
+    fn test_excludes_objects_and_metadata_dirs() {
+        let (tmp, manifest) = vault_with_manifest();
+        let dir = tmp.path().join("2024/01/01");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.jpg"), b"hello").unwrap();
+
+        let object_dir = tmp.path().join("objects/he");
+        fs::create_dir_all(&object_dir).unwrap();
+        fs::write(object_dir.join("llo"), b"hello").unwrap();
+
+        let stats = compute_vault_stats(tmp.path(), &manifest, 10).unwrap();
+        assert_eq!(stats.total_photos, 1);
+    }
+
+    #[test]
+    fn test_detects_exact_duplicate_group() {
+        let (tmp, manifest) = vault_with_manifest();
+        let dir = tmp.path().join("2024/01/01");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.jpg"), b"same bytes").unwrap();
+        fs::write(dir.join("a_1.jpg"), b"same bytes").unwrap();
+        fs::write(dir.join("other.jpg"), b"different").unwrap();
+
+        let stats = compute_vault_stats(tmp.path(), &manifest, 10).unwrap();
+        assert_eq!(stats.total_photos, 3);
+        assert_eq!(stats.exact_duplicate_groups.len(), 1);
+        assert_eq!(stats.exact_duplicate_groups[0].paths.len(), 2);
+        assert_eq!(stats.exact_duplicate_groups[0].bytes_reclaimable, 10);
+        assert_eq!(stats.bytes_reclaimed, 10);
+    }
+
+    #[test]
+    fn test_breaks_down_by_extension() {
+        let (tmp, manifest) = vault_with_manifest();
+        let dir = tmp.path().join("2024/01/01");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.jpg"), b"aaaa").unwrap();
+        fs::write(dir.join("b.JPG"), b"bb").unwrap();
+        fs::write(dir.join("c.png"), b"c").unwrap();
+
+        let stats = compute_vault_stats(tmp.path(), &manifest, 10).unwrap();
+        assert_eq!(stats.by_extension["jpg"].count, 2);
+        assert_eq!(stats.by_extension["jpg"].bytes, 6);
+        assert_eq!(stats.by_extension["png"].count, 1);
+    }
+
+    #[test]
+    fn test_near_duplicate_clusters_only_among_singletons() {
+        let (tmp, manifest) = vault_with_manifest();
+        let dir = tmp.path().join("2024/01/01");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.jpg"), b"content a").unwrap();
+        fs::write(dir.join("b.jpg"), b"content b").unwrap();
+
+        let sha_a = compute_sha256(&dir.join("a.jpg")).unwrap();
+        let sha_b = compute_sha256(&dir.join("b.jpg")).unwrap();
+        let gen = manifest.begin_generation(None).unwrap();
+        manifest
+            .insert_file(
+                &sha_a, "a.jpg", "JPEG", 9, None, None, None, Some(0b0000), gen,
+                IngestReason::New,
+            )
+            .unwrap();
+        manifest
+            .insert_file(
+                &sha_b, "b.jpg", "JPEG", 9, None, None, None, Some(0b0001), gen,
+                IngestReason::New,
+            )
+            .unwrap();
+
+        let stats = compute_vault_stats(tmp.path(), &manifest, 1).unwrap();
+        assert_eq!(stats.near_duplicate_clusters.len(), 1);
+        let mut cluster = stats.near_duplicate_clusters[0].clone();
+        cluster.sort();
+        assert_eq!(cluster, vec!["a.jpg".to_string(), "b.jpg".to_string()]);
+    }
+}
@@ -0,0 +1,486 @@
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+use tar::{Archive as TarArchiveReader, Builder as TarBuilder, EntryType};
+use walkdir::WalkDir;
+
+use crate::error::{Error, Result};
+use crate::vault_save::VaultSaveProgress;
+
+/// Compression applied to the tar stream written by `pack_vault_tar`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TarCompression {
+    /// Plain `tar`, no compression — fastest to pack and unpack.
+    None,
+    /// `tar.zst`, the same codec `archive::pack_vault` uses per member.
+    Zstd,
+    /// `tar.bz2` — slower and smaller, for wide interop with non-Rust tools.
+    Bzip2,
+    /// `tar.gz`, the same codec `source_archive` reads when ingesting a
+    /// `.tar.gz` source — the most widely recognized of the three.
+    Gzip,
+}
+
+/// Ceilings `unpack_vault_tar_with_limits` enforces against the archive's
+/// untrusted headers before writing a single byte of any entry — the same
+/// discipline `archive::ArchiveLimits` applies to the chunked zstd format,
+/// adapted to a format with no tail index to pre-scan: totals are tracked as
+/// the archive streams and a limit is enforced the moment it would be
+/// crossed, never after the fact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TarArchiveLimits {
+    /// Reject the archive once the running sum of entries' declared sizes
+    /// would exceed this.
+    pub max_total_uncompressed_size: u64,
+    /// Reject the archive once more than this many entries have been seen.
+    pub max_entries: usize,
+    /// Reject any single entry whose declared size exceeds this.
+    pub max_entry_uncompressed_size: u64,
+}
+
+impl Default for TarArchiveLimits {
+    fn default() -> Self {
+        Self {
+            max_total_uncompressed_size: 500 * 1024 * 1024 * 1024,
+            max_entries: 1_000_000,
+            max_entry_uncompressed_size: 10 * 1024 * 1024 * 1024,
+        }
+    }
+}
+
+/// Bundle every file under `vault_path` into a single streamed tar archive
+/// at `archive_path`, compressed with `compression`. Unlike `vault_save`'s
+/// content-addressed object tree or `archive::pack_vault`'s chunked
+/// zstd-per-member format, this writes one conventional tar stream any
+/// standard tool can read — meant for cold storage, network transfer, or a
+/// single-file backup rather than incremental local sync.
+///
+/// Emits the same `VaultSaveProgress` events `vault_save` does, so callers
+/// reuse one progress UI for both.
+pub fn pack_vault_tar(
+    vault_path: &Path,
+    archive_path: &Path,
+    compression: TarCompression,
+    mut progress_cb: Option<&mut dyn FnMut(VaultSaveProgress)>,
+) -> Result<usize> {
+    let files: Vec<PathBuf> = WalkDir::new(vault_path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.path().to_path_buf())
+        .collect();
+
+    if let Some(ref mut cb) = progress_cb {
+        cb(VaultSaveProgress::Start { total: files.len() });
+    }
+
+    let out = File::create(archive_path)?;
+    let copied = match compression {
+        TarCompression::None => {
+            let mut builder = TarBuilder::new(BufWriter::new(out));
+            let copied = append_entries(vault_path, &files, &mut builder, &mut progress_cb)?;
+            builder.into_inner()?.flush()?;
+            copied
+        }
+        TarCompression::Zstd => {
+            let mut builder = TarBuilder::new(zstd::Encoder::new(BufWriter::new(out), 0)?);
+            let copied = append_entries(vault_path, &files, &mut builder, &mut progress_cb)?;
+            builder.into_inner()?.finish()?;
+            copied
+        }
+        TarCompression::Bzip2 => {
+            let mut builder = TarBuilder::new(bzip2::write::BzEncoder::new(
+                BufWriter::new(out),
+                bzip2::Compression::default(),
+            ));
+            let copied = append_entries(vault_path, &files, &mut builder, &mut progress_cb)?;
+            builder.into_inner()?.try_finish()?;
+            copied
+        }
+        TarCompression::Gzip => {
+            let mut builder = TarBuilder::new(flate2::write::GzEncoder::new(
+                BufWriter::new(out),
+                flate2::Compression::default(),
+            ));
+            let copied = append_entries(vault_path, &files, &mut builder, &mut progress_cb)?;
+            builder.into_inner()?.finish()?;
+            copied
+        }
+    };
+
+    if let Some(ref mut cb) = progress_cb {
+        cb(VaultSaveProgress::Complete {
+            copied,
+            skipped: 0,
+            deduplicated: 0,
+            bytes_saved: 0,
+            removed: 0,
+        });
+    }
+
+    Ok(copied)
+}
+
+fn append_entries<W: Write>(
+    vault_path: &Path,
+    files: &[PathBuf],
+    builder: &mut TarBuilder<W>,
+    progress_cb: &mut Option<&mut dyn FnMut(VaultSaveProgress)>,
+) -> Result<usize> {
+    let mut copied = 0usize;
+    for path in files {
+        let relative = path.strip_prefix(vault_path).unwrap_or(path);
+        builder.append_path_with_name(path, relative)?;
+        copied += 1;
+        if let Some(cb) = progress_cb.as_mut() {
+            cb(VaultSaveProgress::Copied {
+                source: path.clone(),
+                target: relative.to_path_buf(),
+            });
+        }
+    }
+    Ok(copied)
+}
+
+/// Extract every entry of `archive_path` (written by `pack_vault_tar`, any
+/// `TarCompression`) into `dest_path`, under the default `TarArchiveLimits`.
+/// See `unpack_vault_tar_with_limits`.
+pub fn unpack_vault_tar(
+    archive_path: &Path,
+    dest_path: &Path,
+    progress_cb: Option<&mut dyn FnMut(VaultSaveProgress)>,
+) -> Result<usize> {
+    unpack_vault_tar_with_limits(
+        archive_path,
+        dest_path,
+        TarArchiveLimits::default(),
+        progress_cb,
+    )
+}
+
+/// Extract every entry of `archive_path` into `dest_path`, validating each
+/// entry before writing it: only a plain relative path made of `Normal`/
+/// `CurDir` components is accepted (no `..`, no absolute path), only
+/// `Regular` and `Directory` entry types are accepted (symlinks and other
+/// special types are refused outright), and `limits` are checked against the
+/// running total as the archive streams — an oversized or over-numerous
+/// archive is aborted the moment the violation is detected, never after the
+/// fact. The compression codec is auto-detected from the file's magic bytes,
+/// so callers don't need to remember which `TarCompression` a given archive
+/// was packed with.
+pub fn unpack_vault_tar_with_limits(
+    archive_path: &Path,
+    dest_path: &Path,
+    limits: TarArchiveLimits,
+    mut progress_cb: Option<&mut dyn FnMut(VaultSaveProgress)>,
+) -> Result<usize> {
+    let compression = detect_compression(archive_path)?;
+    let file = File::open(archive_path)?;
+
+    let mut restored = 0usize;
+    let mut dirs = 0usize;
+    let mut total_uncompressed = 0u64;
+
+    match compression {
+        TarCompression::None => {
+            let mut archive = TarArchiveReader::new(BufReader::new(file));
+            unpack_entries(
+                &mut archive,
+                dest_path,
+                limits,
+                &mut progress_cb,
+                &mut restored,
+                &mut dirs,
+                &mut total_uncompressed,
+            )?;
+        }
+        TarCompression::Zstd => {
+            let mut archive = TarArchiveReader::new(zstd::Decoder::new(BufReader::new(file))?);
+            unpack_entries(
+                &mut archive,
+                dest_path,
+                limits,
+                &mut progress_cb,
+                &mut restored,
+                &mut dirs,
+                &mut total_uncompressed,
+            )?;
+        }
+        TarCompression::Bzip2 => {
+            let mut archive = TarArchiveReader::new(bzip2::read::BzDecoder::new(BufReader::new(file)));
+            unpack_entries(
+                &mut archive,
+                dest_path,
+                limits,
+                &mut progress_cb,
+                &mut restored,
+                &mut dirs,
+                &mut total_uncompressed,
+            )?;
+        }
+        TarCompression::Gzip => {
+            let mut archive = TarArchiveReader::new(flate2::read::GzDecoder::new(BufReader::new(file)));
+            unpack_entries(
+                &mut archive,
+                dest_path,
+                limits,
+                &mut progress_cb,
+                &mut restored,
+                &mut dirs,
+                &mut total_uncompressed,
+            )?;
+        }
+    }
+
+    let copied = restored - dirs;
+    if let Some(ref mut cb) = progress_cb {
+        cb(VaultSaveProgress::Complete {
+            copied,
+            skipped: dirs,
+            deduplicated: 0,
+            bytes_saved: 0,
+            removed: 0,
+        });
+    }
+
+    Ok(copied)
+}
+
+fn unpack_entries<R: Read>(
+    archive: &mut TarArchiveReader<R>,
+    dest_path: &Path,
+    limits: TarArchiveLimits,
+    progress_cb: &mut Option<&mut dyn FnMut(VaultSaveProgress)>,
+    restored: &mut usize,
+    dirs: &mut usize,
+    total_uncompressed: &mut u64,
+) -> Result<()> {
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_type = entry.header().entry_type();
+        let relative = entry.path()?.into_owned();
+
+        if !matches!(entry_type, EntryType::Regular | EntryType::Directory) {
+            return Err(Error::ArchiveUnsafePath(relative.display().to_string()));
+        }
+        if relative
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(Error::ArchiveUnsafePath(relative.display().to_string()));
+        }
+        let target = dest_path.join(&relative);
+        if !target.starts_with(dest_path) {
+            return Err(Error::ArchiveUnsafePath(relative.display().to_string()));
+        }
+
+        *restored += 1;
+        if *restored > limits.max_entries {
+            return Err(Error::ArchiveLimitExceeded(format!(
+                "archive exceeds the limit of {} entries",
+                limits.max_entries
+            )));
+        }
+
+        let entry_size = entry.header().size()?;
+        if entry_size > limits.max_entry_uncompressed_size {
+            return Err(Error::ArchiveLimitExceeded(format!(
+                "entry {} is {entry_size} bytes, exceeding the per-entry limit of {}",
+                relative.display(),
+                limits.max_entry_uncompressed_size
+            )));
+        }
+        *total_uncompressed = total_uncompressed.saturating_add(entry_size);
+        if *total_uncompressed > limits.max_total_uncompressed_size {
+            return Err(Error::ArchiveLimitExceeded(format!(
+                "total uncompressed size exceeds the limit of {} bytes",
+                limits.max_total_uncompressed_size
+            )));
+        }
+
+        if entry_type.is_dir() {
+            fs::create_dir_all(&target)?;
+            *dirs += 1;
+            continue;
+        }
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        entry.unpack(&target)?;
+
+        if let Some(cb) = progress_cb.as_mut() {
+            cb(VaultSaveProgress::Copied {
+                source: relative.clone(),
+                target: target.clone(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Sniff `archive_path`'s compression from its leading magic bytes: the
+/// zstd frame magic, the `BZh` bzip2 header, the gzip magic, or neither
+/// (plain tar).
+fn detect_compression(archive_path: &Path) -> Result<TarCompression> {
+    let mut file = File::open(archive_path)?;
+    let mut magic = [0u8; 4];
+    let read = file.read(&mut magic)?;
+
+    if read >= 4 && magic == [0x28, 0xB5, 0x2F, 0xFD] {
+        return Ok(TarCompression::Zstd);
+    }
+    if read >= 3 && &magic[..3] == b"BZh" {
+        return Ok(TarCompression::Bzip2);
+    }
+    if read >= 2 && &magic[..2] == [0x1F, 0x8B] {
+        return Ok(TarCompression::Gzip);
+    }
+    Ok(TarCompression::None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_tree(root: &Path, files: &[(&str, &[u8])]) {
+        for (relative, content) in files {
+            let path = root.join(relative);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).unwrap();
+            }
+            fs::write(path, content).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_pack_then_unpack_roundtrip_uncompressed() {
+        let vault = tempfile::tempdir().unwrap();
+        write_tree(
+            vault.path(),
+            &[
+                ("2024/01/01/a.jpg", b"photo a bytes"),
+                ("2024/01/02/b.jpg", b"photo b bytes, a bit longer"),
+            ],
+        );
+
+        let archive_dir = tempfile::tempdir().unwrap();
+        let archive_path = archive_dir.path().join("vault.tar");
+
+        let copied =
+            pack_vault_tar(vault.path(), &archive_path, TarCompression::None, None).unwrap();
+        assert_eq!(copied, 2);
+
+        let dest = tempfile::tempdir().unwrap();
+        let restored = unpack_vault_tar(&archive_path, dest.path(), None).unwrap();
+        assert_eq!(restored, 2);
+        assert_eq!(
+            fs::read(dest.path().join("2024/01/01/a.jpg")).unwrap(),
+            b"photo a bytes"
+        );
+        assert_eq!(
+            fs::read(dest.path().join("2024/01/02/b.jpg")).unwrap(),
+            b"photo b bytes, a bit longer"
+        );
+    }
+
+    #[test]
+    fn test_pack_then_unpack_roundtrip_zstd() {
+        let vault = tempfile::tempdir().unwrap();
+        write_tree(vault.path(), &[("a.jpg", b"hello vault")]);
+
+        let archive_dir = tempfile::tempdir().unwrap();
+        let archive_path = archive_dir.path().join("vault.tar.zst");
+        pack_vault_tar(vault.path(), &archive_path, TarCompression::Zstd, None).unwrap();
+
+        let dest = tempfile::tempdir().unwrap();
+        let restored = unpack_vault_tar(&archive_path, dest.path(), None).unwrap();
+        assert_eq!(restored, 1);
+        assert_eq!(fs::read(dest.path().join("a.jpg")).unwrap(), b"hello vault");
+    }
+
+    #[test]
+    fn test_pack_then_unpack_roundtrip_bzip2() {
+        let vault = tempfile::tempdir().unwrap();
+        write_tree(vault.path(), &[("a.jpg", b"hello vault, bzip2 this time")]);
+
+        let archive_dir = tempfile::tempdir().unwrap();
+        let archive_path = archive_dir.path().join("vault.tar.bz2");
+        pack_vault_tar(vault.path(), &archive_path, TarCompression::Bzip2, None).unwrap();
+
+        let dest = tempfile::tempdir().unwrap();
+        let restored = unpack_vault_tar(&archive_path, dest.path(), None).unwrap();
+        assert_eq!(restored, 1);
+        assert_eq!(
+            fs::read(dest.path().join("a.jpg")).unwrap(),
+            b"hello vault, bzip2 this time"
+        );
+    }
+
+    #[test]
+    fn test_unpack_rejects_entry_over_total_limit() {
+        let vault = tempfile::tempdir().unwrap();
+        write_tree(vault.path(), &[("a.jpg", b"0123456789")]);
+
+        let archive_dir = tempfile::tempdir().unwrap();
+        let archive_path = archive_dir.path().join("vault.tar");
+        pack_vault_tar(vault.path(), &archive_path, TarCompression::None, None).unwrap();
+
+        let dest = tempfile::tempdir().unwrap();
+        let limits = TarArchiveLimits {
+            max_total_uncompressed_size: 5,
+            ..TarArchiveLimits::default()
+        };
+        let result = unpack_vault_tar_with_limits(&archive_path, dest.path(), limits, None);
+        assert!(matches!(result, Err(Error::ArchiveLimitExceeded(_))));
+    }
+
+    #[test]
+    fn test_unpack_rejects_too_many_entries() {
+        let vault = tempfile::tempdir().unwrap();
+        write_tree(vault.path(), &[("a.jpg", b"1"), ("b.jpg", b"2"), ("c.jpg", b"3")]);
+
+        let archive_dir = tempfile::tempdir().unwrap();
+        let archive_path = archive_dir.path().join("vault.tar");
+        pack_vault_tar(vault.path(), &archive_path, TarCompression::None, None).unwrap();
+
+        let dest = tempfile::tempdir().unwrap();
+        let limits = TarArchiveLimits {
+            max_entries: 1,
+            ..TarArchiveLimits::default()
+        };
+        let result = unpack_vault_tar_with_limits(&archive_path, dest.path(), limits, None);
+        assert!(matches!(result, Err(Error::ArchiveLimitExceeded(_))));
+    }
+
+    #[test]
+    fn test_pack_then_unpack_roundtrip_gzip() {
+        let vault = tempfile::tempdir().unwrap();
+        write_tree(vault.path(), &[("a.jpg", b"hello vault, gzip this time")]);
+
+        let archive_dir = tempfile::tempdir().unwrap();
+        let archive_path = archive_dir.path().join("vault.tar.gz");
+        pack_vault_tar(vault.path(), &archive_path, TarCompression::Gzip, None).unwrap();
+
+        let dest = tempfile::tempdir().unwrap();
+        let restored = unpack_vault_tar(&archive_path, dest.path(), None).unwrap();
+        assert_eq!(restored, 1);
+        assert_eq!(
+            fs::read(dest.path().join("a.jpg")).unwrap(),
+            b"hello vault, gzip this time"
+        );
+    }
+
+    #[test]
+    fn test_detect_compression_plain_tar() {
+        let vault = tempfile::tempdir().unwrap();
+        write_tree(vault.path(), &[("a.jpg", b"data")]);
+        let archive_dir = tempfile::tempdir().unwrap();
+        let archive_path = archive_dir.path().join("vault.tar");
+        pack_vault_tar(vault.path(), &archive_path, TarCompression::None, None).unwrap();
+
+        assert_eq!(detect_compression(&archive_path).unwrap(), TarCompression::None);
+    }
+}
@@ -2,27 +2,86 @@ use std::collections::HashSet;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use crate::catalog::Catalog;
 use crate::domain::{DuplicateGroup, PhotoFile};
-use crate::error::Result;
+use crate::error::{Error, Result};
 
 /// Progress callback events for the vault save operation.
 pub enum VaultSaveProgress {
     /// Starting save with total count.
     Start { total: usize },
-    /// A file was copied.
+    /// A file was copied into a new content-addressed object.
     Copied { source: PathBuf, target: PathBuf },
     /// A file was skipped (already exists with same size).
     Skipped { path: PathBuf },
+    /// A file's content already existed as another object — linked instead of
+    /// copied again, reclaiming `bytes_saved` bytes (e.g. the same card dumped twice).
+    Deduplicated { path: PathBuf, bytes_saved: u64 },
+    /// An existing vault display copy was relocated to a new dated path
+    /// instead of being recopied — its source was renamed or moved between
+    /// scans. See `detect_vault_moves`.
+    Moved { from: PathBuf, to: PathBuf },
     /// A superseded file was removed from the vault (replaced by higher-quality version).
     Removed { path: PathBuf },
+    /// A superseded file was collapsed to a hard link pointing at `canonical`
+    /// instead of being removed outright (`--link` mode) — its path survives,
+    /// but its content is now stored exactly once.
+    Linked { target: PathBuf, canonical: PathBuf },
+    /// A candidate was refused before any bytes were touched — a symlink
+    /// escaping its source root, or one that would have pushed the run past
+    /// `VaultSaveLimits`.
+    Rejected { path: PathBuf, reason: String },
     /// Save completed.
     Complete {
         copied: usize,
         skipped: usize,
+        deduplicated: usize,
+        bytes_saved: u64,
         removed: usize,
     },
 }
 
+/// Ceilings `vault_save` enforces before copying a single byte, mirroring the
+/// "validate everything, then act" approach `archive::unpack_archive_with_limits`
+/// uses for the same class of problem (pathologically large or numerous
+/// inputs). `Default` picks generous ceilings that only guard against truly
+/// runaway inputs, not a real library.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VaultSaveLimits {
+    /// Reject any single file larger than this.
+    pub max_file_size: u64,
+    /// Reject the whole save once the running total of bytes to copy would
+    /// exceed this (already-deduplicated/skipped files don't count).
+    pub max_total_bytes: u64,
+    /// Reject the whole save once more than this many files would be copied.
+    pub max_file_count: usize,
+}
+
+impl Default for VaultSaveLimits {
+    fn default() -> Self {
+        Self {
+            max_file_size: 500 * 1024 * 1024 * 1024, // 500 GiB
+            max_total_bytes: 500 * 1024 * 1024 * 1024 * 1024, // 500 TiB
+            max_file_count: 10_000_000,
+        }
+    }
+}
+
+/// Resolve `path` and reject it if following symlinks would land outside
+/// `source_root` — e.g. a symlink inside a registered source pointing at
+/// `/etc` or a sibling directory the user never registered. `source_root`
+/// must already be canonical (as `Source::path` always is — sources are
+/// canonicalized on `add_source`).
+pub fn safe_source_path(source_root: &Path, path: &Path) -> Result<PathBuf> {
+    let resolved = path
+        .canonicalize()
+        .map_err(|_| Error::VaultSaveSymlinkEscape(path.to_path_buf()))?;
+    if !resolved.starts_with(source_root) {
+        return Err(Error::VaultSaveSymlinkEscape(path.to_path_buf()));
+    }
+    Ok(resolved)
+}
+
 /// Parse an EXIF date string into (year, month, day).
 /// Handles both "2024-01-15 12:00:00" (display_value) and "2024:01:15 12:00:00" (raw EXIF).
 pub fn parse_exif_date(date_str: &str) -> Option<(u32, u32, u32)> {
@@ -59,14 +118,48 @@ pub fn date_for_photo(photo: &PhotoFile) -> (u32, u32, u32) {
     (dt.year() as u32, dt.month(), dt.day())
 }
 
+/// How aggressively `build_target_path`/`copy_photo_to_vault` confirm an
+/// existing vault file is truly the one already being saved before trusting
+/// it as already-present.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VerifyMode {
+    /// Trust a matching file size alone (fast — the long-standing default).
+    /// Two different files landing on the same byte count, as re-encoded
+    /// JPEGs commonly do, can false-positive as "already saved".
+    #[default]
+    SizeOnly,
+    /// On a size match, also re-hash the on-disk file (streaming) and
+    /// compare it against the catalog's recorded SHA-256 before trusting
+    /// it. Slower — a full read of the existing file — but rules out
+    /// same-size, different-content collisions.
+    Sha256,
+}
+
+impl VerifyMode {
+    /// Whether `path` — already known to match `expected_size` — is
+    /// confirmed identical to `expected_sha256` under this mode.
+    fn confirms(self, path: &Path, expected_sha256: &str) -> bool {
+        match self {
+            VerifyMode::SizeOnly => true,
+            VerifyMode::Sha256 => crate::hasher::compute_sha256(path)
+                .map(|actual| actual == expected_sha256)
+                .unwrap_or(false),
+        }
+    }
+}
+
 /// Build the target path: vault_path/YYYY/MM/DD/filename.ext
 /// Handles filename collisions by appending _1, _2, etc.
-/// If a file already exists with a matching size, returns that path (enables incremental skip).
+/// If a file already exists with a matching size (and, under `verify:
+/// VerifyMode::Sha256`, a matching hash), returns that path (enables
+/// incremental skip).
 pub fn build_target_path(
     vault_path: &Path,
     date: (u32, u32, u32),
     original_path: &Path,
     expected_size: u64,
+    expected_sha256: &str,
+    verify: VerifyMode,
 ) -> PathBuf {
     let (year, month, day) = date;
     let dir = vault_path
@@ -92,9 +185,10 @@ pub fn build_target_path(
     let mut target = dir.join(&base_name);
     let mut counter = 1u32;
     while target.exists() {
-        // If existing file matches expected size, this is our file (incremental skip)
+        // If existing file matches expected size (and, under Sha256 mode,
+        // content), this is our file (incremental skip).
         if let Ok(meta) = target.metadata() {
-            if meta.len() == expected_size {
+            if meta.len() == expected_size && verify.confirms(&target, expected_sha256) {
                 return target;
             }
         }
@@ -109,12 +203,87 @@ pub fn build_target_path(
     target
 }
 
+/// Include/exclude glob patterns plus optional EXIF predicates, for carving
+/// a partial selection out of `select_photos_to_export`'s default "every
+/// source-of-truth and every ungrouped photo" set — e.g. skip a screenshots
+/// folder, or only export one camera's shots.
+///
+/// Patterns match against the photo's source `path` using the same
+/// `*`-wildcard glob as `ScanConfig::exclude_patterns`. An empty `include`
+/// means "match all paths"; `exclude` is checked first and always wins.
+#[derive(Debug, Clone, Default)]
+pub struct PhotoMatcher {
+    /// `*`-glob patterns a path must match at least one of. Empty = match all.
+    pub include: Vec<String>,
+    /// `*`-glob patterns that reject a path outright, overriding `include`.
+    pub exclude: Vec<String>,
+    /// Only match photos whose EXIF camera make equals this (case-insensitive).
+    pub camera_make: Option<String>,
+    /// Only match photos whose EXIF camera model equals this (case-insensitive).
+    pub camera_model: Option<String>,
+    /// Only match photos dated on or after this (year, month, day) — see `date_for_photo`.
+    pub date_from: Option<(u32, u32, u32)>,
+    /// Only match photos dated on or before this (year, month, day) — see `date_for_photo`.
+    pub date_to: Option<(u32, u32, u32)>,
+}
+
+impl PhotoMatcher {
+    /// Whether `photo` survives this matcher's include/exclude globs and EXIF predicates.
+    pub fn matches(&self, photo: &PhotoFile) -> bool {
+        let path_str = photo.path.to_string_lossy();
+
+        if self.exclude.iter().any(|pattern| crate::glob_match(pattern, &path_str)) {
+            return false;
+        }
+        if !self.include.is_empty()
+            && !self.include.iter().any(|pattern| crate::glob_match(pattern, &path_str))
+        {
+            return false;
+        }
+
+        if let Some(ref make) = self.camera_make {
+            let matched = photo
+                .exif
+                .as_ref()
+                .and_then(|e| e.camera_make.as_deref())
+                .is_some_and(|m| m.eq_ignore_ascii_case(make));
+            if !matched {
+                return false;
+            }
+        }
+        if let Some(ref model) = self.camera_model {
+            let matched = photo
+                .exif
+                .as_ref()
+                .and_then(|e| e.camera_model.as_deref())
+                .is_some_and(|m| m.eq_ignore_ascii_case(model));
+            if !matched {
+                return false;
+            }
+        }
+
+        if self.date_from.is_some() || self.date_to.is_some() {
+            let date = date_for_photo(photo);
+            if self.date_from.is_some_and(|from| date < from) {
+                return false;
+            }
+            if self.date_to.is_some_and(|to| date > to) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
 /// Determine which photos to save to the vault:
 /// - For each duplicate group, take only the source-of-truth.
 /// - For ungrouped photos, take the photo itself.
+/// - If `matcher` is given, drop any photo it doesn't match — see `PhotoMatcher`.
 pub fn select_photos_to_export<'a>(
     all_photos: &'a [PhotoFile],
     groups: &[DuplicateGroup],
+    matcher: Option<&PhotoMatcher>,
 ) -> Vec<&'a PhotoFile> {
     let mut grouped_ids: HashSet<i64> = HashSet::new();
     let mut sot_ids: HashSet<i64> = HashSet::new();
@@ -135,18 +304,85 @@ pub fn select_photos_to_export<'a>(
                 true
             }
         })
+        .filter(|p| matcher.map(|m| m.matches(p)).unwrap_or(true))
         .collect()
 }
 
-/// Remove superseded vault files: group members that live inside the vault directory
+/// Detect vault display files that should be relocated rather than
+/// recopied: for each `(photo, target)` pair whose `target` doesn't exist
+/// yet, look up every cataloged photo sharing the same `sha256` (content
+/// identity is the "copy source" here, mirroring how `copy_photo_to_vault`
+/// itself keys on hash rather than path) — the vault's own display copies
+/// are among them, since `vault_path` is auto-registered as a scan source.
+/// A match living inside `vault_path` at a *different* path than `target`
+/// is the old dated location of a source file that's since been renamed or
+/// moved: that copy should be renamed in place instead of copied anew and
+/// later cleaned up as an orphan. Returns `(old_path, new_path)` pairs;
+/// `new_path` is always the caller's already-computed `target`.
+pub fn detect_vault_moves(
+    vault_path: &Path,
+    catalog: &Catalog,
+    targets: &[(&PhotoFile, PathBuf)],
+) -> Result<Vec<(PathBuf, PathBuf)>> {
+    let vault_canonical = vault_path
+        .canonicalize()
+        .unwrap_or_else(|_| vault_path.to_path_buf());
+
+    let mut moves = Vec::new();
+    for (photo, new_target) in targets {
+        if new_target.exists() {
+            continue;
+        }
+
+        for candidate in catalog.find_photos_by_sha256(&photo.sha256)? {
+            if candidate.path == *new_target {
+                continue;
+            }
+            let candidate_canonical = candidate
+                .path
+                .canonicalize()
+                .unwrap_or_else(|_| candidate.path.clone());
+            if !candidate_canonical.starts_with(&vault_canonical) {
+                continue;
+            }
+            if !candidate.path.exists() {
+                continue;
+            }
+
+            moves.push((candidate.path, new_target.clone()));
+            break;
+        }
+    }
+
+    Ok(moves)
+}
+
+/// What happened to one superseded vault file during cleanup.
+#[derive(Debug, PartialEq, Eq)]
+pub enum SupersededOutcome {
+    /// The file was deleted outright (the default).
+    Removed { path: PathBuf },
+    /// The file was collapsed to a hard link pointing at `canonical` instead
+    /// of being deleted (`--link` mode) — its path survives on disk.
+    Linked { target: PathBuf, canonical: PathBuf },
+}
+
+/// Handle superseded vault files: group members that live inside the vault directory
 /// and are NOT the source-of-truth. These are lower-quality versions that have been
 /// replaced by a higher-quality source-of-truth.
-/// Returns the list of removed file paths.
+///
+/// When `link` is `false` (the default), each superseded file is deleted, as
+/// before. When `link` is `true`, it is instead collapsed to a hard link at
+/// `make_hard_link` pointing at the source-of-truth — its path survives so
+/// nothing disappears from a user's browsing layout, but its bytes are now
+/// stored exactly once. A file that can't be linked (e.g. `make_hard_link`
+/// rejects it) is left untouched rather than falling back to deletion.
 pub fn cleanup_superseded_vault_files(
     vault_path: &Path,
     all_photos: &[PhotoFile],
     groups: &[DuplicateGroup],
-) -> Vec<PathBuf> {
+    link: bool,
+) -> Vec<SupersededOutcome> {
     let vault_canonical = vault_path
         .canonicalize()
         .unwrap_or_else(|_| vault_path.to_path_buf());
@@ -154,7 +390,7 @@ pub fn cleanup_superseded_vault_files(
     let photo_map: std::collections::HashMap<i64, &PhotoFile> =
         all_photos.iter().map(|p| (p.id, p)).collect();
 
-    let mut removed = Vec::new();
+    let mut outcomes = Vec::new();
     for group in groups {
         for member in &group.members {
             if member.id == group.source_of_truth_id {
@@ -166,44 +402,256 @@ pub fn cleanup_superseded_vault_files(
                 .unwrap_or_else(|_| member.path.clone());
             if member_canonical.starts_with(&vault_canonical) {
                 // Verify the SOT is NOT also in the vault (avoid removing if both are in vault)
-                if let Some(sot) = photo_map.get(&group.source_of_truth_id) {
-                    let sot_canonical = sot
-                        .path
-                        .canonicalize()
-                        .unwrap_or_else(|_| sot.path.clone());
-                    // Only remove if SOT exists outside the vault, or SOT is a different
-                    // (higher-quality) file also being synced to the vault
-                    if sot_canonical == member_canonical {
-                        continue; // SOT and member are the same file
-                    }
+                let Some(sot) = photo_map.get(&group.source_of_truth_id) else {
+                    continue;
+                };
+                let sot_canonical = sot
+                    .path
+                    .canonicalize()
+                    .unwrap_or_else(|_| sot.path.clone());
+                // Only act if SOT exists outside the vault, or SOT is a different
+                // (higher-quality) file also being synced to the vault
+                if sot_canonical == member_canonical {
+                    continue; // SOT and member are the same file
                 }
-                if fs::remove_file(&member.path).is_ok() {
-                    removed.push(member.path.clone());
+
+                if link {
+                    if make_hard_link(&member.path, &sot.path).is_ok() {
+                        outcomes.push(SupersededOutcome::Linked {
+                            target: member.path.clone(),
+                            canonical: sot.path.clone(),
+                        });
+                    }
+                } else if fs::remove_file(&member.path).is_ok() {
+                    outcomes.push(SupersededOutcome::Removed {
+                        path: member.path.clone(),
+                    });
                 }
             }
         }
     }
 
-    removed
+    outcomes
 }
 
-/// Copy a single file to the target path, creating parent directories as needed.
-/// Returns Ok(false) if skipped (file exists with same size), Ok(true) if copied.
-pub fn copy_photo_to_vault(source: &Path, target: &Path, expected_size: u64) -> Result<bool> {
+/// Atomically replace `path` with a hard link to `canonical`, so a
+/// superseded duplicate can be collapsed to the source-of-truth's bytes
+/// without losing its path (see `VaultSaveProgress::Linked`). Rejects the
+/// link if the two files aren't even the same size — a cheap sanity check
+/// against linking away a file that isn't actually a byte-identical
+/// duplicate — then falls back to a plain copy if `path` and `canonical`
+/// live on different filesystems, the same reasoning
+/// `copy_photo_to_vault` uses for its object -> date-folder link.
+pub fn make_hard_link(path: &Path, canonical: &Path) -> Result<()> {
+    let canonical_len = fs::metadata(canonical)?.len();
+    let path_len = fs::metadata(path)?.len();
+    if canonical_len != path_len {
+        return Err(Error::VaultSaveContentMismatch(path.to_path_buf()));
+    }
+
+    // Link into a sibling temp name, then rename into place, so a process
+    // killed mid-link never leaves `path` missing (same atomicity reasoning
+    // as `copy_photo_to_vault`'s tmp + rename).
+    let tmp_path = path.with_extension("lsvault-link-tmp");
+    if fs::hard_link(canonical, &tmp_path).is_err() {
+        fs::copy(canonical, &tmp_path)?;
+    }
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// Outcome of `copy_photo_to_vault`, distinguishing a fresh copy from a
+/// content-addressed dedup hit so callers can report reclaimed space.
+#[derive(Debug, PartialEq, Eq)]
+pub enum CopyOutcome {
+    /// `target` already existed with the expected size; nothing was touched.
+    Skipped,
+    /// The object already existed under a different name — `target` was
+    /// linked to it instead of copying the bytes again.
+    Deduplicated { bytes_saved: u64 },
+    /// No object existed yet; the bytes were copied into a new object.
+    Copied,
+}
+
+/// Try to clone `source`'s extents into `dest` via the Linux `FICLONE`
+/// ioctl — a copy-on-write reflink on filesystems that support it
+/// (btrfs, XFS, overlayfs), near-instant and costing no extra disk until
+/// one side is later edited. Unlike a hard link, the two paths stay
+/// independent inodes, so editing the browse copy in `YYYY/MM/DD/` can
+/// never silently corrupt the shared object bytes. Returns `Ok(false)`
+/// (never an error) when the ioctl isn't supported here — wrong
+/// filesystem, cross-device, or not Linux — so the caller can fall back
+/// to a hard link or a plain copy.
+#[cfg(target_os = "linux")]
+fn try_reflink(source: &Path, dest: &Path) -> std::io::Result<bool> {
+    use std::os::unix::io::AsRawFd;
+
+    // From <linux/fs.h>; not exposed by `libc` under a portable name.
+    const FICLONE: libc::c_ulong = 0x40049409;
+
+    let src_file = fs::File::open(source)?;
+    let dest_file = match fs::OpenOptions::new().write(true).create_new(true).open(dest) {
+        Ok(f) => f,
+        Err(_) => return Ok(false),
+    };
+
+    let ret = unsafe { libc::ioctl(dest_file.as_raw_fd(), FICLONE, src_file.as_raw_fd()) };
+    if ret == 0 {
+        Ok(true)
+    } else {
+        drop(dest_file);
+        let _ = fs::remove_file(dest);
+        Ok(false)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn try_reflink(_source: &Path, _dest: &Path) -> std::io::Result<bool> {
+    Ok(false)
+}
+
+/// Link `dest` to `source`'s bytes as cheaply as the filesystem allows:
+/// reflink first (safest — independent inode), hard link second (cheap but
+/// shares an inode with `source`), plain copy last (always works, costs
+/// full disk space).
+fn link_or_copy(source: &Path, dest: &Path) -> Result<()> {
+    if try_reflink(source, dest)? {
+        return Ok(());
+    }
+    if fs::hard_link(source, dest).is_err() {
+        fs::copy(source, dest)?;
+    }
+    Ok(())
+}
+
+/// Build the content-addressed object path for a SHA-256 hash: `objects/<first2>/<rest>`.
+pub fn object_path_for(vault_path: &Path, sha256: &str) -> PathBuf {
+    let split = sha256.len().min(2);
+    let (prefix, rest) = sha256.split_at(split);
+    vault_path.join("objects").join(prefix).join(rest)
+}
+
+/// Copy (or link) a photo into the content-addressed vault store.
+///
+/// `target` is the YYYY/MM/DD display path kept for backward-compatible
+/// browsing; the real bytes live once at `objects/<first2>/<rest-of-hash>`.
+/// If the object already exists (same content reached the vault under a
+/// different name — e.g. a card dumped twice), `target` is hardlinked to it
+/// instead of copying again, so redundant imports cost near-zero bytes.
+///
+/// `verify` controls how hard an existing same-size `target` is trusted as
+/// already-saved before skipping it — see [`VerifyMode`].
+pub fn copy_photo_to_vault(
+    vault_path: &Path,
+    source: &Path,
+    sha256: &str,
+    target: &Path,
+    expected_size: u64,
+    verify: VerifyMode,
+) -> Result<CopyOutcome> {
     if target.exists() {
         if let Ok(metadata) = target.metadata() {
-            if metadata.len() == expected_size {
-                return Ok(false);
+            if metadata.len() == expected_size && verify.confirms(target, sha256) {
+                return Ok(CopyOutcome::Skipped);
             }
         }
+        // Stale file at this path (wrong size, or failed hash confirmation) —
+        // remove before (re)linking.
+        fs::remove_file(target)?;
+    }
+
+    let object_path = object_path_for(vault_path, sha256);
+    let object_existed = object_path.exists();
+
+    if !object_existed {
+        if let Some(parent) = object_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Copy to a sibling temp name, then rename into place, so a process
+        // killed mid-copy never leaves a partially-written file sitting at
+        // the hash-named path other code treats as "fully present and
+        // verified by its name" (see `test_pack_integrity_sha256_matches_filename`).
+        // Same directory as the final name so the rename is same-filesystem
+        // and therefore atomic.
+        let tmp_path = object_path.with_extension("tmp");
+        fs::copy(source, &tmp_path)?;
+        fs::rename(&tmp_path, &object_path)?;
     }
 
     if let Some(parent) = target.parent() {
         fs::create_dir_all(parent)?;
     }
+    // Reflink (or hard link, or — last resort — a plain copy) for browsing.
+    // See `link_or_copy`.
+    link_or_copy(&object_path, target)?;
+
+    if object_existed {
+        Ok(CopyOutcome::Deduplicated {
+            bytes_saved: expected_size,
+        })
+    } else {
+        Ok(CopyOutcome::Copied)
+    }
+}
+
+/// Walk the vault's content-addressed `objects/` store and remove blobs no
+/// browse path links to anymore — the object-store analogue of
+/// `cleanup_superseded_vault_files`, which does the same job one layer up
+/// (the `YYYY/MM/DD/` display paths).
+///
+/// An object's hard-link count is the primary signal: `copy_photo_to_vault`
+/// hard-links (or, on a reflink-capable filesystem, reflinks) every browse
+/// path to it, so once the last browse path referencing it is replaced or
+/// removed, the object's own link count drops back to 1 (itself). A
+/// reflinked browse path gets its own inode, so it wouldn't show up in that
+/// count — before deleting anything, this also confirms the catalog has no
+/// live photo recorded under that hash, as a safety net against that case.
+#[cfg(unix)]
+pub fn gc_vault(vault_path: &Path, catalog: &Catalog) -> Result<Vec<PathBuf>> {
+    use std::os::unix::fs::MetadataExt;
+
+    let objects_dir = vault_path.join("objects");
+    if !objects_dir.is_dir() {
+        return Ok(Vec::new());
+    }
 
-    fs::copy(source, target)?;
-    Ok(true)
+    let live_shas: HashSet<String> = catalog
+        .list_all_photos()?
+        .into_iter()
+        .map(|p| p.sha256)
+        .collect();
+
+    let mut removed = Vec::new();
+    for prefix_entry in fs::read_dir(&objects_dir)? {
+        let prefix_entry = prefix_entry?;
+        if !prefix_entry.file_type()?.is_dir() {
+            continue;
+        }
+        let prefix = prefix_entry.file_name().to_string_lossy().to_string();
+
+        for object_entry in fs::read_dir(prefix_entry.path())? {
+            let object_entry = object_entry?;
+            if !object_entry.file_type()?.is_file() {
+                continue;
+            }
+            let metadata = object_entry.metadata()?;
+            if metadata.nlink() > 1 {
+                continue; // still linked from at least one browse path
+            }
+
+            let sha256 = format!("{prefix}{}", object_entry.file_name().to_string_lossy());
+            if live_shas.contains(&sha256) {
+                continue; // reflinked browse path — nlink alone missed it
+            }
+
+            let object_path = object_entry.path();
+            if fs::remove_file(&object_path).is_ok() {
+                removed.push(object_path);
+            }
+        }
+    }
+
+    Ok(removed)
 }
 
 #[cfg(test)]
@@ -259,6 +707,7 @@ mod tests {
             sha256: format!("sha_{id}"),
             phash: None,
             dhash: None,
+            ahash: None,
             exif: None,
             mtime,
         }
@@ -303,7 +752,7 @@ mod tests {
             make_photo_with_path(1, "/a.jpg"),
             make_photo_with_path(2, "/b.jpg"),
         ];
-        let selected = select_photos_to_export(&photos, &[]);
+        let selected = select_photos_to_export(&photos, &[], None);
         assert_eq!(selected.len(), 2);
     }
 
@@ -320,7 +769,7 @@ mod tests {
             source_of_truth_id: 1,
             confidence: Confidence::Certain,
         }];
-        let selected = select_photos_to_export(&photos, &groups);
+        let selected = select_photos_to_export(&photos, &groups, None);
         assert_eq!(selected.len(), 2);
         let ids: HashSet<i64> = selected.iter().map(|p| p.id).collect();
         assert!(ids.contains(&1), "SoT should be included");
@@ -333,15 +782,28 @@ mod tests {
     #[test]
     fn test_build_target_path_basic() {
         let vault = PathBuf::from("/vault");
-        let target =
-            build_target_path(&vault, (2024, 6, 15), Path::new("/source/photo.jpg"), 1000);
+        let target = build_target_path(
+            &vault,
+            (2024, 6, 15),
+            Path::new("/source/photo.jpg"),
+            1000,
+            "hash1",
+            VerifyMode::SizeOnly,
+        );
         assert_eq!(target, PathBuf::from("/vault/2024/06/15/photo.jpg"));
     }
 
     #[test]
     fn test_build_target_path_zero_padding() {
         let vault = PathBuf::from("/vault");
-        let target = build_target_path(&vault, (2024, 1, 5), Path::new("/source/img.png"), 1000);
+        let target = build_target_path(
+            &vault,
+            (2024, 1, 5),
+            Path::new("/source/img.png"),
+            1000,
+            "hash1",
+            VerifyMode::SizeOnly,
+        );
         assert_eq!(target, PathBuf::from("/vault/2024/01/05/img.png"));
     }
 
@@ -356,8 +818,14 @@ mod tests {
         fs::write(date_dir.join("photo.jpg"), b"hello").unwrap();
 
         // Build path for a file with a different size (1000) — should get _1 suffix
-        let target =
-            build_target_path(vault, (2024, 6, 15), Path::new("/source/photo.jpg"), 1000);
+        let target = build_target_path(
+            vault,
+            (2024, 6, 15),
+            Path::new("/source/photo.jpg"),
+            1000,
+            "hash1",
+            VerifyMode::SizeOnly,
+        );
         assert_eq!(
             target.file_name().unwrap().to_string_lossy(),
             "photo_1.jpg"
@@ -375,10 +843,66 @@ mod tests {
         fs::write(date_dir.join("photo.jpg"), b"hello").unwrap();
 
         // Build path for a file with matching size (5) — should return existing path
-        let target = build_target_path(vault, (2024, 6, 15), Path::new("/source/photo.jpg"), 5);
+        let target = build_target_path(
+            vault,
+            (2024, 6, 15),
+            Path::new("/source/photo.jpg"),
+            5,
+            "hash1",
+            VerifyMode::SizeOnly,
+        );
+        assert_eq!(target.file_name().unwrap().to_string_lossy(), "photo.jpg");
+    }
+
+    #[test]
+    fn test_build_target_path_sha256_verify_confirms_matching_content() {
+        let tmp = tempfile::tempdir().unwrap();
+        let vault = tmp.path();
+        let date_dir = vault.join("2024/06/15");
+        fs::create_dir_all(&date_dir).unwrap();
+
+        let existing = date_dir.join("photo.jpg");
+        fs::write(&existing, b"hello").unwrap();
+        let sha256 = crate::hasher::compute_sha256(&existing).unwrap();
+
+        // Same size and same hash — confirmed as already-present.
+        let target = build_target_path(
+            vault,
+            (2024, 6, 15),
+            Path::new("/source/photo.jpg"),
+            5,
+            &sha256,
+            VerifyMode::Sha256,
+        );
         assert_eq!(target.file_name().unwrap().to_string_lossy(), "photo.jpg");
     }
 
+    #[test]
+    fn test_build_target_path_sha256_verify_rejects_same_size_different_content() {
+        let tmp = tempfile::tempdir().unwrap();
+        let vault = tmp.path();
+        let date_dir = vault.join("2024/06/15");
+        fs::create_dir_all(&date_dir).unwrap();
+
+        // Existing file is 5 bytes but not the content we expect.
+        fs::write(date_dir.join("photo.jpg"), b"world").unwrap();
+
+        // Same size (5) but a hash that can't match "world" — should NOT be
+        // trusted as already-present, and gets a collision suffix instead.
+        let target = build_target_path(
+            vault,
+            (2024, 6, 15),
+            Path::new("/source/photo.jpg"),
+            5,
+            "not_the_hash_of_world",
+            VerifyMode::Sha256,
+        );
+        assert_eq!(
+            target.file_name().unwrap().to_string_lossy(),
+            "photo_1.jpg"
+        );
+    }
+
     #[test]
     fn test_build_target_path_multiple_collisions() {
         let tmp = tempfile::tempdir().unwrap();
@@ -391,8 +915,14 @@ mod tests {
         fs::write(date_dir.join("photo_1.jpg"), b"ab").unwrap();
         fs::write(date_dir.join("photo_2.jpg"), b"abc").unwrap();
 
-        let target =
-            build_target_path(vault, (2024, 1, 1), Path::new("/source/photo.jpg"), 9999);
+        let target = build_target_path(
+            vault,
+            (2024, 1, 1),
+            Path::new("/source/photo.jpg"),
+            9999,
+            "hash1",
+            VerifyMode::SizeOnly,
+        );
         assert_eq!(
             target.file_name().unwrap().to_string_lossy(),
             "photo_3.jpg"
@@ -462,7 +992,7 @@ mod tests {
                 confidence: Confidence::High,
             },
         ];
-        let selected = select_photos_to_export(&photos, &groups);
+        let selected = select_photos_to_export(&photos, &groups, None);
         // SoT 1 from group 1 + SoT 3 from group 2 + ungrouped 5 = 3
         assert_eq!(selected.len(), 3);
         let ids: HashSet<i64> = selected.iter().map(|p| p.id).collect();
@@ -483,68 +1013,606 @@ mod tests {
             source_of_truth_id: 2,
             confidence: Confidence::Certain,
         }];
-        let selected = select_photos_to_export(&photos, &groups);
+        let selected = select_photos_to_export(&photos, &groups, None);
         assert_eq!(selected.len(), 1);
         assert_eq!(selected[0].id, 2);
     }
 
     #[test]
     fn test_select_photos_empty_input() {
-        let selected = select_photos_to_export(&[], &[]);
+        let selected = select_photos_to_export(&[], &[], None);
         assert!(selected.is_empty());
     }
 
+    // ── PhotoMatcher ──────────────────────────────────────────────
+
+    #[test]
+    fn test_photo_matcher_empty_include_matches_everything() {
+        let matcher = PhotoMatcher::default();
+        let photo = make_photo_with_path(1, "/anything/at/all.jpg");
+        assert!(matcher.matches(&photo));
+    }
+
+    #[test]
+    fn test_photo_matcher_include_glob() {
+        let matcher = PhotoMatcher {
+            include: vec!["*/Camera/*".to_string()],
+            ..Default::default()
+        };
+        assert!(matcher.matches(&make_photo_with_path(1, "/phone/Camera/img.jpg")));
+        assert!(!matcher.matches(&make_photo_with_path(2, "/phone/Screenshots/img.jpg")));
+    }
+
+    #[test]
+    fn test_photo_matcher_exclude_wins_over_include() {
+        let matcher = PhotoMatcher {
+            include: vec!["*/Camera/*".to_string()],
+            exclude: vec!["*/Camera/trash/*".to_string()],
+            ..Default::default()
+        };
+        assert!(!matcher.matches(&make_photo_with_path(1, "/phone/Camera/trash/img.jpg")));
+        assert!(matcher.matches(&make_photo_with_path(2, "/phone/Camera/img.jpg")));
+    }
+
+    #[test]
+    fn test_photo_matcher_camera_make_case_insensitive() {
+        let matcher = PhotoMatcher {
+            camera_make: Some("canon".to_string()),
+            ..Default::default()
+        };
+        let mut photo = make_photo_with_path(1, "/a.jpg");
+        photo.exif = Some(ExifData {
+            date: None,
+            camera_make: Some("Canon".to_string()),
+            camera_model: None,
+            gps_lat: None,
+            gps_lon: None,
+            width: None,
+            height: None,
+        });
+        assert!(matcher.matches(&photo));
+
+        photo.exif.as_mut().unwrap().camera_make = Some("Nikon".to_string());
+        assert!(!matcher.matches(&photo));
+    }
+
+    #[test]
+    fn test_photo_matcher_camera_make_rejects_missing_exif() {
+        let matcher = PhotoMatcher {
+            camera_make: Some("canon".to_string()),
+            ..Default::default()
+        };
+        let photo = make_photo_with_path(1, "/a.jpg");
+        assert!(!matcher.matches(&photo));
+    }
+
+    #[test]
+    fn test_photo_matcher_date_range() {
+        let matcher = PhotoMatcher {
+            date_from: Some((2024, 1, 1)),
+            date_to: Some((2024, 6, 30)),
+            ..Default::default()
+        };
+
+        let mut in_range = make_photo_with_path(1, "/a.jpg");
+        in_range.exif = Some(ExifData {
+            date: Some("2024-03-15 00:00:00".to_string()),
+            camera_make: None,
+            camera_model: None,
+            gps_lat: None,
+            gps_lon: None,
+            width: None,
+            height: None,
+        });
+        assert!(matcher.matches(&in_range));
+
+        let mut out_of_range = make_photo_with_path(2, "/b.jpg");
+        out_of_range.exif = Some(ExifData {
+            date: Some("2024-12-01 00:00:00".to_string()),
+            camera_make: None,
+            camera_model: None,
+            gps_lat: None,
+            gps_lon: None,
+            width: None,
+            height: None,
+        });
+        assert!(!matcher.matches(&out_of_range));
+    }
+
+    #[test]
+    fn test_select_photos_to_export_applies_matcher() {
+        let photos = vec![
+            make_photo_with_path(1, "/Camera/a.jpg"),
+            make_photo_with_path(2, "/Screenshots/b.jpg"),
+        ];
+        let matcher = PhotoMatcher {
+            include: vec!["*/Camera/*".to_string()],
+            ..Default::default()
+        };
+        let selected = select_photos_to_export(&photos, &[], Some(&matcher));
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].id, 1);
+    }
+
+    // ── safe_source_path ─────────────────────────────────────────
+
+    #[test]
+    fn test_safe_source_path_accepts_plain_file_under_root() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().canonicalize().unwrap();
+        let file = root.join("photo.jpg");
+        fs::write(&file, b"data").unwrap();
+
+        let resolved = safe_source_path(&root, &file).unwrap();
+        assert_eq!(resolved, file.canonicalize().unwrap());
+    }
+
+    #[test]
+    fn test_safe_source_path_rejects_nonexistent_path() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().canonicalize().unwrap();
+        let missing = root.join("ghost.jpg");
+
+        assert!(safe_source_path(&root, &missing).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_safe_source_path_rejects_symlink_escaping_root() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("source_root");
+        let outside = tmp.path().join("outside");
+        fs::create_dir_all(&root).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+
+        let secret = outside.join("secret.jpg");
+        fs::write(&secret, b"not yours").unwrap();
+
+        let link = root.join("escape.jpg");
+        std::os::unix::fs::symlink(&secret, &link).unwrap();
+
+        let root = root.canonicalize().unwrap();
+        let err = safe_source_path(&root, &link).unwrap_err();
+        assert!(matches!(err, Error::VaultSaveSymlinkEscape(_)));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_safe_source_path_accepts_symlink_within_root() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("source_root");
+        fs::create_dir_all(&root).unwrap();
+
+        let real = root.join("real.jpg");
+        fs::write(&real, b"data").unwrap();
+        let link = root.join("alias.jpg");
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        let root = root.canonicalize().unwrap();
+        assert!(safe_source_path(&root, &link).is_ok());
+    }
+
+    // ── object_path_for ──────────────────────────────────────────
+
+    #[test]
+    fn test_object_path_for_splits_prefix() {
+        let vault = PathBuf::from("/vault");
+        let path = object_path_for(&vault, "abcdef0123456789");
+        assert_eq!(path, PathBuf::from("/vault/objects/ab/cdef0123456789"));
+    }
+
+    #[test]
+    fn test_object_path_for_short_hash() {
+        let vault = PathBuf::from("/vault");
+        let path = object_path_for(&vault, "a");
+        assert_eq!(path, PathBuf::from("/vault/objects/a/"));
+    }
+
     // ── copy_photo_to_vault ─────────────────────────────────────
 
     #[test]
     fn test_copy_photo_creates_dirs_and_copies() {
         let tmp = tempfile::tempdir().unwrap();
-        let source = tmp.path().join("source.jpg");
+        let vault = tmp.path();
+        let source = vault.join("source.jpg");
         fs::write(&source, b"photo data").unwrap();
 
-        let target = tmp.path().join("deep/nested/dir/target.jpg");
-        let result = copy_photo_to_vault(&source, &target, 1000).unwrap();
-        assert!(result, "should copy when target doesn't exist");
+        let target = vault.join("deep/nested/dir/target.jpg");
+        let result =
+            copy_photo_to_vault(vault, &source, "hash1", &target, 1000, VerifyMode::SizeOnly)
+                .unwrap();
+        assert_eq!(result, CopyOutcome::Copied);
         assert!(target.exists());
         assert_eq!(fs::read(&target).unwrap(), b"photo data");
+        assert!(object_path_for(vault, "hash1").exists());
     }
 
     #[test]
     fn test_copy_photo_skips_same_size() {
         let tmp = tempfile::tempdir().unwrap();
-        let source = tmp.path().join("source.jpg");
+        let vault = tmp.path();
+        let source = vault.join("source.jpg");
         fs::write(&source, b"photo data").unwrap(); // 10 bytes
 
-        let target = tmp.path().join("target.jpg");
+        let target = vault.join("target.jpg");
         fs::write(&target, b"old  data!").unwrap(); // also 10 bytes
 
-        let result = copy_photo_to_vault(&source, &target, 10).unwrap();
-        assert!(!result, "should skip when sizes match");
+        let result =
+            copy_photo_to_vault(vault, &source, "hash1", &target, 10, VerifyMode::SizeOnly)
+                .unwrap();
+        assert_eq!(result, CopyOutcome::Skipped);
         // Content should NOT be overwritten
         assert_eq!(fs::read(&target).unwrap(), b"old  data!");
     }
 
+    #[test]
+    fn test_copy_photo_sha256_verify_rejects_same_size_different_content() {
+        let tmp = tempfile::tempdir().unwrap();
+        let vault = tmp.path();
+        let source = vault.join("source.jpg");
+        fs::write(&source, b"photo data").unwrap(); // 10 bytes
+
+        let target = vault.join("target.jpg");
+        fs::write(&target, b"old  data!").unwrap(); // also 10 bytes, different content
+
+        let result =
+            copy_photo_to_vault(vault, &source, "hash1", &target, 10, VerifyMode::Sha256)
+                .unwrap();
+        assert_eq!(result, CopyOutcome::Copied);
+        // Content SHOULD be overwritten since the hash didn't confirm a match.
+        assert_eq!(fs::read(&target).unwrap(), b"photo data");
+    }
+
+    #[test]
+    fn test_copy_photo_sha256_verify_skips_genuinely_matching_content() {
+        let tmp = tempfile::tempdir().unwrap();
+        let vault = tmp.path();
+        let source = vault.join("source.jpg");
+        fs::write(&source, b"photo data").unwrap();
+
+        let target = vault.join("target.jpg");
+        fs::write(&target, b"photo data").unwrap();
+        let sha256 = crate::hasher::compute_sha256(&target).unwrap();
+
+        let result =
+            copy_photo_to_vault(vault, &source, &sha256, &target, 10, VerifyMode::Sha256)
+                .unwrap();
+        assert_eq!(result, CopyOutcome::Skipped);
+    }
+
     #[test]
     fn test_copy_photo_overwrites_different_size() {
         let tmp = tempfile::tempdir().unwrap();
-        let source = tmp.path().join("source.jpg");
+        let vault = tmp.path();
+        let source = vault.join("source.jpg");
         fs::write(&source, b"new photo data").unwrap(); // 14 bytes
 
-        let target = tmp.path().join("target.jpg");
+        let target = vault.join("target.jpg");
         fs::write(&target, b"old").unwrap(); // 3 bytes
 
-        let result = copy_photo_to_vault(&source, &target, 14).unwrap();
-        assert!(result, "should copy when sizes differ");
+        let result =
+            copy_photo_to_vault(vault, &source, "hash1", &target, 14, VerifyMode::SizeOnly)
+                .unwrap();
+        assert_eq!(result, CopyOutcome::Copied);
         assert_eq!(fs::read(&target).unwrap(), b"new photo data");
     }
 
     #[test]
     fn test_copy_photo_source_not_found() {
         let tmp = tempfile::tempdir().unwrap();
-        let source = tmp.path().join("nonexistent.jpg");
-        let target = tmp.path().join("target.jpg");
+        let vault = tmp.path();
+        let source = vault.join("nonexistent.jpg");
+        let target = vault.join("target.jpg");
 
-        let result = copy_photo_to_vault(&source, &target, 1000);
+        let result =
+            copy_photo_to_vault(vault, &source, "hash1", &target, 1000, VerifyMode::SizeOnly);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_copy_photo_dedup_hit_links_instead_of_copying() {
+        let tmp = tempfile::tempdir().unwrap();
+        let vault = tmp.path();
+
+        let source_a = vault.join("a.jpg");
+        fs::write(&source_a, b"same bytes").unwrap(); // 10 bytes
+        let target_a = vault.join("2024/01/01/a.jpg");
+        let first =
+            copy_photo_to_vault(vault, &source_a, "samehash", &target_a, 10, VerifyMode::SizeOnly)
+                .unwrap();
+        assert_eq!(first, CopyOutcome::Copied);
+
+        // A second file with identical content (same hash) dumped from another card.
+        let source_b = vault.join("b.jpg");
+        fs::write(&source_b, b"same bytes").unwrap();
+        let target_b = vault.join("2024/01/02/b.jpg");
+        let second =
+            copy_photo_to_vault(vault, &source_b, "samehash", &target_b, 10, VerifyMode::SizeOnly)
+                .unwrap();
+        assert_eq!(second, CopyOutcome::Deduplicated { bytes_saved: 10 });
+
+        assert_eq!(fs::read(&target_b).unwrap(), b"same bytes");
+        // Only one object on disk, reached from both target paths.
+        let object = object_path_for(vault, "samehash");
+        assert!(object.exists());
+    }
+
+    #[test]
+    fn test_copy_photo_leaves_no_tmp_artifact_behind() {
+        let tmp = tempfile::tempdir().unwrap();
+        let vault = tmp.path();
+        let source = vault.join("source.jpg");
+        fs::write(&source, b"photo data").unwrap();
+
+        let target = vault.join("target.jpg");
+        copy_photo_to_vault(vault, &source, "hash1", &target, 1000, VerifyMode::SizeOnly).unwrap();
+
+        let object_path = object_path_for(vault, "hash1");
+        assert!(object_path.exists());
+        assert!(
+            !object_path.with_extension("tmp").exists(),
+            "the sibling temp file used for the atomic rename must not survive a successful copy"
+        );
+    }
+
+    #[test]
+    fn test_copy_photo_dedup_hit_target_already_correct_is_skipped() {
+        let tmp = tempfile::tempdir().unwrap();
+        let vault = tmp.path();
+
+        let source = vault.join("a.jpg");
+        fs::write(&source, b"same bytes").unwrap();
+        let target = vault.join("2024/01/01/a.jpg");
+        copy_photo_to_vault(vault, &source, "samehash", &target, 10, VerifyMode::SizeOnly).unwrap();
+
+        // Re-running against the same target is an incremental no-op, not a dedup hit.
+        let result =
+            copy_photo_to_vault(vault, &source, "samehash", &target, 10, VerifyMode::SizeOnly)
+                .unwrap();
+        assert_eq!(result, CopyOutcome::Skipped);
+    }
+
+    // ── make_hard_link ───────────────────────────────────────────
+
+    #[test]
+    fn test_make_hard_link_replaces_with_link_to_canonical() {
+        let tmp = tempfile::tempdir().unwrap();
+        let canonical = tmp.path().join("canonical.jpg");
+        fs::write(&canonical, b"same bytes").unwrap();
+        let path = tmp.path().join("duplicate.jpg");
+        fs::write(&path, b"same bytes").unwrap();
+
+        make_hard_link(&path, &canonical).unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"same bytes");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            assert_eq!(
+                fs::metadata(&path).unwrap().ino(),
+                fs::metadata(&canonical).unwrap().ino(),
+                "path and canonical should now share the same inode"
+            );
+        }
+    }
+
+    #[test]
+    fn test_make_hard_link_rejects_size_mismatch() {
+        let tmp = tempfile::tempdir().unwrap();
+        let canonical = tmp.path().join("canonical.jpg");
+        fs::write(&canonical, b"same bytes").unwrap();
+        let path = tmp.path().join("duplicate.jpg");
+        fs::write(&path, b"different length bytes").unwrap();
+
+        let err = make_hard_link(&path, &canonical).unwrap_err();
+        assert!(matches!(err, Error::VaultSaveContentMismatch(_)));
+        // The mismatched file must be left alone, not clobbered.
+        assert_eq!(fs::read(&path).unwrap(), b"different length bytes");
+    }
+
+    #[test]
+    fn test_make_hard_link_leaves_no_tmp_artifact_behind() {
+        let tmp = tempfile::tempdir().unwrap();
+        let canonical = tmp.path().join("canonical.jpg");
+        fs::write(&canonical, b"same bytes").unwrap();
+        let path = tmp.path().join("duplicate.jpg");
+        fs::write(&path, b"same bytes").unwrap();
+
+        make_hard_link(&path, &canonical).unwrap();
+
+        assert!(!path.with_extension("lsvault-link-tmp").exists());
+    }
+
+    // ── cleanup_superseded_vault_files (link mode) ──────────────
+
+    #[test]
+    fn test_cleanup_superseded_vault_files_link_mode_links_instead_of_removing() {
+        let tmp = tempfile::tempdir().unwrap();
+        let vault = tmp.path().canonicalize().unwrap();
+
+        let sot_path = vault.join("2024/01/01/best.jpg");
+        fs::create_dir_all(sot_path.parent().unwrap()).unwrap();
+        fs::write(&sot_path, b"same bytes").unwrap();
+
+        let superseded_path = vault.join("2024/01/01/best_1.jpg");
+        fs::write(&superseded_path, b"same bytes").unwrap();
+
+        let sot = make_photo_with_path(1, sot_path.to_str().unwrap());
+        let superseded = make_photo_with_path(2, superseded_path.to_str().unwrap());
+        let photos = vec![sot.clone(), superseded.clone()];
+        let groups = vec![DuplicateGroup {
+            id: 1,
+            members: vec![sot, superseded],
+            source_of_truth_id: 1,
+            confidence: Confidence::Certain,
+        }];
+
+        let outcomes = cleanup_superseded_vault_files(&vault, &photos, &groups, true);
+        assert_eq!(outcomes.len(), 1);
+        assert!(matches!(outcomes[0], SupersededOutcome::Linked { .. }));
+        assert!(
+            superseded_path.exists(),
+            "linked file must still exist at its original path"
+        );
+    }
+
+    #[test]
+    fn test_cleanup_superseded_vault_files_default_mode_still_removes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let vault = tmp.path().canonicalize().unwrap();
+
+        let sot_path = vault.join("2024/01/01/best.jpg");
+        fs::create_dir_all(sot_path.parent().unwrap()).unwrap();
+        fs::write(&sot_path, b"same bytes").unwrap();
+
+        let superseded_path = vault.join("2024/01/01/best_1.jpg");
+        fs::write(&superseded_path, b"same bytes").unwrap();
+
+        let sot = make_photo_with_path(1, sot_path.to_str().unwrap());
+        let superseded = make_photo_with_path(2, superseded_path.to_str().unwrap());
+        let photos = vec![sot.clone(), superseded.clone()];
+        let groups = vec![DuplicateGroup {
+            id: 1,
+            members: vec![sot, superseded],
+            source_of_truth_id: 1,
+            confidence: Confidence::Certain,
+        }];
+
+        let outcomes = cleanup_superseded_vault_files(&vault, &photos, &groups, false);
+        assert_eq!(outcomes.len(), 1);
+        assert!(matches!(outcomes[0], SupersededOutcome::Removed { .. }));
+        assert!(!superseded_path.exists());
+    }
+
+    // ── detect_vault_moves ───────────────────────────────────────
+
+    #[test]
+    fn test_detect_vault_moves_finds_renamed_vault_copy() {
+        let tmp = tempfile::tempdir().unwrap();
+        let vault = tmp.path().canonicalize().unwrap();
+
+        let old_path = vault.join("2024/01/01/old_name.jpg");
+        fs::create_dir_all(old_path.parent().unwrap()).unwrap();
+        fs::write(&old_path, b"same bytes").unwrap();
+        let new_target = vault.join("2024/02/02/new_name.jpg");
+
+        let catalog = Catalog::open_in_memory().unwrap();
+        let source = catalog.add_source(&vault).unwrap();
+        let mut vault_copy = make_photo_with_path(1, old_path.to_str().unwrap());
+        vault_copy.source_id = source.id;
+        catalog.upsert_photo(&vault_copy).unwrap();
+
+        let mut photo = make_photo_with_path(2, "/src/renamed.jpg");
+        photo.sha256 = vault_copy.sha256.clone();
+        let targets = vec![(&photo, new_target.clone())];
+
+        let moves = detect_vault_moves(&vault, &catalog, &targets).unwrap();
+        assert_eq!(moves, vec![(old_path, new_target)]);
+    }
+
+    #[test]
+    fn test_detect_vault_moves_skips_when_target_already_exists() {
+        let tmp = tempfile::tempdir().unwrap();
+        let vault = tmp.path().canonicalize().unwrap();
+
+        let target = vault.join("2024/01/01/existing.jpg");
+        fs::create_dir_all(target.parent().unwrap()).unwrap();
+        fs::write(&target, b"bytes").unwrap();
+
+        let catalog = Catalog::open_in_memory().unwrap();
+        let photo = make_photo_with_path(1, "/src/a.jpg");
+        let targets = vec![(&photo, target)];
+
+        let moves = detect_vault_moves(&vault, &catalog, &targets).unwrap();
+        assert!(moves.is_empty());
+    }
+
+    #[test]
+    fn test_detect_vault_moves_ignores_matches_outside_vault() {
+        let tmp = tempfile::tempdir().unwrap();
+        let vault = tmp.path().join("vault");
+        fs::create_dir_all(&vault).unwrap();
+        let vault = vault.canonicalize().unwrap();
+
+        let outside_path = tmp.path().join("outside.jpg");
+        fs::write(&outside_path, b"bytes").unwrap();
+
+        let catalog = Catalog::open_in_memory().unwrap();
+        let source = catalog.add_source(tmp.path()).unwrap();
+        let mut outside_copy = make_photo_with_path(1, outside_path.to_str().unwrap());
+        outside_copy.source_id = source.id;
+        catalog.upsert_photo(&outside_copy).unwrap();
+
+        let mut photo = make_photo_with_path(2, "/src/a.jpg");
+        photo.sha256 = outside_copy.sha256.clone();
+        let new_target = vault.join("2024/01/01/a.jpg");
+        let targets = vec![(&photo, new_target)];
+
+        let moves = detect_vault_moves(&vault, &catalog, &targets).unwrap();
+        assert!(moves.is_empty());
+    }
+
+    // ── gc_vault ─────────────────────────────────────────────────
+
+    #[test]
+    fn test_gc_vault_removes_orphaned_object_with_no_browse_links() {
+        let tmp = tempfile::tempdir().unwrap();
+        let vault = tmp.path();
+        let catalog = Catalog::open_in_memory().unwrap();
+
+        let object_path = object_path_for(vault, "orphanhash");
+        fs::create_dir_all(object_path.parent().unwrap()).unwrap();
+        fs::write(&object_path, b"nobody links to me anymore").unwrap();
+
+        let removed = gc_vault(vault, &catalog).unwrap();
+        assert_eq!(removed, vec![object_path.clone()]);
+        assert!(!object_path.exists());
+    }
+
+    #[test]
+    fn test_gc_vault_keeps_object_still_hard_linked_from_a_browse_path() {
+        let tmp = tempfile::tempdir().unwrap();
+        let vault = tmp.path();
+        let catalog = Catalog::open_in_memory().unwrap();
+
+        let source = vault.join("source.jpg");
+        fs::write(&source, b"still referenced").unwrap();
+        let target = vault.join("2024/01/01/source.jpg");
+        copy_photo_to_vault(vault, &source, "livehash", &target, 16, VerifyMode::SizeOnly).unwrap();
+
+        let removed = gc_vault(vault, &catalog).unwrap();
+        assert!(removed.is_empty());
+        assert!(object_path_for(vault, "livehash").exists());
+    }
+
+    #[test]
+    fn test_gc_vault_keeps_object_whose_hash_is_still_in_the_catalog() {
+        let tmp = tempfile::tempdir().unwrap();
+        let vault = tmp.path();
+        let catalog = Catalog::open_in_memory().unwrap();
+        let source_row = catalog.add_source(Path::new("/sources/a")).unwrap();
+        let mut photo = make_photo(1, 1000);
+        photo.source_id = source_row.id;
+        photo.sha256 = "reflinkedhash".to_string();
+        catalog.upsert_photo(&photo).unwrap();
+
+        // Simulate a reflinked browse path: a separate file with its own
+        // inode, so the object's hard-link count never rises above 1.
+        let object_path = object_path_for(vault, "reflinkedhash");
+        fs::create_dir_all(object_path.parent().unwrap()).unwrap();
+        fs::write(&object_path, b"reflinked elsewhere").unwrap();
+
+        let removed = gc_vault(vault, &catalog).unwrap();
+        assert!(
+            removed.is_empty(),
+            "catalog still records this hash as live, so it must survive even with nlink == 1"
+        );
+    }
+
+    #[test]
+    fn test_gc_vault_no_objects_dir_returns_empty() {
+        let tmp = tempfile::tempdir().unwrap();
+        let catalog = Catalog::open_in_memory().unwrap();
+        assert!(gc_vault(tmp.path(), &catalog).unwrap().is_empty());
+    }
 }
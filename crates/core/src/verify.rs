@@ -0,0 +1,224 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use walkdir::WalkDir;
+
+use crate::error::Result;
+use crate::hasher::compute_sha256;
+use crate::manifest::Manifest;
+use crate::vault_save::object_path_for;
+
+/// Progress callback events for vault verification.
+pub enum VerifyProgress {
+    /// Starting verification with the number of manifest entries to check.
+    Start { total: usize },
+    /// A stored object was re-hashed and matched its recorded digest.
+    Checked { path: PathBuf },
+    /// A stored object's content no longer matches its recorded digest (bitrot).
+    Corrupt {
+        path: PathBuf,
+        expected: String,
+        actual: String,
+    },
+    /// Verification completed.
+    Complete {
+        ok: usize,
+        corrupt: usize,
+        missing: usize,
+    },
+}
+
+/// Result of a vault verification pass.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    /// Objects that re-hashed to their recorded digest.
+    pub ok: usize,
+    /// Objects whose content no longer matches the manifest: (path, expected, actual).
+    pub corrupt: Vec<(PathBuf, String, String)>,
+    /// Manifest entries whose object file no longer exists on disk.
+    pub missing: Vec<PathBuf>,
+    /// Files under `objects/` that no manifest entry references.
+    pub orphaned: Vec<PathBuf>,
+}
+
+/// Re-stream every object referenced by the vault manifest through
+/// `compute_sha256` and compare against its recorded digest. Detects bitrot
+/// (recorded hash no longer matches file content), objects that disappeared
+/// from disk, and objects on disk the manifest no longer references.
+pub fn verify_vault(
+    vault_path: &Path,
+    manifest: &Manifest,
+    mut progress_cb: Option<&mut dyn FnMut(VerifyProgress)>,
+) -> Result<VerifyReport> {
+    let entries = manifest.list_entries()?;
+    let mut report = VerifyReport::default();
+
+    if let Some(ref mut cb) = progress_cb {
+        cb(VerifyProgress::Start {
+            total: entries.len(),
+        });
+    }
+
+    let mut known_hashes: HashSet<String> = HashSet::new();
+    for (sha256, _format) in &entries {
+        known_hashes.insert(sha256.clone());
+        let object_path = object_path_for(vault_path, sha256);
+
+        match compute_sha256(&object_path) {
+            Ok(actual) if &actual == sha256 => {
+                report.ok += 1;
+                if let Some(ref mut cb) = progress_cb {
+                    cb(VerifyProgress::Checked {
+                        path: object_path.clone(),
+                    });
+                }
+            }
+            Ok(actual) => {
+                if let Some(ref mut cb) = progress_cb {
+                    cb(VerifyProgress::Corrupt {
+                        path: object_path.clone(),
+                        expected: sha256.clone(),
+                        actual: actual.clone(),
+                    });
+                }
+                report.corrupt.push((object_path, sha256.clone(), actual));
+            }
+            Err(_) => {
+                report.missing.push(object_path);
+            }
+        }
+    }
+
+    // Orphaned objects: files under objects/ that no manifest entry references.
+    let objects_dir = vault_path.join("objects");
+    if objects_dir.is_dir() {
+        for entry in WalkDir::new(&objects_dir)
+            .into_iter()
+            .filter_map(|e| e.ok())
+        {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let relative = entry
+                .path()
+                .strip_prefix(&objects_dir)
+                .unwrap_or(entry.path());
+            let hash: String = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect();
+            if !known_hashes.contains(&hash) {
+                report.orphaned.push(entry.path().to_path_buf());
+            }
+        }
+    }
+
+    if let Some(ref mut cb) = progress_cb {
+        cb(VerifyProgress::Complete {
+            ok: report.ok,
+            corrupt: report.corrupt.len(),
+            missing: report.missing.len(),
+        });
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::IngestReason;
+    use std::fs;
+
+    /// Hash `content` the same way the production code does, then plant it
+    /// directly at its object path — as if `vault_save` had already copied it.
+    fn vault_with_object(content: &[u8]) -> (tempfile::TempDir, Manifest, String) {
+        let tmp = tempfile::tempdir().unwrap();
+        let scratch = tmp.path().join("scratch.bin");
+        fs::write(&scratch, content).unwrap();
+        let sha256 = compute_sha256(&scratch).unwrap();
+        fs::remove_file(&scratch).unwrap();
+
+        let manifest = Manifest::open(tmp.path()).unwrap();
+        let gen = manifest.begin_generation(None).unwrap();
+        let object_path = object_path_for(tmp.path(), &sha256);
+        fs::create_dir_all(object_path.parent().unwrap()).unwrap();
+        fs::write(&object_path, content).unwrap();
+        manifest
+            .insert_file(
+                &sha256,
+                "photo.jpg",
+                "JPEG",
+                content.len() as u64,
+                None,
+                None,
+                None,
+                None,
+                gen,
+                IngestReason::New,
+            )
+            .unwrap();
+        (tmp, manifest, sha256)
+    }
+
+    #[test]
+    fn test_verify_vault_all_ok() {
+        let (tmp, manifest, _) = vault_with_object(b"hello vault");
+
+        let report = verify_vault(tmp.path(), &manifest, None).unwrap();
+        assert_eq!(report.ok, 1);
+        assert!(report.corrupt.is_empty());
+        assert!(report.missing.is_empty());
+        assert!(report.orphaned.is_empty());
+    }
+
+    #[test]
+    fn test_verify_vault_detects_corruption() {
+        let (tmp, manifest, sha256) = vault_with_object(b"original bytes");
+
+        // Simulate bitrot: overwrite the object with different bytes.
+        let object_path = object_path_for(tmp.path(), &sha256);
+        fs::write(&object_path, b"corrupted!").unwrap();
+
+        let report = verify_vault(tmp.path(), &manifest, None).unwrap();
+        assert_eq!(report.ok, 0);
+        assert_eq!(report.corrupt.len(), 1);
+        assert_eq!(report.corrupt[0].0, object_path);
+        assert_eq!(report.corrupt[0].1, sha256);
+    }
+
+    #[test]
+    fn test_verify_vault_detects_missing_object() {
+        let (tmp, manifest, sha256) = vault_with_object(b"gone");
+
+        let object_path = object_path_for(tmp.path(), &sha256);
+        fs::remove_file(&object_path).unwrap();
+
+        let report = verify_vault(tmp.path(), &manifest, None).unwrap();
+        assert_eq!(report.missing, vec![object_path]);
+        assert_eq!(report.ok, 0);
+    }
+
+    #[test]
+    fn test_verify_vault_detects_orphaned_object() {
+        let (tmp, manifest, _) = vault_with_object(b"known");
+
+        // An object on disk with no manifest entry pointing at it.
+        let orphan_path = tmp.path().join("objects").join("or").join("phan");
+        fs::create_dir_all(orphan_path.parent().unwrap()).unwrap();
+        fs::write(&orphan_path, b"orphan").unwrap();
+
+        let report = verify_vault(tmp.path(), &manifest, None).unwrap();
+        assert_eq!(report.ok, 1);
+        assert_eq!(report.orphaned, vec![orphan_path]);
+    }
+
+    #[test]
+    fn test_verify_vault_empty_manifest() {
+        let tmp = tempfile::tempdir().unwrap();
+        let manifest = Manifest::open(tmp.path()).unwrap();
+
+        let report = verify_vault(tmp.path(), &manifest, None).unwrap();
+        assert_eq!(report, VerifyReport::default());
+    }
+}
@@ -0,0 +1,130 @@
+//! C-compatible FFI surface over `photopack_core`'s perceptual-hash and
+//! confidence logic, so a Python/Node/Swift frontend can drive the exact
+//! same dedup math the CLI uses instead of shelling out to it.
+//!
+//! Every exported function is `extern "C"`, panic-safe (a panic inside
+//! `photopack_core` is caught at the boundary and turned into an error code
+//! rather than unwinding into the caller, which is undefined behavior across
+//! an FFI edge), and takes only plain, caller-owned memory — there are no
+//! heap-allocated return values here, so there's nothing for the caller to
+//! free.
+
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::path::Path;
+use std::sync::Once;
+
+use photopack_core::hasher::perceptual::{compute_perceptual_hashes, hamming_distance};
+use photopack_core::matching::confidence::confidence_from_hamming;
+
+static INIT: Once = Once::new();
+
+/// One-time setup. Currently only installs a panic hook that logs to stderr
+/// instead of printing Rust's default backtrace-laden message, which reads
+/// as an internal crash to a caller that doesn't know this is Rust under the
+/// hood. Safe to call more than once — later calls are no-ops. Not required
+/// before the other functions (they're panic-safe on their own via
+/// `catch_unwind`), but doing it once up front gives cleaner diagnostics.
+#[no_mangle]
+pub extern "C" fn lsvault_init() -> i32 {
+    INIT.call_once(|| {
+        std::panic::set_hook(Box::new(|info| {
+            eprintln!("lsvault: internal error: {info}");
+        }));
+    });
+    0
+}
+
+/// Perceptual hashes for one file, as returned by `lsvault_compute_hashes`.
+/// Plain data — no pointers, nothing to free.
+#[repr(C)]
+pub struct LsvaultHashes {
+    pub ahash: u64,
+    pub dhash: u64,
+}
+
+/// Result codes shared by every function below. Stable across releases —
+/// add new codes rather than renumbering existing ones.
+pub const LSVAULT_OK: i32 = 0;
+pub const LSVAULT_ERR_NULL_PATH: i32 = 1;
+pub const LSVAULT_ERR_INVALID_UTF8: i32 = 2;
+pub const LSVAULT_ERR_DECODE_FAILED: i32 = 3;
+pub const LSVAULT_ERR_PANIC: i32 = 4;
+
+/// Compute the (ahash, dhash) pair for the image at `path`, writing the
+/// result into `out`. `path` is a NUL-terminated UTF-8 string; `out` must be
+/// non-null and point at valid, writable memory for one `LsvaultHashes`.
+///
+/// Runs the same hybrid decode `photopack_core::hasher::perceptual` uses
+/// internally (turbojpeg full-resolution grayscale for JPEG, EXIF-orientation
+/// normalization before resizing, with RAW/HEIC handled via their respective
+/// feature-gated decoders), so a match against hashes produced by a scan is
+/// exact.
+///
+/// Returns `LSVAULT_OK` on success, or one of the `LSVAULT_ERR_*` codes. On
+/// any non-OK return, `out` is left unmodified.
+///
+/// # Safety
+/// `path` must be a valid pointer to a NUL-terminated C string (or null).
+/// `out` must be a valid pointer to writable memory for one `LsvaultHashes`
+/// (or null).
+#[no_mangle]
+pub unsafe extern "C" fn lsvault_compute_hashes(path: *const c_char, out: *mut LsvaultHashes) -> i32 {
+    if path.is_null() || out.is_null() {
+        return LSVAULT_ERR_NULL_PATH;
+    }
+
+    let path_str = match CStr::from_ptr(path).to_str() {
+        Ok(s) => s,
+        Err(_) => return LSVAULT_ERR_INVALID_UTF8,
+    };
+
+    let result = std::panic::catch_unwind(|| compute_perceptual_hashes(Path::new(path_str)));
+
+    match result {
+        Ok(Some((ahash, dhash))) => {
+            *out = LsvaultHashes { ahash, dhash };
+            LSVAULT_OK
+        }
+        Ok(None) => LSVAULT_ERR_DECODE_FAILED,
+        Err(_) => LSVAULT_ERR_PANIC,
+    }
+}
+
+/// Hamming distance between two 64-bit perceptual hashes — popcount of their
+/// XOR. Never fails.
+#[no_mangle]
+pub extern "C" fn lsvault_hamming_distance(a: u64, b: u64) -> u32 {
+    hamming_distance(a, b)
+}
+
+/// Confidence codes returned by `lsvault_confidence_from_hamming`. These are
+/// this crate's own stable numbering — deliberately not the discriminants of
+/// `photopack_core::domain::Confidence`, so an internal reordering there
+/// can't silently change the ABI.
+pub const LSVAULT_CONFIDENCE_NONE: i32 = -1;
+pub const LSVAULT_CONFIDENCE_CERTAIN: i32 = 0;
+pub const LSVAULT_CONFIDENCE_NEAR_CERTAIN: i32 = 1;
+pub const LSVAULT_CONFIDENCE_HIGH: i32 = 2;
+pub const LSVAULT_CONFIDENCE_PROBABLE: i32 = 3;
+pub const LSVAULT_CONFIDENCE_LOW: i32 = 4;
+
+/// Map a Hamming distance to the confidence band `photopack_core`'s fixed,
+/// zero-false-positive thresholds assign it, using the same
+/// `confidence_from_hamming` the matching pipeline runs internally.
+/// Returns `LSVAULT_CONFIDENCE_NONE` if `distance` is outside every band.
+#[no_mangle]
+pub extern "C" fn lsvault_confidence_from_hamming(distance: u32) -> i32 {
+    use photopack_core::domain::Confidence;
+
+    let result = std::panic::catch_unwind(|| confidence_from_hamming(distance));
+    match result {
+        Ok(Some(Confidence::Certain)) => LSVAULT_CONFIDENCE_CERTAIN,
+        Ok(Some(Confidence::NearCertain)) => LSVAULT_CONFIDENCE_NEAR_CERTAIN,
+        Ok(Some(Confidence::High)) => LSVAULT_CONFIDENCE_HIGH,
+        Ok(Some(Confidence::Probable)) => LSVAULT_CONFIDENCE_PROBABLE,
+        Ok(Some(Confidence::Low)) => LSVAULT_CONFIDENCE_LOW,
+        Ok(None) => LSVAULT_CONFIDENCE_NONE,
+        Err(_) => LSVAULT_CONFIDENCE_NONE,
+    }
+}